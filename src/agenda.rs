@@ -0,0 +1,166 @@
+//! `sparrow agenda`: everything happening on a day in one list, under the day's date. Events,
+//! sleep, scheduled work, and due dates are merged in order of time, with the day's Ivy Lee list
+//! (which isn't tied to times) at the top.
+
+use crate::{methods::pomodoro::PomodoroScheduleEntry, Config, UserData};
+use chrono::{Date, DateTime, Duration, Local, NaiveDate};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+#[derive(Serialize)]
+pub struct AgendaDay {
+    pub date: NaiveDate,
+
+    /// The day's Ivy Lee list, most important first.
+    pub ivy_lee: Vec<String>,
+
+    /// Everything else, in order of time.
+    pub items: Vec<AgendaItem>,
+}
+
+#[derive(Serialize)]
+pub struct AgendaItem {
+    /// "calendar", "break", "sleep", "habit", "job", or "due".
+    pub kind: &'static str,
+    pub title: String,
+    pub start: DateTime<Local>,
+
+    /// When the item ends. Due dates don't have an end.
+    pub end: Option<DateTime<Local>>,
+}
+
+/// Returns the agenda for the `days` days starting with `date`.
+pub fn agenda(data: &UserData, date: Date<Local>, days: u32) -> Vec<AgendaDay> {
+    (0..days as i64)
+        .map(|i| agenda_day(data, date + Duration::days(i)))
+        .collect()
+}
+
+fn agenda_day(data: &UserData, day: Date<Local>) -> AgendaDay {
+    let from = day.and_hms(0, 0, 0);
+    let until = (day + Duration::days(1)).and_hms(0, 0, 0);
+    let starts_today = |start: &DateTime<Local>| *start >= from && *start < until;
+
+    // events and sleep come from the data itself, so they're there even if the schedule is older
+    // than them
+    let mut entries: Vec<PomodoroScheduleEntry> = data
+        .all_events()
+        .iter()
+        .flat_map(|e| {
+            e.occurrences(from, until)
+                .map(move |span| PomodoroScheduleEntry::from_event(e, span))
+        })
+        .chain(
+            data.get_bedtime()
+                .occurrences(from, until)
+                .map(PomodoroScheduleEntry::Sleep),
+        )
+        .filter(|e| starts_today(e.span().start()))
+        .collect();
+    if let Some(schedule) = data.get_pomodoro_schedule() {
+        let scheduled: Vec<PomodoroScheduleEntry> = schedule
+            .entries_on(day)
+            .filter(|e| starts_today(e.span().start()))
+            .filter(|e| {
+                !entries
+                    .iter()
+                    .any(|other| other.span() == e.span() && other.kind() == e.kind())
+            })
+            .cloned()
+            .collect();
+        entries.extend(scheduled);
+    }
+
+    let tasks = data.get_tasks();
+    let mut items: Vec<AgendaItem> = entries
+        .iter()
+        .map(|e| AgendaItem {
+            kind: e.kind(),
+            title: e.title(tasks).into_owned(),
+            start: *e.span().start(),
+            end: Some(e.span().end()),
+        })
+        .collect();
+    items.extend(
+        tasks
+            .iter()
+            .flat_map(|t| t.instances(from, until))
+            .filter(|t| !t.done && starts_today(&t.due_date))
+            .map(|t| AgendaItem {
+                kind: "due",
+                title: t.name,
+                start: t.due_date,
+                end: None,
+            }),
+    );
+    items.sort_by_key(|i| i.start);
+
+    let ivy_lee = data
+        .get_ivy_lee_schedule()
+        .as_ref()
+        .and_then(|s| s.tasks_on(day.naive_local()))
+        .map(|list| list.iter().map(|e| e.label(data.get_tasks())).collect())
+        .unwrap_or_default();
+
+    AgendaDay {
+        date: day.naive_local(),
+        ivy_lee,
+        items,
+    }
+}
+
+pub struct AgendaDisplay<'a> {
+    pub days: &'a [AgendaDay],
+    pub config: &'a Config,
+}
+
+impl Serialize for AgendaDisplay<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.days.serialize(serializer)
+    }
+}
+
+impl Display for AgendaDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let time_format = &self.config.time_format;
+        for (i, day) in self.days.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "{} {}",
+                day.date.format("%A"),
+                day.date.format(&self.config.date_format)
+            )?;
+
+            if !day.ivy_lee.is_empty() {
+                writeln!(f, "Ivy Lee list:")?;
+                for entry in &day.ivy_lee {
+                    writeln!(f, "-\t{}", entry)?;
+                }
+            }
+            if day.items.is_empty() && day.ivy_lee.is_empty() {
+                writeln!(f, "Nothing going on :)")?;
+            }
+            for item in &day.items {
+                match item.end {
+                    Some(end) => writeln!(
+                        f,
+                        "{} - {} :: {}",
+                        item.start.format(time_format),
+                        end.format(time_format),
+                        item.title
+                    )?,
+                    None => writeln!(
+                        f,
+                        "{} :: {} is due",
+                        item.start.format(time_format),
+                        item.title
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! `sparrow archive`. Done tasks and past events that don't repeat are moved out of the data file
+//! into an archive next to it (`~/.sparrow.archive` for `~/.sparrow`), so the data file stays
+//! small and lists and prompts stay uncluttered. The archive is YAML, whatever the data file is
+//! written in.
+
+use crate::{CalendarEvent, Repeat, SparrowResult, Task, UserData};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct Archive {
+    pub tasks: Vec<Task>,
+    pub events: Vec<CalendarEvent>,
+}
+
+impl Archive {
+    /// Returns the archive, which is empty if it hasn't been made yet.
+    pub fn load(path: &Path) -> SparrowResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> SparrowResult<()> {
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_yaml::to_string(self)?)?;
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+}
+
+/// How much `archive` moved.
+pub struct ArchiveSummary {
+    pub tasks: usize,
+    pub events: usize,
+}
+
+impl Display for ArchiveSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Archived {} task{} and {} event{}",
+            self.tasks,
+            if self.tasks == 1 { "" } else { "s" },
+            self.events,
+            if self.events == 1 { "" } else { "s" },
+        )
+    }
+}
+
+/// Returns where the archive for the data file at `data_path` is kept.
+pub fn path_for(data_path: &Path) -> PathBuf {
+    let mut name = data_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| ".sparrow".into());
+    name.push(".archive");
+    data_path.with_file_name(name)
+}
+
+/// Moves done tasks and past events that don't repeat from `data` into the archive at
+/// `archive_path`. The archive is saved right away; `data` still has to be saved.
+pub fn archive(data: &mut UserData, archive_path: &Path) -> SparrowResult<ArchiveSummary> {
+    let now = Local::now();
+    let mut archive = Archive::load(archive_path)?;
+
+    let done: Vec<_> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| t.next_due_date(now).is_none())
+        .map(|t| t.id)
+        .collect();
+    let past: Vec<usize> = data
+        .get_events()
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.repeat == Repeat::No && e.time_span.end() < now)
+        .map(|(i, _)| i)
+        .collect();
+    let summary = ArchiveSummary {
+        tasks: done.len(),
+        events: past.len(),
+    };
+
+    // anything already in the archive (say, after undoing an archive) isn't added twice
+    for id in done {
+        if let Some(task) = data.remove_task(id) {
+            if !archive.tasks.iter().any(|t| t.id == task.id) {
+                archive.tasks.push(task);
+            }
+        }
+    }
+    for i in past.into_iter().rev() {
+        let event = data.remove_event(i);
+        if !archive.events.contains(&event) {
+            archive.events.push(event);
+        }
+    }
+
+    archive.save(archive_path)?;
+    Ok(summary)
+}
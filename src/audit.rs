@@ -0,0 +1,202 @@
+//! Compares what was planned with what actually got done, so estimates can get better over time.
+//! Time tracked with `sparrow start` is the record of time worked, along with work periods marked
+//! done outside of it.
+
+use crate::{
+    estimates::EstimateCorrections,
+    methods::pomodoro::{JobStatus, PomodoroScheduleEntry},
+    metrics::format_minutes,
+    Task, TaskId, UserData,
+};
+use chrono::NaiveDate;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// Planned and finished work over a range of days.
+pub struct Audit {
+    pub days: Vec<DayAudit>,
+    pub tasks: Vec<TaskAudit>,
+
+    /// What estimates are scaled by when scheduling, learned from tracked time.
+    pub corrections: EstimateCorrections,
+}
+
+/// Minutes of work planned for a day, and what became of them.
+pub struct DayAudit {
+    pub date: NaiveDate,
+    pub planned_minutes: u64,
+    pub done_minutes: u64,
+    pub skipped_minutes: u64,
+}
+
+/// How a task's work went, compared to its estimate.
+pub struct TaskAudit {
+    pub name: String,
+    pub estimated_minutes: u64,
+
+    /// Minutes of the task planned during the audited days.
+    pub planned_minutes: u64,
+
+    /// Minutes of the task done during the audited days.
+    pub done_minutes: u64,
+
+    /// Minutes of the task worked ever, tracked or done, to compare against the estimate.
+    pub total_done_minutes: u64,
+
+    pub finished: bool,
+}
+
+impl Audit {
+    /// Audits the work periods that have already started, since the day `since` if given. Future
+    /// work periods don't count, since they can't be done yet.
+    pub fn new(data: &UserData, since: Option<NaiveDate>) -> Self {
+        let now = chrono::Local::now();
+        let mut days: BTreeMap<NaiveDate, DayAudit> = BTreeMap::new();
+        let mut tasks: BTreeMap<TaskId, TaskAudit> = BTreeMap::new();
+        let mut total_done: BTreeMap<TaskId, u64> = BTreeMap::new();
+        for w in data.worked() {
+            *total_done.entry(w.task_id).or_insert(0) += w.span.minutes() as u64;
+        }
+
+        for job in data.all_jobs() {
+            let (task_id, status, span) = match job {
+                PomodoroScheduleEntry::Job {
+                    task_id,
+                    status,
+                    span,
+                    ..
+                } => (*task_id, *status, span),
+                _ => continue,
+            };
+            let minutes = span.minutes() as u64;
+            let date = span.start().date().naive_local();
+            if *span.start() > now || since.is_some_and(|s| date < s) {
+                continue;
+            }
+            let task = match data.get_tasks().iter().find(|t| t.id == task_id) {
+                Some(t) => t,
+                None => continue,
+            };
+
+            let day = days.entry(date).or_insert_with(|| DayAudit::new(date));
+            let task_audit = tasks.entry(task_id).or_insert_with(|| TaskAudit::new(task));
+            day.planned_minutes += minutes;
+            task_audit.planned_minutes += minutes;
+            match status {
+                JobStatus::Done => {
+                    day.done_minutes += minutes;
+                    task_audit.done_minutes += minutes;
+                }
+                JobStatus::Skipped => day.skipped_minutes += minutes,
+                JobStatus::Planned => {}
+            }
+        }
+
+        let mut tasks: Vec<TaskAudit> = tasks
+            .into_iter()
+            .map(|(id, mut t)| {
+                t.total_done_minutes = total_done.get(&id).copied().unwrap_or(0);
+                t
+            })
+            .collect();
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            days: days.into_values().collect(),
+            tasks,
+            corrections: data.estimate_corrections(),
+        }
+    }
+}
+
+impl DayAudit {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            planned_minutes: 0,
+            done_minutes: 0,
+            skipped_minutes: 0,
+        }
+    }
+}
+
+impl TaskAudit {
+    fn new(task: &Task) -> Self {
+        Self {
+            name: task.name.clone(),
+            estimated_minutes: task.duration.total_minutes(),
+            planned_minutes: 0,
+            done_minutes: 0,
+            total_done_minutes: 0,
+            finished: task.done,
+        }
+    }
+
+    /// Returns true if the task took longer than estimated, or if it isn't finished even though
+    /// all of its estimated time has been worked.
+    pub fn overran(&self) -> bool {
+        self.total_done_minutes > self.estimated_minutes
+            || (!self.finished && self.total_done_minutes >= self.estimated_minutes)
+    }
+}
+
+impl Audit {
+    fn write_work(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.days.is_empty() {
+            return writeln!(f, "No work periods were scheduled in that time.");
+        }
+
+        writeln!(f, "By day:")?;
+        for d in &self.days {
+            write!(
+                f,
+                "-\t{}: {} of {} done",
+                d.date.format("%a %b %-d"),
+                format_minutes(d.done_minutes as i64),
+                format_minutes(d.planned_minutes as i64)
+            )?;
+            if d.skipped_minutes > 0 {
+                write!(f, ", {} skipped", format_minutes(d.skipped_minutes as i64))?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "\nBy task:")?;
+        for t in &self.tasks {
+            write!(
+                f,
+                "-\t{}: {} of {} done, {} of {} estimated overall",
+                t.name,
+                format_minutes(t.done_minutes as i64),
+                format_minutes(t.planned_minutes as i64),
+                format_minutes(t.total_done_minutes as i64),
+                format_minutes(t.estimated_minutes as i64)
+            )?;
+            if t.overran() {
+                write!(f, " (overran)")?;
+            }
+            writeln!(f)?;
+        }
+
+        let overran = self.tasks.iter().filter(|t| t.overran()).count();
+        if overran > 0 {
+            writeln!(
+                f,
+                "\n{} of {} tasks took longer than estimated.",
+                overran,
+                self.tasks.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Audit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_work(f)?;
+        writeln!(f, "\nEstimates:")?;
+        write!(f, "{}", self.corrections)
+    }
+}
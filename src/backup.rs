@@ -0,0 +1,162 @@
+//! Copies of the data file, taken just before it's written, so that a bad write (or a bug) can't
+//! destroy everything in it. Backups of `~/.sparrow` go in `~/.sparrow.bak/`, named after when
+//! they were taken, and only the newest few (`backup_count` in the config) are kept.
+
+use crate::SparrowResult;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Backup names; these sort in the order the backups were taken.
+const NAME_FORMAT: &str = "%Y-%m-%d_%H%M%S%.3f";
+
+pub struct Backup {
+    pub path: PathBuf,
+    pub taken: DateTime<Local>,
+}
+
+/// Returns the directory backups of the data file at `data_path` go in.
+pub fn dir(data_path: &Path) -> PathBuf {
+    let mut name = data_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| ".sparrow".into());
+    name.push(".bak");
+    data_path.with_file_name(name)
+}
+
+/// Copies the data file at `data_path` into its backup directory, then deletes all but the `keep`
+/// newest backups. Does nothing if there's no data file yet, if `keep` is 0, or if the newest
+/// backup is the same as the data file (which is the case when nothing's changed since the last
+/// write).
+pub fn back_up(data_path: &Path, keep: u32) -> SparrowResult<()> {
+    if keep == 0 || !data_path.exists() {
+        return Ok(());
+    }
+
+    let current = fs::read(data_path)?;
+    let backups = list(data_path)?;
+    if let Some(newest) = backups.first() {
+        if fs::read(&newest.path).is_ok_and(|b| b == current) {
+            return Ok(());
+        }
+    }
+
+    let dir = dir(data_path);
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join(Local::now().format(NAME_FORMAT).to_string()),
+        current,
+    )?;
+
+    // the new backup is the newest, so it's not in `backups`
+    for old in backups.iter().skip(keep as usize - 1) {
+        fs::remove_file(&old.path)?;
+    }
+    Ok(())
+}
+
+/// Returns the backups of the data file at `data_path`, newest first.
+pub fn list(data_path: &Path) -> SparrowResult<Vec<Backup>> {
+    let dir = dir(data_path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let taken = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| NaiveDateTime::parse_from_str(n, NAME_FORMAT).ok())
+            .and_then(|t| Local.from_local_datetime(&t).earliest());
+
+        // anything else in the directory isn't one of ours
+        if let Some(taken) = taken {
+            backups.push(Backup { path, taken });
+        }
+    }
+    backups.sort_by_key(|b| Reverse(b.taken));
+    Ok(backups)
+}
+
+/// Replaces the data file at `data_path` with `backup`. The data file is backed up first, so
+/// restoring can be undone by restoring again.
+pub fn restore(data_path: &Path, backup: &Backup, keep: u32) -> SparrowResult<()> {
+    // read it first, since backing up the data file may delete the oldest backups
+    let contents = fs::read(&backup.path)?;
+    back_up(data_path, keep.max(1))?;
+    fs::write(data_path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    /// Returns a data file path in a new, empty directory.
+    fn data_path(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("sparrow-backup-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(".sparrow")
+    }
+
+    /// Writes `contents` to the data file and backs it up. Backups are named by the millisecond,
+    /// so this waits a little first.
+    fn write_and_back_up(path: &Path, contents: &str, keep: u32) {
+        sleep(Duration::from_millis(5));
+        fs::write(path, contents).unwrap();
+        back_up(path, keep).unwrap();
+    }
+
+    fn contents(backups: &[Backup]) -> Vec<String> {
+        backups
+            .iter()
+            .map(|b| fs::read_to_string(&b.path).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_only_the_newest_backups() {
+        let path = data_path("newest");
+        for contents in ["1", "2", "3", "4"] {
+            write_and_back_up(&path, contents, 2);
+        }
+        assert_eq!(contents(&list(&path).unwrap()), vec!["4", "3"]);
+    }
+
+    #[test]
+    fn skips_backups_of_unchanged_data() {
+        let path = data_path("unchanged");
+        write_and_back_up(&path, "1", 5);
+        write_and_back_up(&path, "1", 5);
+        assert_eq!(contents(&list(&path).unwrap()), vec!["1"]);
+    }
+
+    #[test]
+    fn backs_up_nothing_when_turned_off() {
+        let path = data_path("off");
+        write_and_back_up(&path, "1", 0);
+        assert!(list(&path).unwrap().is_empty());
+        assert!(!dir(&path).exists());
+    }
+
+    #[test]
+    fn restoring_can_be_undone() {
+        let path = data_path("restore");
+        write_and_back_up(&path, "1", 5);
+        sleep(Duration::from_millis(5));
+        fs::write(&path, "2").unwrap();
+
+        let backups = list(&path).unwrap();
+        restore(&path, &backups[0], 5).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1");
+        assert_eq!(contents(&list(&path).unwrap()), vec!["2", "1"]);
+    }
+}
@@ -1,16 +1,66 @@
 use ansi_term::{Color, Style};
-use clap::{App, Arg, SubCommand};
+use chrono::{Date, DateTime, Local, NaiveDate, NaiveTime, TimeZone};
+use clap::{App, Arg, ArgMatches, SubCommand, Values};
+use serde::Serialize;
 use sparrow::{
-    methods::ivy_lee::IvyLeeSchedule, prompts::*, CalendarEvent, Formatting, Schedule,
-    SparrowError, Task, UserData,
+    agenda::{self, AgendaDisplay},
+    archive::{self, Archive},
+    audit::Audit,
+    backup,
+    capacity::{self, CapacityDisplay},
+    config,
+    ctl::{self, CtlCommand},
+    dates, digest,
+    doctor::{self, DoctorDisplay},
+    export, feasibility,
+    free::{self, FreeTimeDisplay},
+    habit::HabitsDisplay,
+    import, integrations,
+    list::{EventList, TaskDetail, TaskList, TaskListOptions, TaskSort},
+    methods::{
+        edf::EdfSchedule,
+        eisenhower::EisenhowerSchedule,
+        frog::FrogSchedule,
+        ivy_lee::IvyLeeSchedule,
+        pomodoro::{PomodoroSchedule, PomodoroScheduleEntry},
+        time_block::TimeBlockSchedule,
+    },
+    metrics::format_minutes,
+    output, plan, profile,
+    prompts::*,
+    quick_add::QuickAdd,
+    reflow::{self, MadeUpDisplay},
+    search::{self, Pattern, SearchDisplay},
+    server::ApiServer,
+    shared, sleep,
+    stats::Stats,
+    status::Status,
+    storage::{self, DataFormat},
+    task::{self, TaskDuration},
+    templates::{TaskTemplate, TemplateList},
+    tracking::TimeEntry,
+    tui, undo,
+    when::TaskPlacement,
+    why::{self, WhyDisplay},
+    Bedtime, CalendarEvent, CalendarEventType, Course, Formatting, Habit, Repeat, Schedule,
+    ScheduleMetrics, SparrowError, SparrowResult, Task, TaskId, TimeSpan, UserData,
 };
 use std::convert::TryFrom;
-use std::path::PathBuf;
+use std::fmt::Display;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Once,
+};
+use std::thread;
 
 enum AddType {
     Task,
     Break,
     Event,
+    Course,
+    Habit,
 }
 
 impl TryFrom<&str> for AddType {
@@ -24,6 +74,10 @@ impl TryFrom<&str> for AddType {
             Ok(Self::Break)
         } else if "event".starts_with(&value) {
             Ok(Self::Event)
+        } else if "course".starts_with(&value) {
+            Ok(Self::Course)
+        } else if "habit".starts_with(&value) {
+            Ok(Self::Habit)
         } else {
             Err(SparrowError::BasicMessage(format!(
                 "'{}' isn't something you can add",
@@ -33,9 +87,15 @@ impl TryFrom<&str> for AddType {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
 enum ScheduleType {
     Pomodoro,
     IvyLee,
+    TimeBlock,
+    Eisenhower,
+    Edf,
+    Frog,
+    All,
 }
 
 impl TryFrom<&str> for ScheduleType {
@@ -47,6 +107,16 @@ impl TryFrom<&str> for ScheduleType {
             Ok(Self::IvyLee)
         } else if "pomodoro".starts_with(&value) {
             Ok(Self::Pomodoro)
+        } else if "timeblock".starts_with(&value) || "time_block".starts_with(&value) {
+            Ok(Self::TimeBlock)
+        } else if "eisenhower".starts_with(&value) {
+            Ok(Self::Eisenhower)
+        } else if "edf".starts_with(&value) || "deadline".starts_with(&value) {
+            Ok(Self::Edf)
+        } else if "frog".starts_with(&value) {
+            Ok(Self::Frog)
+        } else if "all".starts_with(&value) {
+            Ok(Self::All)
         } else {
             Err(SparrowError::BasicMessage(format!(
                 "'{}' isn't a supported type of schedule",
@@ -56,6 +126,133 @@ impl TryFrom<&str> for ScheduleType {
     }
 }
 
+enum ListType {
+    Tasks,
+    Events,
+    All,
+}
+
+impl TryFrom<&str> for ListType {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.to_lowercase();
+        if "tasks".starts_with(&value) {
+            Ok(Self::Tasks)
+        } else if "events".starts_with(&value) {
+            Ok(Self::Events)
+        } else if "all".starts_with(&value) {
+            Ok(Self::All)
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "'{}' can't be listed",
+                value
+            )))
+        }
+    }
+}
+
+enum ImportSource {
+    GoogleTasks,
+    MicrosoftTodo,
+    GitHub,
+    GitLab,
+    Ics,
+    Taskwarrior,
+    Csv,
+    Todo,
+}
+
+impl TryFrom<&str> for ImportSource {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "google-tasks" | "gtasks" => Ok(Self::GoogleTasks),
+            "microsoft-todo" | "mstodo" => Ok(Self::MicrosoftTodo),
+            "github" => Ok(Self::GitHub),
+            "gitlab" => Ok(Self::GitLab),
+            "ics" | "ical" | "icalendar" => Ok(Self::Ics),
+            "taskwarrior" | "task" => Ok(Self::Taskwarrior),
+            "csv" => Ok(Self::Csv),
+            "todo" | "md" | "markdown" | "org" => Ok(Self::Todo),
+            _ => Err(SparrowError::BasicMessage(format!(
+                "sparrow can't import from '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+enum ExportFormat {
+    Timewarrior,
+    Remind,
+    Ics,
+    Taskwarrior,
+    Org,
+    Markdown,
+    Csv,
+}
+
+impl TryFrom<&str> for ExportFormat {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "timew" | "timewarrior" => Ok(Self::Timewarrior),
+            "remind" => Ok(Self::Remind),
+            "ics" | "ical" | "icalendar" => Ok(Self::Ics),
+            "taskwarrior" | "task" => Ok(Self::Taskwarrior),
+            "org" => Ok(Self::Org),
+            "md" | "markdown" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            _ => Err(SparrowError::BasicMessage(format!(
+                "sparrow can't export to '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+/// What a CSV import or export is made of.
+enum CsvTable {
+    Tasks,
+}
+
+impl TryFrom<&str> for CsvTable {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.to_lowercase();
+        if !value.is_empty() && "tasks".starts_with(&value) {
+            Ok(Self::Tasks)
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "'{}' can't be imported or exported as CSV; try `tasks`",
+                value
+            )))
+        }
+    }
+}
+
+enum SyncService {
+    GoogleCalendar,
+}
+
+impl TryFrom<&str> for SyncService {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "gcal" | "google-calendar" => Ok(Self::GoogleCalendar),
+            _ => Err(SparrowError::BasicMessage(format!(
+                "sparrow can't sync with '{}'",
+                value
+            ))),
+        }
+    }
+}
+
 fn main() {
     let mut app = App::new("sparrow")
         .version("0.0.0")
@@ -68,23 +265,695 @@ fn main() {
                 .value_name("PATH")
                 .help("Specifies a different data file"),
         )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .value_name("NAME")
+                .conflicts_with("file")
+                .help(
+                    "Uses the profile called NAME, kept in ~/.config/sparrow/NAME.yaml. Can also \
+                     be set with SPARROW_PROFILE",
+                ),
+        )
+        .arg(
+            Arg::with_name("data-format")
+                .long("format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .help(
+                    "What the data file is written in: `yaml`, `json`, `toml`, or `sqlite`. \
+                     Defaults to going by the file's extension",
+                ),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .global(true)
+                .help("Prints output (and scheduling warnings) as JSON, for scripts"),
+        )
         .subcommand(
             SubCommand::with_name("add")
-                .about("Add a new task, event, or break")
-                .arg(Arg::with_name("type").help("Specify which type of time span to add")),
+                .about("Add a new task, event, break, course, or habit")
+                .arg(Arg::with_name("type").help(
+                    "Specify which type of time span to add, or a whole task in one line, like \
+                     \"Finish essay due fri 17:00 ~3h #school !high\"",
+                ))
+                .arg(
+                    Arg::with_name("due")
+                        .long("due")
+                        .takes_value(true)
+                        .value_name("WHEN")
+                        .help("When a new task is due, like \"tomorrow 5pm\" (just a date means the end of that day), instead of being asked"),
+                )
+                .arg(
+                    Arg::with_name("template")
+                        .long("template")
+                        .takes_value(true)
+                        .value_name("TEMPLATE")
+                        .help("Make the new task from a template, asking only for its name and due date"),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .takes_value(true)
+                        .requires("template")
+                        .help("The name of a task made from a template, instead of being asked"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("delete")
+                .about("Remove tasks, events, or breaks")
+                .arg(Arg::with_name("items").multiple(true).help(
+                    "Names or numbers (from the list shown when none are given) of what to remove",
+                ))
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("Don't ask before removing more than one thing"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("done")
+                .about("Check off tasks")
+                .arg(Arg::with_name("tasks").multiple(true).help(
+                    "Names or numbers (from the list shown when none are given) of the tasks",
+                ))
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("Don't ask before checking off more than one task"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel")
+                .about("Cancel one day of a repeating event, like a class that doesn't meet")
+                .arg(Arg::with_name("event").help("Name of the event. Asked for if not given"))
+                .arg(
+                    Arg::with_name("date")
+                        .help("The day it doesn't happen, like \"friday\". Asked for if not given"),
+                ),
         )
-        .subcommand(SubCommand::with_name("delete").about("Remove a task, event, or break"))
         .subcommand(SubCommand::with_name("check").about("Check off tasks past their due date"))
         .subcommand(SubCommand::with_name("set-sleep").about("Set your sleep schedule"))
+        .subcommand(
+            SubCommand::with_name("late-night")
+                .about("Record sleep lost to a late night, so the next morning is kept free")
+                .arg(
+                    Arg::with_name("lost")
+                        .required(true)
+                        .help("How much sleep you lost, like `90` or `1h30m`"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("make")
                 .about("Create your schedule")
-                .arg(Arg::with_name("method").help("`pomodoro` or `ivylee`")),
+                .arg(Arg::with_name("method").help(
+                    "`pomodoro`, `ivylee`, `timeblock`, `eisenhower`, `edf`, `frog`, or `all`",
+                ))
+                .arg(
+                    Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .help("Only schedule tasks with this tag"),
+                )
+                .arg(Arg::with_name("keep-past").long("keep-past").help(
+                    "Keep what's already started in your pomodoro schedule, and only reschedule \
+                     from now on",
+                ))
+                .arg(Arg::with_name("strict").long("strict").help(
+                    "Don't make the schedule if any task can't be finished before it's due",
+                )),
         )
         .subcommand(
             SubCommand::with_name("show")
-                .about("View your schedule")
-                .arg(Arg::with_name("method").help("`pomodoro` or `ivylee`")),
+                .about("View your schedule, or the details of a task")
+                .arg(
+                    Arg::with_name("method")
+                        .help("`pomodoro`, `ivylee`, `timeblock`, `eisenhower`, `edf`, `frog`, `all`, or `task`"),
+                )
+                .arg(Arg::with_name("name").help("The name of the task to show"))
+                .arg(
+                    Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .help("Only show work and events with this tag"),
+                )
+                .arg(
+                    Arg::with_name("all-profiles")
+                        .long("all-profiles")
+                        .help("Show the schedules of every profile, one after another"),
+                )
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .short("w")
+                        .help("Show the pomodoro schedule's next seven days side by side"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List your tasks and events")
+                .arg(
+                    Arg::with_name("what")
+                        .default_value("all")
+                        .help("`tasks`, `events`, or `all`"),
+                )
+                .arg(
+                    Arg::with_name("due-before")
+                        .long("due-before")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .help("Only list tasks due before this date, like \"friday\" or one in your date format"),
+                )
+                .arg(
+                    Arg::with_name("undone")
+                        .long("undone")
+                        .help("Only list tasks that aren't done"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .default_value("due")
+                        .help("Sort tasks by `due`, `name`, `duration`, `weight`, or `priority`"),
+                )
+                .arg(
+                    Arg::with_name("reverse")
+                        .long("reverse")
+                        .short("r")
+                        .help("Reverse the order of tasks"),
+                )
+                .arg(
+                    Arg::with_name("by-course")
+                        .long("by-course")
+                        .help("Group tasks by course"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .help("Only list tasks and events with this tag"),
+                )
+                .arg(
+                    Arg::with_name("archived")
+                        .long("archived")
+                        .help("List what's been archived with `sparrow archive` instead"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("archive").about(
+            "Move done tasks and past events that don't repeat out of your data file, into an \
+             archive next to it",
+        ))
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Import tasks (or events) from another program")
+                .arg(Arg::with_name("source").required(true).help(
+                    "`google-tasks` (Takeout Tasks.json), `mstodo` (Graph API JSON), \
+                         `taskwarrior` (`task export` JSON), `github`, `gitlab`, `ics` (events \
+                         from an iCalendar file), `csv tasks` (see `sparrow export csv`), or \
+                         `todo` (a markdown or org checklist)",
+                ))
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .multiple(true)
+                        .max_values(2)
+                        .value_name("FILE|REPO")
+                        .help(
+                            "The exported file to import, or the repository to import issues \
+                             from. For `csv`, what to import (`tasks`) and then the file",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("milestone")
+                        .long("milestone")
+                        .takes_value(true)
+                        .help("Only import issues in this milestone"),
+                )
+                .arg(
+                    Arg::with_name("label")
+                        .long("label")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Only import issues with this label. Can be given more than once"),
+                )
+                .arg(
+                    Arg::with_name("token")
+                        .long("token")
+                        .takes_value(true)
+                        .help("API token. Defaults to $GITHUB_TOKEN or $GITLAB_TOKEN"),
+                )
+                .arg(
+                    Arg::with_name("gitlab-url")
+                        .long("gitlab-url")
+                        .takes_value(true)
+                        .value_name("URL")
+                        .env("GITLAB_URL")
+                        .default_value("https://gitlab.com")
+                        .help("GitLab instance to import issues from"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about("Bring in upcoming events from another calendar, then remake your schedules")
+                .arg(
+                    Arg::with_name("service")
+                        .required(true)
+                        .help("`gcal` (Google Calendar)"),
+                )
+                .arg(
+                    Arg::with_name("log-out")
+                        .long("log-out")
+                        .help("Forget sparrow's access to the service instead of syncing"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("plan").about(
+            "Plan your week: review overdue and finished tasks, check estimates, choose days \
+                 off, and make new schedules",
+        ))
+        .subcommand(
+            SubCommand::with_name("habits")
+                .about("See how your habits are going")
+                .subcommand(
+                    SubCommand::with_name("done")
+                        .about("Check off a habit, once for every time it's done")
+                        .arg(
+                            Arg::with_name("name")
+                                .required(true)
+                                .help("The habit's name"),
+                        )
+                        .arg(
+                            Arg::with_name("date")
+                                .long("date")
+                                .takes_value(true)
+                                .default_value("today")
+                                .help("The day it was done: `today`, `yesterday`, or a date"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("schedule")
+                        .about("Set aside time for a habit in schedules, or stop with --off")
+                        .arg(
+                            Arg::with_name("name")
+                                .required(true)
+                                .help("The habit's name"),
+                        )
+                        .arg(
+                            Arg::with_name("off")
+                                .long("off")
+                                .help("Only check the habit off, without time set aside for it"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Find tasks, events, and schedule entries by name, notes, or tags")
+                .arg(
+                    Arg::with_name("query")
+                        .required(true)
+                        .help("Text to look for. Case doesn't matter"),
+                )
+                .arg(
+                    Arg::with_name("regex")
+                        .long("regex")
+                        .short("r")
+                        .help("Read the query as a regular expression"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("templates")
+                .about("See your task templates")
+                .subcommand(
+                    SubCommand::with_name("save")
+                        .about("Save a task as a template, for `sparrow add task --template`")
+                        .arg(Arg::with_name("task").help("The task to save. Asked for if not given"))
+                        .arg(
+                            Arg::with_name("name")
+                                .long("name")
+                                .takes_value(true)
+                                .help("What to call the template. The task's name if not given"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("See and change your settings")
+                .subcommand(SubCommand::with_name("list").about("List every setting"))
+                .subcommand(SubCommand::with_name("get").about("Show a setting").arg(
+                    Arg::with_name("key").required(true).help(
+                        "The setting, like `work_minutes`, or `email.smtp_host` for \
+                             settings within settings",
+                    ),
+                ))
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("Change a setting")
+                        .arg(Arg::with_name("key").required(true).help("The setting"))
+                        .arg(Arg::with_name("value").required(true).help(
+                            "The new value. Lists are written like `[Sat, Sun]`, and `none` \
+                             unsets optional settings",
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("audit")
+                .about("Compare the work you planned with the work you did")
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .help("Only look at the last seven days"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Sum up the work you've done, the tasks you've finished, and what's left")
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .help("Only count the last seven days"),
+                )
+                .arg(
+                    Arg::with_name("month")
+                        .long("month")
+                        .conflicts_with("week")
+                        .help("Only count the last thirty days"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("capacity")
+                .about("Compare free time with work already due in the coming weeks")
+                .arg(
+                    Arg::with_name("weeks")
+                        .long("weeks")
+                        .short("w")
+                        .takes_value(true)
+                        .default_value("4")
+                        .help("How many weeks to look at, starting with this one"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("reflow")
+                .about("Spread the work of a missed day over the free time left before it's due")
+                .arg(
+                    Arg::with_name("day")
+                        .long("day")
+                        .takes_value(true)
+                        .help(
+                            "The day that was missed: `yesterday` or a date. Without one, the \
+                             last day none of the work was done",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("free")
+                .about("Show your free time, between events, sleep, and scheduled work")
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .takes_value(true)
+                        .default_value("today")
+                        .help("The first day to look at: `today`, `tomorrow`, or a date"),
+                )
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("How many days to look at"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("agenda")
+                .about("Show events, sleep, scheduled work, and due dates together, day by day")
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .takes_value(true)
+                        .default_value("today")
+                        .help("The first day to look at: `today`, `tomorrow`, or a date"),
+                )
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .short("w")
+                        .help("Show a week, not just the one day"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("when")
+                .about("Show when a task's work is scheduled in your pomodoro schedule")
+                .arg(
+                    Arg::with_name("task")
+                        .help("Name of the task. Asked for if not given"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pin")
+                .about(
+                    "Set aside a time for work on a task. Pomodoro schedules keep it there and fit \
+                     the rest of the task around it",
+                )
+                .arg(
+                    Arg::with_name("task")
+                        .required(true)
+                        .help("Name of the task"),
+                )
+                .arg(
+                    Arg::with_name("start")
+                        .required(true)
+                        .help("When the work starts, like \"tuesday 14:00\""),
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .required(true)
+                        .help("When the work ends. Without a day, it's the day the work starts"),
+                )
+                .arg(
+                    Arg::with_name("subtask")
+                        .long("subtask")
+                        .takes_value(true)
+                        .help("Name of the subtask to work on. Without one, the first unfinished one"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unpin")
+                .about("Let schedules put a task's pinned work anywhere again")
+                .arg(
+                    Arg::with_name("task")
+                        .help("Name of the task. Asked for if not given"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("defer")
+                .about("Keep tasks out of schedules until a certain day, like when they're waiting on something")
+                .arg(
+                    Arg::with_name("tasks")
+                        .required(true)
+                        .multiple(true)
+                        .help("Names or numbers (from the list `sparrow done` shows) of the tasks"),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .takes_value(true)
+                        .required_unless("clear")
+                        .help("When work on the task can start, like \"monday\" or \"2024/05/03 12:00\""),
+                )
+                .arg(
+                    Arg::with_name("clear")
+                        .long("clear")
+                        .conflicts_with("until")
+                        .help("Let the tasks be scheduled any time again"),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("Don't ask before deferring more than one task"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("move")
+                .about(
+                    "Move a work period in your pomodoro schedule to later, reordering the task's \
+                     other work around it",
+                )
+                .arg(Arg::with_name("task").help(
+                    "Name of the task whose next work period moves. Without one, the work period \
+                     going on now, or else the next one",
+                ))
+                .arg(
+                    Arg::with_name("at")
+                        .long("at")
+                        .takes_value(true)
+                        .help("Move the work period going on at this time instead"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .help("When the work period should start. Without one, the next free time"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("skip")
+                .about("Skip a work period in your pomodoro schedule, doing its work in the next free time")
+                .arg(Arg::with_name("task").help(
+                    "Name of the task whose next work period is skipped. Without one, the work \
+                     period going on now, or else the next one",
+                ))
+                .arg(
+                    Arg::with_name("at")
+                        .long("at")
+                        .takes_value(true)
+                        .help("Skip the work period going on at this time instead"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("why").about(
+            "Explain what took the time that work left out of your pomodoro schedule needed",
+        ))
+        .subcommand(
+            SubCommand::with_name("status")
+                .alias("next")
+                .about("Show what's going on now, how long it has left, and what's next, on one line")
+                .arg(
+                    Arg::with_name("waybar")
+                        .long("waybar")
+                        .help("Print JSON for a waybar custom module"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("tui").about(
+            "Manage tasks and events, and watch today's schedule, in a full-screen view",
+        ))
+        .subcommand(SubCommand::with_name("timer").about(
+            "Count down the current work period and its break, marking the period done when it's \
+             over",
+        ))
+        .subcommand(
+            SubCommand::with_name("start")
+                .about("Start timing work on a task, stopping the clock that was running")
+                .arg(Arg::with_name("task").help(
+                    "Name of the task. Without one, the task scheduled now, or else asked for",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("stop").about("Stop timing work, recording the time spent"),
+        )
+        .subcommand(
+            SubCommand::with_name("ctl")
+                .about("Control a running sparrowd")
+                .arg(
+                    Arg::with_name("command")
+                        .required(true)
+                        .help("`status`, `pause`, `resume`, `skip`, or `reschedule`"),
+                )
+                .arg(
+                    Arg::with_name("minutes")
+                        .help("For `pause`, how many minutes to pause for (until `resume` if not given)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("focus")
+                .about("Turn do-not-disturb on or off with the focus mode in your config")
+                .arg(
+                    Arg::with_name("state")
+                        .required(true)
+                        .possible_values(&["on", "off"]),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Print your data in a format another program can import")
+                .arg(Arg::with_name("format").required(true).help(
+                    "`timew` (finished work periods, for `timew import`), `remind` \
+                             (events and scheduled work, for remind(1)), `taskwarrior` (tasks, for \
+                             `task import`), `org` (tasks and the schedule, for org-agenda), `md` \
+                             (a day's plan as a checklist), `csv tasks` (name, due, minutes, done, \
+                             and tags columns, for spreadsheets), or `ics` (the schedule, for \
+                             calendar apps)",
+                ))
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .takes_value(true)
+                        .default_value("today")
+                        .help("The day to export to md: `today`, `tomorrow`, or a date"),
+                )
+                .arg(Arg::with_name("file").multiple(true).max_values(2).help(
+                    "The file to write to. Without one, the export is printed. For `csv`, \
+                         what to export (`tasks`) comes first",
+                ))
+                .arg(
+                    Arg::with_name("method")
+                        .long("method")
+                        .takes_value(true)
+                        .default_value("pomodoro")
+                        .help("Which schedule to export to ics: `pomodoro`, `ivylee`, `timeblock`, `edf`, `frog`, or `all`"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("digest")
+                .about("Summarize today's plan. Run it from cron each morning to get it daily")
+                .arg(
+                    Arg::with_name("email")
+                        .long("email")
+                        .help("Email the summary using the SMTP settings in your config"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about("Check your data file, config, schedules, and sparrowd for problems"),
+        )
+        .subcommand(
+            SubCommand::with_name("migrate")
+                .about("Copy your data to a new data file, converting between formats")
+                .arg(Arg::with_name("to").required(true).value_name("PATH").help(
+                    "The new data file. Its format goes by its extension: .json, .toml, .db \
+                     (SQLite), or anything else for YAML",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("restore")
+                .about("List backups of your data file, or roll back to one")
+                .arg(Arg::with_name("backup").value_name("BACKUP").help(
+                    "The number of the backup to restore, as listed by `sparrow restore`. Your \
+                     data file is backed up before it's replaced",
+                )),
+        )
+        .subcommand(SubCommand::with_name("undo").about(
+            "Undo the last command that changed your data (add, delete, check, make, and so \
+                 on)",
+        ))
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Serve an HTTP API for other programs and devices")
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .short("p")
+                        .takes_value(true)
+                        .default_value("7878")
+                        .help("Port to listen on"),
+                )
+                .arg(
+                    Arg::with_name("bind")
+                        .long("bind")
+                        .takes_value(true)
+                        .value_name("ADDRESS")
+                        .default_value("127.0.0.1")
+                        .help("Address to listen on. Use 0.0.0.0 to allow other devices"),
+                )
+                .arg(
+                    Arg::with_name("token")
+                        .long("token")
+                        .takes_value(true)
+                        .env("SPARROW_TOKEN")
+                        .help("Token that clients must send. Made up for you if not given"),
+                ),
         );
 
     if std::env::args().count() <= 1 {
@@ -93,63 +962,560 @@ fn main() {
     }
 
     let clap_matches = app.get_matches();
+    output::set_json(clap_matches.is_present("json") || output::json_from_env());
 
     let formatting = Formatting {
         prompt: Style::new().bold(),
         prompt_format: Style::new().bold().italic(),
         error: Color::Red.bold(),
+        job: Color::Green.normal(),
+        break_time: Color::Cyan.normal(),
+        calendar: Color::Yellow.normal(),
+        sleep: Color::Blue.normal(),
+        habit: Color::Purple.normal(),
+        current: Style::new().bold().reverse(),
+        later: Style::new().dimmed(),
+    };
+
+    let profile_name = clap_matches
+        .value_of("profile")
+        .map(String::from)
+        .or_else(|| std::env::var(profile::PROFILE_VAR).ok())
+        .filter(|p| !p.is_empty());
+    let data_file_path = match (clap_matches.value_of("file"), profile_name) {
+        (Some(file), _) => PathBuf::from(file),
+        (None, Some(profile_name)) => profile::data_path(&profile_name).unwrap(),
+        (None, None) => storage::default_data_path(),
     };
 
-    let data_file_path = clap_matches
-        .value_of("file")
-        .map(PathBuf::from)
-        .unwrap_or_else(|| dirs::home_dir().unwrap().join(".sparrow"));
+    // the doctor looks at the data file as it is, without loading (and then rewriting) it
+    if clap_matches.subcommand_matches("doctor").is_some() {
+        let checks = doctor::run(&data_file_path);
+        print!("{}", DoctorDisplay { checks: &checks });
+        return;
+    }
+
+    // the server reads and writes the data file on its own, so it must not fall through to the
+    // write at the end of main
+    if let Some(serve_matches) = clap_matches.subcommand_matches("serve") {
+        serve(serve_matches, data_file_path);
+        return;
+    }
+
+    // same for sparrowd, which might change the data file to answer
+    if let Some(ctl_matches) = clap_matches.subcommand_matches("ctl") {
+        let command = match ctl_matches.value_of("minutes") {
+            Some(minutes) => format!("{} {}", ctl_matches.value_of("command").unwrap(), minutes),
+            None => ctl_matches.value_of("command").unwrap().to_string(),
+        };
+        let command = CtlCommand::try_from(command.as_str()).unwrap();
+        match ctl::send(&data_file_path, command) {
+            Ok(answer) if output::is_json() => println!("{}", answer),
+            Ok(answer) => println!("{}", answer["message"].as_str().unwrap_or_default()),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
     // get data
-    let mut data = UserData::from_file(&data_file_path).unwrap();
+    let data_format = match clap_matches.value_of("data-format") {
+        Some(f) => DataFormat::try_from(f).unwrap(),
+        None => DataFormat::from_path(&data_file_path),
+    };
+    let mut data = UserData::load(&data_file_path, data_format).unwrap();
+
+    // restoring replaces the data file, so the loaded data must not be written over it at the end
+    if let Some(restore_matches) = clap_matches.subcommand_matches("restore") {
+        restore(&data, &data_file_path, restore_matches.value_of("backup"));
+        return;
+    }
+    if clap_matches.subcommand_matches("undo").is_some() {
+        match undo::undo(&data_file_path, data.get_config().backup_count).unwrap() {
+            Some(command) => println!("Undid `sparrow {}`", command),
+            None => println!("There's nothing to undo"),
+        }
+        return;
+    }
+
+    // the timer saves the data file itself whenever a period is over, and the schedule could
+    // change while it runs, so the loaded data must not be written at the end either
+    if clap_matches.subcommand_matches("timer").is_some() {
+        run_timer(&data, &data_file_path, data_format);
+        return;
+    }
+
+    // so does the tui, after every change
+    if clap_matches.subcommand_matches("tui").is_some() {
+        tui::run(
+            &data_file_path,
+            data_format,
+            &formatting,
+            |data, pane| match pane {
+                tui::Pane::Events => add(&formatting, data, AddType::Event, None),
+                _ => add(&formatting, data, AddType::Task, None),
+            },
+        )
+        .unwrap();
+        return;
+    }
 
     if let Some(add_matches) = clap_matches.subcommand_matches("add") {
-        let add_type = if let Some(ty_str) = add_matches.value_of("type") {
-            AddType::try_from(ty_str).unwrap()
+        let due = add_matches
+            .value_of("due")
+            .map(|d| parse_due_date(&data, d).unwrap());
+        // anything that isn't a type of thing to add is a task written out in one line
+        let quick = add_matches
+            .value_of("type")
+            .filter(|t| !add_matches.is_present("template") && AddType::try_from(*t).is_err());
+        if let Some(line) = quick {
+            quick_add(&formatting, &mut data, line, due);
         } else {
-            prompt_add_type(&formatting)
-        };
-        add(&formatting, &mut data, add_type)
-    } else if let Some(_delete_matches) = clap_matches.subcommand_matches("delete") {
-        todo!()
+            let add_type = if let Some(ty_str) = add_matches.value_of("type") {
+                AddType::try_from(ty_str).unwrap()
+            } else if due.is_some() || add_matches.is_present("template") {
+                // only tasks are due
+                AddType::Task
+            } else {
+                prompt_add_type(&formatting)
+            };
+            match add_matches.value_of("template") {
+                None => add(&formatting, &mut data, add_type, due),
+                Some(template) if matches!(add_type, AddType::Task) => add_from_template(
+                    &formatting,
+                    &mut data,
+                    template,
+                    add_matches.value_of("name"),
+                    due,
+                ),
+                Some(_) => {
+                    eprintln!("Only tasks can be made from templates.");
+                    std::process::exit(1);
+                }
+            }
+        }
+    } else if let Some(delete_matches) = clap_matches.subcommand_matches("delete") {
+        delete(&formatting, &mut data, delete_matches);
+    } else if let Some(done_matches) = clap_matches.subcommand_matches("done") {
+        check_off(&formatting, &mut data, done_matches);
+    } else if let Some(cancel_matches) = clap_matches.subcommand_matches("cancel") {
+        cancel(&formatting, &mut data, cancel_matches);
     } else if let Some(_check_matches) = clap_matches.subcommand_matches("check") {
         todo!()
-    } else if let Some(_set_sleep_matches) = clap_matches.subcommand_matches("set-sleep") {
-        todo!()
+    } else if clap_matches.subcommand_matches("set-sleep").is_some() {
+        let bedtime = Bedtime::prompt(&formatting, data.get_config(), data.get_bedtime()).unwrap();
+        data.set_bedtime(bedtime);
+        println!("Remake your schedule with `sparrow make` to plan around your new sleep.");
+    } else if let Some(late_night_matches) = clap_matches.subcommand_matches("late-night") {
+        let lost = late_night_matches.value_of("lost").unwrap();
+        let minutes = task::parse_duration_minutes(lost)
+            .ok_or_else(|| SparrowError::BasicMessage(format!("'{}' isn't a duration", lost)))
+            .unwrap();
+        data.add_late_night(sleep::night_of(Local::now()), minutes as u32);
+        println!(
+            "Sleep debt over the last {} days: {}. Remake your schedule with `sparrow make` to \
+             keep the morning free.",
+            sleep::DEBT_DAYS,
+            format_minutes(data.sleep_debt() as i64)
+        );
     } else if let Some(make_matches) = clap_matches.subcommand_matches("make") {
         let schedule_method = if let Some(method_str) = make_matches.value_of("method") {
             ScheduleType::try_from(method_str).unwrap()
         } else {
             prompt_schedule_type(&formatting)
         };
+        let json = output::is_json();
+        let tag = make_matches.value_of("tag");
+        let keep_past = make_matches.is_present("keep-past");
+        if schedule_method != ScheduleType::Eisenhower {
+            check_feasibility(&data, tag, make_matches.is_present("strict"));
+        }
         match schedule_method {
-            ScheduleType::IvyLee => make_ivy_lee_schedule(&mut data),
-            ScheduleType::Pomodoro => make_pomodoro_schedule(&mut data),
+            ScheduleType::IvyLee => make_ivy_lee_schedule(&mut data, json, tag),
+            ScheduleType::Pomodoro => make_pomodoro_schedule(&mut data, json, tag, keep_past),
+            ScheduleType::TimeBlock => make_time_block_schedule(&mut data, json, tag),
+            ScheduleType::Eisenhower => make_eisenhower_schedule(&mut data, json, tag),
+            ScheduleType::Edf => make_edf_schedule(&mut data, json, tag),
+            ScheduleType::Frog => make_frog_schedule(&mut data, json, tag),
+            ScheduleType::All => make_all_schedules(&mut data, json, tag, keep_past),
+        }
+    } else if let Some(list_matches) = clap_matches.subcommand_matches("list") {
+        if list_matches.is_present("archived") {
+            let archive = Archive::load(&archive::path_for(&data_file_path)).unwrap();
+            let archived = UserData::from_archive(data.get_config().clone(), archive);
+            list(&archived, list_matches);
+        } else {
+            list(&data, list_matches);
+        }
+    } else if clap_matches.subcommand_matches("archive").is_some() {
+        let summary = archive::archive(&mut data, &archive::path_for(&data_file_path)).unwrap();
+        println!("{}", summary);
+    } else if let Some(import_matches) = clap_matches.subcommand_matches("import") {
+        let source = ImportSource::try_from(import_matches.value_of("source").unwrap()).unwrap();
+        import(&formatting, &mut data, source, import_matches);
+    } else if let Some(sync_matches) = clap_matches.subcommand_matches("sync") {
+        match SyncService::try_from(sync_matches.value_of("service").unwrap()).unwrap() {
+            SyncService::GoogleCalendar if sync_matches.is_present("log-out") => {
+                integrations::gcal::log_out().unwrap()
+            }
+            SyncService::GoogleCalendar => {
+                let summary = integrations::gcal::sync(&mut data).unwrap();
+                if !output::is_json() {
+                    println!("{}", summary);
+                }
+                make_all_schedules(&mut data, output::is_json(), None, false);
+            }
+        }
+    } else if clap_matches.subcommand_matches("plan").is_some() {
+        plan::review(&formatting, &mut data).unwrap();
+        make_all_schedules(&mut data, false, None, false);
+    } else if let Some(habits_matches) = clap_matches.subcommand_matches("habits") {
+        let today = Local::today().naive_local();
+        let names: Vec<String> = data.get_habits().iter().map(|h| h.name.clone()).collect();
+        if let Some(done_matches) = habits_matches.subcommand_matches("done") {
+            let name = &names[fuzzy_find(done_matches.value_of("name").unwrap(), &names).unwrap()];
+            let day = parse_day(&data, done_matches.value_of("date").unwrap())
+                .unwrap()
+                .naive_local();
+            if !data.get_habit_mut(name).unwrap().mark_done(day) {
+                let day = day.format(&data.get_config().date_format);
+                println!("{} is already done for {}.\n", name, day);
+            }
+        } else if let Some(schedule_matches) = habits_matches.subcommand_matches("schedule") {
+            let name =
+                &names[fuzzy_find(schedule_matches.value_of("name").unwrap(), &names).unwrap()];
+            let scheduled = !schedule_matches.is_present("off");
+            data.get_habit_mut(name).unwrap().scheduled = scheduled;
+            println!(
+                "{} {} get time in schedules. Remake your schedule with `sparrow make` to plan \
+                 around it.\n",
+                name,
+                if scheduled { "will" } else { "won't" }
+            );
+        }
+        print!(
+            "{}",
+            HabitsDisplay {
+                habits: data.get_habits(),
+                today,
+            }
+        );
+    } else if let Some(search_matches) = clap_matches.subcommand_matches("search") {
+        let query = search_matches.value_of("query").unwrap();
+        let pattern = Pattern::new(query, search_matches.is_present("regex")).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        let hits = search::search(&data, &pattern);
+        print_output(&SearchDisplay {
+            query,
+            hits: &hits,
+            config: data.get_config(),
+        });
+    } else if let Some(templates_matches) = clap_matches.subcommand_matches("templates") {
+        if let Some(save_matches) = templates_matches.subcommand_matches("save") {
+            let task = find_task(&formatting, &data, save_matches.value_of("task"));
+            let name = save_matches
+                .value_of("name")
+                .unwrap_or(&task.name)
+                .to_string();
+            let template = TaskTemplate::from_task(name, task);
+            let templates = &mut data.get_config_mut().templates;
+            match templates
+                .iter_mut()
+                .find(|t| t.name.eq_ignore_ascii_case(&template.name))
+            {
+                Some(existing) => {
+                    println!("Replaced the {} template.\n", template.name);
+                    *existing = template;
+                }
+                None => {
+                    println!("Saved the {} template.\n", template.name);
+                    templates.push(template);
+                }
+            }
+        }
+        let templates = &data.get_config().templates;
+        if output::is_json() {
+            println!("{}", serde_json::to_string_pretty(templates).unwrap());
+        } else {
+            print!("{}", TemplateList(templates));
+        }
+    } else if let Some(config_matches) = clap_matches.subcommand_matches("config") {
+        config(&mut data, config_matches);
+    } else if let Some(audit_matches) = clap_matches.subcommand_matches("audit") {
+        let since = if audit_matches.is_present("week") {
+            Some(Local::today().naive_local() - chrono::Duration::days(6))
+        } else {
+            None
+        };
+        print!("{}", Audit::new(&data, since));
+    } else if let Some(stats_matches) = clap_matches.subcommand_matches("stats") {
+        let today = Local::today().naive_local();
+        let since = if stats_matches.is_present("week") {
+            Some(today - chrono::Duration::days(6))
+        } else if stats_matches.is_present("month") {
+            Some(today - chrono::Duration::days(29))
+        } else {
+            None
+        };
+        print_output(&Stats::new(&data, since));
+    } else if let Some(capacity_matches) = clap_matches.subcommand_matches("capacity") {
+        let weeks: u32 = capacity_matches.value_of("weeks").unwrap().parse().unwrap();
+        let weeks = capacity::weekly_capacity(&data, weeks);
+        print!("{}", CapacityDisplay { weeks: &weeks });
+    } else if let Some(reflow_matches) = clap_matches.subcommand_matches("reflow") {
+        reflow_day(&mut data, reflow_matches);
+    } else if let Some(free_matches) = clap_matches.subcommand_matches("free") {
+        let date = parse_day(&data, free_matches.value_of("date").unwrap()).unwrap();
+        let days: u32 = free_matches.value_of("days").unwrap().parse().unwrap();
+        let free = free::free_time(&data, date, days);
+        print_output(&FreeTimeDisplay {
+            free: &free,
+            config: data.get_config(),
+        });
+    } else if let Some(agenda_matches) = clap_matches.subcommand_matches("agenda") {
+        let date = parse_day(&data, agenda_matches.value_of("date").unwrap()).unwrap();
+        let days = if agenda_matches.is_present("week") {
+            7
+        } else {
+            1
+        };
+        let days = agenda::agenda(&data, date, days);
+        print_output(&AgendaDisplay {
+            days: &days,
+            config: data.get_config(),
+        });
+    } else if let Some(when_matches) = clap_matches.subcommand_matches("when") {
+        let task = find_task(&formatting, &data, when_matches.value_of("task"));
+        print_output(&TaskPlacement::new(&data, task));
+    } else if let Some(pin_matches) = clap_matches.subcommand_matches("pin") {
+        pin_task(&formatting, &mut data, pin_matches);
+    } else if let Some(unpin_matches) = clap_matches.subcommand_matches("unpin") {
+        let task = find_task(&formatting, &data, unpin_matches.value_of("task"));
+        let id = task.id;
+        let pins = std::mem::take(&mut data.get_task_mut(id).unwrap().pins);
+        let task = data.get_tasks().iter().find(|t| t.id == id).unwrap();
+        if pins.is_empty() {
+            println!("{} doesn't have any pinned work.", task.name);
+        } else {
+            println!(
+                "Unpinned {}'s work. Remake your schedule with `sparrow make` to fit it in anywhere.",
+                task.name
+            );
+        }
+    } else if let Some(defer_matches) = clap_matches.subcommand_matches("defer") {
+        defer_tasks(&formatting, &mut data, defer_matches);
+    } else if let Some(move_matches) = clap_matches.subcommand_matches("move") {
+        move_job(&mut data, move_matches);
+    } else if let Some(skip_matches) = clap_matches.subcommand_matches("skip") {
+        skip_job(&mut data, skip_matches);
+    } else if clap_matches.subcommand_matches("why").is_some() {
+        if data.get_pomodoro_schedule().is_none() {
+            eprintln!("there's no pomodoro schedule yet. make one with `sparrow make pomodoro`");
+            std::process::exit(1);
         }
+        let explanations = why::explain(&data);
+        print_output(&WhyDisplay {
+            explanations: &explanations,
+            config: data.get_config(),
+        });
+    } else if let Some(status_matches) = clap_matches.subcommand_matches("status") {
+        let status = Status::new(&data, Local::now());
+        if status_matches.is_present("waybar") {
+            println!("{}", serde_json::to_string(&status.waybar()).unwrap());
+        } else if output::is_json() {
+            println!("{}", serde_json::to_string(&status).unwrap());
+        } else {
+            println!("{}", status);
+        }
+    } else if let Some(start_matches) = clap_matches.subcommand_matches("start") {
+        start_tracking(&formatting, &mut data, start_matches.value_of("task"));
+    } else if clap_matches.subcommand_matches("stop").is_some() {
+        stop_tracking(&mut data);
+    } else if let Some(focus_matches) = clap_matches.subcommand_matches("focus") {
+        let focus = focus_matches.value_of("state") == Some("on");
+        match &data.get_config().focus_mode {
+            Some(backend) => backend.set(focus).unwrap(),
+            None => eprintln!(
+                "no focus mode is set up. set `focus_mode` in your config to Gnome, Dunst, Mako, \
+                 or a Custom pair of commands"
+            ),
+        }
+    } else if let Some(export_matches) = clap_matches.subcommand_matches("export") {
+        let format = ExportFormat::try_from(export_matches.value_of("format").unwrap()).unwrap();
+        let mut args = export_matches.values_of("file").into_iter().flatten();
+        let output = match format {
+            ExportFormat::Csv => {
+                let table = args
+                    .next()
+                    .ok_or_else(|| {
+                        SparrowError::BasicMessage(
+                            "say what to export as CSV, like `tasks`".to_string(),
+                        )
+                    })
+                    .unwrap();
+                match CsvTable::try_from(table).unwrap() {
+                    CsvTable::Tasks => export::csv::export_tasks(&data),
+                }
+            }
+            ExportFormat::Timewarrior => export::timewarrior::export(&data).unwrap(),
+            ExportFormat::Remind => export::remind::export(&data),
+            ExportFormat::Taskwarrior => export::taskwarrior::export(&data),
+            ExportFormat::Org => export::org::export(&data),
+            ExportFormat::Markdown => export::markdown::export(
+                &data,
+                parse_day(&data, export_matches.value_of("date").unwrap()).unwrap(),
+            ),
+            ExportFormat::Ics => {
+                match ScheduleType::try_from(export_matches.value_of("method").unwrap()).unwrap() {
+                    ScheduleType::Pomodoro => export::ical::pomodoro_calendar(&data),
+                    ScheduleType::IvyLee => export::ical::ivy_lee_calendar(&data),
+                    ScheduleType::TimeBlock => export::ical::time_block_calendar(&data),
+                    ScheduleType::Edf => export::ical::edf_calendar(&data),
+                    ScheduleType::Frog => export::ical::frog_calendar(&data),
+                    ScheduleType::Eisenhower => {
+                        eprintln!("the eisenhower matrix doesn't plan times, so it can't be exported to a calendar");
+                        std::process::exit(1);
+                    }
+                    ScheduleType::All => export::ical::combined_calendar(&data),
+                }
+            }
+        };
+        match args.next() {
+            Some(path) => std::fs::write(path, output).unwrap(),
+            None => println!("{}", output),
+        }
+    } else if let Some(migrate_matches) = clap_matches.subcommand_matches("migrate") {
+        let to = PathBuf::from(migrate_matches.value_of("to").unwrap());
+        if to.exists() {
+            eprintln!(
+                "{} already exists; move it out of the way first",
+                to.display()
+            );
+            std::process::exit(1);
+        }
+        data.write_to_file(&to).unwrap();
+        match storage::config_path_for(&to) {
+            // copying to the XDG data file makes it the default
+            Some(config_path) => println!(
+                "Copied your data to {}, and your config to {}. sparrow will use them from now on",
+                to.display(),
+                config_path.display()
+            ),
+            None => println!(
+                "Copied your data to {}. Use it with `sparrow -f {}`",
+                to.display(),
+                to.display()
+            ),
+        }
+    } else if let Some(digest_matches) = clap_matches.subcommand_matches("digest") {
+        send_digest(&data, digest_matches.is_present("email"));
+    } else if let Some(show_matches) = clap_matches
+        .subcommand_matches("show")
+        .filter(|m| m.value_of("method") == Some("task"))
+    {
+        show_task(&formatting, &data, show_matches.value_of("name"));
     } else if let Some(show_matches) = clap_matches.subcommand_matches("show") {
         let schedule_method = if let Some(method_str) = show_matches.value_of("method") {
             ScheduleType::try_from(method_str).unwrap()
         } else {
             prompt_schedule_type(&formatting)
         };
-        match schedule_method {
-            ScheduleType::IvyLee => show_ivy_lee_schedule(&data),
-            ScheduleType::Pomodoro => show_pomodoro_schedule(&data),
+        let tag = show_matches.value_of("tag");
+        let week = show_matches.is_present("week");
+        if show_matches.is_present("all-profiles") {
+            show_all_profiles(&formatting, schedule_method, tag, week);
+        } else {
+            show_schedule(&formatting, &data, schedule_method, tag, week);
+        }
+    }
+
+    let command = undoable_command(&clap_matches);
+    if let Some(command) = command {
+        undo::record(&data_file_path, command, data.get_config().backup_count).unwrap();
+    }
+    data.save(&data_file_path, data_format).unwrap();
+    if command.is_some() {
+        undo::forget_if_unchanged(&data_file_path).unwrap();
+    }
+}
+
+/// Returns the name of the subcommand in `matches` if it changes data, so it can be undone.
+fn undoable_command<'a>(matches: &'a ArgMatches) -> Option<&'a str> {
+    match matches.subcommand() {
+        ("habits", Some(habits_matches)) if habits_matches.subcommand_matches("done").is_some() => {
+            Some("habits done")
+        }
+        ("habits", Some(habits_matches))
+            if habits_matches.subcommand_matches("schedule").is_some() =>
+        {
+            Some("habits schedule")
+        }
+        ("templates", Some(templates_matches))
+            if templates_matches.subcommand_matches("save").is_some() =>
+        {
+            Some("templates save")
         }
+        ("config", Some(config_matches)) if config_matches.subcommand_matches("set").is_some() => {
+            Some("config set")
+        }
+        (
+            name @ ("add" | "delete" | "done" | "set-sleep" | "late-night" | "make" | "import"
+            | "sync" | "plan" | "archive" | "start" | "stop" | "pin" | "unpin" | "move"
+            | "skip" | "defer" | "reflow"),
+            _,
+        ) => Some(name),
+        _ => None,
     }
+}
+
+fn config(data: &mut UserData, config_matches: &ArgMatches) {
+    // mistyped settings are common enough to get a plain error instead of a panic
+    let or_exit = |result: SparrowResult<String>| {
+        result.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    };
 
-    data.write_to_file(data_file_path).unwrap();
+    match config_matches.subcommand() {
+        ("get", Some(get_matches)) => {
+            let key = get_matches.value_of("key").unwrap();
+            println!("{}", or_exit(config::get(data.get_config(), key)));
+        }
+        ("set", Some(set_matches)) => {
+            let key = set_matches.value_of("key").unwrap();
+            let value = set_matches.value_of("value").unwrap();
+            or_exit(config::set(data.get_config_mut(), key, value).map(|_| String::new()));
+            println!("{} = {}", key, or_exit(config::get(data.get_config(), key)));
+        }
+        _ => {
+            for (key, value) in config::list(data.get_config()).unwrap() {
+                println!("{} = {}", key, value);
+            }
+        }
+    }
 }
 
-fn add(formatting: &Formatting, data: &mut UserData, add_type: AddType) {
+/// Asks for something new to add. `due` is when a new task is due, if the user already said.
+fn add(
+    formatting: &Formatting,
+    data: &mut UserData,
+    add_type: AddType,
+    due: Option<DateTime<Local>>,
+) {
     match add_type {
         AddType::Task => {
-            let new_task = Task::prompt_new(&formatting, &data.get_config()).unwrap();
+            let mut new_task = Task::prompt_new(&formatting, &data.get_config(), due).unwrap();
+            if !data.get_courses().is_empty() {
+                new_task.course = prompt_task_course(formatting, data);
+            }
+            if data.get_tasks().iter().any(|t| !t.done) {
+                new_task.depends_on = prompt_task_dependencies(formatting, data);
+            }
             data.add_task(new_task);
         }
         AddType::Break => {
@@ -158,80 +1524,1759 @@ fn add(formatting: &Formatting, data: &mut UserData, add_type: AddType) {
         }
         AddType::Event => {
             let new_event = CalendarEvent::prompt_event(formatting, data.get_config()).unwrap();
-            data.add_event(new_event);
+            if confirm_shared_conflicts(formatting, data, &new_event) {
+                data.add_event(new_event);
+            }
+        }
+        AddType::Course => {
+            let new_course = Course::prompt_new(formatting, data.get_config()).unwrap();
+            data.add_course(new_course);
+        }
+        AddType::Habit => {
+            let new_habit = Habit::prompt_new(formatting, data.get_config()).unwrap();
+            data.add_habit(new_habit);
         }
     }
 }
 
-fn prompt_add_type(formatting: &Formatting) -> AddType {
+/// Adds the task written out in `line`, asking for whatever's missing from it. `due` is used if
+/// the line doesn't say when the task is due.
+fn quick_add(
+    formatting: &Formatting,
+    data: &mut UserData,
+    line: &str,
+    due: Option<DateTime<Local>>,
+) {
+    let config = data.get_config();
+    let mut quick = QuickAdd::parse(line, config, Local::now());
+    quick.due_date = quick.due_date.or(due);
+    let task = quick.into_task(formatting, config).unwrap();
+    println!(
+        "Added {}, due {}, {}.\n",
+        task.name,
+        task.due_date
+            .format(&format!("{} {}", config.date_format, config.time_format)),
+        format_minutes(task.duration.total_minutes() as i64)
+    );
+    data.add_task(task);
+}
+
+/// Adds a task made from the template called (or close to) `template`, asking for its name and
+/// due date unless they're given.
+fn add_from_template(
+    formatting: &Formatting,
+    data: &mut UserData,
+    template: &str,
+    name: Option<&str>,
+    due: Option<DateTime<Local>>,
+) {
+    let config = data.get_config();
+    let names: Vec<&str> = config.templates.iter().map(|t| t.name.as_str()).collect();
+    if names.is_empty() {
+        eprintln!(
+            "There aren't any templates yet. Save a task as one with `sparrow templates save`."
+        );
+        std::process::exit(1);
+    }
+    let template = &config.templates[fuzzy_find(template, &names).unwrap()];
+
+    let (name, tags) = match name {
+        Some(name) => split_tags(name),
+        None => split_tags(
+            &prompt(
+                formatting,
+                "What do you want to name this task?",
+                Some("add tags like #school"),
+                Some(&template.name),
+            )
+            .unwrap(),
+        ),
+    };
+    let due_date = match due {
+        Some(due) => due,
+        None => {
+            prompt_datetime(formatting, &config.date_format, &config.time_format, true).unwrap()
+        }
+    };
+
+    let mut new_task = template.instantiate(name, due_date);
+    for tag in tags {
+        if !new_task.has_tag(&tag) {
+            new_task.tags.push(tag);
+        }
+    }
+    data.add_task(new_task);
+}
+
+/// Warns about events in shared calendars that overlap `event`. Returns false if the user decides
+/// not to add it after all.
+fn confirm_shared_conflicts(
+    formatting: &Formatting,
+    data: &UserData,
+    event: &CalendarEvent,
+) -> bool {
+    let config = data.get_config();
+    let shared = shared::load_events(&config.shared_calendars);
+    let conflicts = shared::conflicts(&shared, event);
+    if conflicts.is_empty() {
+        return true;
+    }
+
+    let format = format!("{} {}", config.date_format, config.time_format);
+    println!("This overlaps with:");
+    for (s, span) in conflicts {
+        println!(
+            "-	{}'s {} ({})",
+            s.owner,
+            s.event.name,
+            span.start().format(&format)
+        );
+    }
+
+    prompt_yn(&format!(
+        "{} {}",
+        formatting.prompt.paint("Add it anyway?"),
+        formatting.prompt_format.paint("[y/N]")
+    ))
+    .unwrap()
+    .map(|d| d.is_yes())
+    .unwrap_or(false)
+}
+
+/// Asks which of the user's courses a new task is for.
+fn prompt_task_course(formatting: &Formatting, data: &UserData) -> Option<String> {
+    let course_names: Vec<&str> = data.get_courses().iter().map(|c| c.name.as_str()).collect();
     prompt_strict(
-        &formatting,
-        "What do you want to add?",
-        Some("[T]ask, [b]reak, [e]vent"),
+        formatting,
+        "Which course is it for?",
+        Some(&format!("{}, or empty for none", course_names.join(", "))),
+        None,
         |i| {
             let i = i.trim();
             if i.is_empty() {
-                Ok(AddType::Task)
+                Ok(None)
             } else {
-                AddType::try_from(i).map_err(|_| {
-                    SparrowError::BasicMessage("Enter 'type', 'break', or 'event'".to_string())
-                })
+                fuzzy_find(i, &course_names).map(|n| Some(course_names[n].to_string()))
             }
         },
     )
     .unwrap()
 }
 
-fn prompt_schedule_type(formatting: &Formatting) -> ScheduleType {
+/// Asks which of the user's unfinished tasks a new task has to wait for.
+fn prompt_task_dependencies(formatting: &Formatting, data: &UserData) -> Vec<TaskId> {
+    let unfinished: Vec<&Task> = data.get_tasks().iter().filter(|t| !t.done).collect();
+    let names: Vec<&str> = unfinished.iter().map(|t| t.name.as_str()).collect();
     prompt_strict(
-        &formatting,
-        "What kind of schedule?",
-        Some("[p]omodoro, [i]vylee"),
+        formatting,
+        "Does it have to wait for any other tasks?",
+        Some(&format!(
+            "{}, separated by commas, or empty for none",
+            names.join(", ")
+        )),
+        None,
         |i| {
-            ScheduleType::try_from(i.trim())
-                .map_err(|_| SparrowError::BasicMessage("Enter 'pomodoro' or 'ivylee'".to_string()))
+            i.split(',')
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(|n| fuzzy_find(n, &names).map(|i| unfinished[i].id))
+                .collect()
         },
     )
     .unwrap()
 }
 
-fn make_pomodoro_schedule(data: &mut UserData) {
-    data.set_pomodoro_schedule(
-        Schedule::make(
-            data.get_config(),
-            data.get_tasks(),
-            data.get_events(),
-            data.get_bedtime(),
+/// Something `sparrow delete` can remove.
+#[derive(Clone, Copy, PartialEq)]
+enum DeleteTarget {
+    Task(TaskId),
+
+    /// An event or break, by its index in the user's events.
+    Event(usize),
+}
+
+/// Returns everything that can be deleted, with how it's listed and the name it can be picked by.
+fn deletable_items(data: &UserData) -> Vec<(DeleteTarget, String, &str)> {
+    let config = data.get_config();
+    let format = format!("{} {}", config.date_format, config.time_format);
+
+    let tasks = data.get_tasks().iter().map(|t| {
+        let label = format!("Task: {} (due {})", t.name, t.due_date.format(&format));
+        (DeleteTarget::Task(t.id), label, t.name.as_str())
+    });
+    let events = data.get_events().iter().enumerate().map(|(i, e)| {
+        let start = e.time_span.start().format(&format);
+        let label = match e.event_type {
+            CalendarEventType::Break => format!("Break ({})", start),
+            _ => format!("Event: {} ({})", e.name, start),
+        };
+        (DeleteTarget::Event(i), label, e.name.as_str())
+    });
+
+    tasks.chain(events).collect()
+}
+
+/// Finds the item picked by `selection`, which is either a number from the list or (part of) a
+/// name.
+fn pick_deletable(
+    items: &[(DeleteTarget, String, &str)],
+    selection: &str,
+) -> SparrowResult<DeleteTarget> {
+    let selection = selection.trim();
+    if let Ok(n) = selection.parse::<usize>() {
+        return n
+            .checked_sub(1)
+            .and_then(|i| items.get(i))
+            .map(|(target, _, _)| *target)
+            .ok_or_else(|| SparrowError::BasicMessage(format!("there's no item number {}", n)));
+    }
+
+    let names: Vec<&str> = items.iter().map(|(_, _, name)| *name).collect();
+    let exact = names
+        .iter()
+        .filter(|name| name.eq_ignore_ascii_case(selection))
+        .count();
+    if exact > 1 {
+        return Err(SparrowError::BasicMessage(format!(
+            "more than one item is called '{}'. use its number instead",
+            selection
+        )));
+    }
+    fuzzy_find(selection, &names).map(|i| items[i].0)
+}
+
+/// Finds the items picked by each of `selections` like `pick_deletable`. A selection that doesn't
+/// pick anything is a typo, so it's an error instead of a panic.
+fn pick_all_deletable(
+    items: &[(DeleteTarget, String, &str)],
+    selections: Values,
+) -> Vec<DeleteTarget> {
+    selections
+        .map(|s| pick_deletable(items, s))
+        .collect::<SparrowResult<_>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+}
+
+/// Cancels a day of a repeating event, asking which event and day if they weren't given.
+fn cancel(formatting: &Formatting, data: &mut UserData, cancel_matches: &ArgMatches) {
+    let mut names: Vec<&str> = data
+        .get_events()
+        .iter()
+        .filter(|e| e.event_type == CalendarEventType::Event && e.repeat != Repeat::No)
+        .map(|e| e.name.as_str())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    if names.is_empty() {
+        println!("There are no repeating events to cancel.");
+        return;
+    }
+
+    let name = match cancel_matches.value_of("event") {
+        Some(name) => names[fuzzy_find(name, &names).unwrap()],
+        None => names[prompt_select(formatting, "Which event?", &names, false).unwrap()[0]],
+    }
+    .to_string();
+    let date = match cancel_matches.value_of("date") {
+        Some(date) => parse_day(data, date).unwrap(),
+        None => prompt_strict(
+            formatting,
+            "Which day?",
+            Some("like \"friday\""),
+            None,
+            |i| parse_day(data, i),
         )
         .unwrap(),
+    };
+
+    data.cancel_event_on(&name, date.naive_local()).unwrap();
+    println!(
+        "Cancelled {} on {}. Remake your schedule with `sparrow make` to work then instead.",
+        name,
+        date.format(&data.get_config().date_format)
+    );
+}
+
+/// Pins work on a task to the time between the `start` and `end` arguments, warning about anything
+/// else already planned then.
+fn pin_task(formatting: &Formatting, data: &mut UserData, pin_matches: &ArgMatches) {
+    let task = find_task(formatting, data, pin_matches.value_of("task"));
+    let start = parse_date_time(data, pin_matches.value_of("start").unwrap()).unwrap();
+    let end = pin_matches.value_of("end").unwrap();
+    let end = match dates::parse_time(end, &data.get_config().time_format) {
+        Some(time) => dates::to_local(start.date().naive_local(), Some(time)).unwrap(),
+        None => parse_date_time(data, end).unwrap(),
+    };
+    if end <= start {
+        eprintln!("the work has to end after it starts");
+        std::process::exit(1);
+    }
+
+    let subtask = match (&task.duration, pin_matches.value_of("subtask")) {
+        (TaskDuration::Subtasks(subs), Some(name)) => {
+            let names: Vec<&str> = subs.iter().map(|s| s.name.as_str()).collect();
+            Some(subs[fuzzy_find(name, &names).unwrap()].id)
+        }
+        (TaskDuration::Subtasks(subs), None) => subs
+            .iter()
+            .find(|s| s.duration > 0)
+            .or_else(|| subs.first())
+            .map(|s| s.id),
+        (TaskDuration::Minutes(_), Some(_)) => {
+            eprintln!("{} doesn't have subtasks", task.name);
+            std::process::exit(1);
+        }
+        (TaskDuration::Minutes(_), None) => None,
+    };
+    let span = TimeSpan::between(start, end);
+
+    let mut clashes: Vec<String> = data
+        .all_events()
+        .iter()
+        .filter(|e| e.occurrences(start, end).any(|o| o.overlaps(&span)))
+        .map(|e| e.name.clone())
+        .collect();
+    if data
+        .get_bedtime()
+        .occurrences(start, end)
+        .any(|o| o.overlaps(&span))
+    {
+        clashes.push("sleep".to_string());
+    }
+    for t in data.get_tasks() {
+        if t.pins.iter().any(|p| p.span.overlaps(&span)) {
+            clashes.push(format!("work pinned to {}", t.name));
+        }
+    }
+    if !clashes.is_empty() {
+        output::warn("the pinned work overlaps other plans", &clashes, None);
+    }
+
+    let id = task.id;
+    let name = task.name.clone();
+    data.get_task_mut(id)
+        .unwrap()
+        .pins
+        .push(task::Pin { subtask, span });
+
+    println!(
+        "Pinned {} to {}. Remake your schedule with `sparrow make` to fit the rest around it.",
+        name,
+        describe_span(data, &span)
+    );
+}
+
+/// Keeps the tasks named in `defer_matches` from being scheduled before `--until`, or lets them be
+/// scheduled any time again with `--clear`.
+fn defer_tasks(formatting: &Formatting, data: &mut UserData, defer_matches: &ArgMatches) {
+    let ids = pick_tasks(
+        formatting,
+        data,
+        defer_matches.values_of("tasks"),
+        "Which tasks?",
+    );
+    let until = defer_matches
+        .value_of("until")
+        .map(|until| parse_date_time(data, until).unwrap());
+    let config = data.get_config();
+    let format = format!("{} {}", config.date_format, config.time_format);
+    let tasks: Vec<&Task> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| ids.contains(&t.id))
+        .collect();
+    let names: Vec<String> = tasks.iter().map(|t| t.name.clone()).collect();
+
+    let action = match until {
+        Some(until) => format!("Defer these tasks until {}", until.format(&format)),
+        None => "Let these tasks be scheduled any time".to_string(),
+    };
+    if !confirm_batch(formatting, defer_matches, &action, &names) {
+        return;
+    }
+
+    match until {
+        Some(until) => {
+            let late: Vec<String> = tasks
+                .iter()
+                .filter(|t| until >= t.due_date)
+                .map(|t| format!("{} is due {}", t.name, t.due_date.format(&format)))
+                .collect();
+            if !late.is_empty() {
+                let message = match late.len() {
+                    1 => "the task is due before then",
+                    _ => "the tasks are due before then",
+                };
+                output::warn(message, &late, None);
+            }
+            println!(
+                "Deferred {} until {}. Remake your schedule with `sparrow make` to move {} work.",
+                names.join(", "),
+                until.format(&format),
+                if names.len() == 1 { "its" } else { "their" }
+            );
+        }
+        None => println!(
+            "{} can be scheduled any time now. Remake your schedule with `sparrow make` to fit \
+             {} in.",
+            names.join(", "),
+            if names.len() == 1 { "it" } else { "them" }
+        ),
+    }
+
+    for id in ids {
+        data.get_task_mut(id).unwrap().deferred_until = until;
+    }
+}
+
+/// Checks off the tasks named in `done_matches`.
+fn check_off(formatting: &Formatting, data: &mut UserData, done_matches: &ArgMatches) {
+    let ids = pick_tasks(
+        formatting,
+        data,
+        done_matches.values_of("tasks"),
+        "Which tasks are done?",
     );
-    println!("Done!");
+    let names: Vec<String> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| ids.contains(&t.id))
+        .map(|t| t.name.clone())
+        .collect();
+    if !confirm_batch(formatting, done_matches, "Check off these tasks", &names) {
+        return;
+    }
+
+    let now = Local::now();
+    for id in ids {
+        let task = data.get_task_mut(id).unwrap();
+        if task.done {
+            println!("{} is already done.", task.name);
+        } else {
+            task.mark_done(now);
+            println!("Checked off {}.", task.name);
+        }
+    }
+}
+
+/// Returns the IDs of the tasks picked by `selections`, which are numbers from the list of tasks
+/// (the same numbers `sparrow delete` uses) or (parts of) names. Asks which tasks if there aren't
+/// any selections.
+fn pick_tasks(
+    formatting: &Formatting,
+    data: &UserData,
+    selections: Option<Values>,
+    prompt: &str,
+) -> Vec<TaskId> {
+    let items: Vec<(DeleteTarget, String, &str)> = deletable_items(data)
+        .into_iter()
+        .filter(|(target, _, _)| matches!(target, DeleteTarget::Task(_)))
+        .collect();
+    if items.is_empty() {
+        eprintln!("There aren't any tasks yet. Add one with `sparrow add task`.");
+        std::process::exit(1);
+    }
+
+    let targets: Vec<DeleteTarget> = match selections {
+        Some(selections) => pick_all_deletable(&items, selections),
+        None => {
+            let labels: Vec<&str> = items.iter().map(|(_, label, _)| label.as_str()).collect();
+            prompt_select(formatting, prompt, &labels, true)
+                .unwrap()
+                .into_iter()
+                .map(|i| items[i].0)
+                .collect()
+        }
+    };
+
+    let mut ids = Vec::new();
+    for target in targets {
+        if let DeleteTarget::Task(id) = target {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// Before doing `action` to more than one thing, lists `items` and asks whether to go ahead,
+/// unless `--yes` was given. Returns false if the user backs out.
+fn confirm_batch(
+    formatting: &Formatting,
+    matches: &ArgMatches,
+    action: &str,
+    items: &[String],
+) -> bool {
+    if items.len() < 2 || matches.is_present("yes") {
+        return true;
+    }
+
+    println!("{}:", action);
+    for item in items {
+        println!("-\t{}", item);
+    }
+    let go = prompt_yn(&format!(
+        "{} {}",
+        formatting.prompt.paint("Go ahead?"),
+        formatting.prompt_format.paint("[Y/n]")
+    ))
+    .unwrap()
+    .is_none_or(|d| d.is_yes());
+    if !go {
+        println!("Nothing was changed.");
+    }
+    go
 }
 
-fn make_ivy_lee_schedule(data: &mut UserData) {
-    data.set_ivy_lee_schedule(
-        IvyLeeSchedule::make(
-            data.get_config(),
-            data.get_tasks(),
-            data.get_events(),
-            data.get_bedtime(),
+/// Returns the pomodoro schedule and the index of the work period that the `at` and `task`
+/// arguments pick: the one going on at `at`, or else the next one for the task, or else the one
+/// going on now or next.
+fn pick_job(data: &UserData, matches: &ArgMatches) -> (PomodoroSchedule, usize) {
+    let schedule = match data.get_pomodoro_schedule() {
+        Some(schedule) => schedule.clone(),
+        None => {
+            eprintln!("there's no pomodoro schedule yet. make one with `sparrow make pomodoro`");
+            std::process::exit(1);
+        }
+    };
+
+    let index = match (matches.value_of("at"), matches.value_of("task")) {
+        (Some(at), _) => {
+            // breaks from events can overlap work, so look for the work itself
+            let at = parse_date_time(data, at).unwrap();
+            schedule.get_entries().iter().position(|e| {
+                e.status().is_some() && *e.span().start() <= at && at < e.span().end()
+            })
+        }
+        (None, Some(name)) => {
+            let names: Vec<&str> = data.get_tasks().iter().map(|t| t.name.as_str()).collect();
+            let task = &data.get_tasks()[fuzzy_find(name, &names).unwrap()];
+            schedule.next_job_index(Some(task.id), Local::now())
+        }
+        (None, None) => schedule.next_job_index(None, Local::now()),
+    };
+    match index {
+        Some(index) => (schedule, index),
+        None => {
+            eprintln!("there's no work period like that left in the schedule");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn move_job(data: &mut UserData, move_matches: &ArgMatches) {
+    let (mut schedule, index) = pick_job(data, move_matches);
+    let to = move_matches
+        .value_of("to")
+        .map(|to| parse_date_time(data, to).unwrap());
+    if to.is_some_and(|to| to < Local::now()) {
+        eprintln!("work periods can't be moved into the past");
+        std::process::exit(1);
+    }
+
+    let title = schedule.get_entries()[index]
+        .title(data.get_tasks())
+        .into_owned();
+    let span = schedule
+        .move_job(data.get_config(), index, to)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    data.set_pomodoro_schedule(schedule);
+    println!("Moved {} to {}", title, describe_span(data, &span));
+    warn_if_late(data, &title, &span);
+}
+
+fn skip_job(data: &mut UserData, skip_matches: &ArgMatches) {
+    let (mut schedule, index) = pick_job(data, skip_matches);
+    let title = schedule.get_entries()[index]
+        .title(data.get_tasks())
+        .into_owned();
+    let redone = schedule.skip_job(data.get_config(), index).unwrap();
+    data.set_pomodoro_schedule(schedule);
+    match redone {
+        Some(span) => {
+            println!(
+                "Skipped {}. Its work is now at {}",
+                title,
+                describe_span(data, &span)
+            );
+            warn_if_late(data, &title, &span);
+        }
+        None => println!(
+            "Skipped {}. There's no free time left in the schedule for its work, so remake your \
+             schedule with `sparrow make` to fit it in.",
+            title
+        ),
+    }
+}
+
+/// Warns if the work period at `span` ends after its task is due.
+fn warn_if_late(data: &UserData, title: &str, span: &TimeSpan) {
+    let schedule = data.get_pomodoro_schedule().as_ref().unwrap();
+    let task_id = schedule.get_entries().iter().find_map(|e| match e {
+        PomodoroScheduleEntry::Job {
+            task_id, span: s, ..
+        } if s == span => Some(*task_id),
+        _ => None,
+    });
+    let due_date = task_id
+        .and_then(|id| data.get_tasks().iter().find(|t| t.id == id))
+        .and_then(|t| t.next_due_date(Local::now()));
+    if let Some(due_date) = due_date.filter(|d| *d < span.end()) {
+        let config = data.get_config();
+        let due = format!(
+            "{} is due {}",
+            title,
+            due_date.format(&format!("{} {}", config.date_format, config.time_format))
+        );
+        output::warn("the work is now planned for after it's due", &[due], None);
+    }
+}
+
+/// Formats `span` like "2024/05/03 14:00 - 14:25".
+fn describe_span(data: &UserData, span: &TimeSpan) -> String {
+    let config = data.get_config();
+    format!(
+        "{} - {}",
+        span.start()
+            .format(&format!("{} {}", config.date_format, config.time_format)),
+        span.end().format(&config.time_format)
+    )
+}
+
+/// Starts the clock on the task called `name`. Without a name, it's the task scheduled for now,
+/// or else the one picked from a list.
+fn start_tracking(formatting: &Formatting, data: &mut UserData, name: Option<&str>) {
+    let now = Local::now();
+    let tasks: Vec<&Task> = data.get_tasks().iter().filter(|t| !t.done).collect();
+    let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+    if tasks.is_empty() {
+        println!("There are no tasks to work on.");
+        return;
+    }
+
+    let scheduled = data
+        .get_pomodoro_schedule()
+        .as_ref()
+        .and_then(|s| s.entry_index_at(now).map(|i| &s.get_entries()[i]))
+        .and_then(|e| match e {
+            PomodoroScheduleEntry::Job { task_id, .. } => Some(*task_id),
+            _ => None,
+        });
+    let id = match (name, scheduled) {
+        (Some(name), _) => tasks[fuzzy_find(name, &names).unwrap()].id,
+        (None, Some(id)) => id,
+        (None, None) => {
+            tasks[prompt_select(formatting, "Which task?", &names, false).unwrap()[0]].id
+        }
+    };
+
+    if let Some(stopped) = data.start_tracking(id, now) {
+        print_stopped(data, &stopped);
+    }
+    let task = data.get_tasks().iter().find(|t| t.id == id).unwrap();
+    println!(
+        "Started {} at {}",
+        task.name,
+        now.format(&data.get_config().time_format)
+    );
+}
+
+fn stop_tracking(data: &mut UserData) {
+    match data.stop_tracking(Local::now()).cloned() {
+        Some(stopped) => print_stopped(data, &stopped),
+        None => println!("Nothing is being timed. Start with `sparrow start`."),
+    }
+}
+
+/// Prints how long `entry` ran, and the time tracked on its task so far against the estimate.
+fn print_stopped(data: &UserData, entry: &TimeEntry) {
+    let task = match data.get_tasks().iter().find(|t| t.id == entry.task_id) {
+        Some(task) => task,
+        None => return,
+    };
+    println!(
+        "Stopped {} after {} ({} of {} estimated so far)",
+        task.name,
+        format_minutes(entry.minutes(Local::now())),
+        format_minutes(data.tracked_minutes(task.id)),
+        format_minutes(task.duration.total_minutes() as i64)
+    );
+}
+
+fn delete(formatting: &Formatting, data: &mut UserData, delete_matches: &ArgMatches) {
+    let items = deletable_items(data);
+    if items.is_empty() {
+        println!("There's nothing to delete.");
+        return;
+    }
+
+    let targets: Vec<DeleteTarget> = match delete_matches.values_of("items") {
+        Some(selections) => pick_all_deletable(&items, selections),
+        None => {
+            let labels: Vec<&str> = items.iter().map(|(_, label, _)| label.as_str()).collect();
+            prompt_select(formatting, "What should be deleted?", &labels, true)
+                .unwrap()
+                .into_iter()
+                .map(|i| items[i].0)
+                .collect()
+        }
+    };
+    let labels: Vec<String> = targets
+        .iter()
+        .filter_map(|t| items.iter().find(|(target, _, _)| target == t))
+        .map(|(_, label, _)| label.clone())
+        .collect();
+    if !confirm_batch(formatting, delete_matches, "Delete these", &labels) {
+        return;
+    }
+
+    // remove events from the back, so the indices of the rest stay the same
+    let mut event_indices: Vec<usize> = targets
+        .iter()
+        .filter_map(|t| match t {
+            DeleteTarget::Event(i) => Some(*i),
+            DeleteTarget::Task(_) => None,
+        })
+        .collect();
+    event_indices.sort_unstable();
+    event_indices.dedup();
+
+    for target in &targets {
+        if let DeleteTarget::Task(id) = target {
+            if let Some(task) = data.remove_task(*id) {
+                println!("Deleted task {}", task.name);
+            }
+        }
+    }
+    for i in event_indices.into_iter().rev() {
+        let event = data.remove_event(i);
+        match event.event_type {
+            CalendarEventType::Break => println!("Deleted a break"),
+            _ => println!("Deleted event {}", event.name),
+        }
+    }
+}
+
+fn list(data: &UserData, list_matches: &ArgMatches) {
+    let list_type = ListType::try_from(list_matches.value_of("what").unwrap()).unwrap();
+    let due_before = list_matches
+        .value_of("due-before")
+        .map(|d| parse_date_time(data, d).unwrap());
+    let options = TaskListOptions {
+        due_before,
+        undone: list_matches.is_present("undone"),
+        sort: TaskSort::try_from(list_matches.value_of("sort").unwrap()).unwrap(),
+        reverse: list_matches.is_present("reverse"),
+        by_course: list_matches.is_present("by-course"),
+        tag: list_matches.value_of("tag").map(String::from),
+    };
+    let tag = list_matches.value_of("tag");
+
+    let tasks = TaskList {
+        data,
+        options: &options,
+    };
+    let events = EventList { data, tag };
+    if output::is_json() {
+        let json = match list_type {
+            ListType::Tasks => serde_json::to_value(&tasks),
+            ListType::Events => serde_json::to_value(&events),
+            ListType::All => Ok(serde_json::json!({ "tasks": tasks, "events": events })),
+        };
+        println!("{}", serde_json::to_string_pretty(&json.unwrap()).unwrap());
+        return;
+    }
+
+    match list_type {
+        ListType::Tasks => print!("{}", tasks),
+        ListType::Events => print!("{}", events),
+        ListType::All => {
+            println!("Tasks:");
+            print!("{}", tasks);
+            println!("\nEvents:");
+            print!("{}", events);
+        }
+    }
+}
+
+fn import(
+    formatting: &Formatting,
+    data: &mut UserData,
+    source: ImportSource,
+    import_matches: &ArgMatches,
+) {
+    let mut args = import_matches.values_of("file").unwrap();
+    let target = args.next().unwrap();
+    if let ImportSource::Csv = source {
+        match CsvTable::try_from(target).unwrap() {
+            CsvTable::Tasks => import_csv_tasks(formatting, data, args.next()),
+        }
+        return;
+    }
+    if let ImportSource::Ics = source {
+        let imported = import::ics::parse(&std::fs::read_to_string(target).unwrap()).unwrap();
+        println!("{}", import::add_imported_events(data, imported));
+        return;
+    }
+    if let ImportSource::Todo = source {
+        let text = std::fs::read_to_string(target).unwrap();
+        let mut imported = import::todo::parse(&text, data.get_config(), Local::now());
+        import::todo::fill_in(&mut imported, formatting, data.get_config()).unwrap();
+        println!("{}", import::add_imported_tasks(data, imported, 0));
+        return;
+    }
+
+    let filter = import::IssueFilter {
+        milestone: import_matches.value_of("milestone").map(String::from),
+        labels: import_matches
+            .values_of("label")
+            .map(|v| v.map(String::from).collect())
+            .unwrap_or_default(),
+    };
+    let token = |env_var: &str| {
+        import_matches
+            .value_of("token")
+            .map(String::from)
+            .or_else(|| std::env::var(env_var).ok())
+            .ok_or_else(|| {
+                SparrowError::BasicMessage(format!("give a token with --token or ${}", env_var))
+            })
+            .unwrap()
+    };
+    let estimate_label_prefix = &data.get_config().estimate_label_prefix;
+
+    let imported = match source {
+        ImportSource::GoogleTasks => {
+            import::google_tasks::parse(&std::fs::read_to_string(target).unwrap())
+        }
+        ImportSource::MicrosoftTodo => {
+            import::microsoft_todo::parse(&std::fs::read_to_string(target).unwrap())
+        }
+        ImportSource::Taskwarrior => {
+            import::taskwarrior::parse(&std::fs::read_to_string(target).unwrap())
+        }
+        ImportSource::GitHub => import::github::fetch(
+            target,
+            &token("GITHUB_TOKEN"),
+            &filter,
+            estimate_label_prefix,
+        ),
+        ImportSource::GitLab => import::gitlab::fetch(
+            import_matches.value_of("gitlab-url").unwrap(),
+            target,
+            &token("GITLAB_TOKEN"),
+            &filter,
+            estimate_label_prefix,
+        ),
+        ImportSource::Ics | ImportSource::Csv | ImportSource::Todo => {
+            unreachable!("these are imported above")
+        }
+    }
+    .unwrap();
+
+    // only ask when something actually came without an estimate
+    let default_minutes = if import::needs_default_minutes(&imported) {
+        prompt_strict(
+            formatting,
+            "How long should each imported task without an estimate take?",
+            Some("minutes"),
+            None,
+            |i| i.trim().parse::<u64>(),
         )
-        .unwrap(),
+        .unwrap()
+    } else {
+        0
+    };
+
+    println!(
+        "{}",
+        import::add_imported_tasks(data, imported, default_minutes)
+    );
+}
+
+fn reflow_day(data: &mut UserData, reflow_matches: &ArgMatches) {
+    let now = Local::now();
+    let day = match reflow_matches.value_of("day") {
+        Some(day) => parse_day(data, day).unwrap().naive_local(),
+        None => match reflow::last_missed_day(data, now) {
+            Some(day) => day,
+            None => {
+                println!(
+                    "No missed days in the last {} days. Nothing to make up!",
+                    reflow::LOOKBACK_DAYS
+                );
+                return;
+            }
+        },
+    };
+
+    let made_up = reflow::make_up_day(data, day, now).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if !output::is_json() {
+        println!(
+            "Made up the work missed on {}:",
+            day.format(&data.get_config().date_format)
+        );
+    }
+    print_output(&MadeUpDisplay {
+        made_up: &made_up,
+        data,
+    });
+}
+
+fn import_csv_tasks(formatting: &Formatting, data: &mut UserData, path: Option<&str>) {
+    let path = path
+        .ok_or_else(|| SparrowError::BasicMessage("give the CSV file to import".to_string()))
+        .unwrap();
+    let text = std::fs::read_to_string(path).unwrap();
+
+    let default_minutes = if import::csv::needs_default_minutes(data, &text) {
+        prompt_strict(
+            formatting,
+            "How long should each new task without an estimate take?",
+            Some("minutes"),
+            None,
+            |i| i.trim().parse::<u64>(),
+        )
+        .unwrap()
+    } else {
+        0
+    };
+
+    println!(
+        "{}",
+        import::csv::import_tasks(data, &text, default_minutes).unwrap()
+    );
+}
+
+/// Parses "today", "tomorrow", or a date in the configured format.
+fn parse_day(data: &UserData, value: &str) -> Result<Date<Local>, SparrowError> {
+    let today = Local::today().naive_local();
+    let date = dates::parse_date(value, &data.get_config().date_format, today)
+        .ok_or_else(|| SparrowError::BasicMessage(format!("'{}' isn't a date", value)))?;
+    Local.from_local_date(&date).earliest().ok_or_else(|| {
+        SparrowError::BasicMessage(format!("{} doesn't exist in your timezone", value))
+    })
+}
+
+/// Reads a date and time from a command line argument, like "2024/05/03 17:00" or "friday 5pm".
+/// Without a time, it's midnight.
+fn parse_date_time(data: &UserData, value: &str) -> SparrowResult<DateTime<Local>> {
+    let (date, time) = read_date_time(data, value)?;
+    dates::to_local(date, time)
+}
+
+/// Reads a due date like `parse_date_time`, except that without a time, it's the end of the day.
+fn parse_due_date(data: &UserData, value: &str) -> SparrowResult<DateTime<Local>> {
+    let (date, time) = read_date_time(data, value)?;
+    dates::to_local_due(date, time)
+}
+
+fn read_date_time(data: &UserData, value: &str) -> SparrowResult<(NaiveDate, Option<NaiveTime>)> {
+    let config = data.get_config();
+    dates::parse_date_time(
+        value,
+        &config.date_format,
+        &config.time_format,
+        Local::now(),
+    )
+    .ok_or_else(|| SparrowError::BasicMessage(format!("'{}' isn't a date and time", value)))
+}
+
+fn send_digest(data: &UserData, email: bool) {
+    let today = Local::today();
+    let digest = digest::daily_digest(data, today);
+    if !email {
+        print!("{}", digest);
+        return;
+    }
+
+    let email_config = data
+        .get_config()
+        .email
+        .as_ref()
+        .ok_or_else(|| {
+            SparrowError::BasicMessage(
+                "there are no email settings. add `email` to the config in your data file"
+                    .to_string(),
+            )
+        })
+        .unwrap();
+    let subject = format!(
+        "Your plan for {}",
+        today.format(&data.get_config().date_format)
+    );
+    digest::send_email(email_config, &subject, digest).unwrap();
+}
+
+fn restore(data: &UserData, data_file_path: &Path, which: Option<&str>) {
+    let backups = backup::list(data_file_path).unwrap();
+    let config = data.get_config();
+    let format = format!("{} {}", config.date_format, config.time_format);
+
+    let which = match which {
+        Some(w) => w,
+        None => {
+            if backups.is_empty() {
+                println!("There are no backups of {} yet", data_file_path.display());
+            } else {
+                println!("Backups of {}, newest first:", data_file_path.display());
+                for (i, b) in backups.iter().enumerate() {
+                    println!("{:>4}. {}", i + 1, b.taken.format(&format));
+                }
+                println!("Restore one with `sparrow restore <number>`");
+            }
+            return;
+        }
+    };
+
+    let chosen = which
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| backups.get(i));
+    match chosen {
+        Some(b) => {
+            backup::restore(data_file_path, b, config.backup_count).unwrap();
+            println!(
+                "Restored your data from the backup taken {}",
+                b.taken.format(&format)
+            );
+        }
+        None => {
+            eprintln!(
+                "There's no backup number {}; run `sparrow restore` to list them",
+                which
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn serve(serve_matches: &ArgMatches, data_file_path: PathBuf) {
+    let address = format!(
+        "{}:{}",
+        serve_matches.value_of("bind").unwrap(),
+        serve_matches.value_of("port").unwrap()
+    );
+    let token = match serve_matches.value_of("token") {
+        Some(t) => t.to_string(),
+        None => {
+            let t = match ApiServer::generate_token() {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("couldn't make up a token: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            println!("Your token for this session is {}", t);
+            t
+        }
+    };
+
+    println!("Serving at http://{}", address);
+    ApiServer::new(data_file_path, token).run(&address).unwrap();
+}
+
+fn prompt_add_type(formatting: &Formatting) -> AddType {
+    prompt_strict(
+        &formatting,
+        "What do you want to add?",
+        Some("[T]ask, [b]reak, [e]vent"),
+        None,
+        |i| {
+            let i = i.trim();
+            if i.is_empty() {
+                Ok(AddType::Task)
+            } else {
+                AddType::try_from(i).map_err(|_| {
+                    SparrowError::BasicMessage("Enter 'type', 'break', or 'event'".to_string())
+                })
+            }
+        },
+    )
+    .unwrap()
+}
+
+fn prompt_schedule_type(formatting: &Formatting) -> ScheduleType {
+    prompt_strict(
+        &formatting,
+        "What kind of schedule?",
+        Some("[p]omodoro, [i]vylee, [t]imeblock, [e]isenhower, e[d]f, [f]rog, [a]ll"),
+        None,
+        |i| {
+            ScheduleType::try_from(i.trim()).map_err(|_| {
+                SparrowError::BasicMessage(
+                    "Enter 'pomodoro', 'ivylee', 'timeblock', 'eisenhower', 'edf', 'frog', or 'all'"
+                        .to_string(),
+                )
+            })
+        },
+    )
+    .unwrap()
+}
+
+/// Returns the tasks to schedule: all of them, or only the ones tagged with `tag`.
+fn tasks_to_schedule(data: &UserData, tag: Option<&str>) -> Vec<Task> {
+    data.get_tasks()
+        .iter()
+        .filter(|t| tag.is_none_or(|tag| t.has_tag(tag)))
+        .cloned()
+        .collect()
+}
+
+/// Warns about tasks that there isn't enough free time to finish before they're due. If `strict`,
+/// exits without making a schedule when there are any.
+fn check_feasibility(data: &UserData, tag: Option<&str>, strict: bool) {
+    let tasks = tasks_to_schedule(data, tag);
+    let config = data.scheduling_config();
+    let infeasible: Vec<String> =
+        feasibility::check(&config, &tasks, &data.all_events(), data.get_bedtime())
+            .iter()
+            .filter(|f| !f.is_feasible())
+            .map(|f| f.describe(&config))
+            .collect();
+    if infeasible.is_empty() {
+        return;
+    }
+
+    let note = if strict {
+        "nothing was scheduled. free up time, push back due dates, or cut estimates first"
+    } else {
+        "the schedule is made anyway, but some work won't fit. use --strict to stop instead"
+    };
+    output::warn(
+        "these tasks can't be finished before they're due",
+        &infeasible,
+        Some(note),
     );
-    println!("Done!");
+    if strict {
+        std::process::exit(1);
+    }
+}
+
+fn make_pomodoro_schedule(data: &mut UserData, json: bool, tag: Option<&str>, keep_past: bool) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let schedule = unwrap_schedule(pomodoro_schedule(
+        data,
+        &tasks,
+        &data.all_events(),
+        &cancel,
+        keep_past,
+    ));
+    print_metrics(&schedule.metrics(data.get_config(), &tasks), data, json);
+    data.replace_pomodoro_schedule(schedule);
+}
+
+fn make_ivy_lee_schedule(data: &mut UserData, json: bool, tag: Option<&str>) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let schedule = unwrap_schedule(IvyLeeSchedule::make(
+        data.get_config(),
+        &tasks,
+        &data.all_events(),
+        data.get_bedtime(),
+        &cancel,
+    ));
+    print_metrics(&schedule.metrics(data.get_config(), &tasks), data, json);
+    data.set_ivy_lee_schedule(schedule);
+}
+
+fn make_time_block_schedule(data: &mut UserData, json: bool, tag: Option<&str>) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let schedule = unwrap_schedule(TimeBlockSchedule::make(
+        &data.scheduling_config(),
+        &tasks,
+        &data.all_events(),
+        data.get_bedtime(),
+        &cancel,
+    ));
+    print_metrics(&schedule.metrics(data.get_config(), &tasks), data, json);
+    data.set_time_block_schedule(schedule);
+}
+
+fn make_eisenhower_schedule(data: &mut UserData, json: bool, tag: Option<&str>) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let schedule = unwrap_schedule(EisenhowerSchedule::make(
+        data.get_config(),
+        &tasks,
+        &data.all_events(),
+        data.get_bedtime(),
+        &cancel,
+    ));
+    print_metrics(&schedule.metrics(data.get_config(), &tasks), data, json);
+    data.set_eisenhower_schedule(schedule);
+}
+
+fn make_edf_schedule(data: &mut UserData, json: bool, tag: Option<&str>) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let schedule = unwrap_schedule(EdfSchedule::make(
+        &data.scheduling_config(),
+        &tasks,
+        &data.all_events(),
+        data.get_bedtime(),
+        &cancel,
+    ));
+    print_metrics(&schedule.metrics(data.get_config(), &tasks), data, json);
+    data.set_edf_schedule(schedule);
+}
+
+fn make_frog_schedule(data: &mut UserData, json: bool, tag: Option<&str>) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let schedule = unwrap_schedule(FrogSchedule::make(
+        &data.scheduling_config(),
+        &tasks,
+        &data.all_events(),
+        data.get_bedtime(),
+        &cancel,
+    ));
+    print_metrics(&schedule.metrics(data.get_config(), &tasks), data, json);
+    data.set_frog_schedule(schedule);
+}
+
+/// Makes a pomodoro schedule. If `keep_past` is true, what's already started in the current one
+/// (if there is one) is kept.
+fn pomodoro_schedule(
+    data: &UserData,
+    tasks: &[Task],
+    events: &[CalendarEvent],
+    cancel: &AtomicBool,
+    keep_past: bool,
+) -> SparrowResult<PomodoroSchedule> {
+    let config = data.scheduling_config();
+    match data.get_pomodoro_schedule() {
+        Some(current) if keep_past => {
+            current.remake(&config, tasks, events, data.get_bedtime(), cancel)
+        }
+        _ => PomodoroSchedule::make(&config, tasks, events, data.get_bedtime(), cancel),
+    }
+}
+
+/// Makes every kind of schedule at once. Each method only reads the user's data, so they're made in
+/// parallel.
+fn make_all_schedules(data: &mut UserData, json: bool, tag: Option<&str>, keep_past: bool) {
+    let cancel = cancel_on_ctrl_c();
+    let tasks = tasks_to_schedule(data, tag);
+    let events = data.all_events();
+    let (pomodoro, ivy_lee, time_block, edf, frog) = thread::scope(|scope| {
+        let make_pomodoro =
+            scope.spawn(|| pomodoro_schedule(data, &tasks, &events, &cancel, keep_past));
+        let make_ivy_lee = scope.spawn(|| {
+            IvyLeeSchedule::make(
+                data.get_config(),
+                &tasks,
+                &events,
+                data.get_bedtime(),
+                &cancel,
+            )
+        });
+        let make_time_block = scope.spawn(|| {
+            TimeBlockSchedule::make(
+                &data.scheduling_config(),
+                &tasks,
+                &events,
+                data.get_bedtime(),
+                &cancel,
+            )
+        });
+        let make_edf = scope.spawn(|| {
+            EdfSchedule::make(
+                &data.scheduling_config(),
+                &tasks,
+                &events,
+                data.get_bedtime(),
+                &cancel,
+            )
+        });
+        let make_frog = scope.spawn(|| {
+            FrogSchedule::make(
+                &data.scheduling_config(),
+                &tasks,
+                &events,
+                data.get_bedtime(),
+                &cancel,
+            )
+        });
+        (
+            make_pomodoro.join().unwrap(),
+            make_ivy_lee.join().unwrap(),
+            make_time_block.join().unwrap(),
+            make_edf.join().unwrap(),
+            make_frog.join().unwrap(),
+        )
+    });
+    let pomodoro = unwrap_schedule(pomodoro);
+    let ivy_lee = unwrap_schedule(ivy_lee);
+    let time_block = unwrap_schedule(time_block);
+    let edf = unwrap_schedule(edf);
+    let frog = unwrap_schedule(frog);
+
+    // the matrix only sorts tasks, so it doesn't need a thread of its own
+    let eisenhower = unwrap_schedule(EisenhowerSchedule::make(
+        data.get_config(),
+        &tasks,
+        &events,
+        data.get_bedtime(),
+        &cancel,
+    ));
+
+    let pomodoro_metrics = pomodoro.metrics(data.get_config(), &tasks);
+    let ivy_lee_metrics = ivy_lee.metrics(data.get_config(), &tasks);
+    let time_block_metrics = time_block.metrics(data.get_config(), &tasks);
+    let eisenhower_metrics = eisenhower.metrics(data.get_config(), &tasks);
+    let edf_metrics = edf.metrics(data.get_config(), &tasks);
+    let frog_metrics = frog.metrics(data.get_config(), &tasks);
+    if json {
+        let all_metrics = serde_json::json!({
+            "pomodoro": pomodoro_metrics,
+            "ivy_lee": ivy_lee_metrics,
+            "time_block": time_block_metrics,
+            "eisenhower": eisenhower_metrics,
+            "edf": edf_metrics,
+            "frog": frog_metrics,
+        });
+        println!("{}", serde_json::to_string_pretty(&all_metrics).unwrap());
+    } else {
+        println!("Pomodoro:\n{}", pomodoro_metrics.display(data.get_config()));
+        println!("Ivy Lee:\n{}", ivy_lee_metrics.display(data.get_config()));
+        println!(
+            "Time blocks:\n{}",
+            time_block_metrics.display(data.get_config())
+        );
+        println!(
+            "Eisenhower:\n{}",
+            eisenhower_metrics.display(data.get_config())
+        );
+        println!(
+            "Earliest deadline first:\n{}",
+            edf_metrics.display(data.get_config())
+        );
+        println!("Frog:\n{}", frog_metrics.display(data.get_config()));
+        println!("Done!");
+    }
+
+    data.replace_pomodoro_schedule(pomodoro);
+    data.set_ivy_lee_schedule(ivy_lee);
+    data.set_time_block_schedule(time_block);
+    data.set_eisenhower_schedule(eisenhower);
+    data.set_edf_schedule(edf);
+    data.set_frog_schedule(frog);
 }
 
-fn show_pomodoro_schedule(data: &UserData) {
+fn print_metrics(metrics: &ScheduleMetrics, data: &UserData, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(metrics).unwrap());
+    } else {
+        println!("{}", metrics.display(data.get_config()));
+        println!("Done!");
+    }
+}
+
+/// Set when the user presses Ctrl-C while `ARMED` is.
+static CANCELED: AtomicBool = AtomicBool::new(false);
+
+/// Set while something can be canceled with Ctrl-C. Otherwise, Ctrl-C exits like it normally does.
+static ARMED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C cancel something instead of killing the process, until it's dropped. It
+/// dereferences to the flag that's set when the user presses Ctrl-C.
+struct CancelOnCtrlC;
+
+impl Deref for CancelOnCtrlC {
+    type Target = AtomicBool;
+
+    fn deref(&self) -> &AtomicBool {
+        &CANCELED
+    }
+}
+
+impl Drop for CancelOnCtrlC {
+    fn drop(&mut self) {
+        ARMED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Lets schedule generation (or the timer) stop cleanly when the user presses Ctrl-C, until the
+/// returned guard is dropped. There can only be one Ctrl-C handler, so it's only set the first
+/// time; every call clears the flag.
+fn cancel_on_ctrl_c() -> CancelOnCtrlC {
+    static SET_HANDLER: Once = Once::new();
+    SET_HANDLER.call_once(|| {
+        ctrlc::set_handler(|| {
+            if ARMED.load(Ordering::SeqCst) {
+                CANCELED.store(true, Ordering::SeqCst);
+            } else {
+                // like the default handler, which this one replaced
+                std::process::exit(130);
+            }
+        })
+        .unwrap();
+    });
+    CANCELED.store(false, Ordering::SeqCst);
+    ARMED.store(true, Ordering::SeqCst);
+    CancelOnCtrlC
+}
+
+/// Counts down the current entry in the pomodoro schedule, and then the break after it if the
+/// entry was work. A work period that runs out is marked done in the data file, but one that's
+/// stopped with Ctrl-C is left alone.
+fn run_timer(data: &UserData, data_path: &Path, data_format: DataFormat) {
+    let schedule = match data.get_pomodoro_schedule() {
+        Some(schedule) => schedule,
+        None => {
+            eprintln!("there's no pomodoro schedule yet. make one with `sparrow make pomodoro`");
+            std::process::exit(1);
+        }
+    };
+    let index = match schedule.entry_index_at(Local::now()) {
+        Some(index) => index,
+        None => {
+            println!("{}", Status::new(data, Local::now()));
+            return;
+        }
+    };
+
+    let cancel = cancel_on_ctrl_c();
+    libnotify::init("sparrow").unwrap();
+
+    let entries = &schedule.get_entries()[index..];
+    for (i, entry) in entries.iter().enumerate() {
+        let title = entry.title(data.get_tasks());
+        if !count_down(&title, entry.span().end(), &cancel) {
+            if entry.status().is_some() {
+                println!("\nStopped. {} wasn't marked done", title);
+            } else {
+                println!("\nStopped");
+            }
+            break;
+        }
+
+        // only a work period goes on into its break
+        let next = entries.get(i + 1);
+        let goes_on = entry.kind() == "job" && next.is_some_and(|e| e.kind() == "break");
+        let summary = if entry.kind() == "job" {
+            mark_job_done(data_path, data_format, entry).unwrap();
+            format!("{} is done", title)
+        } else {
+            format!("{} is over", title)
+        };
+        let body = match next {
+            Some(next) if goes_on => format!(
+                "Time for a break until {}",
+                next.span().end().format(&data.get_config().time_format)
+            ),
+            Some(next) => format!(
+                "Next: {} at {}",
+                next.title(data.get_tasks()),
+                next.span().start().format(&data.get_config().time_format)
+            ),
+            None => "Nothing else is scheduled".to_string(),
+        };
+
+        // the bell is for terminals that chime
+        println!("\r\x07{}. {}\x1b[K", summary, body);
+        let _ = libnotify::Notification::new(&summary, body.as_str(), None).show();
+
+        if !goes_on {
+            break;
+        }
+    }
+
+    libnotify::uninit();
+}
+
+/// Shows how long is left until `end` on one line, updating every second. Returns false if the
+/// user stopped the timer before then.
+fn count_down(title: &str, end: chrono::DateTime<Local>, cancel: &AtomicBool) -> bool {
+    use std::io::Write;
+
+    loop {
+        if cancel.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let left = (end - Local::now()).num_seconds();
+        if left <= 0 {
+            return true;
+        }
+        print!("\r{} {:02}:{:02} left\x1b[K", title, left / 60, left % 60);
+        let _ = std::io::stdout().flush();
+        thread::sleep(std::time::Duration::from_millis(250));
+    }
+}
+
+/// Marks `job` done in the data file as it is now, since sparrowd or the schedule could have
+/// changed it while the timer ran.
+fn mark_job_done(
+    data_path: &Path,
+    data_format: DataFormat,
+    job: &PomodoroScheduleEntry,
+) -> SparrowResult<()> {
+    let mut data = UserData::load(data_path, data_format)?;
+    let mut schedule = match data.get_pomodoro_schedule().clone() {
+        Some(schedule) => schedule,
+        None => return Ok(()),
+    };
+    // the schedule might have been remade without this period
+    let index = match schedule
+        .get_entries()
+        .iter()
+        .position(|e| e.kind() == job.kind() && e.span() == job.span())
+    {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+    schedule.set_job_status(index, sparrow::methods::pomodoro::JobStatus::Done)?;
+    data.set_pomodoro_schedule(schedule);
+    data.save(data_path, data_format)
+}
+
+/// Unwraps the result of making a schedule. If generation was canceled, exits without writing
+/// anything to the data file.
+fn unwrap_schedule<S>(result: Result<S, SparrowError>) -> S {
+    match result {
+        Err(SparrowError::ScheduleCanceled) => {
+            eprintln!("schedule generation canceled. your data file wasn't changed");
+            std::process::exit(130);
+        }
+        Err(e @ SparrowError::DependencyCycle(_)) => {
+            eprintln!("{}. fix the tasks' dependencies in your data file", e);
+            std::process::exit(1);
+        }
+        r => r.unwrap(),
+    }
+}
+
+fn show_schedule(
+    formatting: &Formatting,
+    data: &UserData,
+    schedule_method: ScheduleType,
+    tag: Option<&str>,
+    week: bool,
+) {
+    match schedule_method {
+        ScheduleType::IvyLee => show_ivy_lee_schedule(formatting, data, tag),
+        ScheduleType::Pomodoro => show_pomodoro_schedule(formatting, data, tag, week),
+        ScheduleType::TimeBlock => show_time_block_schedule(data, tag),
+        ScheduleType::Eisenhower => show_eisenhower_schedule(data, tag),
+        ScheduleType::Edf => show_edf_schedule(data, tag),
+        ScheduleType::Frog => show_frog_schedule(data, tag),
+        ScheduleType::All => {
+            show_pomodoro_schedule(formatting, data, tag, week);
+            show_ivy_lee_schedule(formatting, data, tag);
+            show_time_block_schedule(data, tag);
+            show_eisenhower_schedule(data, tag);
+            show_edf_schedule(data, tag);
+            show_frog_schedule(data, tag);
+        }
+    }
+}
+
+/// Shows the schedules of the default data file (if there is one) and every profile, under their
+/// names.
+fn show_all_profiles(
+    formatting: &Formatting,
+    schedule_method: ScheduleType,
+    tag: Option<&str>,
+    week: bool,
+) {
+    let mut profiles = profile::list().unwrap();
+    let default_path = storage::default_data_path();
+    if default_path.exists() {
+        profiles.insert(0, ("default".to_string(), default_path));
+    }
+    if profiles.is_empty() {
+        eprintln!("There are no profiles yet. Make one with `sparrow --profile <name> add task`");
+        return;
+    }
+
+    for (i, (name, path)) in profiles.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", Style::new().bold().paint(format!("== {} ==", name)));
+        match UserData::from_file(path) {
+            Ok(data) => show_schedule(formatting, &data, schedule_method, tag, week),
+            Err(e) => eprintln!("couldn't read {}: {}", path.display(), e),
+        }
+    }
+}
+
+fn show_pomodoro_schedule(formatting: &Formatting, data: &UserData, tag: Option<&str>, week: bool) {
     if let Some(pomodoro) = data.get_pomodoro_schedule() {
-        println!("{}", pomodoro.display(data.get_config()))
+        let mut tagged = pomodoro.clone();
+        if let Some(tag) = tag {
+            tagged.retain_entries(|e| is_tagged_entry(data, e, tag));
+        }
+        let (config, tasks) = (data.get_config(), data.get_tasks());
+        if week {
+            print_output(&tagged.week_display(config, tasks, output::terminal_width()));
+        } else {
+            print_output(&tagged.display(config, tasks).styled(formatting));
+        }
+        if output::is_json() {
+            return;
+        }
+        match data.streak() {
+            0 => {}
+            1 => println!("Streak: 1 day"),
+            n => println!("Streak: {} days", n),
+        }
     } else {
         eprintln!("no schedule here! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make pomodoro`")
     }
 }
 
-fn show_ivy_lee_schedule(data: &UserData) {
+fn show_time_block_schedule(data: &UserData, tag: Option<&str>) {
+    if let Some(time_block) = data.get_time_block_schedule() {
+        match tag {
+            Some(tag) => {
+                let mut tagged = time_block.clone();
+                tagged.retain_entries(|e| is_tagged_entry(data, e, tag));
+                print_output(&tagged.display(data.get_config(), data.get_tasks()));
+            }
+            None => print_output(&time_block.display(data.get_config(), data.get_tasks())),
+        }
+    } else {
+        eprintln!("no schedule here! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make timeblock`")
+    }
+}
+
+fn show_edf_schedule(data: &UserData, tag: Option<&str>) {
+    if let Some(edf) = data.get_edf_schedule() {
+        match tag {
+            Some(tag) => {
+                let mut tagged = edf.clone();
+                tagged.retain_entries(|e| is_tagged_entry(data, e, tag));
+                print_output(&tagged.display(data.get_config(), data.get_tasks()));
+            }
+            None => print_output(&edf.display(data.get_config(), data.get_tasks())),
+        }
+    } else {
+        eprintln!("no schedule here! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make edf`")
+    }
+}
+
+fn show_frog_schedule(data: &UserData, tag: Option<&str>) {
+    if let Some(frog) = data.get_frog_schedule() {
+        match tag {
+            Some(tag) => {
+                let mut tagged = frog.clone();
+                tagged.retain_entries(|e| is_tagged_entry(data, e, tag));
+                print_output(&tagged.display(data.get_config(), data.get_tasks()));
+            }
+            None => print_output(&frog.display(data.get_config(), data.get_tasks())),
+        }
+    } else {
+        eprintln!("no schedule here! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make frog`")
+    }
+}
+
+fn show_eisenhower_schedule(data: &UserData, tag: Option<&str>) {
+    if let Some(eisenhower) = data.get_eisenhower_schedule() {
+        match tag {
+            Some(tag) => {
+                let mut tagged = eisenhower.clone();
+                tagged.retain_tasks(|id| {
+                    data.get_tasks()
+                        .iter()
+                        .any(|t| t.id == id && t.has_tag(tag))
+                });
+                print_output(&tagged.display(data.get_config(), data.get_tasks()));
+            }
+            None => print_output(&eisenhower.display(data.get_config(), data.get_tasks())),
+        }
+    } else {
+        eprintln!("no schedule here! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make eisenhower`")
+    }
+}
+
+/// Shows the details of the task named `name`, asking for a name if there isn't one.
+/// Returns the task called `name`, or the one picked from a list if there's no name.
+fn find_task<'a>(formatting: &Formatting, data: &'a UserData, name: Option<&str>) -> &'a Task {
+    let names: Vec<&str> = data.get_tasks().iter().map(|t| t.name.as_str()).collect();
+    let index = match name {
+        Some(name) => fuzzy_find(name, &names).unwrap(),
+        None => prompt_select(formatting, "Which task?", &names, false).unwrap()[0],
+    };
+    &data.get_tasks()[index]
+}
+
+fn show_task(formatting: &Formatting, data: &UserData, name: Option<&str>) {
+    let task = find_task(formatting, data, name);
+
+    if output::is_json() {
+        println!("{}", serde_json::to_string_pretty(task).unwrap());
+    } else {
+        print!("{}", TaskDetail { data, task });
+    }
+}
+
+/// Prints `value` as JSON or as text, depending on the output mode.
+fn print_output<T: Display + Serialize>(value: &T) {
+    if output::is_json() {
+        println!("{}", serde_json::to_string_pretty(value).unwrap());
+    } else {
+        println!("{}", value);
+    }
+}
+
+/// Returns true if `entry` is work on a task tagged with `tag`, or an event tagged with `tag`.
+fn is_tagged_entry(data: &UserData, entry: &PomodoroScheduleEntry, tag: &str) -> bool {
+    match entry {
+        PomodoroScheduleEntry::Job { task_id, .. } => data
+            .get_tasks()
+            .iter()
+            .any(|t| t.id == *task_id && t.has_tag(tag)),
+        PomodoroScheduleEntry::Calendar { event_id, name, .. } => {
+            data.get_events().iter().any(|e| {
+                let same = match event_id {
+                    0 => e.name == *name,
+                    id => e.id == *id,
+                };
+                same && e.has_tag(tag)
+            })
+        }
+        _ => false,
+    }
+}
+
+fn show_ivy_lee_schedule(formatting: &Formatting, data: &UserData, tag: Option<&str>) {
     if let Some(ivy_lee) = data.get_ivy_lee_schedule() {
-        println!("{}", ivy_lee.display(data.get_config()))
+        match tag {
+            Some(tag) => {
+                let mut tagged = ivy_lee.clone();
+                tagged.retain_tasks(|id| {
+                    data.get_tasks()
+                        .iter()
+                        .any(|t| t.id == id && t.has_tag(tag))
+                });
+                print_output(
+                    &tagged
+                        .display(data.get_config(), data.get_tasks())
+                        .styled(formatting),
+                )
+            }
+            None => print_output(
+                &ivy_lee
+                    .display(data.get_config(), data.get_tasks())
+                    .styled(formatting),
+            ),
+        }
     } else {
         eprintln!("no schedule here! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make ivylee`")
     }
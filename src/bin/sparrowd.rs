@@ -4,12 +4,24 @@
 use clap::{App, Arg};
 use notify::Watcher;
 use sparrow::{
-    methods::pomodoro::{PomodoroSchedule, PomodoroScheduleEntry},
-    SparrowError, UserData,
+    ctl::{self, CtlCommand},
+    methods::pomodoro::{JobStatus, PomodoroScheduleEntry},
+    quiet, reflow, sound,
+    status::Status,
+    storage::{self, DataFormat},
+    webhook, SparrowError, SparrowResult, UserData,
 };
+use chrono::{DateTime, Local, NaiveDate};
 use std::{
-    path::PathBuf,
-    sync::{mpsc, Arc, Mutex},
+    collections::HashSet,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, MutexGuard,
+    },
     thread::{self, JoinHandle},
 };
 
@@ -28,59 +40,150 @@ fn main() {
                 .takes_value(true)
                 .value_name("FILE")
                 .help("Specifies a different data file"),
+        )
+        .arg(
+            Arg::with_name("waybar")
+                .long("waybar")
+                .help("Writes the status of the schedule to stdout as JSON lines, for waybar"),
+        )
+        .arg(
+            Arg::with_name("status-fifo")
+                .long("status-fifo")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Writes the status of the schedule as JSON lines to a FIFO made with mkfifo"),
         );
 
     let clap_matches = app.get_matches();
     let data_file_path = clap_matches
         .value_of("file")
         .map(PathBuf::from)
-        .unwrap_or_else(|| dirs::home_dir().unwrap().join(".sparrow"));
+        .unwrap_or_else(storage::default_data_path);
 
     // get data
     let data = UserData::from_file(&data_file_path).unwrap();
-    let schedule_mutex = if let Some(pomodoro) = data.get_pomodoro_schedule() {
-        Arc::new(Mutex::new(pomodoro.clone()))
-    } else {
+    if data.get_pomodoro_schedule().is_none() {
         eprintln!("no pomodoro schedule found! try adding tasks with `sparrow add task` and then making a schedule with `sparrow make`");
         return;
-    };
+    }
+    let data_mutex = Arc::new(Mutex::new(data));
+
+    // start watching! the watcher sets `schedule_changed` whenever the schedule is reloaded (or
+    // remade), so that the current and next entries are found again
+    let schedule_changed = Arc::new(AtomicBool::new(false));
+    watch_file(data_file_path.clone(), data_mutex.clone(), schedule_changed.clone());
+
+    // `sparrow ctl` can pause notifications, so they're only shown while this is off (and it isn't
+    // quiet hours)
+    let paused = Arc::new(Mutex::new(Pause::Off));
+    let listening = listen_for_commands(
+        &data_file_path,
+        data_mutex.clone(),
+        paused.clone(),
+        schedule_changed.clone(),
+    );
+    if let Err(e) = listening {
+        eprintln!("couldn't listen for `sparrow ctl` commands: {}", e);
+    }
 
-    // start watching!
-    watch_file(data_file_path, schedule_mutex.clone());
+    // the buttons on notifications act on the schedule from their own threads
+    let actions = Actions {
+        data_mutex: data_mutex.clone(),
+        path: data_file_path.clone(),
+        snoozed: Arc::new(Mutex::new(None)),
+    };
+    if let Some(fifo) = clap_matches.value_of("status-fifo") {
+        write_status(Some(PathBuf::from(fifo)), data_mutex.clone());
+    } else if clap_matches.is_present("waybar") {
+        write_status(None, data_mutex.clone());
+    }
 
     let mut current_event: Option<PomodoroScheduleEntry> = None;
     let mut next_event: Option<PomodoroScheduleEntry> = None;
     let mut notified_of_current_event = false;
     let mut warned_of_next_event = false;
+
+    // missed days are looked for again whenever the data changes, and at the start of every day
+    let mut checked_missed_on: Option<NaiveDate> = None;
+    let mut offered_make_up: Option<NaiveDate> = None;
     loop {
         let now = chrono::Local::now();
-        if let (Some(current), Some(next)) = (&current_event, &next_event) {
+        if schedule_changed.swap(false, Ordering::SeqCst) {
+            checked_missed_on = None;
+            reassign_current_next_events(&data_mutex, &mut current_event, &mut next_event);
+            notified_of_current_event = false;
+            warned_of_next_event = false;
+            continue;
+        } else if let (Some(current), Some(next)) = (&current_event, &next_event) {
             if (current.span().end() < now || *next.span().start() <= now)
                 || (current_event.is_none() && next_event.is_none())
             {
-                reassign_current_next_events(&schedule_mutex, &mut current_event, &mut next_event);
+                reassign_current_next_events(&data_mutex, &mut current_event, &mut next_event);
                 notified_of_current_event = false;
                 warned_of_next_event = false;
                 continue;
             }
         } else if current_event.is_none() && next_event.is_none() {
-            reassign_current_next_events(&schedule_mutex, &mut current_event, &mut next_event);
+            reassign_current_next_events(&data_mutex, &mut current_event, &mut next_event);
             notified_of_current_event = false;
             warned_of_next_event = false;
             continue;
         }
 
+        let data = lock_data(&data_mutex);
+        let tasks = data.get_tasks();
+
+        // a snoozed entry is announced again once the snooze is over
+        let snoozed = *actions.snoozed.lock().unwrap();
+        if let (Some(current), Some((until, start))) = (&current_event, snoozed) {
+            if now >= until && *current.span().start() == start {
+                *actions.snoozed.lock().unwrap() = None;
+                notified_of_current_event = false;
+            }
+        }
+
+        // a day whose work was all missed is only offered to be made up once
+        let today = now.date().naive_local();
+        if checked_missed_on != Some(today)
+            && !paused.lock().unwrap().is_on(now)
+            && !quiet::is_quiet(&data, now)
+        {
+            if let Some(day) = reflow::last_missed_day(&data, now) {
+                if offered_make_up != Some(day) {
+                    actions.offer_make_up(&data, day);
+                    offered_make_up = Some(day);
+                }
+            }
+            checked_missed_on = Some(today);
+        }
+
         if !notified_of_current_event {
             if let Some(current) = &current_event {
+                announce_current_event(&data, current);
+                let finished = matches!(
+                    current.status(),
+                    Some(JobStatus::Done) | Some(JobStatus::Skipped)
+                );
+                if paused.lock().unwrap().is_on(now) || quiet::is_quiet(&data, now) || finished {
+                    notified_of_current_event = true;
+                    continue;
+                }
+
+                play_alert(&data, current);
                 let summary = "Sparrow notification";
-                let now_text = format!("Now: {}", current.title());
+                let mut now_text = format!("Now: {}", current.title(tasks));
+                if let PomodoroScheduleEntry::Job { .. } = current {
+                    match data.streak() {
+                        0 => {}
+                        1 => now_text.push_str(" (1 day streak)"),
+                        n => now_text.push_str(&format!(" ({} day streak)", n)),
+                    }
+                }
                 if let Some(next) = &next_event {
-                    let _ = libnotify::Notification::new(
-                        &summary,
-                        format!("{}\nNext: {}", now_text, next.title()).as_str(),
-                        None,
-                    )
-                    .show();
+                    now_text = format!("{}\nNext: {}", now_text, next.title(tasks));
+                }
+                if data.get_config().notification_actions && current.status().is_some() {
+                    actions.notify(summary, &now_text, *current.span().start());
                 } else {
                     let _ = libnotify::Notification::new(&summary, now_text.as_str(), None).show();
                 }
@@ -89,7 +192,9 @@ fn main() {
         } else if !warned_of_next_event {
             // using else-if because I don't want a spam of two notifications at the same time, if applicable
             if let Some(next) = &next_event {
-                if now
+                if paused.lock().unwrap().is_on(now) || quiet::is_quiet(&data, now) {
+                    warned_of_next_event = true;
+                } else if now
                     >= *next.span().start()
                         - chrono::Duration::minutes(
                             data.get_config().next_event_warning_minutes as i64,
@@ -98,7 +203,7 @@ fn main() {
                     let minutes_until = (*next.span().start() - now).num_minutes();
                     let _ = libnotify::Notification::new(
                         "Sparrow notification",
-                        format!("In {} minutes: {}", minutes_until, next.title()).as_str(),
+                        format!("In {} minutes: {}", minutes_until, next.title(tasks)).as_str(),
                         None,
                     )
                     .show();
@@ -109,6 +214,7 @@ fn main() {
             }
         }
 
+        drop(data);
         thread::sleep(std::time::Duration::from_secs(30));
     }
 
@@ -118,20 +224,379 @@ fn main() {
     }
 }
 
+fn lock_data(data_mutex: &Mutex<UserData>) -> MutexGuard<'_, UserData> {
+    match data_mutex.lock() {
+        Ok(g) => g,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Tells MQTT and webhooks about the entry that just started, and switches focus mode, if the
+/// user set them up.
+fn announce_current_event(data: &UserData, current: &PomodoroScheduleEntry) {
+    let config = data.get_config();
+    if let Some(backend) = &config.focus_mode {
+        if let Err(e) = backend.set(current.status().is_some()) {
+            eprintln!("couldn't switch focus mode: {}", e);
+        }
+    }
+    if let Some(mqtt_config) = &config.mqtt {
+        let payload = serde_json::to_vec(&current.view(data.get_tasks())).unwrap();
+        if let Err(e) = sparrow::mqtt::publish(mqtt_config, &payload) {
+            eprintln!("couldn't publish the current entry to mqtt: {}", e);
+        }
+    }
+    webhook::entry_started(&config.webhooks, current, data.get_tasks());
+}
+
+/// Plays the user's alert sound, if they chose one, when a work period, break, or event starts.
+fn play_alert(data: &UserData, current: &PomodoroScheduleEntry) {
+    let config = data.get_config();
+    let sound = match &config.alert_sound {
+        Some(sound) if matches!(current.kind(), "job" | "break" | "calendar") => sound.clone(),
+        _ => return,
+    };
+    let command = config.alert_command.clone();
+
+    // players don't return until the sound is over
+    thread::spawn(move || {
+        if let Err(e) = sound::play(&sound, &command) {
+            eprintln!("couldn't play the alert sound: {}", e);
+        }
+    });
+}
+
+/// Congratulates the user if their streak just grew to one of their milestones. Returns the
+/// current streak.
+fn celebrate_streak(data: &UserData, last_streak: u32) -> u32 {
+    let streak = data.streak();
+    if streak > last_streak && data.get_config().streak_milestones.contains(&streak) {
+        let _ = libnotify::Notification::new(
+            &format!("{} day streak!", streak),
+            "You've done every work period for that many days in a row. Keep it up!",
+            None,
+        )
+        .show();
+    }
+    streak
+}
+
+/// Tells webhooks about tasks that are done in `new_data` but weren't in `old_data`.
+fn announce_finished_tasks(old_data: &UserData, new_data: &UserData) {
+    for task in new_data.get_tasks().iter().filter(|t| t.done) {
+        let was_done = old_data
+            .get_tasks()
+            .iter()
+            .any(|t| t.id == task.id && t.done);
+        if !was_done {
+            webhook::task_done(&new_data.get_config().webhooks, task);
+        }
+    }
+}
+
+/// Returns true if tasks or events were added to or removed from `old_data` to get `new_data`.
+fn plan_changed(old_data: &UserData, new_data: &UserData) -> bool {
+    let task_ids = |d: &UserData| d.get_tasks().iter().map(|t| t.id).collect::<HashSet<_>>();
+    task_ids(old_data) != task_ids(new_data) || old_data.get_events() != new_data.get_events()
+}
+
+/// Remakes the pomodoro schedule in `data`, keeping what has already started, and saves it to
+/// `path`.
+fn reschedule(data: &mut UserData, path: &Path) -> SparrowResult<()> {
+    let schedule = match data.get_pomodoro_schedule() {
+        Some(s) => s.remake(
+            &data.scheduling_config(),
+            data.get_tasks(),
+            &data.all_events(),
+            data.get_bedtime(),
+            &AtomicBool::new(false),
+        )?,
+        None => return Ok(()),
+    };
+    data.replace_pomodoro_schedule(schedule);
+    data.save(path, DataFormat::from_path(path))
+}
+
+/// Starts answering `sparrow ctl` commands on the control socket for the data file at `path`
+fn listen_for_commands(
+    path: &Path,
+    data_mutex: Arc<Mutex<UserData>>,
+    paused: Arc<Mutex<Pause>>,
+    schedule_changed: Arc<AtomicBool>,
+) -> SparrowResult<JoinHandle<()>> {
+    let data_path = path.to_path_buf();
+    ctl::listen(path, move |command| match command {
+        CtlCommand::Status => {
+            let data = lock_data(&data_mutex);
+            let status = Status::new(&data, chrono::Local::now());
+            let now = Local::now();
+            let pause = *paused.lock().unwrap();
+            let quiet = quiet::is_quiet(&data, now);
+            let message = match pause {
+                Pause::Until(until) if until > now => format!(
+                    "{} (notifications paused until {})",
+                    status,
+                    until.format(&data.get_config().time_format)
+                ),
+                Pause::Indefinitely => format!("{} (notifications paused)", status),
+                _ if quiet => format!("{} (quiet hours)", status),
+                _ => status.to_string(),
+            };
+            Ok(serde_json::json!({
+                "message": message,
+                "status": status,
+                "paused": pause.is_on(now),
+                "quiet": quiet,
+            }))
+        }
+        CtlCommand::Pause(minutes) => {
+            let (pause, message) = match minutes {
+                Some(minutes) => (
+                    Pause::Until(Local::now() + chrono::Duration::minutes(minutes as i64)),
+                    format!("Notifications paused for {} minutes", minutes),
+                ),
+                None => (Pause::Indefinitely, "Notifications paused".to_string()),
+            };
+            *paused.lock().unwrap() = pause;
+            Ok(serde_json::json!({ "message": message }))
+        }
+        CtlCommand::Resume => {
+            *paused.lock().unwrap() = Pause::Off;
+            Ok(serde_json::json!({ "message": "Notifications resumed" }))
+        }
+        CtlCommand::Skip => {
+            let title = set_current_status(&data_mutex, &data_path, JobStatus::Skipped)?;
+            schedule_changed.store(true, Ordering::SeqCst);
+            Ok(serde_json::json!({ "message": format!("Skipped {}", title) }))
+        }
+        CtlCommand::Reschedule => {
+            let mut data = lock_data(&data_mutex);
+            reschedule(&mut data, &data_path)?;
+            schedule_changed.store(true, Ordering::SeqCst);
+            Ok(serde_json::json!({ "message": "Rebuilt your schedule from now on" }))
+        }
+    })
+}
+
+/// Whether `sparrow ctl pause` paused notifications, and for how long
+#[derive(Clone, Copy)]
+enum Pause {
+    Off,
+    Until(DateTime<Local>),
+    Indefinitely,
+}
+
+impl Pause {
+    fn is_on(self, now: DateTime<Local>) -> bool {
+        match self {
+            Self::Off => false,
+            Self::Until(until) => now < until,
+            Self::Indefinitely => true,
+        }
+    }
+}
+
+/// Marks the work period going on now with `status` and saves the data file at `path`. Returns
+/// the title of the work period
+fn set_current_status(
+    data_mutex: &Mutex<UserData>,
+    path: &Path,
+    status: JobStatus,
+) -> SparrowResult<String> {
+    let mut data = lock_data(data_mutex);
+    let mut schedule = data.get_pomodoro_schedule().clone().ok_or_else(|| {
+        SparrowError::BasicMessage("there's no schedule to change".to_string())
+    })?;
+    let index = schedule.entry_index_at(Local::now()).ok_or_else(|| {
+        SparrowError::BasicMessage("nothing is scheduled right now".to_string())
+    })?;
+    schedule.set_job_status(index, status)?;
+    let title = schedule.get_entries()[index].title(data.get_tasks()).into_owned();
+    data.set_pomodoro_schedule(schedule);
+    data.save(path, DataFormat::from_path(path))?;
+    Ok(title)
+}
+
+/// What the buttons on a notification need to act on the schedule
+#[derive(Clone)]
+struct Actions {
+    data_mutex: Arc<Mutex<UserData>>,
+    path: PathBuf,
+
+    snoozed: Arc<Mutex<Option<Snooze>>>,
+}
+
+/// When a snoozed entry should be announced again, and when that entry starts (to tell it apart
+/// from others)
+type Snooze = (DateTime<Local>, DateTime<Local>);
+
+impl Actions {
+    const SNOOZE_MINUTES: i64 = 5;
+
+    /// Shows a notification with buttons to snooze, skip, or finish the work period starting at
+    /// `start`. libnotify can't wait for buttons to be clicked, so this runs `notify-send --wait`
+    /// on a new thread, and falls back to a plain notification if that doesn't work
+    fn notify(&self, summary: &str, body: &str, start: DateTime<Local>) -> JoinHandle<()> {
+        let actions = self.clone();
+        let (summary, body) = (summary.to_string(), body.to_string());
+        thread::spawn(move || {
+            let output = Command::new("notify-send")
+                .args(["--app-name=sparrowd", "--wait"])
+                .arg(format!("--action=snooze=Snooze {} min", Self::SNOOZE_MINUTES))
+                .args(["--action=skip=Skip", "--action=done=Mark done"])
+                .args([&summary, &body])
+                .output();
+            let action = match output {
+                Ok(o) if o.status.success() => {
+                    String::from_utf8_lossy(&o.stdout).trim().to_string()
+                }
+                _ => {
+                    let _ = libnotify::Notification::new(&summary, body.as_str(), None).show();
+                    return;
+                }
+            };
+
+            let result = match action.as_str() {
+                "snooze" => {
+                    let until = Local::now() + chrono::Duration::minutes(Self::SNOOZE_MINUTES);
+                    *actions.snoozed.lock().unwrap() = Some((until, start));
+                    Ok(())
+                }
+                "skip" => actions.set_status(JobStatus::Skipped),
+                "done" => actions.set_status(JobStatus::Done),
+                // the notification was dismissed or timed out
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                eprintln!("couldn't {} the current work period: {}", action, e);
+            }
+        })
+    }
+
+    /// Shows a notification offering to spread the work missed on `day` over the free time left
+    /// before it's due, with a button to do it. Without `notify-send`, the notification points to
+    /// `sparrow reflow` instead
+    fn offer_make_up(&self, data: &UserData, day: NaiveDate) -> JoinHandle<()> {
+        let actions = self.clone();
+        let summary = "Missed a day?".to_string();
+        let date = day.format(&data.get_config().date_format).to_string();
+        thread::spawn(move || {
+            let body = format!(
+                "None of the work planned for {} was done. Sparrow can fit it into the free time \
+                 left before it's due.",
+                date
+            );
+            let output = Command::new("notify-send")
+                .args(["--app-name=sparrowd", "--wait"])
+                .arg("--action=reflow=Make it up")
+                .args([&summary, &body])
+                .output();
+            let action = match output {
+                Ok(o) if o.status.success() => {
+                    String::from_utf8_lossy(&o.stdout).trim().to_string()
+                }
+                _ => {
+                    let body = format!("{} Run `sparrow reflow` to do that.", body);
+                    let _ = libnotify::Notification::new(&summary, body.as_str(), None).show();
+                    return;
+                }
+            };
+            if action != "reflow" {
+                return;
+            }
+
+            let result = {
+                let mut data = lock_data(&actions.data_mutex);
+                reflow::make_up_day(&mut data, day, Local::now()).and_then(|made_up| {
+                    data.save(&actions.path, DataFormat::from_path(&actions.path))?;
+                    Ok(made_up)
+                })
+            };
+            match result {
+                Ok(made_up) => {
+                    let left_out = made_up.iter().filter(|m| m.now_at.is_none()).count();
+                    let body = match left_out {
+                        0 => format!("All of {}'s work is back in your schedule.", date),
+                        n => format!(
+                            "{} of {} missed work periods didn't fit before they're due. Remake \
+                             your schedule with `sparrow make` to fit them in.",
+                            n,
+                            made_up.len()
+                        ),
+                    };
+                    let _ = libnotify::Notification::new(
+                        "Sparrow made up a missed day",
+                        body.as_str(),
+                        None,
+                    )
+                    .show();
+                }
+                Err(e) => eprintln!("couldn't make up the work missed on {}: {}", date, e),
+            }
+        })
+    }
+
+    fn set_status(&self, status: JobStatus) -> SparrowResult<()> {
+        set_current_status(&self.data_mutex, &self.path, status).map(|_| ())
+    }
+}
+
+/// Starts a new thread which writes the status of the schedule as waybar JSON to `fifo` (or
+/// stdout, if there's no path), a line whenever it changes
+fn write_status(fifo: Option<PathBuf>, data_mutex: Arc<Mutex<UserData>>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut out: Option<Box<dyn Write>> = None;
+        let mut last_line = String::new();
+        loop {
+            // opening a fifo waits until something reads from it
+            if out.is_none() {
+                out = match &fifo {
+                    Some(path) => match OpenOptions::new().write(true).open(path) {
+                        Ok(f) => Some(Box::new(f)),
+                        Err(e) => {
+                            eprintln!("couldn't open {} for status: {}", path.display(), e);
+                            thread::sleep(std::time::Duration::from_secs(30));
+                            continue;
+                        }
+                    },
+                    None => Some(Box::new(io::stdout())),
+                };
+                last_line.clear();
+            }
+
+            let line = {
+                let data = lock_data(&data_mutex);
+                let status = Status::new(&data, chrono::Local::now());
+                serde_json::to_string(&status.waybar()).unwrap()
+            };
+            if line != last_line {
+                let w = out.as_mut().unwrap();
+                if writeln!(w, "{}", line).and_then(|_| w.flush()).is_err() {
+                    // the reader went away, so wait for a new one
+                    out = None;
+                    continue;
+                }
+                last_line = line;
+            }
+            thread::sleep(std::time::Duration::from_secs(5));
+        }
+    })
+}
+
 fn reassign_current_next_events(
-    schedule_mutex: &Arc<Mutex<PomodoroSchedule>>,
+    data_mutex: &Arc<Mutex<UserData>>,
     current_event: &mut Option<PomodoroScheduleEntry>,
     next_event: &mut Option<PomodoroScheduleEntry>,
 ) {
     let now = chrono::Local::now();
 
-    let schedule = match schedule_mutex.lock() {
-        Ok(g) => g,
-        Err(poisoned) => poisoned.into_inner(),
+    let data = lock_data(data_mutex);
+    let entries = match data.get_pomodoro_schedule() {
+        Some(schedule) => schedule.get_entries(),
+        None => &[],
     };
 
-    let mut skipped = schedule
-        .get_entries()
+    let mut skipped = entries
         .iter()
         .cloned()
         .skip_while(|e| e.span().end() <= now);
@@ -140,8 +605,13 @@ fn reassign_current_next_events(
     *next_event = skipped.next();
 }
 
-/// Starts a new thread which reloads the user data if it is changed
-fn watch_file(path: PathBuf, schedule_mutex: Arc<Mutex<PomodoroSchedule>>) -> JoinHandle<()> {
+/// Starts a new thread which reloads the user data if it is changed, remaking the schedule if tasks
+/// or events were added or removed and the user wants that
+fn watch_file(
+    path: PathBuf,
+    data_mutex: Arc<Mutex<UserData>>,
+    schedule_changed: Arc<AtomicBool>,
+) -> JoinHandle<()> {
     use notify::DebouncedEvent::*;
 
     thread::Builder::new()
@@ -149,22 +619,44 @@ fn watch_file(path: PathBuf, schedule_mutex: Arc<Mutex<PomodoroSchedule>>) -> Jo
         .spawn(move || {
             let (tx, rx) = mpsc::channel();
 
+            // the data file is replaced (not modified in place) when it's written, so watch its
+            // directory and only pay attention to events about the data file itself
+            let dir = match path.parent() {
+                Some(d) if !d.as_os_str().is_empty() => d.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            let is_data_file = |p: &PathBuf| p.file_name() == path.file_name();
+            let mut last_streak = lock_data(&data_mutex).streak();
+
             let mut watcher = notify::watcher(tx, std::time::Duration::from_secs(0)).unwrap();
             watcher
-                .watch(path, notify::RecursiveMode::NonRecursive)
+                .watch(dir, notify::RecursiveMode::NonRecursive)
                 .unwrap();
 
             let err: Box<dyn std::error::Error> = loop {
                 match rx.recv() {
                     Ok(result) => match result {
-                        Write(p) | Rename(_, p) => {
-                            let mut schedule = match schedule_mutex.lock() {
-                                Ok(g) => g,
-                                Err(poisoned) => poisoned.into_inner(),
-                            };
-                            *schedule = match UserData::from_file(p) {
-                                Ok(u) => if let Some(pomodoro) = u.get_pomodoro_schedule() {
-                                    pomodoro.clone()
+                        Write(p) | Create(p) | Rename(_, p) if is_data_file(&p) => {
+                            let mut data = lock_data(&data_mutex);
+                            *data = match UserData::from_file(p) {
+                                Ok(mut u) => if u.get_pomodoro_schedule().is_some() {
+                                    announce_finished_tasks(&data, &u);
+                                    last_streak = celebrate_streak(&u, last_streak);
+                                    if u.get_config().auto_reschedule && plan_changed(&data, &u) {
+                                        match reschedule(&mut u, &path) {
+                                            Ok(()) => {
+                                                let _ = libnotify::Notification::new(
+                                                    "Sparrow rebuilt your plan",
+                                                    "Your tasks or events changed, so your schedule was remade from now on.",
+                                                    None,
+                                                )
+                                                .show();
+                                            }
+                                            Err(e) => eprintln!("couldn't remake the schedule: {}", e),
+                                        }
+                                    }
+                                    schedule_changed.store(true, Ordering::SeqCst);
+                                    u
                                 } else {
                                     eprintln!("no schedule anymore. finna quit");
                                     break Box::new(SparrowError::BasicMessage("the schedule in sparrow's data file went missing".to_string()));
@@ -172,7 +664,7 @@ fn watch_file(path: PathBuf, schedule_mutex: Arc<Mutex<PomodoroSchedule>>) -> Jo
                                 Err(e) => break Box::new(e),
                             }
                         },
-                        Remove(_) => break Box::new(SparrowError::BasicMessage("the sparrow data file was deleted".to_string())),
+                        Remove(p) if is_data_file(&p) => break Box::new(SparrowError::BasicMessage("the sparrow data file was deleted".to_string())),
                         Error(e, _) => break Box::new(e),
                         _ => {}
                     },
@@ -0,0 +1,123 @@
+//! A binary copy of the data file, kept in the user's cache directory, so that the YAML doesn't
+//! have to be parsed on every run. The copy is only used while the data file hasn't changed since
+//! it was made, and only by the version of sparrow that made it; otherwise, it's ignored and
+//! rewritten.
+
+use crate::UserData;
+use std::path::Path;
+
+#[cfg(feature = "cache")]
+mod imp {
+    use crate::UserData;
+    use serde::{Deserialize, Serialize};
+    use std::collections::hash_map::DefaultHasher;
+    use std::fs;
+    use std::hash::{Hash, Hasher};
+    use std::io::{BufReader, BufWriter};
+    use std::path::{Path, PathBuf};
+    use std::time::UNIX_EPOCH;
+
+    /// Every cache starts with this. Bump the last byte whenever `UserData` changes how it's
+    /// serialized, so that caches in the old format aren't read. Caches from before there was a
+    /// format at all start with the data file's length, which is never this big.
+    const FORMAT: u64 = 0x7370_6172_726f_7701;
+
+    /// Describes the data file and the version of sparrow a cache was made from. bincode isn't
+    /// self-describing, so a new version of sparrow (which may have changed `UserData`) must not
+    /// read old caches. sparrow and sparrowd share caches, so this can't depend on which program
+    /// is running.
+    #[derive(Deserialize, Serialize, PartialEq)]
+    struct Stamp {
+        version: String,
+        data_len: u64,
+        data_modified_nanos: u128,
+    }
+
+    impl Stamp {
+        fn of(data_path: &Path) -> Option<Self> {
+            let data_meta = fs::metadata(data_path).ok()?;
+            let data_modified = data_meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+            Some(Self {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                data_len: data_meta.len(),
+                data_modified_nanos: data_modified.as_nanos(),
+            })
+        }
+    }
+
+    /// Each data file gets its own cache, named after a hash of the data file's full path.
+    fn cache_path(data_path: &Path) -> Option<PathBuf> {
+        let full_path = fs::canonicalize(data_path).ok()?;
+        let mut hasher = DefaultHasher::new();
+        full_path.hash(&mut hasher);
+        Some(
+            dirs::cache_dir()?
+                .join("sparrow")
+                .join(format!("{:016x}.bin", hasher.finish())),
+        )
+    }
+
+    pub fn load(data_path: &Path) -> Option<UserData> {
+        let stamp = Stamp::of(data_path)?;
+        let mut reader = BufReader::new(fs::File::open(cache_path(data_path)?).ok()?);
+
+        // the format has to be checked first, since an old stamp can't be read as a new one
+        let format: u64 = bincode::deserialize_from(&mut reader).ok()?;
+        if format != FORMAT {
+            return None;
+        }
+        let cached_stamp: Stamp = bincode::deserialize_from(&mut reader).ok()?;
+        if cached_stamp == stamp {
+            bincode::deserialize_from(&mut reader).ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn store(data_path: &Path, data: &UserData) {
+        // the cache is only an optimization, so failing to write it isn't an error
+        let _ = (|| -> Option<()> {
+            let stamp = Stamp::of(data_path)?;
+            let path = cache_path(data_path)?;
+            fs::create_dir_all(path.parent()?).ok()?;
+
+            // sparrow and sparrowd can both be writing the cache at once, so each writes its own
+            // temporary file
+            let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+            let written = (|| -> Option<()> {
+                let mut writer = BufWriter::new(fs::File::create(&temp_path).ok()?);
+                bincode::serialize_into(&mut writer, &FORMAT).ok()?;
+                bincode::serialize_into(&mut writer, &stamp).ok()?;
+                bincode::serialize_into(&mut writer, data).ok()?;
+                drop(writer);
+                fs::rename(&temp_path, path).ok()
+            })();
+            if written.is_none() {
+                let _ = fs::remove_file(&temp_path);
+            }
+            written
+        })();
+    }
+}
+
+/// Returns the cached copy of the data at `data_path`, if there's an up-to-date one.
+pub fn load(data_path: &Path) -> Option<UserData> {
+    #[cfg(feature = "cache")]
+    return imp::load(data_path);
+
+    #[cfg(not(feature = "cache"))]
+    {
+        let _ = data_path;
+        None
+    }
+}
+
+/// Caches `data`, which must be what's currently in the file at `data_path`.
+pub fn store(data_path: &Path, data: &UserData) {
+    #[cfg(feature = "cache")]
+    imp::store(data_path, data);
+
+    #[cfg(not(feature = "cache"))]
+    let _ = (data_path, data);
+}
@@ -0,0 +1,173 @@
+//! Compares the free time in upcoming weeks with the work already committed to them, so that
+//! overbooked weeks show up before more gets added to them.
+
+use crate::{
+    habit::week_start,
+    methods::pomodoro::{JobStatus, PomodoroScheduleEntry},
+    metrics::format_minutes,
+    TaskId, UserData,
+};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+/// Free time and committed work for one week.
+pub struct WeekCapacity {
+    /// The Monday the week starts on.
+    pub start: NaiveDate,
+
+    /// Minutes not taken by events, sleep, or skipped days.
+    pub free_minutes: u64,
+
+    /// Minutes of unfinished work on tasks due this week.
+    pub due_minutes: u64,
+
+    /// Whether the work due by the end of this week is more than the free time until then.
+    pub oversubscribed: bool,
+}
+
+/// Returns the capacity of `weeks` weeks, starting with the current one.
+pub fn weekly_capacity(data: &UserData, weeks: u32) -> Vec<WeekCapacity> {
+    let now = Local::now();
+    let first_week = week_start(now.date().naive_local());
+    let days_left = 7 * weeks as i64 - now.weekday().num_days_from_monday() as i64;
+    let until = now.date() + Duration::days(days_left);
+
+    let busy = busy_spans(data, now, until.and_hms(0, 0, 0));
+    let remaining = remaining_minutes(data);
+
+    let mut capacity: Vec<WeekCapacity> = (0..weeks)
+        .map(|w| WeekCapacity {
+            start: first_week + Duration::days(7 * w as i64),
+            free_minutes: 0,
+            due_minutes: 0,
+            oversubscribed: false,
+        })
+        .collect();
+
+    let skip_days = &data.get_config().skip_days;
+    let mut day = now.date();
+    while day < until {
+        let week = (day.naive_local() - first_week).num_days() as usize / 7;
+        if !skip_days.contains(&day.weekday()) {
+            let start = day.and_hms(0, 0, 0).max(now);
+            let end = (day + Duration::days(1)).and_hms(0, 0, 0);
+            let taken: i64 = busy
+                .iter()
+                .map(|(b_start, b_end)| {
+                    (*b_end.min(&end) - *b_start.max(&start))
+                        .num_minutes()
+                        .max(0)
+                })
+                .sum();
+            capacity[week].free_minutes += ((end - start).num_minutes() - taken).max(0) as u64;
+        }
+        day = day + Duration::days(1);
+    }
+
+    for task in data
+        .get_tasks()
+        .iter()
+        .filter(|t| !t.done && t.due_date > now)
+    {
+        let week = (task.due_date.date().naive_local() - first_week).num_days() as usize / 7;
+        if let Some(c) = capacity.get_mut(week) {
+            c.due_minutes += remaining.get(&task.id).copied().unwrap_or(0);
+        }
+    }
+
+    let (mut free_so_far, mut due_so_far) = (0, 0);
+    for c in capacity.iter_mut() {
+        free_so_far += c.free_minutes;
+        due_so_far += c.due_minutes;
+        c.oversubscribed = due_so_far > free_so_far;
+    }
+
+    capacity
+}
+
+/// Returns the times taken by events and sleep between `from` and `until`, merged so that none of
+/// them overlap.
+fn busy_spans(
+    data: &UserData,
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+) -> Vec<(DateTime<Local>, DateTime<Local>)> {
+    let mut spans: Vec<(DateTime<Local>, DateTime<Local>)> = data
+        .all_events()
+        .iter()
+        .flat_map(|e| e.occurrences(from, until))
+        .chain(data.get_bedtime().occurrences(from, until))
+        .map(|s| (*s.start(), s.end()))
+        .collect();
+    spans.sort();
+
+    let mut merged: Vec<(DateTime<Local>, DateTime<Local>)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end.max(*last_end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Returns, for each task, how many of its estimated minutes haven't been done yet.
+fn remaining_minutes(data: &UserData) -> HashMap<TaskId, u64> {
+    let mut done: HashMap<TaskId, u64> = HashMap::new();
+    for job in data.all_jobs() {
+        if let PomodoroScheduleEntry::Job {
+            task_id,
+            status: JobStatus::Done,
+            span,
+            ..
+        } = job
+        {
+            *done.entry(*task_id).or_insert(0) += span.minutes() as u64;
+        }
+    }
+
+    data.get_tasks()
+        .iter()
+        .map(|t| {
+            let done = done.get(&t.id).copied().unwrap_or(0);
+            (t.id, t.duration.total_minutes().saturating_sub(done))
+        })
+        .collect()
+}
+
+/// Shows weekly capacity, one week per line.
+pub struct CapacityDisplay<'a> {
+    pub weeks: &'a [WeekCapacity],
+}
+
+impl Display for CapacityDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for w in self.weeks {
+            write!(
+                f,
+                "-\tWeek of {}: {} free, {} due",
+                w.start.format("%b %-d"),
+                format_minutes(w.free_minutes as i64),
+                format_minutes(w.due_minutes as i64)
+            )?;
+            if w.oversubscribed {
+                write!(f, " (oversubscribed)")?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(first) = self.weeks.iter().find(|w| w.oversubscribed) {
+            writeln!(
+                f,
+                "\nThere's more work due by the week of {} than there's free time for. Think twice \
+                 before taking on anything new that's due by then.",
+                first.start.format("%b %-d")
+            )?;
+        }
+
+        Ok(())
+    }
+}
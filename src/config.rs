@@ -0,0 +1,276 @@
+//! `sparrow config`, for seeing and changing settings without editing the data file by hand.
+//! Settings are named by their field in `Config`, with dots for nested settings, like
+//! `work_minutes` or `email.smtp_host`.
+//!
+//! Settings can also be overridden for a single run with environment variables named after them,
+//! like `SPARROW_WORK_MINUTES` or `SPARROW_EMAIL_SMTP_HOST`, even when the section they're in (like
+//! `email`) isn't set up in the data file. Overrides are never saved. That makes them the place
+//! for secrets like `SPARROW_EMAIL_PASSWORD`.
+
+use crate::{Config, SparrowError, SparrowResult};
+use serde_json::{Map, Value};
+
+/// Environment variables that override settings start with this.
+pub const ENV_PREFIX: &str = "SPARROW_";
+
+/// The old name of `SPARROW_EMAIL_PASSWORD`, which still works.
+const SMTP_PASSWORD_VAR: &str = "SPARROW_SMTP_PASSWORD";
+
+/// Returns the environment variable that overrides the setting called `key`.
+pub fn env_var(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// Returns `config` with the overrides from environment variables applied, or `None` if no
+/// setting is overridden. `SPARROW_` variables that don't name a setting, like `SPARROW_PROFILE`,
+/// are left alone. So are ones whose setting doesn't exist or can't take their value, with a
+/// warning, so that one stray variable doesn't stop every command.
+pub fn with_env_overrides(config: &Config) -> SparrowResult<Option<Config>> {
+    let tree = serde_json::to_value(config)?;
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(var, _)| var.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+
+    let mut overridden = None;
+    for (var, value) in vars {
+        let key = if var == SMTP_PASSWORD_VAR {
+            Some("email.password".to_string())
+        } else {
+            key_for_var(&tree, &var[ENV_PREFIX.len()..].to_lowercase())
+        };
+        let key = match key {
+            Some(key) => key,
+            None => continue,
+        };
+        let new_config = overridden.get_or_insert_with(|| config.clone());
+        if let Err(e) = set(new_config, &key, &value) {
+            eprintln!("warning: {} is set, but {}", var, message_of(e));
+        }
+    }
+    Ok(overridden)
+}
+
+/// Returns the setting in `tree` that the environment variable named `name` (without
+/// `ENV_PREFIX`, in lowercase) overrides. Underscores could be dots or part of a name, so the
+/// names of the settings in `tree` are matched one level at a time. Under an unset optional
+/// setting, like `email`, the rest of the name is taken as the setting within it, since there's
+/// nothing there to match.
+fn key_for_var(tree: &Value, name: &str) -> Option<String> {
+    let object = tree.as_object()?;
+    if object.contains_key(name) {
+        return Some(name.to_string());
+    }
+    object.iter().find_map(|(k, v)| {
+        let rest = name.strip_prefix(k.as_str())?.strip_prefix('_')?;
+        match v {
+            Value::Object(_) => Some(format!("{}.{}", k, key_for_var(v, rest)?)),
+            Value::Null => Some(format!("{}.{}", k, rest)),
+            _ => None,
+        }
+    })
+}
+
+fn message_of(error: SparrowError) -> String {
+    match error {
+        SparrowError::BasicMessage(m) => m,
+        e => e.to_string(),
+    }
+}
+
+/// Returns every setting and its value, in order of name.
+pub fn list(config: &Config) -> SparrowResult<Vec<(String, String)>> {
+    let mut settings = Vec::new();
+    flatten(&serde_json::to_value(config)?, String::new(), &mut settings);
+    settings.sort();
+    Ok(settings)
+}
+
+/// Returns the value of the setting called `key`.
+pub fn get(config: &Config, key: &str) -> SparrowResult<String> {
+    let value = serde_json::to_value(config)?;
+    match value.pointer(&pointer(key)) {
+        Some(v) => Ok(format_value(v)),
+        None => Err(unknown_key(config, key)),
+    }
+}
+
+/// Sets the setting called `key` to `value`, which is read as YAML (so lists can be given like
+/// `[Sat, Sun]`), unless the setting is text. "none" unsets optional settings.
+pub fn set(config: &mut Config, key: &str, value: &str) -> SparrowResult<()> {
+    let tree = serde_json::to_value(&*config)?;
+    let is_text = tree.pointer(&pointer(key)).is_some_and(Value::is_string);
+    let parsed = if is_text {
+        Value::from(value)
+    } else if value.eq_ignore_ascii_case("none") {
+        Value::Null
+    } else {
+        serde_yaml::from_str(value).unwrap_or_else(|_| Value::from(value))
+    };
+
+    // settings under an unset optional setting (like `email.password`) can't be checked for
+    // text beforehand, so try them as text if they don't work otherwise
+    let new_config = match with_value(tree.clone(), key, parsed.clone()) {
+        Err(_) if !parsed.is_string() => with_value(tree, key, Value::from(value)),
+        result => result,
+    }
+    .map_err(|e| match e {
+        SparrowError::JsonError(e) => {
+            SparrowError::BasicMessage(format!("'{}' can't be {}: {}", key, value, e))
+        }
+        e => e,
+    })?;
+
+    // `Config` ignores fields it doesn't know, so a setting that didn't stick doesn't exist
+    let stuck = serde_json::to_value(&new_config)?;
+    if stuck.pointer(&pointer(key)).is_none() {
+        return Err(unknown_key(config, key));
+    }
+
+    *config = new_config;
+    Ok(())
+}
+
+/// Returns the config in `tree` with the setting called `key` set to `value`.
+fn with_value(mut tree: Value, key: &str, value: Value) -> SparrowResult<Config> {
+    // settings under an unset optional setting (like `email`) set it, with defaults for the rest
+    let mut node = &mut tree;
+    let parts: Vec<&str> = key.split('.').collect();
+    for part in &parts[..parts.len() - 1] {
+        if node.is_null() {
+            *node = Value::Object(Map::new());
+        }
+        node = match node {
+            Value::Object(object) => object.entry(part.to_string()).or_insert(Value::Null),
+            _ => return Err(not_nested(key)),
+        };
+    }
+    if node.is_null() {
+        *node = Value::Object(Map::new());
+    }
+    match node {
+        Value::Object(object) => {
+            object.insert(parts[parts.len() - 1].to_string(), value);
+        }
+        _ => return Err(not_nested(key)),
+    }
+
+    Ok(serde_json::from_value(tree)?)
+}
+
+fn not_nested(key: &str) -> SparrowError {
+    SparrowError::BasicMessage(format!(
+        "there's no setting called '{}' (see them all with `sparrow config list`)",
+        key
+    ))
+}
+
+fn flatten(value: &Value, prefix: String, settings: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (k, v) in object {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten(v, key, settings);
+            }
+        }
+        _ => settings.push((prefix, format_value(value))),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "none".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Turns a setting name like "email.smtp_host" into a JSON pointer like "/email/smtp_host".
+fn pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
+
+/// Makes an error for a setting that doesn't exist, suggesting ones with similar names.
+fn unknown_key(config: &Config, key: &str) -> SparrowError {
+    let last = key.rsplit('.').next().unwrap_or(key).to_lowercase();
+    let similar: Vec<String> = list(config)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, _)| k)
+        .filter(|k| {
+            let k = k.to_lowercase();
+            let k_last = k.rsplit('.').next().unwrap_or(&k);
+            k.contains(&last) || k_last.len() > 3 && last.contains(k_last)
+        })
+        .collect();
+
+    let mut message = format!("there's no setting called '{}'", key);
+    if !similar.is_empty() {
+        message.push_str(&format!("; did you mean {}?", similar.join(", ")));
+    }
+    message.push_str(" (see them all with `sparrow config list`)");
+    SparrowError::BasicMessage(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tree() -> Value {
+        json!({
+            "work_minutes": 25,
+            "short_break_minutes": 5,
+            "email": null,
+            "gcal": { "client_id": "", "client_secret": "" },
+            "focus_mode": null,
+        })
+    }
+
+    #[test]
+    fn finds_settings_by_variable_name() {
+        assert_eq!(
+            key_for_var(&tree(), "work_minutes"),
+            Some("work_minutes".to_string())
+        );
+        assert_eq!(
+            key_for_var(&tree(), "gcal_client_secret"),
+            Some("gcal.client_secret".to_string())
+        );
+        assert_eq!(
+            key_for_var(&tree(), "focus_mode"),
+            Some("focus_mode".to_string())
+        );
+    }
+
+    #[test]
+    fn guesses_settings_under_unset_sections() {
+        assert_eq!(
+            key_for_var(&tree(), "email_smtp_host"),
+            Some("email.smtp_host".to_string())
+        );
+    }
+
+    #[test]
+    fn leaves_other_variables_alone() {
+        assert_eq!(key_for_var(&tree(), "profile"), None);
+        assert_eq!(key_for_var(&tree(), "gcal_nothing"), None);
+        assert_eq!(key_for_var(&tree(), "work"), None);
+    }
+
+    #[test]
+    fn skips_overrides_that_do_not_work() {
+        let mut config = Config::default();
+        assert!(set(&mut config, "email.foo", "1").is_err());
+        assert!(config.email.is_none());
+        assert!(set(&mut config, "work_minutes", "lots").is_err());
+        assert_eq!(config.work_minutes, 25);
+
+        set(&mut config, "email.smtp_host", "smtp.example.com").unwrap();
+        assert_eq!(config.email.unwrap().smtp_host, "smtp.example.com");
+    }
+}
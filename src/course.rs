@@ -0,0 +1,96 @@
+//! Courses, for students. A course meets at the same times every week until its semester ends, and
+//! tasks can belong to a course.
+
+use crate::{
+    prompts::*, CalendarEvent, CalendarEventType, Config, Formatting, Repeat, SparrowError,
+    SparrowResult, TimeSpan,
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Course {
+    /// The name of the course. Tasks refer to their course by this name.
+    pub name: String,
+
+    /// The first of each weekly meeting, like the first Monday lecture and the first Wednesday
+    /// lab. Each one repeats every week until the semester ends.
+    pub meetings: Vec<TimeSpan>,
+
+    /// When the semester ends.
+    pub semester_end: DateTime<Local>,
+}
+
+impl Course {
+    pub fn prompt_new(formatting: &Formatting, config: &Config) -> SparrowResult<Self> {
+        let name = prompt_strict(
+            formatting,
+            "What's the name of the course?",
+            None,
+            None,
+            |i| {
+                let i = i.trim();
+                if i.is_empty() {
+                    Err(SparrowError::BasicMessage(
+                        "Courses need a name".to_string(),
+                    ))
+                } else {
+                    Ok(i.to_string())
+                }
+            },
+        )?;
+
+        println!("{}", formatting.prompt.paint("When does the semester end?"));
+        let semester_end =
+            prompt_datetime(formatting, &config.date_format, &config.time_format, true)?;
+
+        let mut meetings = Vec::new();
+        loop {
+            let question = format!("When is the first meeting #{}?", meetings.len() + 1);
+            meetings.push(TimeSpan::prompt(
+                formatting,
+                &question,
+                &config.date_format,
+                &config.time_format,
+            )?);
+
+            let another = prompt_yn(&format!(
+                "{} {}",
+                formatting
+                    .prompt
+                    .paint("Does it meet at another time each week?"),
+                formatting.prompt_format.paint("[y/N]")
+            ))?;
+            if !another.map(|d| d.is_yes()).unwrap_or(false) {
+                break;
+            }
+        }
+
+        Ok(Self {
+            name,
+            meetings,
+            semester_end,
+        })
+    }
+
+    /// Returns true if the semester is over at `when`.
+    pub fn is_over(&self, when: &DateTime<Local>) -> bool {
+        *when >= self.semester_end
+    }
+
+    /// Returns the course's meetings as weekly events, which stop when the semester ends.
+    pub fn meeting_events(&self) -> impl Iterator<Item = CalendarEvent> + '_ {
+        self.meetings.iter().map(move |m| CalendarEvent {
+            id: 0,
+            name: self.name.clone(),
+            time_span: *m,
+            event_type: CalendarEventType::Event,
+            repeat: Repeat::Weekly,
+            tags: Vec::new(),
+            repeat_until: Some(self.semester_end),
+            repeat_count: None,
+            exceptions: Vec::new(),
+            timezone: None,
+        })
+    }
+}
@@ -0,0 +1,163 @@
+//! A control socket for sparrowd, so that `sparrow ctl` can talk to a running daemon without
+//! restarting it.
+//!
+//! The socket sits next to the data file (as `<data file>.sock`), so each profile's sparrowd has
+//! its own. A client connects, sends one command on a line, and gets one line of JSON back. Every
+//! answer has a `message` for people, or an `error` if the command failed.
+//!
+//! | Command    | Does                                                               |
+//! |------------|--------------------------------------------------------------------|
+//! | status     | returns the current and next entries, and whether sparrowd is paused |
+//! | pause [N]  | stops notifications for N minutes, or until `resume`               |
+//! | resume     | starts notifications again                                         |
+//! | skip       | marks the current work period as skipped                           |
+//! | reschedule | remakes the pomodoro schedule from now on, keeping what's past     |
+
+use crate::{SparrowError, SparrowResult};
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CtlCommand {
+    Status,
+
+    /// Pauses notifications for some minutes, or until `Resume` if there's no number.
+    Pause(Option<u32>),
+    Resume,
+    Skip,
+    Reschedule,
+}
+
+impl CtlCommand {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Pause(_) => "pause",
+            Self::Resume => "resume",
+            Self::Skip => "skip",
+            Self::Reschedule => "reschedule",
+        }
+    }
+
+    /// Returns the command as it's sent over the socket.
+    pub fn line(self) -> String {
+        match self {
+            Self::Pause(Some(minutes)) => format!("pause {}", minutes),
+            command => command.name().to_string(),
+        }
+    }
+}
+
+/// Reads a command like "pause 30". Commands can be shortened, like "st" for status.
+impl TryFrom<&str> for CtlCommand {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.to_lowercase();
+        let mut words = value.split_whitespace();
+        let (value, arg) = (words.next().unwrap_or_default(), words.next());
+        if "status".starts_with(value) {
+            Ok(Self::Status)
+        } else if "pause".starts_with(value) {
+            let minutes = arg.map(|m| m.parse()).transpose().map_err(|_| {
+                SparrowError::BasicMessage(format!("'{}' isn't a number of minutes", arg.unwrap()))
+            })?;
+            Ok(Self::Pause(minutes))
+        } else if "resume".starts_with(value) && value.len() > 2 {
+            Ok(Self::Resume)
+        } else if "skip".starts_with(value) && value.len() > 1 {
+            Ok(Self::Skip)
+        } else if "reschedule".starts_with(value) && value.len() > 2 {
+            Ok(Self::Reschedule)
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "'{}' isn't something sparrowd can do. try status, pause, resume, skip, or \
+                 reschedule",
+                value
+            )))
+        }
+    }
+}
+
+/// Returns where the control socket for the data file at `data_path` is.
+pub fn socket_path(data_path: &Path) -> PathBuf {
+    let mut name = data_path.as_os_str().to_owned();
+    name.push(".sock");
+    PathBuf::from(name)
+}
+
+/// Sends `command` to the sparrowd running for the data file at `data_path`, and returns its
+/// answer.
+pub fn send(data_path: &Path, command: CtlCommand) -> SparrowResult<Value> {
+    let path = socket_path(data_path);
+    let mut stream = UnixStream::connect(&path).map_err(|e| {
+        SparrowError::BasicMessage(format!(
+            "couldn't reach sparrowd at {} (is it running?): {}",
+            path.display(),
+            e
+        ))
+    })?;
+    writeln!(stream, "{}", command.line())?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    let answer: Value = serde_json::from_str(&answer)?;
+    match answer.get("error").and_then(Value::as_str) {
+        Some(error) => Err(SparrowError::BasicMessage(error.to_string())),
+        None => Ok(answer),
+    }
+}
+
+/// Listens on the control socket for the data file at `data_path`, answering commands with
+/// `handle` on a new thread.
+pub fn listen<F>(data_path: &Path, mut handle: F) -> SparrowResult<JoinHandle<()>>
+where
+    F: FnMut(CtlCommand) -> SparrowResult<Value> + Send + 'static,
+{
+    let path = socket_path(data_path);
+
+    // a sparrowd that didn't get to clean up leaves its socket behind, which would keep this one
+    // from listening. a socket nothing answers on is safe to remove
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(SparrowError::BasicMessage(format!(
+                "another sparrowd is already listening at {}",
+                path.display()
+            )));
+        }
+        fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(e) = answer(stream, &mut handle) {
+                eprintln!("couldn't answer a control command: {}", e);
+            }
+        }
+    }))
+}
+
+fn answer<F>(stream: UnixStream, handle: &mut F) -> io::Result<()>
+where
+    F: FnMut(CtlCommand) -> SparrowResult<Value>,
+{
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let answer = CtlCommand::try_from(line.trim())
+        .and_then(handle)
+        .unwrap_or_else(|e| {
+            // the client wraps the error in a BasicMessage again
+            let error = match e {
+                SparrowError::BasicMessage(message) => message,
+                e => e.to_string(),
+            };
+            json!({ "error": error })
+        });
+    writeln!(&stream, "{}", answer)
+}
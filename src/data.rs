@@ -1,14 +1,38 @@
 use crate::{
-    methods::ivy_lee::IvyLeeSchedule, methods::pomodoro::PomodoroSchedule, Bedtime, CalendarEvent,
-    SparrowError, Task,
+    archive::Archive,
+    backup, cache, config,
+    estimates::EstimateCorrections,
+    focus::FocusBackend,
+    goals::{self, Goal},
+    habit,
+    meals::Meal,
+    methods::edf::EdfSchedule,
+    methods::eisenhower::EisenhowerSchedule,
+    methods::frog::FrogSchedule,
+    methods::ivy_lee::IvyLeeSchedule,
+    methods::pomodoro::{PomodoroSchedule, PomodoroScheduleEntry},
+    methods::time_block::TimeBlockSchedule,
+    migrations::SchemaVersion,
+    quiet::QuietHours,
+    shared::SharedCalendar,
+    sleep,
+    storage::{self, DataFormat},
+    streak,
+    templates::TaskTemplate,
+    tracking::{self, TimeEntry, Worked},
+    work_windows::WorkWindow,
+    Bedtime, CalendarEvent, Course, EventId, Habit, Repeat, SparrowError, Task, TaskId, TimeSpan,
 };
-use chrono::Weekday;
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use chrono::{DateTime, Local, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-#[derive(Deserialize, Serialize)]
+/// Missing settings (for example, ones added after the data file was made) take their default
+/// values.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     /// Date format used when parsing/formatting dates.
     pub date_format: String,
@@ -34,11 +58,95 @@ pub struct Config {
     /// How long before the next job/break starts sparrowd notifies the user.
     pub next_event_warning_minutes: u32,
 
+    /// If true, sparrowd remakes the pomodoro schedule when tasks or events are added or removed.
+    pub auto_reschedule: bool,
+
+    /// If true, sparrowd's notifications for work periods have buttons to snooze, skip, or finish
+    /// them. Needs a `notify-send` that supports `--action` and `--wait`.
+    pub notification_actions: bool,
+
+    /// Times of day when sparrowd doesn't show notifications.
+    pub quiet_hours: Vec<QuietHours>,
+
+    /// sparrowd doesn't show notifications during events with any of these tags, like "focus".
+    pub quiet_tags: Vec<String>,
+
+    /// A sound file sparrowd plays when a work period, break, or event starts.
+    pub alert_sound: Option<PathBuf>,
+
+    /// The program (and its arguments) that plays `alert_sound`, like `["aplay", "-q"]`. The
+    /// sound's path is added to the end. If empty, sparrowd tries paplay, pw-play, and aplay.
+    pub alert_command: Vec<String>,
+
+    /// Breaks for meals, every day.
+    pub meals: Vec<Meal>,
+
+    /// Times of day work can be scheduled in. If there aren't any, work can go in any free time.
+    pub work_windows: Vec<WorkWindow>,
+
+    /// Time to spend on tags or courses every week, set aside in schedules.
+    pub goals: Vec<Goal>,
+
+    /// Kinds of tasks that come up again and again, for `sparrow add task --template`.
+    pub templates: Vec<TaskTemplate>,
+
     /// Weekdays to skip, if any
+    #[serde(serialize_with = "serialize_weekdays")]
     pub skip_days: HashSet<Weekday>,
 
     /// Maximum number of tasks allowed to be scheduled per day with Ivy-Lee method
     pub ivy_lee_tasks_per_day: u32,
+
+    /// The longest a time block can be.
+    pub time_block_minutes: u32,
+
+    /// The shortest a time block can be, unless it finishes a task.
+    pub min_time_block_minutes: u32,
+
+    /// Tasks due within this many days are urgent in the Eisenhower matrix.
+    pub eisenhower_urgent_days: u32,
+
+    /// How many days ahead instances of repeating tasks are scheduled, or further if a task that
+    /// doesn't repeat is due later.
+    pub repeat_horizon_days: u32,
+
+    /// Scale estimates by how long finished tasks of the same course or tags actually took, going
+    /// by time tracked with `sparrow start`. See `sparrow audit`.
+    pub correct_estimates: bool,
+
+    /// What estimates get scaled by. Learned from tracked time rather than set, and filled in by
+    /// `UserData::scheduling_config`.
+    #[serde(skip)]
+    pub estimate_corrections: EstimateCorrections,
+
+    /// Imported issues with a label starting with this (like "estimate: 2h") use the rest of the
+    /// label as their estimate.
+    pub estimate_label_prefix: String,
+
+    /// How to send `sparrow digest --email`, if at all.
+    pub email: Option<EmailConfig>,
+
+    /// Where sparrowd publishes the current entry, if anywhere.
+    pub mqtt: Option<MqttConfig>,
+
+    /// URLs sparrowd POSTs JSON to when an entry starts or a task is finished.
+    pub webhooks: Vec<String>,
+
+    /// Streak lengths, in days, that sparrowd congratulates the user on.
+    pub streak_milestones: Vec<u32>,
+
+    /// If set, sparrowd turns on do-not-disturb when a work period starts and turns it off when
+    /// anything else starts.
+    pub focus_mode: Option<FocusBackend>,
+
+    /// Other people's data files, checked for conflicts when adding events.
+    pub shared_calendars: Vec<SharedCalendar>,
+
+    /// Which Google Calendars `sparrow sync gcal` reads, if set up.
+    pub gcal: Option<GcalConfig>,
+
+    /// How many backups of the data file to keep. 0 turns backups off.
+    pub backup_count: u32,
 }
 
 impl Default for Config {
@@ -52,63 +160,592 @@ impl Default for Config {
             work_periods_per_job_session: 4,
             allow_repeats: false,
             next_event_warning_minutes: 5,
+            auto_reschedule: false,
+            notification_actions: false,
+            quiet_hours: Vec::new(),
+            quiet_tags: Vec::new(),
+            alert_sound: None,
+            alert_command: Vec::new(),
+            meals: Vec::new(),
+            work_windows: Vec::new(),
+            goals: Vec::new(),
+            templates: Vec::new(),
             skip_days: HashSet::new(),
             ivy_lee_tasks_per_day: 6,
+            time_block_minutes: 90,
+            min_time_block_minutes: 30,
+            eisenhower_urgent_days: 2,
+            repeat_horizon_days: 7,
+            correct_estimates: true,
+            estimate_corrections: EstimateCorrections::default(),
+            estimate_label_prefix: "estimate:".to_string(),
+            email: None,
+            mqtt: None,
+            webhooks: Vec::new(),
+            streak_milestones: vec![3, 7, 14, 30, 60, 100],
+            focus_mode: None,
+            shared_calendars: Vec::new(),
+            gcal: None,
+            backup_count: 20,
+        }
+    }
+}
+
+/// Writes weekdays in order, so that saving the same data always writes the same file.
+fn serialize_weekdays<S: Serializer>(days: &HashSet<Weekday>, s: S) -> Result<S::Ok, S::Error> {
+    let mut days: Vec<&Weekday> = days.iter().collect();
+    days.sort_by_key(|d| d.num_days_from_monday());
+    s.collect_seq(days)
+}
+
+/// SMTP settings for emailing the daily digest.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// The SMTP server, like "smtp.example.com".
+    pub smtp_host: String,
+
+    /// 465 connects with TLS right away. Any other port upgrades to TLS with STARTTLS.
+    pub smtp_port: u16,
+
+    pub username: String,
+
+    /// Can be left empty and given with the SPARROW_EMAIL_PASSWORD environment variable (or its
+    /// old name, SPARROW_SMTP_PASSWORD) instead.
+    pub password: String,
+
+    /// Address the digest is sent from.
+    pub from: String,
+
+    /// Address the digest is sent to.
+    pub to: String,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: String::new(),
+            smtp_port: 587,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: String::new(),
+        }
+    }
+}
+
+/// MQTT broker settings for publishing the current entry, so home automation can react to work
+/// periods and breaks.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+
+    /// Topic the current entry is published (and retained) to.
+    pub topic: String,
+
+    pub client_id: String,
+
+    /// Leave empty if the broker doesn't need logging in to.
+    pub username: String,
+    pub password: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 1883,
+            topic: "sparrow/current".to_string(),
+            client_id: "sparrowd".to_string(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+}
+
+/// Google Calendar settings. The OAuth client comes from a "Desktop app" client made in the
+/// Google Cloud console.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GcalConfig {
+    pub client_id: String,
+
+    /// Can be left empty and given with the SPARROW_GCAL_CLIENT_SECRET environment variable
+    /// instead.
+    pub client_secret: String,
+
+    /// IDs of the calendars to read. "primary" is the user's main calendar.
+    pub calendars: Vec<String>,
+
+    /// How many days ahead to read events for.
+    pub days_ahead: u32,
+}
+
+impl Default for GcalConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: String::new(),
+            calendars: vec!["primary".to_string()],
+            days_ahead: 14,
         }
     }
 }
 
 #[derive(Default, Deserialize, Serialize)]
 pub struct UserData {
+    /// The layout of the data file, so that files from older versions of sparrow can be
+    /// upgraded. See `migrations`.
+    #[serde(default)]
+    version: SchemaVersion,
+
+    /// Left out of data files whose config is kept separately (see `storage`).
+    #[serde(default)]
     config: Config,
     bedtime: Bedtime,
     tasks: Vec<Task>,
     events: Vec<CalendarEvent>,
     pomodoro_schedule: Option<PomodoroSchedule>,
     ivy_lee_schedule: Option<IvyLeeSchedule>,
+
+    #[serde(default)]
+    time_block_schedule: Option<TimeBlockSchedule>,
+
+    #[serde(default)]
+    eisenhower_schedule: Option<EisenhowerSchedule>,
+
+    #[serde(default)]
+    edf_schedule: Option<EdfSchedule>,
+
+    #[serde(default)]
+    frog_schedule: Option<FrogSchedule>,
+
+    /// The ID the next new Task will get.
+    #[serde(default)]
+    next_task_id: TaskId,
+
+    /// The ID the next new event will get.
+    #[serde(default)]
+    next_event_id: EventId,
+
+    #[serde(default)]
+    courses: Vec<Course>,
+
+    #[serde(default)]
+    habits: Vec<Habit>,
+
+    /// Jobs from earlier schedules that started before those schedules were replaced. Kept so
+    /// that streaks and audits survive remaking the schedule.
+    #[serde(default)]
+    job_history: Vec<PomodoroScheduleEntry>,
+
+    /// Minutes of sleep lost, by the date of the night they were lost on.
+    #[serde(default)]
+    late_nights: BTreeMap<NaiveDate, u32>,
+
+    /// Time actually spent on tasks, from `sparrow start` and `sparrow stop`.
+    #[serde(default)]
+    time_entries: Vec<TimeEntry>,
+
+    /// Events from the last `sparrow sync gcal`, replaced on every sync.
+    #[serde(default)]
+    synced_events: Vec<CalendarEvent>,
+
+    /// The config with overrides from environment variables, if there are any. These are only
+    /// for this run, so they're kept apart from `config`, which is saved.
+    #[serde(skip)]
+    overridden_config: Option<Config>,
 }
 
 impl UserData {
+    /// Returns the tasks and events in `archive` as data of their own, listed with `config`.
+    pub fn from_archive(config: Config, archive: Archive) -> Self {
+        Self {
+            config,
+            tasks: archive.tasks,
+            events: archive.events,
+            ..Self::default()
+        }
+    }
+
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, SparrowError> {
-        if !path.as_ref().exists() {
-            Ok(Self::default())
+        let path = path.as_ref();
+        Self::load(path, DataFormat::from_path(path))
+    }
+
+    /// Loads the data file at `path`, written in `format` whatever its extension is.
+    pub fn load(path: &Path, format: DataFormat) -> Result<Self, SparrowError> {
+        let mut data = if !path.exists() {
+            Self::default()
+        } else if let Some(data) = cache::load(path) {
+            data
         } else {
-            Ok(serde_yaml::from_reader(fs::File::open(path)?)?)
+            let mut data = storage::open(path, format)?.load()?;
+            data.assign_missing_ids();
+            cache::store(path, &data);
+            data
+        };
+
+        // a separate config file isn't part of the cache, so it's always read
+        if let Some(config) = storage::load_config(path)? {
+            data.config = config;
+        }
+        data.overridden_config = config::with_env_overrides(&data.config)?;
+        Ok(data)
+    }
+
+    /// Gives IDs to tasks, subtasks, and events that don't have one, such as ones from older data
+    /// files.
+    fn assign_missing_ids(&mut self) {
+        let max_id = self.tasks.iter().map(|t| t.id).max().unwrap_or(0);
+        self.next_task_id = self.next_task_id.max(max_id + 1);
+        for i in 0..self.tasks.len() {
+            if self.tasks[i].id == 0 {
+                self.tasks[i].id = self.take_task_id();
+            }
+            self.tasks[i].assign_subtask_ids();
         }
+
+        let max_id = self.events.iter().map(|e| e.id).max().unwrap_or(0);
+        self.next_event_id = self.next_event_id.max(max_id + 1);
+        for i in 0..self.events.len() {
+            if self.events[i].id == 0 {
+                self.events[i].id = self.take_event_id();
+            }
+        }
+    }
+
+    fn take_task_id(&mut self) -> TaskId {
+        // IDs start at 1, since 0 means "no ID"
+        let id = self.next_task_id.max(1);
+        self.next_task_id = id + 1;
+        id
+    }
+
+    fn take_event_id(&mut self) -> EventId {
+        let id = self.next_event_id.max(1);
+        self.next_event_id = id + 1;
+        id
     }
 
-    pub fn add_task(&mut self, task: Task) {
+    pub fn add_task(&mut self, mut task: Task) {
+        task.id = self.take_task_id();
+        task.assign_subtask_ids();
         self.tasks.push(task);
     }
 
-    pub fn add_event(&mut self, event: CalendarEvent) {
+    pub fn add_event(&mut self, mut event: CalendarEvent) {
+        event.id = self.take_event_id();
         self.events.push(event);
     }
 
+    pub fn set_synced_events(&mut self, events: Vec<CalendarEvent>) {
+        self.synced_events = events;
+    }
+
+    /// Removes the task with `id`, along with its place in any schedules.
+    pub fn remove_task(&mut self, id: TaskId) -> Option<Task> {
+        let index = self.tasks.iter().position(|t| t.id == id)?;
+        let task = self.tasks.remove(index);
+        for t in &mut self.tasks {
+            t.depends_on.retain(|d| *d != id);
+        }
+        if let Some(schedule) = &mut self.pomodoro_schedule {
+            schedule.remove_task(id);
+        }
+        if let Some(schedule) = &mut self.ivy_lee_schedule {
+            schedule.remove_task(id);
+        }
+        if let Some(schedule) = &mut self.time_block_schedule {
+            schedule.remove_task(id);
+        }
+        if let Some(schedule) = &mut self.eisenhower_schedule {
+            schedule.remove_task(id);
+        }
+        if let Some(schedule) = &mut self.edf_schedule {
+            schedule.remove_task(id);
+        }
+        if let Some(schedule) = &mut self.frog_schedule {
+            schedule.remove_task(id);
+        }
+        Some(task)
+    }
+
+    /// Removes the event at `index`, along with its place in the pomodoro schedule.
+    pub fn remove_event(&mut self, index: usize) -> CalendarEvent {
+        let event = self.events.remove(index);
+        if let Some(schedule) = &mut self.pomodoro_schedule {
+            schedule.remove_event(&event);
+        }
+        event
+    }
+
+    /// Cancels the repeating events named `name` on `date`, and takes them out of the pomodoro
+    /// schedule, leaving the time free.
+    pub fn cancel_event_on(&mut self, name: &str, date: NaiveDate) -> Result<(), SparrowError> {
+        let mut cancelled = false;
+        for event in self
+            .events
+            .iter_mut()
+            .filter(|e| e.name == name && e.repeat != Repeat::No)
+        {
+            let span = match event.occurrence_on(date) {
+                Some(s) => s,
+                None => continue,
+            };
+            if let Some(schedule) = &mut self.pomodoro_schedule {
+                schedule.remove_event(&CalendarEvent {
+                    time_span: span,
+                    repeat: Repeat::No,
+                    ..event.clone()
+                });
+            }
+            event.exceptions.push(date);
+            cancelled = true;
+        }
+
+        if cancelled {
+            Ok(())
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "'{}' doesn't happen on {}",
+                name,
+                date.format(&self.get_config().date_format)
+            )))
+        }
+    }
+
+    /// Saves the data to `path`, in the format its extension calls for (see `storage`).
     pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), SparrowError> {
-        Ok(fs::write(path, serde_yaml::to_string(self)?)?)
+        let path = path.as_ref();
+        self.save(path, DataFormat::from_path(path))
+    }
+
+    /// Saves the data to `path`, written in `format` whatever its extension is. Whatever was at
+    /// `path` is backed up first.
+    pub fn save(&self, path: &Path, format: DataFormat) -> Result<(), SparrowError> {
+        backup::back_up(path, self.get_config().backup_count)?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        storage::save_config(path, &self.config)?;
+        storage::open(path, format)?.save(self)?;
+        cache::store(path, self);
+        Ok(())
     }
 
+    /// Returns the config to make schedules with: the config, along with corrections to estimates
+    /// learned from tracked time if `correct_estimates` is on, and the time worked toward each goal
+    /// this week.
+    pub fn scheduling_config(&self) -> Config {
+        let mut config = self.get_config().clone();
+        if config.correct_estimates {
+            config.estimate_corrections = self.estimate_corrections();
+        }
+        if !config.goals.is_empty() {
+            let worked = self.worked();
+            let this_week = goals::this_week(Local::now());
+            for goal in config.goals.iter_mut() {
+                goal.minutes_done = goal.worked_minutes(&worked, &self.tasks, this_week);
+            }
+        }
+        config
+    }
+
+    /// Returns how far off the estimates of finished tasks were, going by tracked time.
+    pub fn estimate_corrections(&self) -> EstimateCorrections {
+        EstimateCorrections::learn(&self.tasks, &self.time_entries)
+    }
+
+    /// Returns the config, with any overrides from environment variables.
     pub fn get_config(&self) -> &Config {
-        &self.config
+        self.overridden_config.as_ref().unwrap_or(&self.config)
+    }
+
+    /// Returns the config as it's saved. Overrides from environment variables no longer apply
+    /// after this, so that changes made through it show up in `get_config`.
+    pub fn get_config_mut(&mut self) -> &mut Config {
+        self.overridden_config = None;
+        &mut self.config
     }
 
     pub fn get_tasks(&self) -> &[Task] {
         &self.tasks
     }
 
+    pub fn get_task_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|t| t.id == id)
+    }
+
     pub fn get_events(&self) -> &[CalendarEvent] {
         &self.events
     }
 
+    /// Returns the user's events along with the meetings of courses that haven't ended yet, time
+    /// to sleep in after late nights, meals, and slots for habits. This is what schedules should be
+    /// made around.
+    pub fn all_events(&self) -> Vec<CalendarEvent> {
+        let now = Local::now();
+        let mut events = self.events.clone();
+        events.extend(self.synced_events.iter().cloned());
+        for course in self.courses.iter().filter(|c| !c.is_over(&now)) {
+            events.extend(course.meeting_events());
+        }
+        events.extend(sleep::recovery_events(
+            &self.late_nights,
+            &self.bedtime,
+            now,
+        ));
+        let today = now.date().naive_local();
+        events.extend(
+            self.get_config()
+                .meals
+                .iter()
+                .filter_map(|m| m.as_event(today)),
+        );
+
+        if !self.habits.is_empty() {
+            // habits are planned as far ahead as there are tasks to schedule
+            let until = self
+                .tasks
+                .iter()
+                .filter(|t| !t.done)
+                .map(|t| t.due_date)
+                .max()
+                .unwrap_or(now);
+            let busy: Vec<TimeSpan> = events
+                .iter()
+                .flat_map(|e| e.occurrences(now, until))
+                .chain(self.bedtime.occurrences(now, until))
+                .collect();
+            events.extend(habit::habit_slots(&self.habits, busy, now, until));
+        }
+
+        events
+    }
+
+    pub fn add_habit(&mut self, habit: Habit) {
+        self.habits.push(habit);
+    }
+
+    pub fn get_habits(&self) -> &[Habit] {
+        &self.habits
+    }
+
+    /// Finds a habit by name, ignoring case.
+    pub fn get_habit_mut(&mut self, name: &str) -> Option<&mut Habit> {
+        self.habits
+            .iter_mut()
+            .find(|h| h.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn add_course(&mut self, course: Course) {
+        self.courses.push(course);
+    }
+
+    pub fn get_courses(&self) -> &[Course] {
+        &self.courses
+    }
+
     pub fn get_pomodoro_schedule(&self) -> &Option<PomodoroSchedule> {
         &self.pomodoro_schedule
     }
 
+    /// Sets the pomodoro schedule to an edited copy of the current one, like after a job is moved
+    /// or marked done. Use `replace_pomodoro_schedule` for a newly made schedule instead.
     pub fn set_pomodoro_schedule(&mut self, schedule: PomodoroSchedule) {
         self.pomodoro_schedule = Some(schedule);
     }
 
+    /// Replaces the pomodoro schedule with a newly made one, remembering the jobs of the old one
+    /// that already started. Jobs the new schedule kept aren't remembered, since they're still in
+    /// it.
+    pub fn replace_pomodoro_schedule(&mut self, schedule: PomodoroSchedule) {
+        if let Some(old) = self.pomodoro_schedule.take() {
+            let now = Local::now();
+            let kept = |job: &PomodoroScheduleEntry| {
+                schedule
+                    .get_entries()
+                    .iter()
+                    .any(|e| e.status().is_some() && e.span() == job.span())
+            };
+            let started_jobs = old
+                .get_entries()
+                .iter()
+                .filter(|e| e.status().is_some() && *e.span().start() < now && !kept(e))
+                .cloned();
+            self.job_history.extend(started_jobs);
+        }
+        self.pomodoro_schedule = Some(schedule);
+    }
+
+    /// Returns every job, past and planned, from the current schedule and the ones before it.
+    pub fn all_jobs(&self) -> impl Iterator<Item = &PomodoroScheduleEntry> {
+        let current = match &self.pomodoro_schedule {
+            Some(schedule) => schedule.get_entries(),
+            None => &[],
+        };
+        self.job_history
+            .iter()
+            .chain(current.iter().filter(|e| e.status().is_some()))
+    }
+
+    /// Records `minutes` of sleep lost on the night of `night`.
+    pub fn add_late_night(&mut self, night: NaiveDate, minutes: u32) {
+        *self.late_nights.entry(night).or_insert(0) += minutes;
+    }
+
+    /// Returns the minutes of sleep lost over the last couple of weeks.
+    pub fn sleep_debt(&self) -> u32 {
+        sleep::sleep_debt(&self.late_nights, Local::today().naive_local())
+    }
+
+    pub fn get_time_entries(&self) -> &[TimeEntry] {
+        &self.time_entries
+    }
+
+    /// Returns the entry whose clock is still running, if any.
+    pub fn running_time_entry(&self) -> Option<&TimeEntry> {
+        self.time_entries.iter().rev().find(|e| e.is_running())
+    }
+
+    /// Starts tracking time on the task with `id` at `now`. Only one clock runs at a time, so
+    /// the one that was running is stopped and returned, if there was one.
+    pub fn start_tracking(&mut self, id: TaskId, now: DateTime<Local>) -> Option<TimeEntry> {
+        let stopped = self.stop_tracking(now).cloned();
+        self.time_entries.push(TimeEntry::new(id, now));
+        stopped
+    }
+
+    /// Stops the running clock at `now`, returning its entry, or None if no clock was running.
+    pub fn stop_tracking(&mut self, now: DateTime<Local>) -> Option<&TimeEntry> {
+        let entry = self
+            .time_entries
+            .iter_mut()
+            .rev()
+            .find(|e| e.is_running())?;
+        entry.end = Some(now);
+        Some(entry)
+    }
+
+    /// Returns the time worked on tasks: tracked time, and work periods marked done outside of it.
+    pub fn worked(&self) -> Vec<Worked> {
+        tracking::worked(&self.time_entries, self.all_jobs())
+    }
+
+    /// Returns the minutes tracked on the task with `id` so far, including a running clock.
+    pub fn tracked_minutes(&self, id: TaskId) -> i64 {
+        tracking::tracked_minutes(&self.time_entries, id, Local::now())
+    }
+
+    /// Returns how many days in a row every work period was done.
+    pub fn streak(&self) -> u32 {
+        let work_days = streak::work_day_results(self.all_jobs());
+        streak::current_streak(&work_days, Local::today().naive_local())
+    }
+
     pub fn get_ivy_lee_schedule(&self) -> &Option<IvyLeeSchedule> {
         &self.ivy_lee_schedule
     }
@@ -117,6 +754,38 @@ impl UserData {
         self.ivy_lee_schedule = Some(schedule);
     }
 
+    pub fn get_time_block_schedule(&self) -> &Option<TimeBlockSchedule> {
+        &self.time_block_schedule
+    }
+
+    pub fn set_time_block_schedule(&mut self, schedule: TimeBlockSchedule) {
+        self.time_block_schedule = Some(schedule);
+    }
+
+    pub fn get_eisenhower_schedule(&self) -> &Option<EisenhowerSchedule> {
+        &self.eisenhower_schedule
+    }
+
+    pub fn set_eisenhower_schedule(&mut self, schedule: EisenhowerSchedule) {
+        self.eisenhower_schedule = Some(schedule);
+    }
+
+    pub fn get_edf_schedule(&self) -> &Option<EdfSchedule> {
+        &self.edf_schedule
+    }
+
+    pub fn set_edf_schedule(&mut self, schedule: EdfSchedule) {
+        self.edf_schedule = Some(schedule);
+    }
+
+    pub fn get_frog_schedule(&self) -> &Option<FrogSchedule> {
+        &self.frog_schedule
+    }
+
+    pub fn set_frog_schedule(&mut self, schedule: FrogSchedule) {
+        self.frog_schedule = Some(schedule);
+    }
+
     pub fn delete_pomodoro_schedule(&mut self) {
         self.pomodoro_schedule = None;
     }
@@ -124,4 +793,8 @@ impl UserData {
     pub fn get_bedtime(&self) -> &Bedtime {
         &self.bedtime
     }
+
+    pub fn set_bedtime(&mut self, bedtime: Bedtime) {
+        self.bedtime = bedtime;
+    }
 }
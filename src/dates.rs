@@ -0,0 +1,296 @@
+//! Reads dates and times the way people type them, like "tomorrow 5pm", "friday", "in 3 days", or
+//! "next monday 09:00". Anything that isn't in those words is read with the user's `date_format`
+//! and `time_format`, like before.
+
+use crate::{SparrowError, SparrowResult};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+
+/// Reads a date and maybe a time from `input`, like "friday 17:00" or "2024/05/03". Words are
+/// understood relative to `now`. If there's only a time, the date is today.
+pub fn parse_date_time(
+    input: &str,
+    date_format: &str,
+    time_format: &str,
+    now: DateTime<Local>,
+) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return None;
+    }
+    if let Some(when) = parse_relative_time(&input, now) {
+        return Some((when.date().naive_local(), Some(when.time())));
+    }
+
+    let words: Vec<&str> = input.split_whitespace().filter(|&w| w != "at").collect();
+    let today = now.date().naive_local();
+
+    // try every way of splitting the words into a date and a time, date first
+    (0..=words.len()).rev().find_map(|split| {
+        let (date, time) = (words[..split].join(" "), words[split..].join(" "));
+        let time = match time.as_str() {
+            "" => None,
+            time => Some(parse_time(time, time_format)?),
+        };
+        let date = match date.as_str() {
+            "" => time.map(|_| today)?,
+            date => parse_date(date, date_format, today)?,
+        };
+        Some((date, time))
+    })
+}
+
+/// Reads a date like "tomorrow", "fri", "next monday", "in 2 weeks", or one in `date_format`.
+/// Weekdays mean the next one after `today`.
+pub fn parse_date(input: &str, date_format: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        "yesterday" => return today.pred_opt(),
+        _ => {}
+    }
+
+    let words: Vec<&str> = input.split_whitespace().collect();
+    match words.as_slice() {
+        ["next", "week"] => return today.checked_add_signed(Duration::weeks(1)),
+        [day] | ["next", day] => {
+            if let Ok(weekday) = day.parse::<Weekday>() {
+                let ahead = (weekday.num_days_from_monday() + 7
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                let ahead = if ahead == 0 { 7 } else { ahead };
+                return today.checked_add_signed(Duration::days(ahead as i64));
+            }
+        }
+        ["in", amount, unit] => {
+            let amount = parse_amount(amount)?;
+            return match unit.trim_end_matches('s') {
+                "day" => today.checked_add_signed(Duration::days(amount)),
+                "week" => today.checked_add_signed(Duration::weeks(amount)),
+                _ => None,
+            };
+        }
+        _ => {}
+    }
+
+    NaiveDate::parse_from_str(&input, date_format).ok()
+}
+
+/// Reads a time like "5pm", "5:30 pm", "noon", "midnight", or one in `time_format`.
+pub fn parse_time(input: &str, time_format: &str) -> Option<NaiveTime> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    for (suffix, offset) in [("am", 0), ("pm", 12)].iter() {
+        if let Some(clock) = input.strip_suffix(suffix) {
+            let (hour, minute) = match clock.trim().split_once(':') {
+                Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse().ok()?),
+                None => (clock.trim().parse::<u32>().ok()?, 0),
+            };
+            if hour == 0 || hour > 12 {
+                return None;
+            }
+            return NaiveTime::from_hms_opt(hour % 12 + offset, minute, 0);
+        }
+    }
+
+    NaiveTime::parse_from_str(&input, time_format).ok()
+}
+
+/// Reads a time from now, like "in 3 hours" or "in 45 minutes".
+fn parse_relative_time(input: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    match words.as_slice() {
+        ["in", amount, unit] => {
+            let amount = parse_amount(amount)?;
+            match unit.trim_end_matches('s') {
+                "hour" | "hr" => now.checked_add_signed(Duration::hours(amount)),
+                "minute" | "min" => now.checked_add_signed(Duration::minutes(amount)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Reads a number that isn't negative, or "a" or "an" for one. It's at most `u32::MAX`, so that
+/// even that many weeks is a duration chrono can hold.
+fn parse_amount(amount: &str) -> Option<i64> {
+    match amount {
+        "a" | "an" => Some(1),
+        amount => amount.parse::<u32>().ok().map(i64::from),
+    }
+}
+
+/// Splits a timezone like "America/New_York" off the end of `input`, if there's one there.
+pub fn split_timezone(input: &str) -> (&str, Option<Tz>) {
+    let input = input.trim();
+    match input.rsplit_once(char::is_whitespace) {
+        Some((rest, zone)) => match zone.parse::<Tz>() {
+            Ok(tz) => (rest.trim_end(), Some(tz)),
+            Err(_) => (input, None),
+        },
+        None => (input, None),
+    }
+}
+
+/// Returns `date` at `time` in the local timezone, or at midnight if there's no time.
+pub fn to_local(date: NaiveDate, time: Option<NaiveTime>) -> SparrowResult<DateTime<Local>> {
+    let time = time.unwrap_or_else(|| NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| {
+            SparrowError::BasicMessage(
+                "Sorry, that time can't be converted to your local timezone.".to_string(),
+            )
+        })
+}
+
+/// Returns the last second of a day, when something given only a date is due.
+pub fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+}
+
+/// Returns when something due on `date` at `time` is due in the local timezone. Without a time,
+/// it's due by the end of the day, not at its start.
+pub fn to_local_due(date: NaiveDate, time: Option<NaiveTime>) -> SparrowResult<DateTime<Local>> {
+    to_local(date, Some(time.unwrap_or_else(end_of_day)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATE_FORMAT: &str = "%Y/%m/%d";
+    const TIME_FORMAT: &str = "%H:%M";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd(y, m, d)
+    }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms(h, m, 0)
+    }
+
+    /// Wednesday, May 1st 2024 at noon
+    fn now() -> DateTime<Local> {
+        Local.ymd(2024, 5, 1).and_hms(12, 0, 0)
+    }
+
+    fn today() -> NaiveDate {
+        date(2024, 5, 1)
+    }
+
+    fn day(input: &str) -> Option<NaiveDate> {
+        parse_date(input, DATE_FORMAT, today())
+    }
+
+    fn time_of(input: &str) -> Option<NaiveTime> {
+        parse_time(input, TIME_FORMAT)
+    }
+
+    #[test]
+    fn parses_relative_days() {
+        assert_eq!(day("today"), Some(today()));
+        assert_eq!(day("Tomorrow"), Some(date(2024, 5, 2)));
+        assert_eq!(day("yesterday"), Some(date(2024, 4, 30)));
+        assert_eq!(day("next week"), Some(date(2024, 5, 8)));
+    }
+
+    #[test]
+    fn parses_weekdays_after_today() {
+        assert_eq!(day("fri"), Some(date(2024, 5, 3)));
+        assert_eq!(day("friday"), Some(date(2024, 5, 3)));
+        assert_eq!(day("next monday"), Some(date(2024, 5, 6)));
+        // today's weekday means a week from now
+        assert_eq!(day("wed"), Some(date(2024, 5, 8)));
+    }
+
+    #[test]
+    fn parses_days_and_weeks_from_today() {
+        assert_eq!(day("in 3 days"), Some(date(2024, 5, 4)));
+        assert_eq!(day("in a day"), Some(date(2024, 5, 2)));
+        assert_eq!(day("in 2 weeks"), Some(date(2024, 5, 15)));
+        assert_eq!(day("in a week"), Some(date(2024, 5, 8)));
+        assert_eq!(day("in 3 months"), None);
+    }
+
+    #[test]
+    fn rejects_negative_and_huge_amounts() {
+        assert_eq!(day("in -3 days"), None);
+        assert_eq!(day("in 4294967295 weeks"), None);
+        assert_eq!(day("in 99999999999999999999 days"), None);
+        assert_eq!(parse_relative_time("in -2 hours", now()), None);
+        assert_eq!(parse_relative_time("in 4294967295 hours", now()), None);
+    }
+
+    #[test]
+    fn parses_dates_in_the_date_format() {
+        assert_eq!(day("2024/06/30"), Some(date(2024, 6, 30)));
+        assert_eq!(day("30/06/2024"), None);
+        assert_eq!(day("someday"), None);
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(time_of("5pm"), Some(time(17, 0)));
+        assert_eq!(time_of("5:30 pm"), Some(time(17, 30)));
+        assert_eq!(time_of("12am"), Some(time(0, 0)));
+        assert_eq!(time_of("12pm"), Some(time(12, 0)));
+        assert_eq!(time_of("Noon"), Some(time(12, 0)));
+        assert_eq!(time_of("midnight"), Some(time(0, 0)));
+        assert_eq!(time_of("09:15"), Some(time(9, 15)));
+        assert_eq!(time_of("13pm"), None);
+        assert_eq!(time_of("0am"), None);
+        assert_eq!(time_of("soon"), None);
+    }
+
+    #[test]
+    fn parses_dates_with_times() {
+        let parse = |input| parse_date_time(input, DATE_FORMAT, TIME_FORMAT, now());
+        assert_eq!(
+            parse("tomorrow 5pm"),
+            Some((date(2024, 5, 2), Some(time(17, 0))))
+        );
+        assert_eq!(
+            parse("friday at 17:00"),
+            Some((date(2024, 5, 3), Some(time(17, 0))))
+        );
+        assert_eq!(
+            parse("next monday 09:00"),
+            Some((date(2024, 5, 6), Some(time(9, 0))))
+        );
+        assert_eq!(
+            parse("2024/06/30 5:30 pm"),
+            Some((date(2024, 6, 30), Some(time(17, 30))))
+        );
+        assert_eq!(parse("in 3 days"), Some((date(2024, 5, 4), None)));
+        assert_eq!(parse("noon"), Some((today(), Some(time(12, 0)))));
+        assert_eq!(parse("in 2 hours"), Some((today(), Some(time(14, 0)))));
+        assert_eq!(parse("in 45 minutes"), Some((today(), Some(time(12, 45)))));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("whenever"), None);
+    }
+
+    #[test]
+    fn dates_without_times_are_due_at_the_end_of_the_day() {
+        assert_eq!(
+            to_local_due(date(2024, 5, 2), None).unwrap(),
+            Local.ymd(2024, 5, 2).and_hms(23, 59, 59)
+        );
+        assert_eq!(
+            to_local_due(date(2024, 5, 2), Some(time(17, 0))).unwrap(),
+            Local.ymd(2024, 5, 2).and_hms(17, 0, 0)
+        );
+        assert_eq!(
+            to_local(date(2024, 5, 2), None).unwrap(),
+            Local.ymd(2024, 5, 2).and_hms(0, 0, 0)
+        );
+    }
+}
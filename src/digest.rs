@@ -0,0 +1,90 @@
+//! A plain-text summary of one day's plan, for people who'd rather read it in their inbox than
+//! in a terminal.
+
+use crate::{EmailConfig, SparrowError, SparrowResult, UserData};
+use chrono::{Date, Local};
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, Message,
+    SmtpTransport, Transport,
+};
+use std::fmt::Write;
+
+/// Lists the pomodoro schedule and the Ivy Lee list for `day`.
+pub fn daily_digest(data: &UserData, day: Date<Local>) -> String {
+    let config = data.get_config();
+    let mut digest = String::new();
+
+    writeln!(digest, "Your plan for {}", day.format("%A, %B %-d")).unwrap();
+
+    writeln!(digest, "\nSchedule:").unwrap();
+    match data.get_pomodoro_schedule() {
+        Some(schedule) if schedule.entries_on(day).next().is_some() => {
+            for entry in schedule.entries_on(day) {
+                let span = entry.span();
+                writeln!(
+                    digest,
+                    "{} - {}\t{}",
+                    span.start().format(&config.time_format),
+                    span.end().format(&config.time_format),
+                    entry.title(data.get_tasks())
+                )
+                .unwrap();
+            }
+        }
+        Some(_) => writeln!(digest, "Nothing scheduled.").unwrap(),
+        None => writeln!(
+            digest,
+            "No schedule yet. Make one with `sparrow make pomodoro`."
+        )
+        .unwrap(),
+    }
+
+    writeln!(digest, "\nIvy Lee list:").unwrap();
+    match data
+        .get_ivy_lee_schedule()
+        .as_ref()
+        .map(|s| s.tasks_on(day.naive_local()))
+    {
+        Some(Some(tasks)) => {
+            for (i, t) in tasks.iter().enumerate() {
+                writeln!(digest, "{}. {}", i + 1, t.label(data.get_tasks())).unwrap();
+            }
+        }
+        Some(None) => writeln!(digest, "Nothing to do. Enjoy your day off!").unwrap(),
+        None => writeln!(digest, "No list yet. Make one with `sparrow make ivylee`.").unwrap(),
+    }
+
+    digest
+}
+
+/// Emails `body` to the address in `email`.
+pub fn send_email(email: &EmailConfig, subject: &str, body: String) -> SparrowResult<()> {
+    let to_error = |e: &dyn std::fmt::Display| {
+        SparrowError::BasicMessage(format!("couldn't send the email: {}", e))
+    };
+
+    let message = Message::builder()
+        .from(email.from.parse().map_err(|e| to_error(&e))?)
+        .to(email.to.parse().map_err(|e| to_error(&e))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| to_error(&e))?;
+
+    let builder = if email.smtp_port == 465 {
+        SmtpTransport::relay(&email.smtp_host)
+    } else {
+        SmtpTransport::starttls_relay(&email.smtp_host)
+    }
+    .map_err(|e| to_error(&e))?;
+    let mailer = builder
+        .port(email.smtp_port)
+        .credentials(Credentials::new(
+            email.username.clone(),
+            email.password.clone(),
+        ))
+        .build();
+
+    mailer.send(&message).map_err(|e| to_error(&e))?;
+    Ok(())
+}
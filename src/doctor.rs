@@ -0,0 +1,395 @@
+//! `sparrow doctor` checks everything sparrow depends on and suggests fixes for what's wrong.
+
+use crate::{focus::FocusBackend, methods::pomodoro::PomodoroScheduleEntry, storage, UserData};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Duration, Local, Offset};
+use std::{
+    fmt::{Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Notification daemons that libnotify's notifications can be shown by.
+const NOTIFICATION_DAEMONS: &[&str] = &[
+    "dunst",
+    "mako",
+    "gnome-shell",
+    "plasmashell",
+    "xfce4-notifyd",
+    "swaync",
+    "notify-osd",
+    "fnott",
+    "lxqt-notificationd",
+    "mate-notification-daemon",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+
+    /// What to do about a warning or failure.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Runs every check against the data file at `data_path`.
+pub fn run(data_path: &Path) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let processes = running_processes();
+
+    // read the file itself rather than going through the cache, which could hide a broken file
+    let data = if !data_path.exists() {
+        checks.push(Check::warn(
+            "data file",
+            format!("{} doesn't exist yet", data_path.display()),
+            "add a task with `sparrow add task` to create it",
+        ));
+        None
+    } else {
+        match storage::for_path(data_path)
+            .and_then(|s| s.load())
+            .and_then(|mut d| {
+                if let Some(config) = storage::load_config(data_path)? {
+                    *d.get_config_mut() = config;
+                }
+                Ok(d)
+            })
+            .map_err(|e| e.to_string())
+        {
+            Ok(d) => {
+                checks.push(Check::pass(
+                    "data file",
+                    format!("{} is readable and valid", data_path.display()),
+                ));
+                Some(d)
+            }
+            Err(e) => {
+                checks.push(Check::fail(
+                    "data file",
+                    format!("{} can't be read: {}", data_path.display(), e),
+                    "fix the file by hand, or restore it from a backup",
+                ));
+                None
+            }
+        }
+    };
+
+    if let Some(data) = &data {
+        checks.push(check_config(data));
+        checks.push(check_schedule(data));
+    }
+    checks.push(check_daemon(data_path, &processes));
+    checks.push(check_notifications(&processes));
+    if let Some(backend) = data
+        .as_ref()
+        .and_then(|d| d.get_config().focus_mode.as_ref())
+    {
+        checks.push(check_focus_mode(backend));
+    }
+    checks.push(check_timezone());
+
+    checks
+}
+
+fn check_config(data: &UserData) -> Check {
+    let config = data.get_config();
+    let mut problems = Vec::new();
+
+    if config.work_minutes == 0 {
+        problems.push("work_minutes is 0");
+    }
+    if config.work_periods_per_job_session == 0 {
+        problems.push("work_periods_per_job_session is 0");
+    }
+    if config.ivy_lee_tasks_per_day == 0 {
+        problems.push("ivy_lee_tasks_per_day is 0");
+    }
+    if config.skip_days.len() >= 7 {
+        problems.push("every day is a skip day");
+    }
+    if !is_valid_format(&config.date_format) {
+        problems.push("date_format isn't a valid format");
+    }
+    if !is_valid_format(&config.time_format) {
+        problems.push("time_format isn't a valid format");
+    }
+    let bedtime = data.get_bedtime();
+    let mut sleep_hours = std::iter::once(bedtime.duration_hours())
+        .chain(bedtime.overrides().iter().map(|o| o.hours));
+    if sleep_hours.any(|h| h <= 0.0 || h >= 24.0) {
+        problems.push("sleep doesn't last between 0 and 24 hours");
+    }
+
+    if problems.is_empty() {
+        Check::pass("config", "settings make sense")
+    } else {
+        Check::fail(
+            "config",
+            problems.join(", "),
+            "fix these settings in the config section of your data file",
+        )
+    }
+}
+
+fn check_schedule(data: &UserData) -> Check {
+    let now = Local::now();
+    let schedule = match data.get_pomodoro_schedule() {
+        Some(s) => s,
+        None => {
+            return Check::warn(
+                "schedule",
+                "there's no pomodoro schedule",
+                "make one with `sparrow make pomodoro`",
+            )
+        }
+    };
+
+    let last_end = schedule.get_entries().iter().map(|e| e.span().end()).max();
+    if last_end.is_none_or(|end| end <= now) {
+        return Check::warn(
+            "schedule",
+            "the pomodoro schedule is over",
+            "make a new one with `sparrow make pomodoro`",
+        );
+    }
+
+    let unscheduled: Vec<&str> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| !t.done && !t.is_past_due(&now))
+        .filter(|t| {
+            !schedule.get_entries().iter().any(
+                |e| matches!(e, PomodoroScheduleEntry::Job { task_id, .. } if *task_id == t.id),
+            )
+        })
+        .map(|t| t.name.as_str())
+        .collect();
+    if unscheduled.is_empty() {
+        Check::pass("schedule", "the pomodoro schedule is current")
+    } else {
+        Check::warn(
+            "schedule",
+            format!(
+                "these tasks have no work scheduled: {}",
+                unscheduled.join(", ")
+            ),
+            "remake the schedule with `sparrow make pomodoro` if they were added since",
+        )
+    }
+}
+
+fn check_daemon(data_path: &Path, processes: &[Vec<String>]) -> Check {
+    let daemons: Vec<&Vec<String>> = processes
+        .iter()
+        .filter(|args| program_name(args) == Some("sparrowd"))
+        .collect();
+    if daemons.is_empty() {
+        return Check::warn(
+            "daemon",
+            "sparrowd isn't running",
+            "start sparrowd (for example, from your window manager's autostart) to get notified",
+        );
+    }
+
+    let wanted = fs::canonicalize(data_path).unwrap_or_else(|_| data_path.to_path_buf());
+    let watching_right_file = daemons.iter().any(|args| {
+        let watched = daemon_file(args);
+        fs::canonicalize(&watched).unwrap_or(watched) == wanted
+    });
+    if watching_right_file {
+        Check::pass("daemon", "sparrowd is running and watching this data file")
+    } else {
+        Check::fail(
+            "daemon",
+            "sparrowd is running, but watching a different data file",
+            format!("restart it with `sparrowd -f {}`", data_path.display()),
+        )
+    }
+}
+
+fn check_notifications(processes: &[Vec<String>]) -> Check {
+    if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_none() {
+        return Check::fail(
+            "notifications",
+            "there's no D-Bus session bus, so notifications can't be sent",
+            "run sparrowd from inside your desktop session",
+        );
+    }
+
+    let daemon = processes
+        .iter()
+        .filter_map(|args| program_name(args))
+        .find(|name| NOTIFICATION_DAEMONS.contains(name));
+    match daemon {
+        Some(name) => Check::pass("notifications", format!("{} can show notifications", name)),
+        None => Check::warn(
+            "notifications",
+            "no known notification daemon is running",
+            "install and start one, like dunst or mako, if notifications don't show up",
+        ),
+    }
+}
+
+fn check_focus_mode(backend: &FocusBackend) -> Check {
+    let program = match backend {
+        FocusBackend::Gnome => "gsettings",
+        FocusBackend::Dunst => "dunstctl",
+        FocusBackend::Mako => "makoctl",
+        FocusBackend::Custom { on, .. } => match on.first() {
+            Some(p) => p.as_str(),
+            None => {
+                return Check::fail(
+                    "focus mode",
+                    "the custom command to turn focus mode on is empty",
+                    "fill in `focus_mode` in your config",
+                )
+            }
+        },
+    };
+
+    if is_on_path(program) {
+        Check::pass("focus mode", format!("{} is installed", program))
+    } else {
+        Check::fail(
+            "focus mode",
+            format!("{} isn't installed", program),
+            "install it, or change `focus_mode` in your config",
+        )
+    }
+}
+
+fn check_timezone() -> Check {
+    let now = Local::now();
+    let offset = now.offset().fix();
+    let next_week = now + Duration::weeks(1);
+    if next_week.offset().fix() != offset {
+        Check::warn(
+            "timezone",
+            format!(
+                "the clocks change in the next week (UTC{} now, UTC{} then)",
+                offset,
+                next_week.offset().fix()
+            ),
+            "check your schedule around the change, and remake it if anything looks off",
+        )
+    } else if std::env::var_os("TZ").is_none() && !Path::new("/etc/localtime").exists() {
+        Check::warn(
+            "timezone",
+            "no timezone is set, so times are in UTC",
+            "set the TZ environment variable or your system's timezone",
+        )
+    } else {
+        Check::pass("timezone", format!("local time is UTC{}", offset))
+    }
+}
+
+/// Returns false if `format` has anything chrono doesn't understand.
+fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|i| matches!(i, Item::Error))
+}
+
+fn is_on_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).exists();
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Returns the command line of every running process that can be read.
+fn running_processes() -> Vec<Vec<String>> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|e| fs::read(e.ok()?.path().join("cmdline")).ok())
+        .filter(|cmdline| !cmdline.is_empty())
+        .map(|cmdline| {
+            cmdline
+                .split(|b| *b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
+        })
+        .collect()
+}
+
+fn program_name(args: &[String]) -> Option<&str> {
+    Path::new(args.first()?).file_name()?.to_str()
+}
+
+/// Returns the data file a sparrowd process was started with.
+fn daemon_file(args: &[String]) -> PathBuf {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-f" || arg == "--file" {
+            if let Some(path) = args.next() {
+                return PathBuf::from(path);
+            }
+        } else if let Some(path) = arg.strip_prefix("--file=") {
+            return PathBuf::from(path);
+        }
+    }
+    storage::default_data_path()
+}
+
+/// Lists checks with their outcomes and fixes.
+pub struct DoctorDisplay<'a> {
+    pub checks: &'a [Check],
+}
+
+impl Display for DoctorDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for c in self.checks {
+            let tag = match c.outcome {
+                Outcome::Pass => "ok  ",
+                Outcome::Warn => "warn",
+                Outcome::Fail => "FAIL",
+            };
+            writeln!(f, "[{}] {}: {}", tag, c.name, c.detail)?;
+            if let Some(fix) = &c.fix {
+                writeln!(f, "\t-> {}", fix)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -7,20 +7,44 @@ pub type SparrowResult<T> = Result<T, SparrowError>;
 #[derive(Debug)]
 pub enum SparrowError {
     InputCanceled,
+    ScheduleCanceled,
     BasicMessage(String),
     Io(io::Error),
     ChronoParse(chrono::ParseError),
     YamlError(serde_yaml::Error),
+    JsonError(serde_json::Error),
+    TomlError(toml::de::Error),
+    TomlWriteError(toml::ser::Error),
+    Http(Box<ureq::Error>),
+
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+
+    /// Tasks that depend on each other in a circle, by name, starting and ending with the same
+    /// task.
+    DependencyCycle(Vec<String>),
 }
 
 impl Display for SparrowError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::InputCanceled => write!(f, "input canceled"),
+            Self::ScheduleCanceled => write!(f, "schedule generation canceled"),
             Self::BasicMessage(b) => write!(f, "sparrow hit an error: {}", b),
             Self::Io(i) => write!(f, "there was an i/o error: {}", i),
             Self::ChronoParse(e) => e.fmt(f),
             Self::YamlError(y) => y.fmt(f),
+            Self::JsonError(j) => j.fmt(f),
+            Self::TomlError(t) => t.fmt(f),
+            Self::TomlWriteError(t) => t.fmt(f),
+            Self::Http(h) => write!(f, "there was an http error: {}", h),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(s) => write!(f, "there was a database error: {}", s),
+            Self::DependencyCycle(names) => write!(
+                f,
+                "tasks can't depend on each other in a circle: {}",
+                names.join(" -> ")
+            ),
         }
     }
 }
@@ -44,3 +68,34 @@ impl From<serde_yaml::Error> for SparrowError {
         Self::YamlError(e)
     }
 }
+
+impl From<serde_json::Error> for SparrowError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::JsonError(e)
+    }
+}
+
+impl From<toml::de::Error> for SparrowError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::TomlError(e)
+    }
+}
+
+impl From<toml::ser::Error> for SparrowError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::TomlWriteError(e)
+    }
+}
+
+impl From<ureq::Error> for SparrowError {
+    fn from(e: ureq::Error) -> Self {
+        Self::Http(Box::new(e))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for SparrowError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Sqlite(e)
+    }
+}
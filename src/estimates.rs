@@ -0,0 +1,158 @@
+//! Learns how far off estimates run, from the time tracked on finished tasks, so that schedules can
+//! set aside the time tasks like them actually take.
+
+use crate::{
+    metrics::format_minutes,
+    tracking::{self, TimeEntry},
+    Repeat, Task,
+};
+use chrono::Local;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// How many finished tasks a course or tag needs before its correction is trusted.
+const MIN_TASKS: usize = 2;
+
+/// Corrections are kept between these, so that one odd task can't wreck a schedule.
+const MIN_FACTOR: f64 = 0.5;
+const MAX_FACTOR: f64 = 3.0;
+
+/// What to multiply estimates by, for tasks of each course and tag. 1.5 means tasks took half again
+/// as long as estimated.
+#[derive(Clone, Debug, Default)]
+pub struct EstimateCorrections {
+    pub by_course: BTreeMap<String, f64>,
+    pub by_tag: BTreeMap<String, f64>,
+
+    /// The correction for every finished task, for tasks without a course or tag of their own.
+    pub overall: Option<f64>,
+}
+
+/// Estimated and tracked minutes of finished tasks.
+#[derive(Default)]
+struct Totals {
+    tasks: usize,
+    estimated: u64,
+    tracked: i64,
+}
+
+impl Totals {
+    fn add(&mut self, estimated: u64, tracked: i64) {
+        self.tasks += 1;
+        self.estimated += estimated;
+        self.tracked += tracked;
+    }
+
+    fn factor(&self) -> Option<f64> {
+        if self.tasks < MIN_TASKS || self.estimated == 0 {
+            return None;
+        }
+        Some((self.tracked as f64 / self.estimated as f64).clamp(MIN_FACTOR, MAX_FACTOR))
+    }
+}
+
+impl EstimateCorrections {
+    /// Compares the estimates of finished `tasks` with the time tracked on them in `entries`.
+    /// Tasks without any tracked time are left out, since there's nothing to compare.
+    pub fn learn(tasks: &[Task], entries: &[TimeEntry]) -> Self {
+        let now = Local::now();
+        let mut overall = Totals::default();
+        let mut by_course: BTreeMap<String, Totals> = BTreeMap::new();
+        let mut by_tag: BTreeMap<String, Totals> = BTreeMap::new();
+
+        // a repeating task's estimate is for each instance, but its time is tracked all together
+        for task in tasks.iter().filter(|t| t.done && t.repeat == Repeat::No) {
+            let tracked = tracking::tracked_minutes(entries, task.id, now);
+            let estimated = task.duration.total_minutes();
+            if tracked == 0 || estimated == 0 {
+                continue;
+            }
+
+            overall.add(estimated, tracked);
+            if let Some(course) = &task.course {
+                by_course
+                    .entry(course.clone())
+                    .or_default()
+                    .add(estimated, tracked);
+            }
+            for tag in &task.tags {
+                by_tag
+                    .entry(tag.clone())
+                    .or_default()
+                    .add(estimated, tracked);
+            }
+        }
+
+        let factors = |totals: BTreeMap<String, Totals>| {
+            totals
+                .into_iter()
+                .filter_map(|(name, t)| Some((name, t.factor()?)))
+                .collect()
+        };
+        Self {
+            by_course: factors(by_course),
+            by_tag: factors(by_tag),
+            overall: overall.factor(),
+        }
+    }
+
+    /// Returns what to multiply `task`'s estimate by: the correction for its course, or else the
+    /// average of its tags' corrections, or else the overall one. Without any, it's 1.
+    pub fn factor(&self, task: &Task) -> f64 {
+        if let Some(f) = task.course.as_ref().and_then(|c| self.by_course.get(c)) {
+            return *f;
+        }
+
+        let tags: Vec<f64> = task
+            .tags
+            .iter()
+            .filter_map(|t| self.by_tag.get(t))
+            .copied()
+            .collect();
+        if !tags.is_empty() {
+            return tags.iter().sum::<f64>() / tags.len() as f64;
+        }
+
+        self.overall.unwrap_or(1.0)
+    }
+
+    /// Returns `minutes` of `task`'s estimate, corrected.
+    pub fn correct(&self, task: &Task, minutes: u64) -> u64 {
+        (minutes as f64 * self.factor(task)).round() as u64
+    }
+}
+
+/// Describes a correction, like "take 1.5x as long as estimated (an hour becomes 1h 30m)".
+fn describe(factor: f64) -> String {
+    format!(
+        "take {:.2}x as long as estimated (an hour becomes {})",
+        factor,
+        format_minutes((60.0 * factor).round() as i64)
+    )
+}
+
+impl Display for EstimateCorrections {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let overall = match self.overall {
+            Some(o) => o,
+            None => {
+                return writeln!(
+                    f,
+                    "Not enough tracked time on finished tasks to learn from yet. Track work with \
+                     `sparrow start` and `sparrow stop`."
+                )
+            }
+        };
+
+        writeln!(f, "Tasks {}", describe(overall))?;
+        for (course, factor) in &self.by_course {
+            writeln!(f, "-\t{} tasks {}", course, describe(*factor))?;
+        }
+        for (tag, factor) in &self.by_tag {
+            writeln!(f, "-\tTasks tagged {} {}", tag, describe(*factor))?;
+        }
+        Ok(())
+    }
+}
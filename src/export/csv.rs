@@ -0,0 +1,49 @@
+//! Writes tasks as CSV, for editing in a spreadsheet and importing again with
+//! `sparrow import csv tasks`. The columns are:
+//!
+//! - `name`: the task's name
+//! - `due`: when the task is due, like "2024-01-31 17:00"
+//! - `minutes`: how long the task should take, including all subtasks
+//! - `done`: `true` or `false`
+//! - `tags`: the task's tags, separated by spaces
+
+use crate::UserData;
+
+/// The header row of a task CSV.
+pub const TASK_COLUMNS: [&str; 5] = ["name", "due", "minutes", "done", "tags"];
+
+/// How the `due` column is written. Spreadsheets recognize this as a date and time.
+pub const DUE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+pub fn export_tasks(data: &UserData) -> String {
+    let mut csv = String::new();
+    write_row(&mut csv, &TASK_COLUMNS);
+    for task in data.get_tasks() {
+        write_row(
+            &mut csv,
+            &[
+                &task.name,
+                &task.due_date.format(DUE_FORMAT).to_string(),
+                &task.duration.total_minutes().to_string(),
+                &task.done.to_string(),
+                &task.tags.join(" "),
+            ],
+        );
+    }
+    csv
+}
+
+fn write_row(csv: &mut String, fields: &[&str]) {
+    let fields: Vec<String> = fields.iter().map(|f| quote(f)).collect();
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
+
+/// Quotes `field` if it has a comma, quote, or line break in it.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
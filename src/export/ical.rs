@@ -0,0 +1,376 @@
+//! Writes schedules as iCalendar (.ics) files, which calendar apps can import or subscribe to.
+
+use super::utc_timestamp;
+use crate::{
+    methods::pomodoro::PomodoroScheduleEntry, CalendarEvent, CalendarEventType, Repeat, UserData,
+};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+
+/// iCalendar lines longer than this many bytes must be folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// A VEVENT, before it's written.
+struct VEvent<'a> {
+    uid: String,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    summary: &'a str,
+    repeat: &'a Repeat,
+
+    /// When the last repeat starts, if the event stops repeating.
+    last_start: Option<DateTime<Local>>,
+
+    /// Days the event doesn't happen on, though it repeats then.
+    exceptions: &'a [NaiveDate],
+
+    /// The timezone a repeating event keeps its time of day in, if not the local one.
+    timezone: Option<Tz>,
+}
+
+/// Returns a calendar with an event for each pomodoro schedule entry, except sleep.
+pub fn schedule_calendar(data: &UserData) -> String {
+    let entries = match data.get_pomodoro_schedule() {
+        Some(schedule) => schedule.get_entries(),
+        None => &[],
+    };
+
+    let mut calendar = begin_calendar();
+    for entry in entries {
+        if let PomodoroScheduleEntry::Sleep(_) = entry {
+            continue;
+        }
+        write_entry(&mut calendar, data, entry);
+    }
+    end_calendar(calendar)
+}
+
+/// Returns a calendar with the pomodoro schedule's work and breaks, and the user's events, breaks,
+/// and sleep. Events that repeat are written once, with a rule for how they repeat, instead of
+/// once for each time they come up in the schedule.
+pub fn pomodoro_calendar(data: &UserData) -> String {
+    let mut calendar = begin_calendar();
+    write_pomodoro(&mut calendar, data);
+    end_calendar(calendar)
+}
+
+/// Returns a calendar with the earliest-deadline-first schedule, and the user's events, breaks,
+/// and sleep.
+pub fn edf_calendar(data: &UserData) -> String {
+    let entries = match data.get_edf_schedule() {
+        Some(schedule) => schedule.get_entries(),
+        None => &[],
+    };
+    let mut calendar = begin_calendar();
+    write_schedule(&mut calendar, data, entries);
+    end_calendar(calendar)
+}
+
+/// Returns a calendar with the eat-the-frog schedule, and the user's events, breaks, and sleep.
+pub fn frog_calendar(data: &UserData) -> String {
+    let entries = match data.get_frog_schedule() {
+        Some(schedule) => schedule.get_entries(),
+        None => &[],
+    };
+    let mut calendar = begin_calendar();
+    write_schedule(&mut calendar, data, entries);
+    end_calendar(calendar)
+}
+
+/// Returns a calendar with the time blocks, and the user's events, breaks, and sleep.
+pub fn time_block_calendar(data: &UserData) -> String {
+    let entries = match data.get_time_block_schedule() {
+        Some(schedule) => schedule.get_entries(),
+        None => &[],
+    };
+    let mut calendar = begin_calendar();
+    write_schedule(&mut calendar, data, entries);
+    end_calendar(calendar)
+}
+
+/// Returns a calendar with each day's Ivy Lee list, as all-day events in order of importance.
+pub fn ivy_lee_calendar(data: &UserData) -> String {
+    let mut calendar = begin_calendar();
+    write_ivy_lee(&mut calendar, data);
+    end_calendar(calendar)
+}
+
+/// Returns a calendar with both the pomodoro schedule and the Ivy Lee lists.
+pub fn combined_calendar(data: &UserData) -> String {
+    let mut calendar = begin_calendar();
+    write_pomodoro(&mut calendar, data);
+    write_ivy_lee(&mut calendar, data);
+    end_calendar(calendar)
+}
+
+fn write_pomodoro(calendar: &mut String, data: &UserData) {
+    let entries = match data.get_pomodoro_schedule() {
+        Some(schedule) => schedule.get_entries(),
+        None => &[],
+    };
+    write_schedule(calendar, data, entries);
+}
+
+/// Writes the user's events and sleep, and the entries in `entries` that didn't come from them.
+fn write_schedule(calendar: &mut String, data: &UserData, entries: &[PomodoroScheduleEntry]) {
+    let events = data.all_events();
+    for event in &events {
+        let name: String = event_summary(event)
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect();
+        write_vevent(
+            calendar,
+            &VEvent {
+                uid: format!(
+                    "{}-event-{}",
+                    utc_timestamp(*event.time_span.start()),
+                    name.to_lowercase()
+                ),
+                start: *event.time_span.start(),
+                end: event.time_span.end(),
+                summary: event_summary(event),
+                repeat: &event.repeat,
+                last_start: event.last_start(),
+                exceptions: &event.exceptions,
+                timezone: event.tz(),
+            },
+        );
+    }
+
+    let bedtime = data.get_bedtime();
+    if bedtime.overrides().is_empty() {
+        if let Some(sleep_start) = Local::today().and_time(*bedtime.start()) {
+            write_vevent(
+                calendar,
+                &VEvent {
+                    uid: "sleep".to_string(),
+                    start: sleep_start,
+                    end: sleep_start + Duration::minutes((bedtime.duration_hours() * 60.0) as i64),
+                    summary: "Sleep",
+                    repeat: &Repeat::Daily,
+                    last_start: None,
+                    exceptions: &[],
+                    timezone: None,
+                },
+            );
+        }
+    } else {
+        // sleep that's different on some nights is written as a weekly event for each night
+        let today = Local::today().naive_local();
+        for night in (0..7).map(|d| today + Duration::days(d)) {
+            if let Some(sleep) = bedtime.on_night(night) {
+                write_vevent(
+                    calendar,
+                    &VEvent {
+                        uid: format!("sleep-{}", night.weekday().to_string().to_lowercase()),
+                        start: *sleep.start(),
+                        end: sleep.end(),
+                        summary: "Sleep",
+                        repeat: &Repeat::Weekly,
+                        last_start: None,
+                        exceptions: &[],
+                        timezone: None,
+                    },
+                );
+            }
+        }
+    }
+
+    for entry in entries {
+        // everything else was written from the events it came from
+        let is_generated = match entry {
+            PomodoroScheduleEntry::Job { .. } => true,
+            PomodoroScheduleEntry::Break(span) => !events.iter().any(|e| {
+                e.event_type == CalendarEventType::Break
+                    && e.occurrences(*span.start(), span.end()).any(|o| o == *span)
+            }),
+            _ => false,
+        };
+        if is_generated {
+            write_entry(calendar, data, entry);
+        }
+    }
+}
+
+fn write_ivy_lee(calendar: &mut String, data: &UserData) {
+    if let Some(schedule) = data.get_ivy_lee_schedule() {
+        for (date, tasks) in schedule.days() {
+            for (i, task) in tasks.iter().enumerate() {
+                let label = task.label(data.get_tasks());
+                write_all_day(calendar, date, i, &format!("{}. {}", i + 1, label));
+            }
+        }
+    }
+}
+
+fn begin_calendar() -> String {
+    let mut calendar = String::new();
+    write_line(&mut calendar, "BEGIN:VCALENDAR");
+    write_line(&mut calendar, "VERSION:2.0");
+    write_line(&mut calendar, "PRODID:-//sparrow//sparrow//EN");
+    write_line(&mut calendar, "X-WR-CALNAME:sparrow");
+    calendar
+}
+
+fn end_calendar(mut calendar: String) -> String {
+    write_line(&mut calendar, "END:VCALENDAR");
+    calendar
+}
+
+fn event_summary(event: &CalendarEvent) -> &str {
+    match event.event_type {
+        CalendarEventType::Break => "Break",
+        _ => &event.name,
+    }
+}
+
+fn write_entry(calendar: &mut String, data: &UserData, entry: &PomodoroScheduleEntry) {
+    // an entry is the only one of its kind starting when it does, so this stays the same
+    // across remakes of the schedule as long as the entry doesn't move
+    write_vevent(
+        calendar,
+        &VEvent {
+            uid: format!("{}-{}", utc_timestamp(*entry.span().start()), entry.kind()),
+            start: *entry.span().start(),
+            end: entry.span().end(),
+            summary: &entry.title(data.get_tasks()),
+            repeat: &Repeat::No,
+            last_start: None,
+            exceptions: &[],
+            timezone: None,
+        },
+    );
+}
+
+fn write_vevent(calendar: &mut String, event: &VEvent) {
+    write_line(calendar, "BEGIN:VEVENT");
+    write_line(calendar, &format!("UID:{}@sparrow", event.uid));
+    write_line(
+        calendar,
+        &format!("DTSTAMP:{}", utc_timestamp(Local::now())),
+    );
+
+    let rule = match *event.repeat {
+        Repeat::No => None,
+        Repeat::Daily => Some("FREQ=DAILY".to_string()),
+        Repeat::Weekly => Some("FREQ=WEEKLY".to_string()),
+
+        // the last of days 28 through `day_of_month` that a month has, like sparrow does for
+        // months that are too short
+        Repeat::Monthly { day_of_month } if day_of_month > 28 => Some(format!(
+            "FREQ=MONTHLY;BYMONTHDAY={};BYSETPOS=-1",
+            (28..=day_of_month)
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        )),
+        Repeat::Monthly { day_of_month } => {
+            Some(format!("FREQ=MONTHLY;BYMONTHDAY={}", day_of_month))
+        }
+        Repeat::Yearly => Some("FREQ=YEARLY".to_string()),
+        Repeat::EveryNDays(every) => Some(format!("FREQ=DAILY;INTERVAL={}", every)),
+        Repeat::EveryNWeeks(every) => Some(format!("FREQ=WEEKLY;INTERVAL={}", every)),
+    };
+    match rule {
+        Some(rule) => {
+            // repeats are written in floating (local) time, or in their own timezone, so that they
+            // stay at the same time of day across daylight saving changes, like sparrow's do
+            let (tzid, start, end, until) = match event.timezone {
+                Some(tz) => (
+                    format!(";TZID={}", tz.name()),
+                    event.start.with_timezone(&tz).naive_local(),
+                    event.end.with_timezone(&tz).naive_local(),
+                    event.last_start.map(utc_timestamp),
+                ),
+                None => (
+                    String::new(),
+                    event.start.naive_local(),
+                    event.end.naive_local(),
+                    event
+                        .last_start
+                        .map(|l| floating_timestamp(l.naive_local())),
+                ),
+            };
+            write_line(
+                calendar,
+                &format!("DTSTART{}:{}", tzid, floating_timestamp(start)),
+            );
+            write_line(
+                calendar,
+                &format!("DTEND{}:{}", tzid, floating_timestamp(end)),
+            );
+            match until {
+                Some(until) => write_line(calendar, &format!("RRULE:{};UNTIL={}", rule, until)),
+                None => write_line(calendar, &format!("RRULE:{}", rule)),
+            }
+            for day in event.exceptions {
+                write_line(
+                    calendar,
+                    &format!(
+                        "EXDATE{}:{}",
+                        tzid,
+                        floating_timestamp(day.and_time(start.time()))
+                    ),
+                );
+            }
+        }
+        None => {
+            write_line(calendar, &format!("DTSTART:{}", utc_timestamp(event.start)));
+            write_line(calendar, &format!("DTEND:{}", utc_timestamp(event.end)));
+        }
+    }
+
+    write_line(calendar, &format!("SUMMARY:{}", escape_text(event.summary)));
+    write_line(calendar, "END:VEVENT");
+}
+
+/// Writes an all-day event on `date`. `index` tells apart events on the same day.
+fn write_all_day(calendar: &mut String, date: NaiveDate, index: usize, summary: &str) {
+    write_line(calendar, "BEGIN:VEVENT");
+    write_line(
+        calendar,
+        &format!("UID:{}-ivylee-{}@sparrow", date.format("%Y%m%d"), index),
+    );
+    write_line(
+        calendar,
+        &format!("DTSTAMP:{}", utc_timestamp(Local::now())),
+    );
+    write_line(
+        calendar,
+        &format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+    );
+    write_line(
+        calendar,
+        &format!("DTEND;VALUE=DATE:{}", date.succ().format("%Y%m%d")),
+    );
+    write_line(calendar, &format!("SUMMARY:{}", escape_text(summary)));
+    write_line(calendar, "END:VEVENT");
+}
+
+/// Formats `time` like "20240131T170000", without a timezone.
+fn floating_timestamp(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Escapes characters that mean something in iCalendar text values.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Writes `line`, folding it onto continuation lines (which start with a space) if it's too long.
+fn write_line(calendar: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            calendar.push_str("\r\n ");
+            length = 1;
+        }
+        calendar.push(c);
+        length += c.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
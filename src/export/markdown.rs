@@ -0,0 +1,55 @@
+//! Writes one day's plan as a Markdown checklist, for pasting into a daily note. Entries that are
+//! done, or already over, are checked off.
+
+use crate::{methods::pomodoro::JobStatus, UserData};
+use chrono::{Date, Local};
+use std::fmt::Write;
+
+/// Lists the pomodoro schedule and the Ivy Lee list for `day`.
+pub fn export(data: &UserData, day: Date<Local>) -> String {
+    let now = Local::now();
+    let config = data.get_config();
+    let check = |done: bool| if done { "[x]" } else { "[ ]" };
+    let mut md = format!("# {}\n", day.format("%A, %B %-d"));
+
+    writeln!(md, "\n## Schedule\n").unwrap();
+    match data.get_pomodoro_schedule() {
+        Some(schedule) if schedule.entries_on(day).next().is_some() => {
+            for entry in schedule.entries_on(day) {
+                let span = entry.span();
+                let done = span.end() <= now || entry.status() == Some(JobStatus::Done);
+                writeln!(
+                    md,
+                    "- {} {} - {} {}",
+                    check(done),
+                    span.start().format(&config.time_format),
+                    span.end().format(&config.time_format),
+                    entry.title(data.get_tasks())
+                )
+                .unwrap();
+            }
+        }
+        _ => writeln!(md, "Nothing scheduled.").unwrap(),
+    }
+
+    writeln!(md, "\n## Ivy Lee list\n").unwrap();
+    match data
+        .get_ivy_lee_schedule()
+        .as_ref()
+        .and_then(|s| s.tasks_on(day.naive_local()))
+    {
+        Some(entries) => {
+            for (i, entry) in entries.iter().enumerate() {
+                let done = day < now.date()
+                    || entry
+                        .task(data.get_tasks())
+                        .is_some_and(|t| t.next_due_date(now).is_none());
+                let label = entry.label(data.get_tasks());
+                writeln!(md, "{}. {} {}", i + 1, check(done), label).unwrap();
+            }
+        }
+        None => writeln!(md, "Nothing to do.").unwrap(),
+    }
+
+    md
+}
@@ -0,0 +1,18 @@
+//! Writes sparrow's data out in formats other programs understand.
+
+pub mod csv;
+pub mod ical;
+pub mod markdown;
+pub mod org;
+pub mod remind;
+pub mod taskwarrior;
+pub mod timewarrior;
+
+use chrono::{DateTime, Local, Utc};
+
+/// Formats `time` like "20240131T170000Z", as both iCalendar and timewarrior write times in UTC.
+fn utc_timestamp(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
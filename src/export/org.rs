@@ -0,0 +1,147 @@
+//! Writes tasks and the pomodoro schedule as an Org file, for org-agenda. Tasks are TODO headings
+//! with deadlines, and schedule entries are headings with active timestamps, so both show up in
+//! the agenda once the file is in `org-agenda-files`.
+
+use crate::{
+    methods::pomodoro::{JobStatus, PomodoroScheduleEntry},
+    task::TaskDuration,
+    Repeat, Task, TimeSpan, UserData,
+};
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+
+pub fn export(data: &UserData) -> String {
+    let now = Local::now();
+    let mut org = String::from("#+TITLE: sparrow\n# made by `sparrow export org`\n");
+
+    writeln!(org, "\n* Tasks").unwrap();
+    for task in data.get_tasks() {
+        write_task(&mut org, task, now);
+    }
+
+    writeln!(org, "\n* Schedule").unwrap();
+    if let Some(schedule) = data.get_pomodoro_schedule() {
+        for entry in schedule.get_entries() {
+            // sleep would only clutter the agenda
+            if let PomodoroScheduleEntry::Sleep(_) = entry {
+                continue;
+            }
+
+            let keyword = match entry.status() {
+                Some(JobStatus::Done) => "DONE ",
+                Some(JobStatus::Skipped) => "CANCELLED ",
+                _ => "",
+            };
+            writeln!(
+                org,
+                "** {}{}\n   {}",
+                keyword,
+                entry.title(data.get_tasks()),
+                timestamp_range(entry.span())
+            )
+            .unwrap();
+        }
+    }
+
+    org
+}
+
+fn write_task(org: &mut String, task: &Task, now: DateTime<Local>) {
+    let done = task.next_due_date(now).is_none();
+    write!(
+        org,
+        "** {} {}",
+        if done { "DONE" } else { "TODO" },
+        task.name
+    )
+    .unwrap();
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|t| org_tag(t)).collect();
+        write!(org, "  :{}:", tags.join(":")).unwrap();
+    }
+
+    // repeating tasks get a repeater, so org moves the deadline along when they're checked off
+    let due = task.next_due_date(now).unwrap_or(task.due_date);
+    let repeater = match task.repeat {
+        Repeat::No => String::new(),
+        Repeat::Daily => " +1d".to_string(),
+        Repeat::Weekly => " +1w".to_string(),
+        Repeat::Monthly { .. } => " +1m".to_string(),
+        Repeat::Yearly => " +1y".to_string(),
+        Repeat::EveryNDays(every) => format!(" +{}d", every),
+        Repeat::EveryNWeeks(every) => format!(" +{}w", every),
+    };
+    writeln!(
+        org,
+        "\n   DEADLINE: <{}{}>",
+        due.format("%Y-%m-%d %a %H:%M"),
+        repeater
+    )
+    .unwrap();
+
+    // org's column view and clocking understand estimates in the Effort property
+    writeln!(org, "   :PROPERTIES:").unwrap();
+    writeln!(org, "   :Effort: {}", effort(task.duration.total_minutes())).unwrap();
+    if let Some(course) = &task.course {
+        writeln!(org, "   :COURSE: {}", course).unwrap();
+    }
+    writeln!(org, "   :END:").unwrap();
+    write_notes(org, task.notes.as_deref(), "   ");
+
+    if let TaskDuration::Subtasks(subtasks) = &task.duration {
+        for subtask in subtasks {
+            writeln!(
+                org,
+                "*** {} {}\n    :PROPERTIES:\n    :Effort: {}\n    :END:",
+                if done { "DONE" } else { "TODO" },
+                subtask.name,
+                effort(subtask.duration)
+            )
+            .unwrap();
+            write_notes(org, subtask.notes.as_deref(), "    ");
+        }
+    }
+}
+
+fn write_notes(org: &mut String, notes: Option<&str>, indent: &str) {
+    for line in notes.into_iter().flat_map(str::lines) {
+        writeln!(org, "{}{}", indent, line).unwrap();
+    }
+}
+
+/// Formats `span` like "<2024-01-31 Wed 17:00-17:25>", or as a range of two timestamps if it
+/// goes past midnight.
+fn timestamp_range(span: &TimeSpan) -> String {
+    let (start, end) = (span.start(), span.end());
+    if start.date() == end.date() {
+        format!(
+            "<{}-{}>",
+            start.format("%Y-%m-%d %a %H:%M"),
+            end.format("%H:%M")
+        )
+    } else {
+        format!(
+            "<{}>--<{}>",
+            start.format("%Y-%m-%d %a %H:%M"),
+            end.format("%Y-%m-%d %a %H:%M")
+        )
+    }
+}
+
+/// Formats minutes like org's Effort property does, like "1:30".
+fn effort(minutes: u64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Org tags can only have letters, numbers, and `_@#%`.
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_@#%".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
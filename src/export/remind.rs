@@ -0,0 +1,79 @@
+//! Writes events and scheduled work as reminders for remind(1).
+
+use crate::{
+    methods::pomodoro::PomodoroScheduleEntry, CalendarEventType, Repeat, TimeSpan, UserData,
+};
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+
+/// Returns a reminders file with the user's events (repeating like they do) and the work periods
+/// in the pomodoro schedule.
+pub fn export(data: &UserData) -> String {
+    let mut reminders = String::from("# made by `sparrow export remind`\n");
+
+    writeln!(reminders, "\n# events").unwrap();
+    for event in data
+        .all_events()
+        .iter()
+        .filter(|e| e.event_type == CalendarEventType::Event)
+    {
+        let start = event.time_span.start();
+        let when = match event.repeat {
+            Repeat::No => date(start),
+            Repeat::Daily => format!("{} *1", date(start)),
+            Repeat::Weekly => format!("{} *7", date(start)),
+            Repeat::EveryNDays(every) => format!("{} *{}", date(start), every),
+            Repeat::EveryNWeeks(every) => format!("{} *{}", date(start), every * 7),
+
+            // leaving out the month (and year) makes remind repeat on that day every month (or
+            // year)
+            Repeat::Monthly { day_of_month } => format!("{} FROM {}", day_of_month, date(start)),
+            Repeat::Yearly => format!("{} FROM {}", start.format("%-d %b"), date(start)),
+        };
+        let when = match event.last_start() {
+            Some(last) if event.repeat != Repeat::No => format!("{} UNTIL {}", when, date(&last)),
+            _ => when,
+        };
+        writeln!(
+            reminders,
+            "{}",
+            reminder(&event.time_span, &when, &event.name)
+        )
+        .unwrap();
+    }
+
+    writeln!(reminders, "\n# work").unwrap();
+    if let Some(schedule) = data.get_pomodoro_schedule() {
+        for entry in schedule.get_entries() {
+            if let PomodoroScheduleEntry::Job { .. } = entry {
+                let title = entry.title(data.get_tasks());
+                let date = date(entry.span().start());
+                writeln!(reminders, "{}", reminder(entry.span(), &date, &title)).unwrap();
+            }
+        }
+    }
+
+    reminders
+}
+
+/// Returns a REM line for `span`. `date` is when it happens, with any repeat, like "3 Feb 2025 *7".
+fn reminder(span: &TimeSpan, date: &str, message: &str) -> String {
+    format!(
+        "REM {} AT {} DURATION {}:{:02} MSG {}",
+        date,
+        span.start().format("%H:%M"),
+        span.minutes() / 60,
+        span.minutes() % 60,
+        escape(message)
+    )
+}
+
+/// Returns `when` as a date remind understands.
+fn date(when: &DateTime<Local>) -> String {
+    when.format("%-d %b %Y").to_string()
+}
+
+/// Escapes the characters remind would otherwise treat as substitutions or expressions.
+fn escape(message: &str) -> String {
+    message.replace('%', "%%").replace('[', "[\"[\"]")
+}
@@ -0,0 +1,59 @@
+//! Exports tasks for Taskwarrior's `task import`. Each task gets a UUID made from its ID, so
+//! exporting again updates the same Taskwarrior tasks instead of adding copies.
+//! Estimates are written to an `estimate` UDA, which Taskwarrior keeps even if it isn't defined
+//! (`task config uda.estimate.type duration` makes it show up in reports).
+
+use super::utc_timestamp;
+use crate::{TaskId, UserData};
+use chrono::Local;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct TaskwarriorTask {
+    uuid: String,
+    description: String,
+    status: &'static str,
+    entry: String,
+    due: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+
+    estimate: String,
+}
+
+/// Returns a JSON array with a Taskwarrior task for each task. A task's course becomes its
+/// project.
+pub fn export(data: &UserData) -> String {
+    let now = utc_timestamp(Local::now());
+    let tasks: Vec<TaskwarriorTask> = data
+        .get_tasks()
+        .iter()
+        .map(|t| TaskwarriorTask {
+            uuid: uuid(t.id),
+            description: t.name.clone(),
+            status: if t.done { "completed" } else { "pending" },
+            entry: now.clone(),
+            due: utc_timestamp(t.due_date),
+            end: if t.done { Some(now.clone()) } else { None },
+            project: t.course.clone(),
+            tags: t.tags.clone(),
+            estimate: format!("PT{}M", t.duration.total_minutes()),
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&tasks).unwrap_or_default()
+}
+
+/// Makes a UUID (in the format Taskwarrior expects) that stays the same for the same task, even
+/// if it's renamed or moved.
+fn uuid(id: TaskId) -> String {
+    // the same for all of sparrow's tasks, except for the ID at the end
+    format!("5ba770e0-0000-4000-8000-{:012x}", id)
+}
@@ -0,0 +1,37 @@
+//! Exports finished work for `timew import`: time tracked with `sparrow start` and `sparrow stop`,
+//! and work periods marked done, for time that wasn't tracked.
+
+use super::utc_timestamp;
+use crate::{SparrowResult, UserData};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Interval {
+    start: String,
+    end: String,
+    tags: Vec<String>,
+}
+
+/// Returns a JSON array of intervals, one for each stretch of time worked (see
+/// `UserData::worked`), tagged with "sparrow", the task's name, and the subtask's name (if any).
+pub fn export(data: &UserData) -> SparrowResult<String> {
+    let intervals: Vec<Interval> = data
+        .worked()
+        .into_iter()
+        .filter_map(|w| {
+            let task = data.get_tasks().iter().find(|t| t.id == w.task_id)?;
+            let mut tags = vec!["sparrow".to_string(), task.name.clone()];
+            if let Some(s) = w.subtask.and_then(|i| task.subtask(i)) {
+                tags.push(s.name.clone());
+            }
+
+            Some(Interval {
+                start: utc_timestamp(*w.span.start()),
+                end: utc_timestamp(w.span.end()),
+                tags,
+            })
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&intervals)?)
+}
@@ -0,0 +1,96 @@
+//! Checks whether there's time to finish each task before it's due, before a schedule is made, so
+//! that tasks that can't make it are named along with how short the time is.
+
+use crate::{
+    methods::pomodoro::{self, PomodoroSchedule},
+    metrics::format_minutes,
+    task, Bedtime, CalendarEvent, Config, Task,
+};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+/// How much work a task needs, and how much fits before it's due.
+#[derive(Clone, Debug, Serialize)]
+pub struct Feasibility {
+    pub name: String,
+    pub due_date: DateTime<Local>,
+
+    /// Minutes of work the task needs.
+    pub needed_minutes: u64,
+
+    /// Minutes of work the task and every task due before it need, since those come first.
+    pub needed_by_due_minutes: u64,
+
+    /// Minutes of work that fit in the free time until the task is due.
+    pub free_minutes: u64,
+}
+
+impl Feasibility {
+    pub fn is_feasible(&self) -> bool {
+        self.needed_by_due_minutes <= self.free_minutes
+    }
+
+    /// Describes the task's shortfall, like "Essay: need 6h 0m, only 3h 30m free before
+    /// 2024/03/01 17:00".
+    pub fn describe(&self, config: &Config) -> String {
+        let format = format!("{} {}", config.date_format, config.time_format);
+        let mut need = format_minutes(self.needed_minutes as i64);
+        if self.needed_by_due_minutes > self.needed_minutes {
+            need += &format!(
+                " ({} with the work due before it)",
+                format_minutes(self.needed_by_due_minutes as i64)
+            );
+        }
+        format!(
+            "{}: need {}, only {} free before {}",
+            self.name,
+            need,
+            format_minutes(self.free_minutes as i64),
+            self.due_date.format(&format)
+        )
+    }
+}
+
+/// Returns, for each unfinished task in `tasks` (and instance of a repeating one) that isn't due
+/// yet, in due date order, how much work it needs and how much fits before it's due around
+/// `events` and sleep (and after the date it's deferred until, if it is). Work is counted in whole
+/// work periods and sessions, like the pomodoro schedule counts it.
+pub fn check(
+    config: &Config,
+    tasks: &[Task],
+    events: &[CalendarEvent],
+    bedtime: &Bedtime,
+) -> Vec<Feasibility> {
+    let now = Local::now();
+    let instances = task::expand_recurring(config, tasks, now);
+    let mut tasks: Vec<&Task> = instances
+        .iter()
+        .filter(|t| !t.done && t.due_date > now)
+        .collect();
+    tasks.sort_by_key(|t| t.due_date);
+
+    let last_due_date = match tasks.last() {
+        Some(t) => t.due_date,
+        None => return Vec::new(),
+    };
+    let entries = PomodoroSchedule::breaks_to_schedule_entries(events, last_due_date, bedtime);
+
+    let work_minutes = config.work_minutes as u64;
+    let mut needed_so_far = 0;
+    tasks
+        .into_iter()
+        .map(|t| {
+            let needed = pomodoro::periods_needed(config, t) as u64 * work_minutes;
+            needed_so_far += needed;
+            let from = t.deferred_until.map_or(now, |d| d.max(now));
+            Feasibility {
+                name: t.name.clone(),
+                due_date: t.due_date,
+                needed_minutes: needed,
+                needed_by_due_minutes: needed_so_far,
+                free_minutes: pomodoro::periods_free(config, &entries, from, t.due_date) as u64
+                    * work_minutes,
+            }
+        })
+        .collect()
+}
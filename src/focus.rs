@@ -0,0 +1,67 @@
+//! Turns the desktop's do-not-disturb mode on and off, so notifications don't interrupt work
+//! periods. Each notification daemon is switched with the tool it ships with, which does the D-Bus
+//! talking for us.
+
+use crate::{SparrowError, SparrowResult};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// The notification daemon to silence during work periods.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum FocusBackend {
+    /// GNOME Shell, through `gsettings`.
+    Gnome,
+
+    /// dunst, through `dunstctl`.
+    Dunst,
+
+    /// mako, through `makoctl`.
+    Mako,
+
+    /// Any other setup: commands (a program and its arguments) to run to turn do-not-disturb on
+    /// and off.
+    Custom { on: Vec<String>, off: Vec<String> },
+}
+
+impl FocusBackend {
+    /// Turns do-not-disturb on if `focus` is true, or off otherwise.
+    pub fn set(&self, focus: bool) -> SparrowResult<()> {
+        let args: Vec<&str> = match self {
+            Self::Gnome => vec![
+                "gsettings",
+                "set",
+                "org.gnome.desktop.notifications",
+                "show-banners",
+                if focus { "false" } else { "true" },
+            ],
+            Self::Dunst => vec![
+                "dunstctl",
+                "set-paused",
+                if focus { "true" } else { "false" },
+            ],
+            Self::Mako => vec![
+                "makoctl",
+                "mode",
+                if focus { "-a" } else { "-r" },
+                "do-not-disturb",
+            ],
+            Self::Custom { on, off } => {
+                let command = if focus { on } else { off };
+                command.iter().map(String::as_str).collect()
+            }
+        };
+
+        let (program, args) = args.split_first().ok_or_else(|| {
+            SparrowError::BasicMessage("the focus mode command is empty".to_string())
+        })?;
+        let status = Command::new(program).args(args).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "`{}` failed ({})",
+                program, status
+            )))
+        }
+    }
+}
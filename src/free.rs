@@ -0,0 +1,98 @@
+//! `sparrow free`: the free time left between events, sleep, and scheduled work, for finding a
+//! spot for something new.
+
+use crate::{metrics::format_minutes, spans, Config, TimeSpan, UserData};
+use chrono::{Date, Duration, Local, NaiveDate};
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+/// Returns the free time on the `days` days starting with `date`, leaving out anything before
+/// now.
+pub fn free_time(data: &UserData, date: Date<Local>, days: u32) -> Vec<TimeSpan> {
+    let from = date.and_hms(0, 0, 0).max(Local::now());
+    let until = (date + Duration::days(days as i64)).and_hms(0, 0, 0);
+
+    let mut busy: Vec<TimeSpan> = data
+        .all_events()
+        .iter()
+        .flat_map(|e| e.occurrences(from, until))
+        .chain(data.get_bedtime().occurrences(from, until))
+        .collect();
+    if let Some(schedule) = data.get_pomodoro_schedule() {
+        busy.extend(schedule.get_entries().iter().map(|e| *e.span()));
+    }
+
+    // a window that goes past midnight is split, so that each day shows its own free time
+    spans::free_time(&busy, from, until)
+        .into_iter()
+        .flat_map(split_at_midnight)
+        .collect()
+}
+
+fn split_at_midnight(span: TimeSpan) -> Vec<TimeSpan> {
+    let mut pieces = Vec::new();
+    let mut start = *span.start();
+    while start.date() < span.end().date() {
+        let midnight = start.date().succ().and_hms(0, 0, 0);
+        pieces.push(TimeSpan::between(start, midnight));
+        start = midnight;
+    }
+    pieces.push(TimeSpan::between(start, span.end()));
+    pieces.retain(|p| p.minutes() > 0);
+    pieces
+}
+
+pub struct FreeTimeDisplay<'a> {
+    pub free: &'a [TimeSpan],
+    pub config: &'a Config,
+}
+
+/// A free window, ready to be serialized.
+#[derive(Serialize)]
+struct WindowView {
+    start: chrono::DateTime<Local>,
+    end: chrono::DateTime<Local>,
+    minutes: u32,
+}
+
+impl Serialize for FreeTimeDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.free.iter().map(|f| WindowView {
+            start: *f.start(),
+            end: f.end(),
+            minutes: f.minutes(),
+        }))
+    }
+}
+
+impl Display for FreeTimeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.free.is_empty() {
+            return writeln!(f, "No free time then. Busy busy!");
+        }
+
+        let mut day: Option<NaiveDate> = None;
+        for window in self.free {
+            let window_day = window.start().naive_local().date();
+            if day != Some(window_day) {
+                if day.is_some() {
+                    writeln!(f)?;
+                }
+                writeln!(
+                    f,
+                    "Free time on {}:",
+                    window_day.format(&self.config.date_format)
+                )?;
+                day = Some(window_day);
+            }
+            writeln!(
+                f,
+                "-\t{} - {} ({})",
+                window.start().format(&self.config.time_format),
+                window.end().format(&self.config.time_format),
+                format_minutes(window.minutes() as i64)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+//! Goals: time to spend on a tag or course every week, like 6 hours a week on "piano". Schedules
+//! set aside whatever time the week's work on the goal's tasks doesn't already cover.
+
+use crate::{
+    habit::week_start,
+    methods::pomodoro::PomodoroScheduleEntry,
+    metrics::format_minutes,
+    spans::{self, TimeSpan},
+    tracking::Worked,
+    work_windows, Config, Task,
+};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// Minutes a week to spend on tasks with a tag or course.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Goal {
+    /// The tag or course the time is for.
+    pub name: String,
+
+    pub minutes_per_week: u32,
+
+    /// Minutes already worked toward the goal this week. Filled in by
+    /// `UserData::scheduling_config` rather than set.
+    #[serde(skip)]
+    pub minutes_done: u32,
+}
+
+impl Goal {
+    /// Returns true if work on `task` counts toward the goal.
+    pub fn counts(&self, task: &Task) -> bool {
+        task.has_tag(&self.name)
+            || task
+                .course
+                .as_ref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(&self.name))
+    }
+
+    /// Returns the minutes in `worked` on `tasks` that count toward the goal, since `since`.
+    pub fn worked_minutes(&self, worked: &[Worked], tasks: &[Task], since: DateTime<Local>) -> u32 {
+        worked
+            .iter()
+            .filter(|w| *w.span.start() >= since)
+            .filter(|w| tasks.iter().any(|t| t.id == w.task_id && self.counts(t)))
+            .map(|w| w.span.minutes())
+            .sum()
+    }
+
+    /// Names the time set aside for the goal in schedules.
+    fn entry_name(&self) -> String {
+        format!("{} goal", self.name)
+    }
+}
+
+/// Returns when the week that `now` is in started.
+pub fn this_week(now: DateTime<Local>) -> DateTime<Local> {
+    let monday = week_start(now.date().naive_local());
+    Local
+        .from_local_date(&monday)
+        .earliest()
+        .map_or(now, |d| d.and_hms(0, 0, 0))
+}
+
+/// Sets aside time in `entries` for each of the config's goals, every week from now until
+/// `until`, for whatever time work on `tasks` doesn't already cover. The time is taken in work
+/// period sized pieces from the free time in the work windows, one a day at a time so that it's
+/// spread over the week.
+pub(crate) fn reserve(
+    config: &Config,
+    tasks: &[&Task],
+    entries: &mut Vec<PomodoroScheduleEntry>,
+    until: DateTime<Local>,
+) {
+    let now = Local::now();
+    let piece = config.work_minutes;
+    if piece == 0 {
+        return;
+    }
+
+    let mut week = this_week(now);
+    while week < until {
+        let week_end = week + Duration::days(7);
+        for goal in &config.goals {
+            let done = if week <= now { goal.minutes_done } else { 0 };
+            let planned: u32 = entries
+                .iter()
+                .filter(|e| *e.span().start() >= now.max(week) && *e.span().start() < week_end)
+                .filter(|e| match e {
+                    PomodoroScheduleEntry::Job { task_id, .. } => {
+                        tasks.iter().any(|t| t.id == *task_id && goal.counts(t))
+                    }
+                    _ => false,
+                })
+                .map(|e| e.span().minutes())
+                .sum();
+            let needed = goal.minutes_per_week.saturating_sub(done + planned);
+            if needed == 0 {
+                continue;
+            }
+
+            let slots = slots_by_day(config, entries, now.max(week), week_end.min(until));
+            for span in spread(slots, needed.div_ceil(piece) as usize) {
+                entries.push(PomodoroScheduleEntry::Habit {
+                    name: goal.entry_name(),
+                    span,
+                });
+            }
+        }
+        week = week_end;
+    }
+
+    entries.sort_by_cached_key(|e| *e.span().start());
+}
+
+/// Returns the work period sized pieces of free time around `entries` in the work windows from
+/// `from` until `until`, by day. Pieces are a short break apart.
+fn slots_by_day(
+    config: &Config,
+    entries: &[PomodoroScheduleEntry],
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+) -> BTreeMap<NaiveDate, Vec<TimeSpan>> {
+    let busy: Vec<TimeSpan> = entries.iter().map(|e| *e.span()).collect();
+    let step = (config.work_minutes + config.short_break_minutes) as i64;
+    let mut slots: BTreeMap<NaiveDate, Vec<TimeSpan>> = BTreeMap::new();
+    for free in spans::free_time(&busy, from, until)
+        .iter()
+        .flat_map(|free| work_windows::within(&config.work_windows, free))
+    {
+        let mut start = *free.start();
+        while start + Duration::minutes(config.work_minutes as i64) <= free.end() {
+            slots
+                .entry(start.date().naive_local())
+                .or_default()
+                .push(TimeSpan::new(start, config.work_minutes));
+            start = start + Duration::minutes(step);
+        }
+    }
+    slots
+}
+
+/// Takes up to `count` of `slots`, one from each day in turn.
+fn spread(slots: BTreeMap<NaiveDate, Vec<TimeSpan>>, count: usize) -> Vec<TimeSpan> {
+    let mut days: Vec<std::vec::IntoIter<TimeSpan>> =
+        slots.into_values().map(|s| s.into_iter()).collect();
+    let mut taken = Vec::new();
+    while taken.len() < count {
+        let before = taken.len();
+        for day in days.iter_mut() {
+            if taken.len() == count {
+                break;
+            }
+            if let Some(slot) = day.next() {
+                taken.push(slot);
+            }
+        }
+        if taken.len() == before {
+            break;
+        }
+    }
+    taken
+}
+
+/// How a goal is going this week.
+#[derive(Serialize)]
+pub struct GoalProgress {
+    pub name: String,
+    pub minutes_per_week: u32,
+    pub minutes_done: u32,
+}
+
+impl GoalProgress {
+    pub fn new(goal: &Goal, worked: &[Worked], tasks: &[Task], now: DateTime<Local>) -> Self {
+        Self {
+            name: goal.name.clone(),
+            minutes_per_week: goal.minutes_per_week,
+            minutes_done: goal.worked_minutes(worked, tasks, this_week(now)),
+        }
+    }
+}
+
+impl Display for GoalProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} of {} this week ({}%)",
+            self.name,
+            format_minutes(self.minutes_done as i64),
+            format_minutes(self.minutes_per_week as i64),
+            (self.minutes_done * 100)
+                .checked_div(self.minutes_per_week)
+                .unwrap_or(100)
+        )
+    }
+}
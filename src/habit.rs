@@ -0,0 +1,367 @@
+//! Habits are small things to do every day or a few times every week, like stretching or
+//! practicing an instrument. Unlike tasks, they're never finished; schedules can set aside a short
+//! slot for them, and the days or weeks they're done often enough count toward a streak.
+
+use crate::{
+    prompts::*, CalendarEvent, CalendarEventType, Config, Formatting, Repeat, SparrowError,
+    SparrowResult, TimeSpan,
+};
+use chrono::{Date, DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// How often a habit comes around.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub enum Cadence {
+    Daily,
+
+    #[default]
+    Weekly,
+}
+
+impl Cadence {
+    /// Returns the first day of the day or week that `day` is in.
+    pub fn period_start(&self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Daily => day,
+            Self::Weekly => week_start(day),
+        }
+    }
+
+    pub fn period_days(&self) -> i64 {
+        match self {
+            Self::Daily => 1,
+            Self::Weekly => 7,
+        }
+    }
+
+    /// Names the period, like "day" or "week".
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Daily => "day",
+            Self::Weekly => "week",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Habit {
+    pub name: String,
+
+    #[serde(default)]
+    pub cadence: Cadence,
+
+    /// How many times each day the habit should be done, for daily habits, or how many days each
+    /// week, for weekly ones.
+    #[serde(alias = "times_per_week")]
+    pub target: u32,
+
+    /// How long the habit takes each time.
+    pub minutes: u32,
+
+    /// True if schedules set aside time for the habit. Otherwise it's only checked off.
+    #[serde(default = "default_scheduled")]
+    pub scheduled: bool,
+
+    /// The habit is scheduled between these times of day.
+    pub window_start: NaiveTime,
+    pub window_end: NaiveTime,
+
+    /// The days the habit was done, once for every time it was done that day.
+    #[serde(default)]
+    pub done_days: Vec<NaiveDate>,
+}
+
+fn default_scheduled() -> bool {
+    true
+}
+
+impl Habit {
+    pub fn prompt_new(formatting: &Formatting, config: &Config) -> SparrowResult<Self> {
+        let name = prompt_strict(formatting, "What's the habit?", None, None, |i| {
+            let i = i.trim();
+            if i.is_empty() {
+                Err(SparrowError::BasicMessage("Habits need a name".to_string()))
+            } else {
+                Ok(i.to_string())
+            }
+        })?;
+        let cadence = prompt_strict(
+            formatting,
+            "Every day or every week?",
+            Some("day/week"),
+            Some("week"),
+            |i| match i.trim().to_lowercase().as_str() {
+                "" | "w" | "week" | "weekly" => Ok(Cadence::Weekly),
+                "d" | "day" | "daily" => Ok(Cadence::Daily),
+                _ => Err(SparrowError::BasicMessage(
+                    "Enter \"day\" or \"week\"".to_string(),
+                )),
+            },
+        )?;
+        let target = match cadence {
+            Cadence::Daily => prompt_strict(
+                formatting,
+                "How many times a day?",
+                None,
+                Some("1"),
+                |i| match i.trim() {
+                    "" => Ok(1),
+                    i => match i.parse::<u32>() {
+                        Ok(n) if n > 0 => Ok(n),
+                        _ => Err(SparrowError::BasicMessage(
+                            "Enter a number of at least 1".to_string(),
+                        )),
+                    },
+                },
+            )?,
+            Cadence::Weekly => prompt_strict(
+                formatting,
+                "How many days a week?",
+                Some("1-7"),
+                None,
+                |i| match i.trim().parse::<u32>() {
+                    Ok(n) if (1..=7).contains(&n) => Ok(n),
+                    _ => Err(SparrowError::BasicMessage(
+                        "Enter a number from 1 to 7".to_string(),
+                    )),
+                },
+            )?,
+        };
+        let minutes = prompt_strict(
+            formatting,
+            "How long does it take?",
+            Some("minutes"),
+            None,
+            |i| i.trim().parse::<u32>(),
+        )?;
+
+        let scheduled = !prompt_yn(&format!(
+            "{} {}",
+            formatting
+                .prompt
+                .paint("Set aside time for it in schedules?"),
+            formatting.prompt_format.paint("[Y/n]")
+        ))?
+        .is_some_and(|d| d.is_no());
+        if !scheduled {
+            return Ok(Self {
+                name,
+                cadence,
+                target,
+                minutes,
+                scheduled,
+                window_start: NaiveTime::from_hms(0, 0, 0),
+                window_end: NaiveTime::from_hms(0, 0, 0),
+                done_days: Vec::new(),
+            });
+        }
+
+        let window_start = prompt_strict(
+            formatting,
+            "What's the earliest it should be scheduled?",
+            Some(&config.time_format),
+            None,
+            |i| NaiveTime::parse_from_str(i.trim(), &config.time_format),
+        )?;
+        let window_end = prompt_strict(
+            formatting,
+            "And the latest it should be finished by?",
+            Some(&config.time_format),
+            None,
+            |i| NaiveTime::parse_from_str(i.trim(), &config.time_format),
+        )?;
+
+        Ok(Self {
+            name,
+            cadence,
+            target,
+            minutes,
+            scheduled,
+            window_start,
+            window_end,
+            done_days: Vec::new(),
+        })
+    }
+
+    /// Records that the habit was done once on `day`. Returns false, without recording anything,
+    /// if there's nothing left to do that day: a daily habit done as many times as it should be,
+    /// or a weekly one that was already done.
+    pub fn mark_done(&mut self, day: NaiveDate) -> bool {
+        let times = self.times_done_on(day);
+        let left = match self.cadence {
+            Cadence::Daily => times < self.target,
+            Cadence::Weekly => times == 0,
+        };
+        if left {
+            self.done_days.push(day);
+            self.done_days.sort();
+        }
+        left
+    }
+
+    /// Returns how many times the habit was done on `day`.
+    pub fn times_done_on(&self, day: NaiveDate) -> u32 {
+        self.done_days.iter().filter(|d| **d == day).count() as u32
+    }
+
+    /// Returns how many times the habit was done in the day or week (depending on its cadence)
+    /// that starts on `period_start`. For weekly habits, that's the days it was done.
+    pub fn done_in(&self, period_start: NaiveDate) -> u32 {
+        let period_end = period_start + Duration::days(self.cadence.period_days());
+        self.done_days
+            .iter()
+            .filter(|d| **d >= period_start && **d < period_end)
+            .count() as u32
+    }
+
+    /// Returns how many days or weeks in a row (depending on its cadence) the habit was done as
+    /// often as it should be, up to the one `today` is in. The current one only counts once it's
+    /// met, so an unfinished day or week doesn't break a streak.
+    pub fn streak(&self, today: NaiveDate) -> u32 {
+        let first_done = match self.done_days.first() {
+            Some(d) => *d,
+            None => return 0,
+        };
+
+        let step = Duration::days(self.cadence.period_days());
+        let mut period = self.cadence.period_start(today);
+        if self.done_in(period) < self.target {
+            period -= step;
+        }
+
+        let mut streak = 0;
+        while period + step > first_done && self.done_in(period) >= self.target {
+            streak += 1;
+            period -= step;
+        }
+        streak
+    }
+
+    /// Returns how many slots the habit gets on `day`. Daily habits get one for each time they're
+    /// still to be done that day. A weekly habit's days are spread out over the week, so one done
+    /// three times a week is planned on Wednesdays, Fridays, and Sundays, unless it's done already.
+    fn slots_planned_on(&self, day: Date<Local>) -> u32 {
+        let times_done = self.times_done_on(day.naive_local());
+        match self.cadence {
+            Cadence::Daily => self.target.saturating_sub(times_done),
+            Cadence::Weekly => {
+                let i = day.weekday().num_days_from_monday();
+                let n = self.target.min(7);
+                let planned = (i + 1) * n / 7 > i * n / 7;
+                (planned && times_done == 0) as u32
+            }
+        }
+    }
+
+    /// Finds the earliest slot on `day`, within the habit's window and no earlier than
+    /// `not_before`, that doesn't overlap anything in `busy`.
+    fn slot_on(
+        &self,
+        day: Date<Local>,
+        busy: &[TimeSpan],
+        not_before: DateTime<Local>,
+    ) -> Option<TimeSpan> {
+        let window_start = day.and_time(self.window_start)?.max(not_before);
+        let window_end = day.and_time(self.window_end)?;
+
+        // a slot either starts with the window or right after something else ends
+        let mut starts: Vec<DateTime<Local>> = busy
+            .iter()
+            .map(|b| b.end())
+            .filter(|end| *end > window_start && *end < window_end)
+            .collect();
+        starts.push(window_start);
+        starts.sort();
+
+        starts
+            .into_iter()
+            .map(|start| TimeSpan::new(start, self.minutes))
+            .filter(|slot| slot.end() <= window_end)
+            .find(|slot| !busy.iter().any(|b| b.overlaps(slot)))
+    }
+}
+
+/// Returns events that set aside time for `habits` between `from` and `until`, around the time
+/// that's already `busy`. Habits that aren't scheduled, or are already done on a day, aren't
+/// given a slot that day.
+pub fn habit_slots(
+    habits: &[Habit],
+    mut busy: Vec<TimeSpan>,
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+) -> Vec<CalendarEvent> {
+    let mut slots = Vec::new();
+
+    let mut day = from.date();
+    while day <= until.date() {
+        for habit in habits.iter().filter(|h| h.scheduled) {
+            for _ in 0..habit.slots_planned_on(day) {
+                let span = match habit.slot_on(day, &busy, from) {
+                    Some(span) => span,
+                    None => break,
+                };
+                busy.push(span);
+                slots.push(CalendarEvent {
+                    id: 0,
+                    name: habit.name.clone(),
+                    time_span: span,
+                    event_type: CalendarEventType::Habit,
+                    repeat: Repeat::No,
+                    tags: Vec::new(),
+                    repeat_until: None,
+                    repeat_count: None,
+                    exceptions: Vec::new(),
+                    timezone: None,
+                });
+            }
+        }
+
+        day = match day.succ_opt() {
+            Some(d) => d,
+            None => break,
+        };
+    }
+
+    slots
+}
+
+/// Returns the Monday of the week `day` is in.
+pub fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday() as i64)
+}
+
+/// Shows how the user is doing on their habits.
+pub struct HabitsDisplay<'a> {
+    pub habits: &'a [Habit],
+    pub today: NaiveDate,
+}
+
+impl Display for HabitsDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.habits.is_empty() {
+            return writeln!(f, "No habits yet. Add one with `sparrow add habit`");
+        }
+
+        for h in self.habits {
+            let this = match h.cadence {
+                Cadence::Daily => "today",
+                Cadence::Weekly => "this week",
+            };
+            write!(
+                f,
+                "-\t{}: {}/{} {}",
+                h.name,
+                h.done_in(h.cadence.period_start(self.today)),
+                h.target,
+                this
+            )?;
+            match h.streak(self.today) {
+                0 => writeln!(f)?,
+                n => writeln!(f, ", {} {} streak", n, h.cadence.unit())?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+//! Reads tasks from CSV laid out like `sparrow export csv tasks` writes it (see `export::csv`).
+//! Columns can be in any order, and only `name` and `due` are needed. Rows naming a task that's
+//! already in sparrow update that task, so estimates can be edited in bulk in a spreadsheet.
+
+use crate::{
+    export::csv::DUE_FORMAT,
+    task::{parse_duration_minutes, TaskDuration},
+    SparrowError, SparrowResult, Task, UserData,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::fmt::{Display, Formatter};
+
+/// What happened to each row of a CSV import.
+#[derive(Default)]
+pub struct CsvImportSummary {
+    pub added: usize,
+    pub updated: usize,
+
+    /// Tasks whose estimate changed, but weren't given it, since it's split among subtasks.
+    pub kept_subtask_estimates: usize,
+}
+
+impl Display for CsvImportSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Added {} tasks and updated {}", self.added, self.updated)?;
+        if self.kept_subtask_estimates > 0 {
+            write!(
+                f,
+                "\n\t{} have subtasks, so their estimates weren't changed; edit their subtasks \
+                 instead",
+                self.kept_subtask_estimates
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the tasks in `text` to `data`, or updates them if they're already there (by name).
+/// Tasks added without a `minutes` column take `default_minutes`.
+pub fn import_tasks(
+    data: &mut UserData,
+    text: &str,
+    default_minutes: u64,
+) -> SparrowResult<CsvImportSummary> {
+    let mut rows = parse(text).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (name_column, due_column) = match (column("name"), column("due")) {
+        (Some(n), Some(d)) => (n, d),
+        _ => {
+            return Err(SparrowError::BasicMessage(
+                "the CSV needs `name` and `due` columns".to_string(),
+            ))
+        }
+    };
+    let (minutes_column, done_column, tags_column) =
+        (column("minutes"), column("done"), column("tags"));
+
+    let mut summary = CsvImportSummary::default();
+    for (i, row) in rows.enumerate() {
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| row.get(c))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+        let name = match field(Some(name_column)) {
+            Some(n) => n.to_string(),
+            None => continue,
+        };
+
+        // the header is row 1
+        let row_error = |problem: String| {
+            SparrowError::BasicMessage(format!("row {} ('{}'): {}", i + 2, name, problem))
+        };
+        let due_date = field(Some(due_column))
+            .ok_or_else(|| row_error("there's no due date".to_string()))
+            .and_then(|d| parse_due(d).ok_or_else(|| row_error(format!("'{}' isn't a date", d))))?;
+        let minutes = field(minutes_column)
+            .map(|m| {
+                parse_duration_minutes(m)
+                    .ok_or_else(|| row_error(format!("'{}' isn't a number of minutes", m)))
+            })
+            .transpose()?;
+        let done = field(done_column)
+            .map(|d| parse_done(d).ok_or_else(|| row_error(format!("'{}' isn't true or false", d))))
+            .transpose()?;
+        let tags = field(tags_column).map(|t| {
+            t.split_whitespace()
+                .map(|tag| tag.trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect::<Vec<_>>()
+        });
+
+        match data.get_tasks().iter().position(|t| t.name == name) {
+            Some(index) => {
+                let id = data.get_tasks()[index].id;
+                let task = data.get_task_mut(id).unwrap();
+                task.due_date = due_date;
+                match (&task.duration, minutes) {
+                    (TaskDuration::Subtasks(_), Some(m)) if m != task.duration.total_minutes() => {
+                        summary.kept_subtask_estimates += 1
+                    }
+                    (TaskDuration::Minutes(_), Some(m)) => task.duration = TaskDuration::Minutes(m),
+                    _ => {}
+                }
+                if let Some(done) = done {
+                    task.done = done;
+                }
+                if let Some(tags) = tags {
+                    task.tags = tags;
+                }
+                summary.updated += 1;
+            }
+            None => {
+                let mut task = Task::new(
+                    name,
+                    due_date,
+                    TaskDuration::Minutes(minutes.unwrap_or(default_minutes)),
+                );
+                task.done = done.unwrap_or(false);
+                task.tags = tags.unwrap_or_default();
+                data.add_task(task);
+                summary.added += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Returns true if a task in `text` that isn't in `data` yet has no estimate of its own.
+pub fn needs_default_minutes(data: &UserData, text: &str) -> bool {
+    let mut rows = parse(text).into_iter();
+    let header = rows.next().unwrap_or_default();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let (name_column, minutes_column) = (column("name"), column("minutes"));
+
+    rows.any(|row| {
+        let field = |c: Option<usize>| c.and_then(|c| row.get(c)).map(|f| f.trim());
+        field(name_column)
+            .is_some_and(|n| !n.is_empty() && !data.get_tasks().iter().any(|t| t.name == n))
+            && field(minutes_column).is_none_or(str::is_empty)
+    })
+}
+
+/// Splits CSV into rows of fields. Quoted fields can have commas, quotes (written twice), and line
+/// breaks in them.
+fn parse(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // spreadsheets sometimes leave empty rows at the end
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+/// Parses a due date like "2024-01-31 17:00", or a date alone, which is due at midnight.
+fn parse_due(due: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(due, DUE_FORMAT)
+        .or_else(|_| NaiveDate::parse_from_str(due, "%Y-%m-%d").map(|d| d.and_hms(0, 0, 0)))
+        .ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+fn parse_done(done: &str) -> Option<bool> {
+    match done.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "x" | "1" => Some(true),
+        "false" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+//! Imports open GitHub issues assigned to the user. Issues are due when their milestone is, and
+//! their estimates come from labels (see `Config::estimate_label_prefix`).
+
+use super::{estimate_from_labels, local_midnight, ImportedTask, IssueFilter};
+use crate::SparrowResult;
+use chrono::{DateTime, Local, Utc};
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.github.com";
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    labels: Vec<Label>,
+    milestone: Option<Milestone>,
+
+    /// GitHub lists pull requests as issues too. Only pull requests have this.
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Milestone {
+    title: String,
+    due_on: Option<DateTime<Utc>>,
+}
+
+/// Fetches issues from `repo` (like "muni-corn/sparrow") using the API token `token`.
+pub fn fetch(
+    repo: &str,
+    token: &str,
+    filter: &IssueFilter,
+    estimate_label_prefix: &str,
+) -> SparrowResult<Vec<ImportedTask>> {
+    let get = |url: &str| {
+        ureq::get(url)
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", "sparrow")
+    };
+
+    let user: User = get(&format!("{}/user", API_URL)).call()?.into_json()?;
+
+    let mut issues: Vec<Issue> = Vec::new();
+    for page in 1.. {
+        let mut request = get(&format!("{}/repos/{}/issues", API_URL, repo))
+            .query("state", "open")
+            .query("assignee", &user.login)
+            .query("per_page", &PAGE_SIZE.to_string())
+            .query("page", &page.to_string());
+        if !filter.labels.is_empty() {
+            request = request.query("labels", &filter.labels.join(","));
+        }
+
+        let page_issues: Vec<Issue> = request.call()?.into_json()?;
+        let last_page = page_issues.len() < PAGE_SIZE;
+        issues.extend(page_issues);
+        if last_page {
+            break;
+        }
+    }
+
+    Ok(issues
+        .into_iter()
+        .filter(|i| i.pull_request.is_none())
+        .filter(|i| match &filter.milestone {
+            Some(title) => i.milestone.as_ref().map(|m| &m.title) == Some(title),
+            None => true,
+        })
+        .map(|i| ImportedTask {
+            name: format!("{} ({}#{})", i.title.trim(), repo, i.number),
+            due_date: i
+                .milestone
+                .as_ref()
+                .and_then(|m| m.due_on)
+                .and_then(|d| local_midnight(d.with_timezone(&Local).naive_local().date())),
+            done: false,
+            minutes: estimate_from_labels(
+                i.labels.iter().map(|l| l.name.as_str()),
+                estimate_label_prefix,
+            ),
+            tags: Vec::new(),
+            project: None,
+        })
+        .collect())
+}
@@ -0,0 +1,102 @@
+//! Imports open GitLab issues assigned to the user. Issues are due on their own due date, or
+//! else when their milestone is. Their estimates come from GitLab's time tracking, or else from
+//! labels (see `Config::estimate_label_prefix`).
+
+use super::{estimate_from_labels, local_midnight, ImportedTask, IssueFilter};
+use crate::SparrowResult;
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct User {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    iid: u64,
+    title: String,
+    labels: Vec<String>,
+    milestone: Option<Milestone>,
+    due_date: Option<NaiveDate>,
+    time_stats: Option<TimeStats>,
+}
+
+#[derive(Deserialize)]
+struct Milestone {
+    due_date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+struct TimeStats {
+    /// In seconds.
+    time_estimate: u64,
+}
+
+/// Fetches issues from `project` (like "muni-corn/sparrow") on the GitLab instance at `base_url`
+/// (like "https://gitlab.com"), using the personal access token `token`.
+pub fn fetch(
+    base_url: &str,
+    project: &str,
+    token: &str,
+    filter: &IssueFilter,
+    estimate_label_prefix: &str,
+) -> SparrowResult<Vec<ImportedTask>> {
+    let api_url = format!("{}/api/v4", base_url.trim_end_matches('/'));
+    let get = |url: &str| ureq::get(url).set("PRIVATE-TOKEN", token);
+
+    let user: User = get(&format!("{}/user", api_url)).call()?.into_json()?;
+
+    let mut issues: Vec<Issue> = Vec::new();
+    for page in 1.. {
+        let mut request = get(&format!(
+            "{}/projects/{}/issues",
+            api_url,
+            project.replace('/', "%2F")
+        ))
+        .query("state", "opened")
+        .query("assignee_username", &user.username)
+        .query("per_page", &PAGE_SIZE.to_string())
+        .query("page", &page.to_string());
+        if let Some(m) = &filter.milestone {
+            request = request.query("milestone", m);
+        }
+        if !filter.labels.is_empty() {
+            request = request.query("labels", &filter.labels.join(","));
+        }
+
+        let page_issues: Vec<Issue> = request.call()?.into_json()?;
+        let last_page = page_issues.len() < PAGE_SIZE;
+        issues.extend(page_issues);
+        if last_page {
+            break;
+        }
+    }
+
+    Ok(issues
+        .into_iter()
+        .map(|i| {
+            let tracked_estimate = i
+                .time_stats
+                .as_ref()
+                .map(|t| t.time_estimate / 60)
+                .filter(|m| *m > 0);
+
+            ImportedTask {
+                name: format!("{} ({}#{})", i.title.trim(), project, i.iid),
+                due_date: i
+                    .due_date
+                    .or_else(|| i.milestone.as_ref().and_then(|m| m.due_date))
+                    .and_then(local_midnight),
+                done: false,
+                minutes: tracked_estimate.or_else(|| {
+                    estimate_from_labels(i.labels.iter().map(|l| l.as_str()), estimate_label_prefix)
+                }),
+                tags: Vec::new(),
+                project: None,
+            }
+        })
+        .collect())
+}
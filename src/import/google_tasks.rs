@@ -0,0 +1,51 @@
+//! Reads `Tasks.json` from a Google Takeout export of Google Tasks.
+
+use super::{local_midnight, ImportedTask};
+use crate::SparrowResult;
+use chrono::DateTime;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct TaskLists {
+    #[serde(default)]
+    items: Vec<TaskList>,
+}
+
+#[derive(Deserialize)]
+struct TaskList {
+    #[serde(default)]
+    items: Vec<GoogleTask>,
+}
+
+#[derive(Deserialize)]
+struct GoogleTask {
+    title: String,
+
+    /// An RFC 3339 timestamp, but Google Tasks only uses the date.
+    due: Option<String>,
+
+    /// Either "needsAction" or "completed".
+    status: String,
+}
+
+pub fn parse(json: &str) -> SparrowResult<Vec<ImportedTask>> {
+    let lists: TaskLists = serde_json::from_str(json)?;
+
+    Ok(lists
+        .items
+        .into_iter()
+        .flat_map(|list| list.items)
+        .filter(|t| !t.title.trim().is_empty())
+        .map(|t| ImportedTask {
+            name: t.title.trim().to_string(),
+            due_date: t
+                .due
+                .and_then(|d| DateTime::parse_from_rfc3339(&d).ok())
+                .and_then(|d| local_midnight(d.naive_utc().date())),
+            done: t.status == "completed",
+            minutes: None,
+            tags: Vec::new(),
+            project: None,
+        })
+        .collect())
+}
@@ -0,0 +1,369 @@
+//! Reads events from an iCalendar (.ics) file, like a class timetable exported by a university.
+//! Times with a TZID are read in that timezone, and events keep it so they repeat at the same time
+//! there. Times with a TZID sparrow doesn't know are read as local time.
+
+use super::{parse_iso_duration, ImportedEvent};
+use crate::{Repeat, SparrowError, SparrowResult};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+/// One `NAME;PARAM=VALUE:VALUE` line of an iCalendar file.
+struct Property {
+    name: String,
+    params: HashMap<String, String>,
+    value: String,
+}
+
+/// A date and time from an iCalendar file, or only a date (for all-day events).
+#[derive(Clone, Copy)]
+enum Start {
+    DateTime(DateTime<Local>),
+    Date(NaiveDate),
+}
+
+pub fn parse(text: &str) -> SparrowResult<Vec<ImportedEvent>> {
+    let mut events = Vec::new();
+    let mut vevent: Option<Vec<Property>> = None;
+
+    for line in unfold(text) {
+        let property = match parse_property(&line) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        match (
+            property.name.as_str(),
+            property.value.to_uppercase().as_str(),
+        ) {
+            ("BEGIN", "VEVENT") => vevent = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(properties) = vevent.take() {
+                    events.extend(parse_vevent(&properties)?);
+                }
+            }
+            _ => {
+                if let Some(properties) = &mut vevent {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Turns the properties of a VEVENT into events. An event repeating on several days of the week
+/// becomes one weekly event for each day.
+fn parse_vevent(properties: &[Property]) -> SparrowResult<Vec<ImportedEvent>> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name);
+
+    if get("STATUS").is_some_and(|p| p.value.eq_ignore_ascii_case("CANCELLED")) {
+        return Ok(Vec::new());
+    }
+
+    let name = get("SUMMARY")
+        .map(|p| unescape(&p.value))
+        .unwrap_or_default();
+    let timezone = get("DTSTART").and_then(timezone_of);
+    let start = match get("DTSTART") {
+        Some(p) => parse_start(p)?,
+        None => {
+            return Err(SparrowError::BasicMessage(format!(
+                "the event '{}' doesn't have a start time",
+                name
+            )))
+        }
+    };
+    let start = match start {
+        Start::DateTime(d) => d,
+        Start::Date(date) => {
+            return Ok(vec![ImportedEvent {
+                name,
+                start: local_start(date.and_hms(0, 0, 0))?,
+                minutes: 24 * 60,
+                all_day: true,
+                repeat: Some(Repeat::No),
+                repeat_until: None,
+                repeat_count: None,
+                exceptions: Vec::new(),
+                timezone: None,
+            }])
+        }
+    };
+
+    let minutes = if let Some(end) = get("DTEND") {
+        match parse_start(end)? {
+            Start::DateTime(end) => (end - start).num_minutes(),
+            Start::Date(_) => 0,
+        }
+    } else if let Some(duration) = get("DURATION") {
+        parse_iso_duration(&duration.value)
+            .ok_or_else(|| {
+                SparrowError::BasicMessage(format!(
+                    "'{}' isn't a duration sparrow understands",
+                    duration.value
+                ))
+            })?
+            .num_minutes()
+    } else {
+        0
+    };
+    let minutes = minutes.max(0) as u32;
+
+    let rule = match get("RRULE") {
+        Some(r) => parse_rule(&r.value),
+        None => HashMap::new(),
+    };
+    let repeat_until = rule.get("UNTIL").map(|u| parse_until(u)).transpose()?;
+    let repeat_count = rule.get("COUNT").and_then(|c| c.parse::<u32>().ok());
+    let exceptions = parse_exceptions(properties)?;
+    let days: Vec<Weekday> = rule
+        .get("BYDAY")
+        .map(|d| d.split(',').filter_map(parse_weekday).collect())
+        .unwrap_or_default();
+    let event = |start, repeat, repeat_until, repeat_count| ImportedEvent {
+        name: name.clone(),
+        start,
+        minutes,
+        all_day: false,
+        repeat,
+        repeat_until,
+        repeat_count,
+        exceptions: exceptions.clone(),
+        timezone: timezone.map(|tz| tz.name().to_string()),
+    };
+
+    let interval = rule
+        .get("INTERVAL")
+        .and_then(|i| i.parse::<u32>().ok())
+        .filter(|i| *i > 0)
+        .unwrap_or(1);
+    let repeat = match (rule.get("FREQ").map(String::as_str), interval) {
+        (None, _) => Repeat::No,
+        (Some("DAILY"), 1) if days.is_empty() => Repeat::Daily,
+        (Some("DAILY"), every) if days.is_empty() => Repeat::EveryNDays(every),
+        (Some("DAILY"), 1) | (Some("WEEKLY"), 1) => Repeat::Weekly,
+        (Some("WEEKLY"), every) => Repeat::EveryNWeeks(every),
+
+        // only monthly and yearly repeats on the date the event starts are understood
+        (Some("MONTHLY"), 1) if days.is_empty() && !rule.contains_key("BYMONTHDAY") => {
+            Repeat::Monthly {
+                day_of_month: start.day(),
+            }
+        }
+        (Some("YEARLY"), 1) if days.is_empty() && !rule.contains_key("BYMONTH") => Repeat::Yearly,
+
+        // other repeats, like every few months, can't be imported yet
+        _ => return Ok(vec![event(start, None, None, None)]),
+    };
+
+    let weeks_apart = match repeat {
+        Repeat::Weekly => Some(1),
+        Repeat::EveryNWeeks(every) => Some(every),
+        _ => None,
+    };
+    if let (Some(weeks_apart), false) = (weeks_apart, days.is_empty()) {
+        let starts = days
+            .iter()
+            .map(|day| {
+                // weeks start on Monday, so days earlier in the week than `start` come in the next
+                // week that the event repeats in
+                let next = next_on_weekday(start, *day)?;
+                if day.num_days_from_monday() < start.weekday().num_days_from_monday() {
+                    Ok(next + Duration::weeks(weeks_apart as i64 - 1))
+                } else {
+                    Ok(next)
+                }
+            })
+            .collect::<SparrowResult<Vec<_>>>()?;
+
+        // a count covers every day together, so it has to become the start of the last one
+        let (repeat_until, repeat_count) = match repeat_count {
+            Some(count) if count > 0 && starts.len() > 1 => {
+                let mut sorted = starts.clone();
+                sorted.sort();
+                let last = (count - 1) as usize;
+                let last_start =
+                    repeat.nth_start(&sorted[last % sorted.len()], (last / sorted.len()) as i64);
+                let until = match (repeat_until, last_start) {
+                    (Some(u), Some(l)) => Some(u.min(l)),
+                    (u, l) => u.or(l),
+                };
+                (until, None)
+            }
+            _ => (repeat_until, repeat_count),
+        };
+
+        Ok(starts
+            .into_iter()
+            .map(|s| event(s, Some(repeat.clone()), repeat_until, repeat_count))
+            .collect())
+    } else {
+        Ok(vec![event(start, Some(repeat), repeat_until, repeat_count)])
+    }
+}
+
+/// Joins folded lines (continued on the next line after a space or tab) back together.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // the value starts after the first colon that isn't in a quoted parameter value
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: value.trim().to_string(),
+    })
+}
+
+fn parse_start(property: &Property) -> SparrowResult<Start> {
+    let is_date = property
+        .params
+        .get("VALUE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("DATE"));
+    parse_timestamp(&property.value, is_date, timezone_of(property))
+}
+
+/// Returns the timezone in a property's TZID, if sparrow knows it.
+fn timezone_of(property: &Property) -> Option<Tz> {
+    property.params.get("TZID")?.parse().ok()
+}
+
+/// Returns the days in the EXDATEs of an event, which can each list several times.
+fn parse_exceptions(properties: &[Property]) -> SparrowResult<Vec<NaiveDate>> {
+    let mut days = Vec::new();
+    for property in properties.iter().filter(|p| p.name == "EXDATE") {
+        let is_date = property
+            .params
+            .get("VALUE")
+            .is_some_and(|v| v.eq_ignore_ascii_case("DATE"));
+        for value in property.value.split(',') {
+            let day = match parse_timestamp(value.trim(), is_date, timezone_of(property))? {
+                Start::DateTime(d) => d.date().naive_local(),
+                Start::Date(date) => date,
+            };
+            days.push(day);
+        }
+    }
+    Ok(days)
+}
+
+/// Parses the UNTIL of an RRULE. An UNTIL that's only a date includes that whole day.
+fn parse_until(value: &str) -> SparrowResult<DateTime<Local>> {
+    match parse_timestamp(value, false, None)? {
+        Start::DateTime(d) => Ok(d),
+        Start::Date(date) => local_start(date.and_hms(23, 59, 59)),
+    }
+}
+
+/// Parses a date or date-time. A date-time that isn't in UTC is in `timezone`, or local time.
+fn parse_timestamp(value: &str, is_date: bool, timezone: Option<Tz>) -> SparrowResult<Start> {
+    if is_date || !value.contains('T') {
+        Ok(Start::Date(NaiveDate::parse_from_str(value, "%Y%m%d")?))
+    } else if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?;
+        Ok(Start::DateTime(
+            Utc.from_utc_datetime(&naive).with_timezone(&Local),
+        ))
+    } else {
+        let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+        match timezone {
+            Some(tz) => match tz.from_local_datetime(&naive).earliest() {
+                Some(start) => Ok(Start::DateTime(start.with_timezone(&Local))),
+                None => Err(SparrowError::BasicMessage(format!(
+                    "{} doesn't exist in {}",
+                    naive,
+                    tz.name()
+                ))),
+            },
+            None => Ok(Start::DateTime(local_start(naive)?)),
+        }
+    }
+}
+
+fn local_start(naive: NaiveDateTime) -> SparrowResult<DateTime<Local>> {
+    Local.from_local_datetime(&naive).earliest().ok_or_else(|| {
+        SparrowError::BasicMessage(format!("{} doesn't exist in your timezone", naive))
+    })
+}
+
+/// Splits an RRULE like "FREQ=WEEKLY;BYDAY=MO,WE" into its parts.
+fn parse_rule(value: &str) -> HashMap<String, String> {
+    value
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(k, v)| (k.to_uppercase(), v.to_uppercase()))
+        .collect()
+}
+
+/// Parses a BYDAY day like "MO". Days with an ordinal, like "1MO" (the first Monday), only mean
+/// something for monthly and yearly repeats, so the ordinal is ignored.
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    let day = day.trim_start_matches(|c: char| c.is_ascii_digit() || c == '+' || c == '-');
+    match day {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Returns the first time on `day` (of the week), at the same time as `start`, on or after it.
+fn next_on_weekday(start: DateTime<Local>, day: Weekday) -> SparrowResult<DateTime<Local>> {
+    let days_ahead = (day.num_days_from_monday() + 7 - start.weekday().num_days_from_monday()) % 7;
+    local_start(start.naive_local() + Duration::days(days_ahead as i64))
+}
+
+/// Undoes the escaping of iCalendar text values.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => {}
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped.trim().to_string()
+}
@@ -0,0 +1,54 @@
+//! Reads Microsoft To Do tasks, as returned by the Microsoft Graph API's
+//! `/me/todo/lists/{id}/tasks` endpoint (for example, saved from Graph Explorer).
+
+use super::{local_midnight, ImportedTask};
+use crate::SparrowResult;
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskCollection {
+    value: Vec<TodoTask>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodoTask {
+    title: String,
+
+    /// "notStarted", "inProgress", "completed", "waitingOnOthers", or "deferred".
+    status: String,
+
+    due_date_time: Option<DateTimeTimeZone>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DateTimeTimeZone {
+    /// Like "2020-10-20T00:00:00.0000000". To Do only uses the date.
+    date_time: String,
+}
+
+pub fn parse(json: &str) -> SparrowResult<Vec<ImportedTask>> {
+    let collection: TaskCollection = serde_json::from_str(json)?;
+
+    Ok(collection
+        .value
+        .into_iter()
+        .filter(|t| !t.title.trim().is_empty())
+        .map(|t| ImportedTask {
+            name: t.title.trim().to_string(),
+            due_date: t
+                .due_date_time
+                .and_then(|d| {
+                    NaiveDateTime::parse_from_str(&d.date_time, "%Y-%m-%dT%H:%M:%S%.f").ok()
+                })
+                .and_then(|d| local_midnight(d.date())),
+            done: t.status == "completed",
+            minutes: None,
+            tags: Vec::new(),
+            project: None,
+        })
+        .collect())
+}
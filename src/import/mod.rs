@@ -0,0 +1,279 @@
+//! Brings tasks and events from other programs into sparrow.
+
+pub mod csv;
+pub mod github;
+pub mod gitlab;
+pub mod google_tasks;
+pub mod ics;
+pub mod microsoft_todo;
+pub mod taskwarrior;
+pub mod todo;
+
+use crate::{
+    task::{parse_duration_minutes, TaskDuration},
+    CalendarEvent, CalendarEventType, Repeat, Task, TimeSpan, UserData,
+};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use std::fmt::{Display, Formatter};
+
+/// A to-do item from another program, before it's turned into a Task.
+#[derive(Clone, Debug)]
+pub struct ImportedTask {
+    pub name: String,
+    pub due_date: Option<DateTime<Local>>,
+    pub done: bool,
+
+    /// How many minutes the task is estimated to take, if the other program knows.
+    pub minutes: Option<u64>,
+
+    pub tags: Vec<String>,
+
+    /// The project the task belongs to, if the other program has projects. Tasks in a project
+    /// named like one of the user's courses go in that course.
+    pub project: Option<String>,
+}
+
+/// An event from another program, before it's turned into a CalendarEvent.
+#[derive(Clone, Debug)]
+pub struct ImportedEvent {
+    pub name: String,
+    pub start: DateTime<Local>,
+    pub minutes: u32,
+
+    /// True if the event lasts all day instead of at a certain time, like a holiday.
+    pub all_day: bool,
+
+    /// How the event repeats, or None if it repeats in a way sparrow doesn't support.
+    pub repeat: Option<Repeat>,
+
+    /// When the event stops repeating, if it does.
+    pub repeat_until: Option<DateTime<Local>>,
+
+    /// How many times the event happens in all, if it stops repeating after some.
+    pub repeat_count: Option<u32>,
+
+    /// Days the event doesn't happen on, even though it repeats then.
+    pub exceptions: Vec<NaiveDate>,
+
+    /// The IANA timezone the event's times were given in, if it had one sparrow knows.
+    pub timezone: Option<String>,
+}
+
+/// Narrows down which issues are imported from an issue tracker.
+#[derive(Default)]
+pub struct IssueFilter {
+    /// Only import issues in the milestone with this title.
+    pub milestone: Option<String>,
+
+    /// Only import issues with all of these labels.
+    pub labels: Vec<String>,
+}
+
+/// What happened to each item of an import.
+#[derive(Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub skipped_done: usize,
+    pub skipped_without_due_date: usize,
+    pub skipped_duplicates: usize,
+}
+
+impl Display for ImportSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Added {} tasks", self.added)?;
+        let skipped = [
+            (self.skipped_done, "already done"),
+            (self.skipped_without_due_date, "without a due date"),
+            (self.skipped_duplicates, "already in sparrow"),
+        ];
+        for (count, reason) in skipped.iter().filter(|(count, _)| *count > 0) {
+            write!(f, "\n\tskipped {} {}", count, reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What happened to each event of an import.
+#[derive(Default)]
+pub struct EventImportSummary {
+    pub added: usize,
+    pub skipped_all_day: usize,
+    pub skipped_unsupported_repeats: usize,
+    pub skipped_duplicates: usize,
+}
+
+impl Display for EventImportSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Added {} events", self.added)?;
+        let skipped = [
+            (self.skipped_all_day, "lasting all day"),
+            (
+                self.skipped_unsupported_repeats,
+                "repeating in ways sparrow can't",
+            ),
+            (self.skipped_duplicates, "already in sparrow"),
+        ];
+        for (count, reason) in skipped.iter().filter(|(count, _)| *count > 0) {
+            write!(f, "\n\tskipped {} {}", count, reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds the events in `imported` to `data`. All-day events are left out, since they'd block out
+/// whole days of work. So are events matching an existing event's name and start, so importing
+/// the same file twice is harmless.
+pub fn add_imported_events(
+    data: &mut UserData,
+    imported: Vec<ImportedEvent>,
+) -> EventImportSummary {
+    let mut summary = EventImportSummary::default();
+
+    for item in imported {
+        if item.all_day {
+            summary.skipped_all_day += 1;
+            continue;
+        }
+        let repeat = match item.repeat.clone() {
+            Some(r) => r,
+            None => {
+                summary.skipped_unsupported_repeats += 1;
+                continue;
+            }
+        };
+
+        if data
+            .get_events()
+            .iter()
+            .any(|e| e.name == item.name && *e.time_span.start() == item.start)
+        {
+            summary.skipped_duplicates += 1;
+            continue;
+        }
+
+        data.add_event(CalendarEvent {
+            id: 0,
+            name: item.name,
+            time_span: TimeSpan::new(item.start, item.minutes),
+            event_type: CalendarEventType::Event,
+            repeat,
+            tags: Vec::new(),
+            repeat_until: item.repeat_until,
+            repeat_count: item.repeat_count,
+            exceptions: item.exceptions,
+            timezone: item.timezone,
+        });
+        summary.added += 1;
+    }
+
+    summary
+}
+
+/// Returns true if any item that would be added has no estimate of its own.
+pub fn needs_default_minutes(imported: &[ImportedTask]) -> bool {
+    imported
+        .iter()
+        .any(|i| !i.done && i.due_date.is_some() && i.minutes.is_none())
+}
+
+/// Adds unfinished, due items from `imported` to `data` as tasks. Items without an estimate take
+/// `default_minutes`. Items that match an existing task's name and due date are left out, so
+/// importing the same file twice is harmless.
+pub fn add_imported_tasks(
+    data: &mut UserData,
+    imported: Vec<ImportedTask>,
+    default_minutes: u64,
+) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+
+    for item in imported {
+        let due_date = match item.due_date {
+            _ if item.done => {
+                summary.skipped_done += 1;
+                continue;
+            }
+            Some(d) => d,
+            None => {
+                summary.skipped_without_due_date += 1;
+                continue;
+            }
+        };
+
+        if data
+            .get_tasks()
+            .iter()
+            .any(|t| t.name == item.name && t.due_date == due_date)
+        {
+            summary.skipped_duplicates += 1;
+            continue;
+        }
+
+        let mut task = Task::new(
+            item.name,
+            due_date,
+            TaskDuration::Minutes(item.minutes.unwrap_or(default_minutes)),
+        );
+        task.tags = item.tags;
+        if let Some(project) = item.project {
+            match data
+                .get_courses()
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(&project))
+            {
+                Some(course) => task.course = Some(course.name.clone()),
+                None => task.tags.push(project),
+            }
+        }
+        data.add_task(task);
+        summary.added += 1;
+    }
+
+    summary
+}
+
+/// Finds an estimate in labels like "estimate: 2h", where `prefix` is "estimate:".
+fn estimate_from_labels<'a, I>(labels: I, prefix: &str) -> Option<u64>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    labels
+        .into_iter()
+        .filter_map(|l| l.strip_prefix(prefix))
+        .find_map(parse_duration_minutes)
+}
+
+/// Parses an ISO 8601 duration, like "PT1H30M", "P1D", or "P1W", as iCalendar and Taskwarrior
+/// write them.
+fn parse_iso_duration(value: &str) -> Option<Duration> {
+    let value = value.trim_start_matches('+').strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total = total
+                    + match unit {
+                        'W' => Duration::weeks(n),
+                        'D' => Duration::days(n),
+                        'H' => Duration::hours(n),
+                        'M' => Duration::minutes(n),
+                        'S' => Duration::seconds(n),
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    Some(total)
+}
+
+/// Most to-do programs only keep a due *date*. Like `prompt_datetime` does for an empty time,
+/// those become due at midnight.
+fn local_midnight(date: NaiveDate) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest()
+}
@@ -0,0 +1,58 @@
+//! Reads the JSON written by Taskwarrior's `task export`. Estimates come from an `estimate` UDA
+//! (user defined attribute), which can be a duration like "PT2H" or "2h", or a number of minutes.
+
+use super::{parse_iso_duration, ImportedTask};
+use crate::{task::parse_duration_minutes, SparrowResult};
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct TaskwarriorTask {
+    description: String,
+
+    /// "pending", "waiting", "completed", "deleted", or "recurring". Recurring tasks are the
+    /// templates of repeating tasks; their instances are exported as pending tasks of their own.
+    status: String,
+
+    /// In UTC, like "20240131T170000Z".
+    due: Option<String>,
+
+    project: Option<String>,
+
+    #[serde(default)]
+    tags: Vec<String>,
+
+    estimate: Option<serde_json::Value>,
+}
+
+pub fn parse(json: &str) -> SparrowResult<Vec<ImportedTask>> {
+    let tasks: Vec<TaskwarriorTask> = serde_json::from_str(json)?;
+
+    Ok(tasks
+        .into_iter()
+        .filter(|t| t.status != "deleted" && t.status != "recurring")
+        .filter(|t| !t.description.trim().is_empty())
+        .map(|t| ImportedTask {
+            name: t.description.trim().to_string(),
+            due_date: t
+                .due
+                .and_then(|d| NaiveDateTime::parse_from_str(&d, "%Y%m%dT%H%M%SZ").ok())
+                .map(|d| Utc.from_utc_datetime(&d).with_timezone(&Local)),
+            done: t.status == "completed",
+            minutes: t.estimate.as_ref().and_then(estimate_minutes),
+            tags: t.tags,
+            project: t.project,
+        })
+        .collect())
+}
+
+fn estimate_minutes(estimate: &serde_json::Value) -> Option<u64> {
+    match estimate {
+        serde_json::Value::Number(n) => n.as_f64().filter(|m| *m > 0.0).map(|m| m as u64),
+        serde_json::Value::String(s) => match parse_iso_duration(s) {
+            Some(d) => Some(d.num_minutes() as u64).filter(|m| *m > 0),
+            None => parse_duration_minutes(s),
+        },
+        _ => None,
+    }
+}
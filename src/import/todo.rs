@@ -0,0 +1,219 @@
+//! Reads to-do lists kept by hand: markdown checklists like `- [ ] Finish report (2h, due 5/10)`,
+//! and org-mode checklists and `TODO`/`DONE` headlines. Estimates and due dates can be in
+//! parentheses at the end of an item, or written like quick-add (`~2h due fri #work`). Anything
+//! that isn't an item is ignored.
+
+use super::ImportedTask;
+use crate::{
+    dates,
+    prompts::{prompt_datetime, prompt_strict},
+    quick_add::QuickAdd,
+    task::parse_duration_minutes,
+    Config, Formatting, SparrowError, SparrowResult,
+};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime};
+
+/// Reads the items in `text`. Dates are understood relative to `now`.
+pub fn parse(text: &str, config: &Config, now: DateTime<Local>) -> Vec<ImportedTask> {
+    let mut tasks: Vec<ImportedTask> = Vec::new();
+    // whether the last line was an org headline, which a DEADLINE line below it belongs to
+    let mut last_was_headline = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if last_was_headline {
+            if let Some(deadline) = org_deadline(trimmed) {
+                let last = tasks.last_mut().unwrap();
+                if last.due_date.is_none() {
+                    last.due_date = dates::to_local(deadline.0, deadline.1).ok();
+                }
+                continue;
+            }
+        }
+
+        let (done, rest, headline) = match checklist_item(trimmed) {
+            Some((done, rest)) => (done, rest, false),
+            None => match org_headline(line) {
+                Some((done, rest)) => (done, rest, true),
+                None => {
+                    last_was_headline = false;
+                    continue;
+                }
+            },
+        };
+        last_was_headline = headline;
+
+        let (rest, org_tags) = if headline {
+            split_org_tags(rest)
+        } else {
+            (rest, Vec::new())
+        };
+        let (rest, details) = split_details(rest, config, now);
+
+        let item = QuickAdd::parse(rest, config, now);
+        if item.name.is_empty() {
+            continue;
+        }
+        let mut tags = item.tags;
+        tags.extend(org_tags);
+        tasks.push(ImportedTask {
+            name: item.name,
+            due_date: details.due_date.or(item.due_date),
+            done,
+            minutes: details.minutes.or(item.minutes),
+            tags,
+            project: None,
+        });
+    }
+
+    tasks
+}
+
+/// Asks for the due dates and estimates that unfinished items in `tasks` didn't come with.
+pub fn fill_in(
+    tasks: &mut [ImportedTask],
+    formatting: &Formatting,
+    config: &Config,
+) -> SparrowResult<()> {
+    for task in tasks.iter_mut().filter(|t| !t.done) {
+        if task.due_date.is_none() {
+            println!("When is '{}' due?", task.name);
+            task.due_date = Some(prompt_datetime(
+                formatting,
+                &config.date_format,
+                &config.time_format,
+                true,
+            )?);
+        }
+        if task.minutes.is_none() {
+            task.minutes = Some(prompt_strict(
+                formatting,
+                &format!("How long will '{}' take?", task.name),
+                Some("like 90m or 2h"),
+                None,
+                |i| {
+                    parse_duration_minutes(i).ok_or_else(|| {
+                        SparrowError::BasicMessage(format!("'{}' isn't a duration", i.trim()))
+                    })
+                },
+            )?);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a checklist item, like "- [ ] Finish report" or "1. [x] Call mom", into whether it's
+/// checked off and the rest of the line.
+fn checklist_item(line: &str) -> Option<(bool, &str)> {
+    let rest = match line.strip_prefix(&['-', '*', '+'][..]) {
+        Some(rest) => rest,
+        None => {
+            let digits = line
+                .find(|c: char| !c.is_ascii_digit())
+                .filter(|&i| i > 0)?;
+            line[digits..].strip_prefix(&['.', ')'][..])?
+        }
+    };
+    let rest = rest.strip_prefix(char::is_whitespace)?.trim_start();
+    let done = match rest.get(..3)? {
+        "[ ]" | "[-]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((done, rest[3..].trim()))
+}
+
+/// Reads an org headline with a TODO or DONE keyword, like "** TODO Finish report", into whether
+/// it's done and the rest of the line.
+fn org_headline(line: &str) -> Option<(bool, &str)> {
+    let rest = line.trim_start_matches('*');
+    if rest.len() == line.len() {
+        return None;
+    }
+    let rest = rest.strip_prefix(' ')?.trim_start();
+    let (keyword, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    match keyword {
+        "TODO" => Some((false, rest.trim())),
+        "DONE" => Some((true, rest.trim())),
+        _ => None,
+    }
+}
+
+/// Splits org tags like ":work:urgent:" off the end of a headline.
+fn split_org_tags(headline: &str) -> (&str, Vec<String>) {
+    if let Some((rest, tags)) = headline.rsplit_once(char::is_whitespace) {
+        if tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') {
+            let tags = tags
+                .trim_matches(':')
+                .split(':')
+                .map(String::from)
+                .collect();
+            return (rest.trim_end(), tags);
+        }
+    }
+    (headline, Vec::new())
+}
+
+/// Reads an org deadline line, like "DEADLINE: <2024-05-10 Fri 17:00>".
+fn org_deadline(line: &str) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let (_, stamp) = line.split_once("DEADLINE:")?;
+    let stamp = stamp.trim_start().strip_prefix('<')?;
+    let stamp = &stamp[..stamp.find('>')?];
+    let mut words = stamp.split_whitespace();
+    let date = NaiveDate::parse_from_str(words.next()?, "%Y-%m-%d").ok()?;
+    let time = words.find_map(|w| NaiveTime::parse_from_str(w, "%H:%M").ok());
+    Some((date, time))
+}
+
+/// What's in parentheses at the end of an item, like "(2h, due 5/10)".
+#[derive(Default)]
+struct Details {
+    due_date: Option<DateTime<Local>>,
+    minutes: Option<u64>,
+}
+
+/// Splits details in parentheses off the end of `item`. If anything in them can't be read,
+/// they're left as part of the name.
+fn split_details<'a>(item: &'a str, config: &Config, now: DateTime<Local>) -> (&'a str, Details) {
+    let read = || {
+        let inner = item.strip_suffix(')')?;
+        let open = inner.rfind('(')?;
+        let mut details = Details::default();
+        for part in inner[open + 1..].split(',').map(str::trim) {
+            if let Some(due) = part.strip_prefix("due ") {
+                let (date, time) = parse_due(due, config, now)?;
+                details.due_date = Some(dates::to_local_due(date, time).ok()?);
+            } else {
+                details.minutes = Some(parse_duration_minutes(part.trim_start_matches('~'))?);
+            }
+        }
+        Some((inner[..open].trim_end(), details))
+    };
+    read().unwrap_or((item, Details::default()))
+}
+
+/// Reads a due date like sparrow does everywhere else, or a month and day like "5/10", which
+/// means the next 10th of May.
+fn parse_due(
+    input: &str,
+    config: &Config,
+    now: DateTime<Local>,
+) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    if let Some(due) = dates::parse_date_time(input, &config.date_format, &config.time_format, now)
+    {
+        return Some(due);
+    }
+
+    let (date, time) = input.trim().split_once(' ').unwrap_or((input.trim(), ""));
+    let (month, day) = date.split_once('/')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    let today = now.date().naive_local();
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)
+        .filter(|d| *d >= today)
+        .or_else(|| NaiveDate::from_ymd_opt(today.year() + 1, month, day))?;
+    let time = match time.trim() {
+        "" => None,
+        time => Some(dates::parse_time(time, &config.time_format)?),
+    };
+    Some((date, time))
+}
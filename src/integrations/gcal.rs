@@ -0,0 +1,384 @@
+//! Reads upcoming events from Google Calendar, so schedules can be made around meetings. sparrow
+//! logs in with OAuth like other desktop apps do: the user approves access in their browser, which
+//! then sends them back to a small server sparrow runs on localhost for a moment.
+//!
+//! Tokens are kept in the user's config directory, away from the data file, since the data file
+//! is meant to be safe to share or sync.
+
+use crate::{
+    CalendarEvent, CalendarEventType, GcalConfig, Repeat, SparrowError, SparrowResult, TimeSpan,
+    UserData,
+};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API_URL: &str = "https://www.googleapis.com/calendar/v3";
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.readonly";
+
+/// Tokens are refreshed this long before they expire, so they don't expire mid-sync.
+const EXPIRY_MARGIN_SECONDS: i64 = 60;
+
+/// The environment variable the client secret can be given with instead of the config.
+pub const CLIENT_SECRET_VAR: &str = "SPARROW_GCAL_CLIENT_SECRET";
+
+/// What's kept between runs after logging in.
+#[derive(Deserialize, Serialize)]
+struct Token {
+    access_token: String,
+    refresh_token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// The response from the token endpoint, both when logging in and when refreshing.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+
+    /// Only given when logging in, not when refreshing.
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EventPage {
+    #[serde(default)]
+    items: Vec<GoogleEvent>,
+
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GoogleEvent {
+    summary: Option<String>,
+    start: EventTime,
+    end: EventTime,
+
+    /// "cancelled" for events that were deleted or declined by the organizer.
+    #[serde(default)]
+    status: String,
+
+    /// "transparent" for events that don't block time, like reminders.
+    #[serde(default)]
+    transparency: String,
+}
+
+/// All-day events only have a date, so they don't have this.
+#[derive(Deserialize)]
+struct EventTime {
+    #[serde(rename = "dateTime")]
+    date_time: Option<DateTime<Utc>>,
+}
+
+/// What happened in a sync.
+pub struct SyncSummary {
+    pub synced: usize,
+    pub skipped_all_day: usize,
+}
+
+impl Display for SyncSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Synced {} events from Google Calendar", self.synced)?;
+        if self.skipped_all_day > 0 {
+            write!(f, "\n\tskipped {} lasting all day", self.skipped_all_day)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the events from the last sync with the events coming up in the configured calendars.
+/// All-day events are left out, since they'd block out whole days of work.
+pub fn sync(data: &mut UserData) -> SparrowResult<SyncSummary> {
+    let config = data.get_config().gcal.clone().ok_or_else(|| {
+        SparrowError::BasicMessage(
+            "Google Calendar isn't set up; add a `gcal` section with your OAuth client to the \
+             config"
+                .to_string(),
+        )
+    })?;
+
+    let access_token = access_token(&config)?;
+    let mut summary = SyncSummary {
+        synced: 0,
+        skipped_all_day: 0,
+    };
+    let mut events = Vec::new();
+    for calendar in &config.calendars {
+        for event in fetch(&access_token, calendar, config.days_ahead)? {
+            let (start, end) = match (event.start.date_time, event.end.date_time) {
+                (Some(start), Some(end)) => (start, end),
+                _ => {
+                    summary.skipped_all_day += 1;
+                    continue;
+                }
+            };
+            events.push(CalendarEvent {
+                id: 0,
+                name: event.summary.unwrap_or_default().trim().to_string(),
+                time_span: TimeSpan::new(
+                    start.with_timezone(&Local),
+                    (end - start).num_minutes().max(0) as u32,
+                ),
+                event_type: CalendarEventType::Event,
+                repeat: Repeat::No,
+                tags: vec!["gcal".to_string()],
+                repeat_until: None,
+                repeat_count: None,
+                exceptions: Vec::new(),
+                timezone: None,
+            });
+        }
+    }
+
+    summary.synced = events.len();
+    data.set_synced_events(events);
+    Ok(summary)
+}
+
+/// Fetches the events in `calendar` from now until `days_ahead` days from now. Repeating events
+/// come as one event for each time they happen.
+fn fetch(access_token: &str, calendar: &str, days_ahead: u32) -> SparrowResult<Vec<GoogleEvent>> {
+    let now = Utc::now();
+    let until = now + Duration::days(days_ahead as i64);
+
+    let mut events = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = ureq::get(&format!(
+            "{}/calendars/{}/events",
+            API_URL,
+            percent_encode(calendar)
+        ))
+        .set("Authorization", &format!("Bearer {}", access_token))
+        .query("timeMin", &now.to_rfc3339())
+        .query("timeMax", &until.to_rfc3339())
+        .query("singleEvents", "true")
+        .query("orderBy", "startTime");
+        if let Some(token) = &page_token {
+            request = request.query("pageToken", token);
+        }
+
+        let page: EventPage = request.call()?.into_json()?;
+        events.extend(
+            page.items
+                .into_iter()
+                .filter(|e| e.status != "cancelled" && e.transparency != "transparent"),
+        );
+
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(events)
+}
+
+/// Returns an access token, refreshing it or logging in first if needed.
+fn access_token(config: &GcalConfig) -> SparrowResult<String> {
+    let token = match load_token() {
+        Some(token) if token.expires_at - Duration::seconds(EXPIRY_MARGIN_SECONDS) > Utc::now() => {
+            return Ok(token.access_token)
+        }
+        Some(token) => refresh(config, token)?,
+        None => log_in(config)?,
+    };
+    store_token(&token)?;
+    Ok(token.access_token)
+}
+
+fn refresh(config: &GcalConfig, token: Token) -> SparrowResult<Token> {
+    let response: TokenResponse = ureq::post(TOKEN_URL)
+        .send_form(&[
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", &client_secret(config)),
+            ("refresh_token", &token.refresh_token),
+            ("grant_type", "refresh_token"),
+        ])?
+        .into_json()?;
+
+    Ok(Token {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token.unwrap_or(token.refresh_token),
+        expires_at: Utc::now() + Duration::seconds(response.expires_in),
+    })
+}
+
+/// Asks the user to approve access in their browser, then waits for the browser to come back
+/// with a code to trade for tokens.
+fn log_in(config: &GcalConfig) -> SparrowResult<Token> {
+    let server = tiny_http::Server::http("127.0.0.1:0").map_err(|e| {
+        SparrowError::BasicMessage(format!("couldn't start the login server: {}", e))
+    })?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|a| a.port())
+        .ok_or_else(|| SparrowError::BasicMessage("the login server has no port".to_string()))?;
+    let redirect_uri = format!("http://127.0.0.1:{}", port);
+    let state = random_state();
+
+    let url = format!(
+        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&access_type=offline\
+         &prompt=consent&state={}",
+        AUTH_URL,
+        percent_encode(&config.client_id),
+        percent_encode(&redirect_uri),
+        percent_encode(SCOPE),
+        state
+    );
+    println!(
+        "Open this link to let sparrow read your Google Calendar:\n\n{}\n",
+        url
+    );
+
+    let code = loop {
+        let request = server.recv()?;
+        let params = query_params(request.url());
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+        };
+
+        // browsers ask for other things too, like favicons
+        if param("state").as_deref() != Some(state.as_str()) {
+            let _ = request.respond(tiny_http::Response::empty(404));
+            continue;
+        }
+        if let Some(error) = param("error") {
+            let _ = request.respond(tiny_http::Response::from_string(
+                "sparrow wasn't given access. You can close this tab.",
+            ));
+            return Err(SparrowError::BasicMessage(format!(
+                "Google Calendar access wasn't given: {}",
+                error
+            )));
+        }
+        if let Some(code) = param("code") {
+            let _ = request.respond(tiny_http::Response::from_string(
+                "sparrow can read your calendar now. You can close this tab.",
+            ));
+            break code;
+        }
+    };
+
+    let response: TokenResponse = ureq::post(TOKEN_URL)
+        .send_form(&[
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", &client_secret(config)),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("grant_type", "authorization_code"),
+        ])?
+        .into_json()?;
+
+    Ok(Token {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token.ok_or_else(|| {
+            SparrowError::BasicMessage("Google didn't give sparrow a refresh token".to_string())
+        })?,
+        expires_at: Utc::now() + Duration::seconds(response.expires_in),
+    })
+}
+
+/// Forgets the stored tokens, so the next sync logs in again.
+pub fn log_out() -> SparrowResult<()> {
+    match token_path() {
+        Some(path) if path.exists() => Ok(fs::remove_file(path)?),
+        _ => Ok(()),
+    }
+}
+
+fn client_secret(config: &GcalConfig) -> String {
+    std::env::var(CLIENT_SECRET_VAR).unwrap_or_else(|_| config.client_secret.clone())
+}
+
+fn token_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("sparrow").join("gcal_token.json"))
+}
+
+fn load_token() -> Option<Token> {
+    serde_json::from_str(&fs::read_to_string(token_path()?).ok()?).ok()
+}
+
+fn store_token(token: &Token) -> SparrowResult<()> {
+    let path = token_path().ok_or_else(|| {
+        SparrowError::BasicMessage("there's no config directory to keep tokens in".to_string())
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    // only the user should be able to read their tokens
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    serde_json::to_writer(options.open(path)?, token)?;
+    Ok(())
+}
+
+/// A value that's hard to guess, so that only the browser sparrow sent can log in.
+fn random_state() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_i64(Utc::now().timestamp_nanos());
+    format!("{:016x}", hasher.finish())
+}
+
+/// Splits the query of a URL like "/?code=abc&state=123" into decoded names and values.
+fn query_params(url: &str) -> Vec<(String, String)> {
+    url.split_once('?')
+        .map(|(_, query)| query)
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (percent_decode(k), percent_decode(v)))
+        .collect()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        decoded.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,3 @@
+//! Keeps sparrow in sync with other services, unlike `import`, which brings things in once.
+
+pub mod gcal;
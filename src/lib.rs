@@ -1,21 +1,118 @@
 use ansi_term::Style;
 
+pub mod agenda;
+pub mod archive;
+pub mod audit;
+pub mod backup;
+pub mod cache;
+pub mod capacity;
+pub mod config;
+pub mod course;
+pub mod ctl;
 pub mod data;
+pub mod dates;
+pub mod digest;
+pub mod doctor;
 pub mod errors;
+pub mod estimates;
+pub mod export;
+pub mod feasibility;
+pub mod focus;
+pub mod free;
+pub mod goals;
+pub mod habit;
+pub mod import;
+pub mod integrations;
+pub mod list;
+pub mod meals;
+pub mod metrics;
+pub mod migrations;
+pub mod plan;
+pub mod profile;
+pub mod mqtt;
+pub mod output;
 pub mod prompts;
+pub mod quick_add;
+pub mod quiet;
+pub mod recurrence;
+pub mod reflow;
 pub mod schedule;
+pub mod search;
+pub mod server;
+pub mod shared;
+pub mod sleep;
+pub mod sound;
 pub mod spans;
+pub mod stats;
+pub mod status;
+pub mod storage;
+pub mod streak;
 pub mod task;
+pub mod templates;
+pub mod tracking;
+pub mod tui;
+pub mod undo;
+pub mod webhook;
+pub mod when;
+pub mod why;
+pub mod work_windows;
 pub mod methods;
 
+pub use course::Course;
 pub use data::*;
+pub use habit::Habit;
 pub use errors::{SparrowError, SparrowResult};
+pub use metrics::ScheduleMetrics;
 pub use schedule::Schedule;
 pub use spans::*;
-pub use task::Task;
+pub use task::{Task, TaskId};
 
 pub struct Formatting {
     pub prompt: Style,
     pub prompt_format: Style,
     pub error: Style,
+
+    /// Styles for each kind of schedule entry.
+    pub job: Style,
+    pub break_time: Style,
+    pub calendar: Style,
+    pub sleep: Style,
+    pub habit: Style,
+
+    /// Laid over an entry's style when it's going on right now.
+    pub current: Style,
+
+    /// Laid over an entry's style when it's more than an hour away.
+    pub later: Style,
+}
+
+impl Formatting {
+    /// Returns the style for a kind of schedule entry, like "job" or "sleep".
+    pub fn entry_style(&self, kind: &str) -> Style {
+        match kind {
+            "job" => self.job,
+            "break" => self.break_time,
+            "calendar" => self.calendar,
+            "sleep" => self.sleep,
+            "habit" => self.habit,
+            _ => Style::new(),
+        }
+    }
+
+    /// Returns `base` with `over` laid on top: colors from `over` win, and effects from both are
+    /// kept.
+    pub fn layer(base: Style, over: Style) -> Style {
+        Style {
+            foreground: over.foreground.or(base.foreground),
+            background: over.background.or(base.background),
+            is_bold: base.is_bold || over.is_bold,
+            is_dimmed: base.is_dimmed || over.is_dimmed,
+            is_italic: base.is_italic || over.is_italic,
+            is_underline: base.is_underline || over.is_underline,
+            is_blink: base.is_blink || over.is_blink,
+            is_reverse: base.is_reverse || over.is_reverse,
+            is_hidden: base.is_hidden || over.is_hidden,
+            is_strikethrough: base.is_strikethrough || over.is_strikethrough,
+        }
+    }
 }
@@ -0,0 +1,396 @@
+//! Plain listings of the user's tasks and events, so they can be looked over without opening the
+//! data file.
+
+use crate::{
+    metrics::format_minutes,
+    task::{Priority, TaskDuration},
+    CalendarEvent, CalendarEventType, Config, Repeat, SparrowError, Task, UserData,
+};
+use chrono::{DateTime, Local};
+use serde::{Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+};
+
+/// How tasks are ordered in a listing.
+#[derive(Clone, Copy)]
+pub enum TaskSort {
+    DueDate,
+    Name,
+    Duration,
+    Weight,
+    Priority,
+}
+
+impl TryFrom<&str> for TaskSort {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.to_lowercase();
+        if "due".starts_with(&value) {
+            Ok(Self::DueDate)
+        } else if "name".starts_with(&value) {
+            Ok(Self::Name)
+        } else if "duration".starts_with(&value) {
+            Ok(Self::Duration)
+        } else if "weight".starts_with(&value) {
+            Ok(Self::Weight)
+        } else if "priority".starts_with(&value) {
+            Ok(Self::Priority)
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "tasks can't be sorted by '{}'",
+                value
+            )))
+        }
+    }
+}
+
+/// Which tasks to list, and how.
+pub struct TaskListOptions {
+    /// Only list tasks due before this.
+    pub due_before: Option<DateTime<Local>>,
+
+    /// Only list tasks that aren't done.
+    pub undone: bool,
+
+    /// Only list tasks with this tag.
+    pub tag: Option<String>,
+
+    pub sort: TaskSort,
+    pub reverse: bool,
+
+    /// List tasks under the name of the course they're for.
+    pub by_course: bool,
+}
+
+impl TaskListOptions {
+    /// Returns the tasks in `tasks` that pass the filters, in order.
+    pub fn apply<'a>(&self, tasks: &'a [Task]) -> Vec<&'a Task> {
+        let mut listed: Vec<&Task> = tasks
+            .iter()
+            .filter(|t| !self.undone || !t.done)
+            .filter(|t| self.tag.as_ref().is_none_or(|tag| t.has_tag(tag)))
+            .filter(|t| self.due_before.is_none_or(|d| t.due_date < d))
+            .collect();
+
+        listed.sort_by(|a, b| match self.sort {
+            TaskSort::DueDate => a.due_date.cmp(&b.due_date),
+            TaskSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            TaskSort::Duration => a.duration.total_minutes().cmp(&b.duration.total_minutes()),
+            TaskSort::Weight => b.weight.partial_cmp(&a.weight).unwrap_or(Ordering::Equal),
+            TaskSort::Priority => b.priority.cmp(&a.priority),
+        });
+        if self.reverse {
+            listed.reverse();
+        }
+
+        if self.by_course {
+            // sorting is stable, so tasks keep their order within each course. tasks without a
+            // course go last
+            listed.sort_by(|a, b| match (&a.course, &b.course) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+
+        listed
+    }
+}
+
+pub struct TaskList<'a> {
+    pub data: &'a UserData,
+    pub options: &'a TaskListOptions,
+}
+
+impl Serialize for TaskList<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.options.apply(self.data.get_tasks()))
+    }
+}
+
+impl Display for TaskList<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let config = self.data.get_config();
+        let tasks = self.options.apply(self.data.get_tasks());
+        if tasks.is_empty() {
+            return writeln!(f, "No tasks.");
+        }
+
+        let mut course = None;
+        for (i, task) in tasks.iter().enumerate() {
+            if self.options.by_course && (i == 0 || task.course != course) {
+                course = task.course.clone();
+                writeln!(f, "{}:", course.as_deref().unwrap_or("No course"))?;
+            }
+            write_task(f, task, self.data.get_tasks(), config)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_task(
+    f: &mut Formatter<'_>,
+    task: &Task,
+    all_tasks: &[Task],
+    config: &Config,
+) -> std::fmt::Result {
+    let format = format!("{} {}", config.date_format, config.time_format);
+    write!(
+        f,
+        "-\t[{}] {}",
+        if task.done { "x" } else { " " },
+        task.name
+    )?;
+    write_tags(f, &task.tags)?;
+
+    // repeating tasks are listed with their next instance
+    let due_date = match task.repeat {
+        Repeat::No => Some(task.due_date),
+        _ => task.next_due_date(Local::now()),
+    };
+    match due_date {
+        Some(d) => write!(f, ": due {}", d.format(&format))?,
+        None => write!(f, ":")?,
+    }
+    write!(
+        f,
+        ", {}",
+        format_minutes(task.duration.total_minutes() as i64)
+    )?;
+    if task.repeat != Repeat::No {
+        write!(f, ", {}", task.repeat)?;
+    }
+    if task.priority != Priority::Normal {
+        write!(f, ", {} priority", task.priority)?;
+    }
+    if task.important {
+        write!(f, ", important")?;
+    }
+    if (task.weight - 1.0).abs() > f64::EPSILON {
+        write!(f, ", weight {}", task.weight)?;
+    }
+    let prerequisites: Vec<&str> = all_tasks
+        .iter()
+        .filter(|t| task.depends_on(t.id))
+        .map(|t| t.name.as_str())
+        .collect();
+    if !prerequisites.is_empty() {
+        write!(f, ", after {}", prerequisites.join(" and "))?;
+    }
+    writeln!(f)?;
+
+    if let TaskDuration::Subtasks(subtasks) = &task.duration {
+        for s in subtasks {
+            writeln!(f, "\t\t{} ({})", s.name, format_minutes(s.duration as i64))?;
+            write_notes(f, s.notes.as_deref(), "\t\t\t")?;
+        }
+    }
+    write_notes(f, task.notes.as_deref(), "\t\t")?;
+
+    Ok(())
+}
+
+fn write_notes(f: &mut Formatter<'_>, notes: Option<&str>, indent: &str) -> std::fmt::Result {
+    for line in notes.unwrap_or_default().lines() {
+        writeln!(f, "{}| {}", indent, line)?;
+    }
+    Ok(())
+}
+
+/// Shows everything about one task.
+pub struct TaskDetail<'a> {
+    pub data: &'a UserData,
+    pub task: &'a Task,
+}
+
+impl Display for TaskDetail<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let config = self.data.get_config();
+        let format = format!("{} {}", config.date_format, config.time_format);
+        let task = self.task;
+
+        write!(f, "{}", task.name)?;
+        write_tags(f, &task.tags)?;
+        writeln!(f, "{}", if task.done { " (done)" } else { "" })?;
+
+        writeln!(f, "Due:\t\t{}", task.due_date.format(&format))?;
+        match task.repeat {
+            Repeat::No => {}
+            Repeat::Monthly { day_of_month } => {
+                writeln!(f, "Repeats:\tmonthly, on day {}", day_of_month)?
+            }
+            _ => writeln!(f, "Repeats:\t{}", task.repeat)?,
+        }
+        if task.repeat != Repeat::No {
+            if let Some(next) = task.next_due_date(Local::now()) {
+                writeln!(f, "Next due:\t{}", next.format(&format))?;
+            }
+        }
+        if let Some(until) = task.deferred_until.filter(|d| *d > Local::now()) {
+            writeln!(f, "Deferred until:\t{}", until.format(&format))?;
+        }
+        writeln!(
+            f,
+            "Estimate:\t{}",
+            format_minutes(task.duration.total_minutes() as i64)
+        )?;
+        let tracked = self.data.tracked_minutes(task.id);
+        if tracked > 0 {
+            writeln!(f, "Tracked:\t{}", format_minutes(tracked))?;
+        }
+        writeln!(f, "Priority:\t{}", task.priority)?;
+        writeln!(
+            f,
+            "Important:\t{}",
+            if task.important { "yes" } else { "no" }
+        )?;
+        writeln!(f, "Weight:\t\t{}", task.weight)?;
+        if let Some(course) = &task.course {
+            writeln!(f, "Course:\t\t{}", course)?;
+        }
+
+        let prerequisites: Vec<&str> = self
+            .data
+            .get_tasks()
+            .iter()
+            .filter(|t| task.depends_on(t.id))
+            .map(|t| t.name.as_str())
+            .collect();
+        if !prerequisites.is_empty() {
+            writeln!(f, "After:\t\t{}", prerequisites.join(", "))?;
+        }
+
+        for pin in task.pins.iter().filter(|p| p.span.end() > Local::now()) {
+            write!(
+                f,
+                "Pinned:\t\t{} - {}",
+                pin.span.start().format(&format),
+                pin.span.end().format(&config.time_format)
+            )?;
+            match pin.subtask.and_then(|id| task.subtask(id)) {
+                Some(s) => writeln!(f, " ({})", s.name)?,
+                None => writeln!(f)?,
+            }
+        }
+
+        if let TaskDuration::Subtasks(subtasks) = &task.duration {
+            writeln!(f, "Subtasks:")?;
+            for s in subtasks {
+                writeln!(f, "-\t{} ({})", s.name, format_minutes(s.duration as i64))?;
+                write_notes(f, s.notes.as_deref(), "\t")?;
+            }
+        }
+
+        if let Some(notes) = &task.notes {
+            writeln!(f, "Notes:")?;
+            for line in notes.lines() {
+                writeln!(f, "\t{}", line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_tags(f: &mut Formatter<'_>, tags: &[String]) -> std::fmt::Result {
+    for tag in tags {
+        write!(f, " #{}", tag)?;
+    }
+    Ok(())
+}
+
+/// Lists the user's events and breaks, in the order they start.
+pub struct EventList<'a> {
+    pub data: &'a UserData,
+
+    /// Only list events with this tag.
+    pub tag: Option<&'a str>,
+}
+
+impl EventList<'_> {
+    /// Returns the events to list, in the order they start.
+    fn events(&self) -> Vec<&CalendarEvent> {
+        let mut events: Vec<_> = self
+            .data
+            .get_events()
+            .iter()
+            .filter(|e| self.tag.is_none_or(|tag| e.has_tag(tag)))
+            .collect();
+        events.sort_by_key(|e| *e.time_span.start());
+        events
+    }
+}
+
+impl Serialize for EventList<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.events())
+    }
+}
+
+impl Display for EventList<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let config = self.data.get_config();
+        let format = format!("{} {}", config.date_format, config.time_format);
+
+        let events = self.events();
+        if events.is_empty() {
+            return writeln!(f, "No events.");
+        }
+
+        for e in events {
+            let name = match e.event_type {
+                CalendarEventType::Break => "Break",
+                _ => &e.name,
+            };
+            let repeat = match e.repeat {
+                Repeat::No => String::new(),
+                _ => format!(", {}", e.repeat),
+            };
+            let mut stops = match e.last_start() {
+                Some(last) if e.repeat != Repeat::No => {
+                    format!(" until {}", last.format(&config.date_format))
+                }
+                _ => String::new(),
+            };
+            let exceptions: Vec<String> = e
+                .exceptions
+                .iter()
+                .map(|d| d.format(&config.date_format).to_string())
+                .collect();
+            if !exceptions.is_empty() {
+                stops.push_str(&format!(", except {}", exceptions.join(", ")));
+            }
+            // events planned in another timezone show the time there too
+            let elsewhere = match e.tz() {
+                Some(tz) => format!(
+                    " ({} {})",
+                    e.time_span
+                        .start()
+                        .with_timezone(&tz)
+                        .format(&config.time_format),
+                    tz.name()
+                ),
+                None => String::new(),
+            };
+            write!(f, "-\t{}", name)?;
+            write_tags(f, &e.tags)?;
+            writeln!(
+                f,
+                ": {}{}, {}{}{}",
+                e.time_span.start().format(&format),
+                elsewhere,
+                format_minutes(e.time_span.minutes() as i64),
+                repeat,
+                stops
+            )?;
+        }
+
+        Ok(())
+    }
+}
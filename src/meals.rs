@@ -0,0 +1,37 @@
+//! Meals: breaks for eating at the same time every day, set in the config instead of being added
+//! as repeating break events.
+
+use crate::{CalendarEvent, CalendarEventType, Repeat, TimeSpan};
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// A meal eaten every day. Times are written like "12:30:00".
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Meal {
+    /// What the meal's called, like "lunch".
+    pub name: String,
+
+    pub start: NaiveTime,
+    pub minutes: u32,
+}
+
+impl Meal {
+    /// Returns the meal as a break repeating every day from `first_day` on.
+    pub fn as_event(&self, first_day: NaiveDate) -> Option<CalendarEvent> {
+        let start = Local
+            .from_local_datetime(&first_day.and_time(self.start))
+            .earliest()?;
+        Some(CalendarEvent {
+            id: 0,
+            name: self.name.clone(),
+            time_span: TimeSpan::new(start, self.minutes),
+            event_type: CalendarEventType::Break,
+            repeat: Repeat::Daily,
+            tags: Vec::new(),
+            repeat_until: None,
+            repeat_count: None,
+            exceptions: Vec::new(),
+            timezone: None,
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! Earliest deadline first: free time is packed with whatever task is due soonest, for as long as
+//! it takes, before anything due later is touched. Nothing is spread out, so it suits crunch weeks
+//! (like exams) where everything is due soon anyway. Each task's slack, the margin left between
+//! when its work is done and when it's due, is shown along with the schedule.
+
+use crate::{
+    methods::{
+        pomodoro::{self, PomodoroSchedule, PomodoroScheduleEntry},
+        time_block::{self, Unblocked},
+    },
+    goals,
+    metrics::{format_minutes, TaskMetrics},
+    schedule::check_canceled,
+    task, Bedtime, CalendarEvent, Config, Schedule, ScheduleMetrics, SparrowError, SparrowResult,
+    Task,
+};
+use chrono::Local;
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicBool;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct EdfSchedule {
+    entries: Vec<PomodoroScheduleEntry>,
+}
+
+impl<'a> Schedule<'a> for EdfSchedule {
+    type Display = EdfDisplay<'a>;
+
+    fn make(
+        config: &Config,
+        tasks: &[Task],
+        events: &[CalendarEvent],
+        bedtime: &Bedtime,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<Self> {
+        // repeating tasks are scheduled one instance at a time
+        let instances = task::expand_recurring(config, tasks, Local::now());
+        let now = Local::now();
+        let mut tasks: Vec<&Task> = instances
+            .iter()
+            .filter(|t| !t.done && t.due_date > now)
+            .collect();
+        let until = match tasks.iter().map(|t| t.due_date).max() {
+            Some(u) => u,
+            None => {
+                return Err(SparrowError::BasicMessage(
+                    "can't make a schedule without tasks".to_string(),
+                ))
+            }
+        };
+
+        // strictly by due date. priority only breaks ties
+        tasks.sort_by_key(|t| (t.due_date, Reverse(t.priority)));
+        let mut work: Vec<Unblocked> = tasks
+            .iter()
+            .flat_map(|t| time_block::unblocked(t))
+            .collect();
+
+        let mut entries = PomodoroSchedule::breaks_to_schedule_entries(events, until, bedtime);
+        let mut blocks = Vec::new();
+        for gap in pomodoro::free_time(&entries, now, until) {
+            check_canceled(cancel)?;
+            let gap = (*gap.start(), gap.end());
+            time_block::fill(&mut work, &mut blocks, gap, i64::MAX, 0);
+        }
+        time_block::warn_unscheduled(&work);
+
+        entries.append(&mut blocks);
+        goals::reserve(config, &tasks, &mut entries, until);
+        entries.sort_by_key(|e| *e.span().start());
+        Ok(Self { entries })
+    }
+
+    fn display(&'a self, config: &'a Config, tasks: &'a [Task]) -> Self::Display {
+        EdfDisplay {
+            schedule: self,
+            metrics: self.metrics(config, tasks).tasks,
+            config,
+            tasks,
+        }
+    }
+
+    fn metrics(&self, config: &Config, tasks: &[Task]) -> ScheduleMetrics {
+        pomodoro::entry_metrics(config, &self.entries, tasks)
+    }
+}
+
+impl EdfSchedule {
+    pub fn get_entries(&self) -> &[PomodoroScheduleEntry] {
+        &self.entries
+    }
+
+    /// Removes the work for the task with `task_id`, leaving its time free.
+    pub fn remove_task(&mut self, task_id: task::TaskId) {
+        self.entries.retain(|e| match e {
+            PomodoroScheduleEntry::Job { task_id: id, .. } => *id != task_id,
+            _ => true,
+        });
+    }
+
+    pub fn retain_entries<F>(&mut self, keep: F)
+    where
+        F: FnMut(&PomodoroScheduleEntry) -> bool,
+    {
+        self.entries.retain(keep);
+    }
+}
+
+pub struct EdfDisplay<'a> {
+    schedule: &'a EdfSchedule,
+    metrics: Vec<TaskMetrics>,
+    config: &'a Config,
+    tasks: &'a [Task],
+}
+
+/// A task's slack, ready to be serialized.
+#[derive(Serialize)]
+struct SlackView<'a> {
+    task: &'a str,
+    due_date: chrono::DateTime<Local>,
+    finishes: Option<chrono::DateTime<Local>>,
+    slack_minutes: Option<i64>,
+    unscheduled_minutes: u64,
+}
+
+impl EdfDisplay<'_> {
+    fn slack(&self) -> impl Iterator<Item = SlackView<'_>> {
+        self.metrics.iter().map(|m| SlackView {
+            task: &m.name,
+            due_date: m.due_date,
+            finishes: m.latest_finish,
+            slack_minutes: m.slack_minutes,
+            unscheduled_minutes: m.requested_minutes.saturating_sub(m.scheduled_minutes),
+        })
+    }
+}
+
+impl Serialize for EdfDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<_> = time_block::upcoming(&self.schedule.entries)
+            .map(|e| e.view(self.tasks))
+            .collect();
+        let slack: Vec<_> = self.slack().collect();
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("entries", &entries)?;
+        map.serialize_entry("slack", &slack)?;
+        map.end()
+    }
+}
+
+impl Display for EdfDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        time_block::write_entries(
+            f,
+            time_block::upcoming(&self.schedule.entries),
+            self.config,
+            self.tasks,
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "Slack:")?;
+        for s in self.slack() {
+            write!(f, "-\t{}: ", s.task)?;
+            match s.slack_minutes {
+                Some(slack) if slack < 0 => write!(f, "{} late", format_minutes(-slack))?,
+                Some(slack) => write!(f, "{} to spare", format_minutes(slack))?,
+                None => write!(f, "not scheduled")?,
+            }
+            if s.slack_minutes.is_some() && s.unscheduled_minutes > 0 {
+                write!(
+                    f,
+                    ", but {} didn't fit",
+                    format_minutes(s.unscheduled_minutes as i64)
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+//! The Eisenhower matrix: tasks are sorted into four lists by whether they're urgent (due within
+//! `eisenhower_urgent_days`, or of urgent priority) and whether they're important (see
+//! `Task::important`). Urgent, important tasks are done now, important ones that can wait are
+//! scheduled, urgent ones that aren't important are delegated, and the rest are dropped.
+
+use crate::{
+    metrics::TaskMetrics,
+    task::{self, Priority, TaskId},
+    Bedtime, CalendarEvent, Config, Schedule, ScheduleMetrics, SparrowError, SparrowResult, Task,
+};
+use chrono::{DateTime, Duration, Local};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicBool;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quadrant {
+    DoNow,
+    Schedule,
+    Delegate,
+    Drop,
+}
+
+impl Quadrant {
+    pub const ALL: [Quadrant; 4] = [
+        Quadrant::DoNow,
+        Quadrant::Schedule,
+        Quadrant::Delegate,
+        Quadrant::Drop,
+    ];
+
+    /// Returns the quadrant the instance of `task` due at `due_date` belongs in.
+    pub fn of(task: &Task, due_date: DateTime<Local>, config: &Config) -> Self {
+        let urgent = task.priority == Priority::Urgent
+            || due_date - Local::now() <= Duration::days(config.eisenhower_urgent_days as i64);
+        match (urgent, task.important) {
+            (true, true) => Self::DoNow,
+            (false, true) => Self::Schedule,
+            (true, false) => Self::Delegate,
+            (false, false) => Self::Drop,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::DoNow => "do_now",
+            Self::Schedule => "schedule",
+            Self::Delegate => "delegate",
+            Self::Drop => "drop",
+        }
+    }
+
+    fn heading(self) -> &'static str {
+        match self {
+            Self::DoNow => "Do now (urgent and important):",
+            Self::Schedule => "Schedule (important, but not urgent):",
+            Self::Delegate => "Delegate (urgent, but not important):",
+            Self::Drop => "Drop (neither urgent nor important):",
+        }
+    }
+}
+
+/// A task (or, for a repeating task, its next instance) in one of the lists.
+#[derive(Clone, Deserialize, Serialize)]
+struct MatrixEntry {
+    task_id: TaskId,
+    due_date: DateTime<Local>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct EisenhowerSchedule {
+    do_now: Vec<MatrixEntry>,
+    schedule: Vec<MatrixEntry>,
+    delegate: Vec<MatrixEntry>,
+    drop: Vec<MatrixEntry>,
+}
+
+impl<'d> Schedule<'d> for EisenhowerSchedule {
+    type Display = EisenhowerDisplay<'d>;
+
+    fn make(
+        config: &Config,
+        tasks: &[Task],
+        _: &[CalendarEvent],
+        _: &Bedtime,
+        _: &AtomicBool,
+    ) -> SparrowResult<Self> {
+        let now = Local::now();
+        let mut due: Vec<(&Task, DateTime<Local>)> = tasks
+            .iter()
+            .filter_map(|t| t.next_due_date(now).map(|d| (t, d)))
+            .collect();
+        if due.is_empty() {
+            return Err(SparrowError::BasicMessage(String::from(
+                "can't make a schedule without tasks. try `sparrow add task` to add something",
+            )));
+        }
+
+        // each list is in due date order, with higher priority tasks first on the same day
+        due.sort_by_key(|(t, d)| (d.naive_local().date(), Reverse(t.priority), *d));
+
+        let mut schedule = Self {
+            do_now: Vec::new(),
+            schedule: Vec::new(),
+            delegate: Vec::new(),
+            drop: Vec::new(),
+        };
+        for (task, due_date) in due {
+            schedule
+                .list_mut(Quadrant::of(task, due_date, config))
+                .push(MatrixEntry {
+                    task_id: task.id,
+                    due_date,
+                });
+        }
+        Ok(schedule)
+    }
+
+    fn display(&'d self, config: &'d Config, tasks: &'d [Task]) -> Self::Display {
+        EisenhowerDisplay {
+            schedule: self,
+            config,
+            tasks,
+        }
+    }
+
+    /// The matrix doesn't plan times, so tasks to do now or schedule count as done by their due
+    /// dates, and tasks to delegate or drop count as unscheduled.
+    fn metrics(&self, _config: &Config, tasks: &[Task]) -> ScheduleMetrics {
+        let mut task_metrics = Vec::new();
+        for quadrant in Quadrant::ALL {
+            for entry in self.list(quadrant) {
+                let task = match tasks.iter().find(|t| t.id == entry.task_id) {
+                    Some(t) if !t.is_instance_done(&entry.due_date) => t,
+                    _ => continue,
+                };
+                let mut metrics = TaskMetrics::new(&Task {
+                    due_date: entry.due_date,
+                    ..task.clone()
+                });
+                if matches!(quadrant, Quadrant::DoNow | Quadrant::Schedule) {
+                    let minutes = metrics.requested_minutes;
+                    metrics.add_piece(entry.due_date, minutes, false);
+                }
+                task_metrics.push(metrics);
+            }
+        }
+        ScheduleMetrics::new(task_metrics)
+    }
+}
+
+impl EisenhowerSchedule {
+    fn list(&self, quadrant: Quadrant) -> &[MatrixEntry] {
+        match quadrant {
+            Quadrant::DoNow => &self.do_now,
+            Quadrant::Schedule => &self.schedule,
+            Quadrant::Delegate => &self.delegate,
+            Quadrant::Drop => &self.drop,
+        }
+    }
+
+    fn list_mut(&mut self, quadrant: Quadrant) -> &mut Vec<MatrixEntry> {
+        match quadrant {
+            Quadrant::DoNow => &mut self.do_now,
+            Quadrant::Schedule => &mut self.schedule,
+            Quadrant::Delegate => &mut self.delegate,
+            Quadrant::Drop => &mut self.drop,
+        }
+    }
+
+    /// Keeps only the tasks that `keep` returns true for.
+    pub fn retain_tasks<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(TaskId) -> bool,
+    {
+        for quadrant in Quadrant::ALL {
+            self.list_mut(quadrant).retain(|e| keep(e.task_id));
+        }
+    }
+
+    /// Takes the task with `task_id` off every list.
+    pub fn remove_task(&mut self, task_id: TaskId) {
+        self.retain_tasks(|id| id != task_id);
+    }
+}
+
+pub struct EisenhowerDisplay<'a> {
+    schedule: &'a EisenhowerSchedule,
+    config: &'a Config,
+    tasks: &'a [Task],
+}
+
+/// A task in one of the lists, ready to be serialized.
+#[derive(Serialize)]
+struct EntryView<'a> {
+    task: &'a str,
+    due_date: DateTime<Local>,
+    notes: Option<&'a str>,
+}
+
+impl<'a> EisenhowerDisplay<'a> {
+    /// Returns the tasks in `quadrant` that still exist and aren't finished yet.
+    fn views(&self, quadrant: Quadrant) -> Vec<EntryView<'a>> {
+        self.schedule
+            .list(quadrant)
+            .iter()
+            .filter_map(|e| {
+                let task = self.tasks.iter().find(|t| t.id == e.task_id)?;
+                if task.is_instance_done(&e.due_date) {
+                    return None;
+                }
+                Some(EntryView {
+                    task: &task.name,
+                    due_date: e.due_date,
+                    notes: task.notes.as_deref(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Serialize for EisenhowerDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(Quadrant::ALL.len()))?;
+        for quadrant in Quadrant::ALL {
+            map.serialize_entry(quadrant.key(), &self.views(quadrant))?;
+        }
+        map.end()
+    }
+}
+
+impl Display for EisenhowerDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = format!("{} {}", self.config.date_format, self.config.time_format);
+        for (i, quadrant) in Quadrant::ALL.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", quadrant.heading())?;
+
+            let views = self.views(*quadrant);
+            if views.is_empty() {
+                writeln!(f, "-\tnothing here")?;
+            }
+            for view in views {
+                write!(
+                    f,
+                    "-\t{} (due {})",
+                    view.task,
+                    view.due_date.format(&format)
+                )?;
+                if let Some(notes) = view.notes {
+                    write!(f, " - {}", task::summarize_notes(notes))?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
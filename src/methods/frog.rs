@@ -0,0 +1,190 @@
+//! Eat the frog: every day starts with the day's "frog", the hardest task there's time for (the
+//! one with the highest priority, then the biggest estimate), before anything else. The rest of
+//! the day is filled with smaller tasks, smallest first. Free time is found the same way as for
+//! time blocks, and blocks are as long as `time_block_minutes` allows.
+
+use crate::{
+    methods::{
+        pomodoro::{self, PomodoroSchedule, PomodoroScheduleEntry},
+        time_block::{self, Unblocked},
+    },
+    goals,
+    schedule::check_canceled,
+    task::{self, TaskId},
+    Bedtime, CalendarEvent, Config, Schedule, ScheduleMetrics, SparrowError, SparrowResult, Task,
+};
+use chrono::{Local, NaiveDate};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use std::cmp::Reverse;
+use std::collections::{btree_map::Entry, BTreeMap};
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicBool;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct FrogSchedule {
+    entries: Vec<PomodoroScheduleEntry>,
+
+    /// The task that starts each day.
+    frogs: BTreeMap<NaiveDate, TaskId>,
+}
+
+impl<'a> Schedule<'a> for FrogSchedule {
+    type Display = FrogDisplay<'a>;
+
+    fn make(
+        config: &Config,
+        tasks: &[Task],
+        events: &[CalendarEvent],
+        bedtime: &Bedtime,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<Self> {
+        // repeating tasks are scheduled one instance at a time
+        let instances = task::expand_recurring(config, tasks, Local::now());
+        let now = Local::now();
+        let mut tasks: Vec<&Task> = instances
+            .iter()
+            .filter(|t| !t.done && t.due_date > now)
+            .collect();
+        let until = match tasks.iter().map(|t| t.due_date).max() {
+            Some(u) => u,
+            None => {
+                return Err(SparrowError::BasicMessage(
+                    "can't make a schedule without tasks".to_string(),
+                ))
+            }
+        };
+
+        // smaller tasks first for the rest of the day, then the ones due sooner
+        tasks.sort_by_key(|t| (t.duration.total_minutes(), t.due_date));
+        let mut work: Vec<Unblocked> = tasks
+            .iter()
+            .flat_map(|t| time_block::unblocked(t))
+            .collect();
+
+        let longest = config.time_block_minutes as i64;
+        let shortest = config.min_time_block_minutes as i64;
+        let mut entries = PomodoroSchedule::breaks_to_schedule_entries(events, until, bedtime);
+        let mut blocks = Vec::new();
+        let mut frogs = BTreeMap::new();
+        for gap in pomodoro::free_time(&entries, now, until) {
+            check_canceled(cancel)?;
+            let (start, end) = (*gap.start(), gap.end());
+
+            // the first free time of the day starts with the frog
+            let day = start.naive_local().date();
+            let mut cursor = start;
+            if let Entry::Vacant(frog_of_day) = frogs.entry(day) {
+                let free = (end - start).num_minutes();
+                let frog = (0..work.len())
+                    .filter_map(|i| {
+                        time_block::fit(&work, i, start, free, longest, shortest).map(|m| (i, m))
+                    })
+                    .min_by_key(|(i, _)| {
+                        let t = work[*i].task;
+                        (
+                            Reverse(t.priority),
+                            Reverse(t.duration.total_minutes()),
+                            t.due_date,
+                            *i,
+                        )
+                    });
+                if let Some((i, minutes)) = frog {
+                    frog_of_day.insert(work[i].task.id);
+                    cursor = time_block::add_block(&mut work, i, start, minutes, &mut blocks);
+                }
+            }
+
+            time_block::fill(&mut work, &mut blocks, (cursor, end), longest, shortest);
+        }
+        time_block::warn_unscheduled(&work);
+
+        entries.append(&mut blocks);
+        goals::reserve(config, &tasks, &mut entries, until);
+        entries.sort_by_key(|e| *e.span().start());
+        Ok(Self { entries, frogs })
+    }
+
+    fn display(&'a self, config: &'a Config, tasks: &'a [Task]) -> Self::Display {
+        FrogDisplay {
+            schedule: self,
+            config,
+            tasks,
+        }
+    }
+
+    fn metrics(&self, config: &Config, tasks: &[Task]) -> ScheduleMetrics {
+        pomodoro::entry_metrics(config, &self.entries, tasks)
+    }
+}
+
+impl FrogSchedule {
+    pub fn get_entries(&self) -> &[PomodoroScheduleEntry] {
+        &self.entries
+    }
+
+    /// Returns the task that starts `date`, if there is one.
+    pub fn frog_on(&self, date: NaiveDate) -> Option<TaskId> {
+        self.frogs.get(&date).copied()
+    }
+
+    /// Removes the work for the task with `task_id`, leaving its time free.
+    pub fn remove_task(&mut self, task_id: TaskId) {
+        self.entries.retain(|e| match e {
+            PomodoroScheduleEntry::Job { task_id: id, .. } => *id != task_id,
+            _ => true,
+        });
+        self.frogs.retain(|_, id| *id != task_id);
+    }
+
+    pub fn retain_entries<F>(&mut self, keep: F)
+    where
+        F: FnMut(&PomodoroScheduleEntry) -> bool,
+    {
+        self.entries.retain(keep);
+    }
+}
+
+pub struct FrogDisplay<'a> {
+    schedule: &'a FrogSchedule,
+    config: &'a Config,
+    tasks: &'a [Task],
+}
+
+impl FrogDisplay<'_> {
+    /// Returns the name of today's frog, if there is one.
+    fn todays_frog(&self) -> Option<&str> {
+        let id = self.schedule.frog_on(Local::today().naive_local())?;
+        self.tasks
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.name.as_str())
+    }
+}
+
+impl Serialize for FrogDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<_> = time_block::upcoming(&self.schedule.entries)
+            .map(|e| e.view(self.tasks))
+            .collect();
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("frog", &self.todays_frog())?;
+        map.serialize_entry("entries", &entries)?;
+        map.end()
+    }
+}
+
+impl Display for FrogDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.todays_frog() {
+            Some(frog) => writeln!(f, "Today's frog: {}. Eat it first!", frog)?,
+            None => writeln!(f, "No frog today :)")?,
+        }
+        writeln!(f)?;
+        time_block::write_entries(
+            f,
+            time_block::upcoming(&self.schedule.entries),
+            self.config,
+            self.tasks,
+        )
+    }
+}
@@ -1,15 +1,55 @@
-use crate::{Bedtime, CalendarEvent, Config, Schedule, SparrowError, SparrowResult, Task};
+use crate::{
+    metrics::TaskMetrics, output, schedule::check_canceled, task, Bedtime, CalendarEvent, Config,
+    Formatting, Schedule, ScheduleMetrics, SparrowError, SparrowResult, Task, TaskId,
+};
+use ansi_term::Style;
 use chrono::Datelike;
-use chrono::{Local, NaiveDate};
-use serde::{Deserialize, Serialize};
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 use std::{
-    collections::HashMap,
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, HashSet},
     fmt::{Display, Formatter},
+    sync::atomic::AtomicBool,
 };
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct IvyLeeSchedule {
-    task_days: HashMap<NaiveDate, Vec<String>>,
+    task_days: BTreeMap<NaiveDate, Vec<DayEntry>>,
+}
+
+/// Something on a day's list: all or part of what's left of a task.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DayEntry {
+    pub task_id: TaskId,
+
+    /// How many days, counting this one, the rest of the task is spread over. 1 means the task
+    /// gets finished that day.
+    pub days_left: u32,
+}
+
+impl DayEntry {
+    /// Returns the entry's task, if it's in `tasks`.
+    pub fn task<'a>(&self, tasks: &'a [Task]) -> Option<&'a Task> {
+        tasks.iter().find(|t| t.id == self.task_id)
+    }
+
+    /// Describes the entry, like "Finish Essay" or "1/3 of remaining Essay".
+    pub fn label(&self, tasks: &[Task]) -> String {
+        let name = self
+            .task(tasks)
+            .map_or("a deleted task", |t| t.name.as_str());
+        if self.days_left <= 1 {
+            format!("Finish {}", name)
+        } else {
+            format!("1/{} of remaining {}", self.days_left, name)
+        }
+    }
+
+    /// Returns how many of the task's `remaining_minutes` the entry plans for.
+    fn minutes(&self, remaining_minutes: u64) -> u64 {
+        remaining_minutes / self.days_left.max(1) as u64
+    }
 }
 
 impl<'d> Schedule<'d> for IvyLeeSchedule {
@@ -20,19 +60,38 @@ impl<'d> Schedule<'d> for IvyLeeSchedule {
         tasks: &[Task],
         _: &[CalendarEvent],
         bedtime: &Bedtime,
+        cancel: &AtomicBool,
     ) -> SparrowResult<Self> {
-        let mut task_days = HashMap::<NaiveDate, Vec<String>>::new();
+        let mut task_days = BTreeMap::<NaiveDate, Vec<DayEntry>>::new();
 
-        // tasks will need to be sorted by due date
+        // repeating tasks are scheduled one instance at a time
+        let tasks = &task::expand_recurring(config, tasks, Local::now());
+
+        // tasks will need to be sorted by due date. higher priority and heavier tasks go first,
+        // though, so that when a day is full, the others are the ones that wait (sorting is
+        // stable, so tasks of the same priority and weight stay in due date order)
         let mut sorted_tasks = {
-            let mut v = tasks.to_vec();
+            let mut v: Vec<&Task> = tasks.iter().collect();
             v.sort_by_key(|t| t.due_date);
+            v.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(Ordering::Equal));
+            v.sort_by_key(|t| Reverse(t.priority));
+
+            // prerequisites come before the tasks that depend on them
+            let order = task::dependency_order(&v)?;
+            v.sort_by_key(|t| order.iter().position(|id| *id == t.id));
             v
         };
 
+        // tasks that aren't finished yet, so that tasks depending on them can wait
+        let mut unfinished: HashSet<_> = sorted_tasks
+            .iter()
+            .filter(|t| !t.done)
+            .map(|t| t.id)
+            .collect();
+
         // get latest due due of the tasks
-        let latest_due_date = if let Some(d) = sorted_tasks.last() {
-            d.due_date
+        let latest_due_date = if let Some(d) = sorted_tasks.iter().map(|t| t.due_date).max() {
+            d
         } else if tasks.is_empty() {
             return Err(SparrowError::BasicMessage(String::from(
                 "can't make a schedule without tasks. try `sparrow add task` to add something",
@@ -44,10 +103,13 @@ impl<'d> Schedule<'d> for IvyLeeSchedule {
         let mut day = Local::today();
 
         while day <= latest_due_date.date() {
+            check_canceled(cancel)?;
+
             // if we're not to skip the day in question, we can schedule for it
             if !config.skip_days.contains(&day.weekday()) {
                 // get the time for when the day begins (when the user wakes up)
-                let start_of_day = if let Some(s) = day.and_time(bedtime.end()) {
+                let wake = bedtime.wake_on(day.naive_local());
+                let start_of_day = if let Some(s) = day.and_time(wake) {
                     s
                 } else {
                     break;
@@ -62,22 +124,26 @@ impl<'d> Schedule<'d> for IvyLeeSchedule {
                     } else {
                         // if the task is considered at `start_of_day`, we can add it to the day if
                         // there is room. tasks that have one day left will be scheduled regardless
-                        // of whether there is room or not
+                        // of whether there is room or not (or whether what they depend on is
+                        // finished)
                         let days_until_due = (t.due_date - start_of_day).num_days() + 1;
+                        let waiting = t.depends_on.iter().any(|id| unfinished.contains(id));
                         if days_until_due == 1
                             || (day_tasks.len() < config.ivy_lee_tasks_per_day as usize
+                                && !waiting
                                 && !t.is_past_due(&start_of_day)
                                 && t.is_considered(&start_of_day))
                         {
+                            day_tasks.push(DayEntry {
+                                task_id: t.id,
+                                days_left: days_until_due as u32,
+                            });
                             if days_until_due == 1 {
-                                day_tasks.push(format!("Finish {}", t.name));
+                                unfinished.remove(&t.id);
 
                                 // return false, as this task is finished and won't be done again
                                 false
                             } else {
-                                day_tasks
-                                    .push(format!("1/{} of remaining {}", days_until_due, t.name));
-
                                 // since the task was only partially complete, keep it
                                 true
                             }
@@ -101,13 +167,12 @@ impl<'d> Schedule<'d> for IvyLeeSchedule {
         // warn of any unscheduled tasks. This shouldn't happen, as tasks are almost guaranteed to
         // be finished, but we'll leave it here just in case
         if !sorted_tasks.is_empty() {
-            eprintln!("warning: the following tasks couldn't be scheduled completely:");
-            for t in sorted_tasks {
-                eprintln!("\t{}", t.name)
-            }
-
-            // extra line
-            eprintln!();
+            let names: Vec<String> = sorted_tasks.iter().map(|t| t.name.clone()).collect();
+            output::warn(
+                "the following tasks couldn't be scheduled completely",
+                &names,
+                None,
+            );
         }
 
         let overscheduled_days: Vec<String> = task_days
@@ -125,48 +190,189 @@ impl<'d> Schedule<'d> for IvyLeeSchedule {
             })
             .collect();
         if !overscheduled_days.is_empty() {
-            eprintln!("warning: the following days are over-scheduled:");
-            for d in overscheduled_days {
-                eprintln!("\t{}", d)
-            }
-            eprintln!(
-                "note: your maximum task-per-day limit is {}",
-                config.ivy_lee_tasks_per_day
+            output::warn(
+                "the following days are over-scheduled",
+                &overscheduled_days,
+                Some(&format!(
+                    "your maximum task-per-day limit is {}",
+                    config.ivy_lee_tasks_per_day
+                )),
             );
-
-            // extra line
-            eprintln!();
         }
 
         Ok(Self { task_days })
     }
 
-    fn display(&'d self, _config: &'d crate::Config) -> Self::Display {
+    fn display(&'d self, _config: &'d Config, tasks: &'d [Task]) -> Self::Display {
         let today = Local::today();
         let tomorrow = today.succ_opt();
 
         IvyLeeScheduleDisplay {
+            tasks,
             today: self.task_days.get(&today.naive_local()),
             tomorrow: if let Some(d) = tomorrow {
                 self.task_days.get(&d.naive_local())
             } else {
                 None
             },
+            formatting: None,
+        }
+    }
+
+    fn metrics(&self, config: &Config, tasks: &[Task]) -> ScheduleMetrics {
+        let instances = task::expand_recurring(config, tasks, Local::now());
+        let unfinished: Vec<&Task> = instances.iter().filter(|t| !t.done).collect();
+        let mut task_metrics: Vec<TaskMetrics> =
+            unfinished.iter().map(|t| TaskMetrics::new(t)).collect();
+        let mut remaining: Vec<u64> = task_metrics.iter().map(|m| m.requested_minutes).collect();
+        let mut last_days: Vec<Option<NaiveDate>> = vec![None; unfinished.len()];
+
+        let mut days: Vec<(&NaiveDate, &Vec<DayEntry>)> = self.task_days.iter().collect();
+        days.sort_by_key(|(date, _)| **date);
+
+        for (date, day_tasks) in days {
+            // ivy lee plans by the day, so count work as finished at the end of its day (or by
+            // the due date, on the day the task is due)
+            let end_of_day = match Local.from_local_date(&date.succ()).earliest() {
+                Some(d) => d.and_hms(0, 0, 0),
+                None => continue,
+            };
+
+            for entry in day_tasks {
+                for (i, t) in unfinished.iter().enumerate() {
+                    // instances of a repeating task share its ID, so skip finished ones
+                    if t.id != entry.task_id || remaining[i] == 0 {
+                        continue;
+                    }
+                    let minutes = entry.minutes(remaining[i]);
+                    remaining[i] -= minutes;
+                    let continues_block = last_days[i].map(|d| d.succ()) == Some(*date);
+                    let end = end_of_day.min(t.due_date);
+                    task_metrics[i].add_piece(end, minutes, continues_block);
+                    last_days[i] = Some(*date);
+                    break;
+                }
+            }
         }
+
+        ScheduleMetrics::new(task_metrics)
+    }
+}
+
+impl IvyLeeSchedule {
+    /// Returns every day's list, in order.
+    pub fn days(&self) -> Vec<(NaiveDate, &[DayEntry])> {
+        let mut days: Vec<(NaiveDate, &[DayEntry])> = self
+            .task_days
+            .iter()
+            .map(|(date, tasks)| (*date, tasks.as_slice()))
+            .collect();
+        days.sort_by_key(|(date, _)| *date);
+        days
+    }
+
+    /// Returns the list of things to do on `date`, most important first.
+    pub fn tasks_on(&self, date: NaiveDate) -> Option<&[DayEntry]> {
+        self.task_days.get(&date).map(|t| t.as_slice())
+    }
+
+    /// Keeps only the list entries whose task IDs `keep` returns true for.
+    pub fn retain_tasks<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(TaskId) -> bool,
+    {
+        for entries in self.task_days.values_mut() {
+            entries.retain(|e| keep(e.task_id));
+        }
+    }
+
+    /// Takes the task with `task_id` off every day's list.
+    pub fn remove_task(&mut self, task_id: TaskId) {
+        self.retain_tasks(|id| id != task_id);
     }
 }
 
 pub struct IvyLeeScheduleDisplay<'a> {
-    today: Option<&'a Vec<String>>,
-    tomorrow: Option<&'a Vec<String>>,
+    tasks: &'a [Task],
+    today: Option<&'a Vec<DayEntry>>,
+    tomorrow: Option<&'a Vec<DayEntry>>,
+    formatting: Option<&'a Formatting>,
+}
+
+/// A day list entry with its task's notes, ready to be serialized.
+#[derive(Serialize)]
+struct EntryView<'a> {
+    task_id: TaskId,
+    entry: String,
+    notes: Option<&'a str>,
+}
+
+impl<'a> IvyLeeScheduleDisplay<'a> {
+    /// Styles the lists like jobs in other schedules, highlighting the task to work on now (the
+    /// first one today) and dimming tomorrow's list. Does nothing if output can't have colors.
+    pub fn styled(self, formatting: &'a Formatting) -> Self {
+        Self {
+            formatting: Some(formatting).filter(|_| output::use_color()),
+            ..self
+        }
+    }
+
+    /// Returns the style for an entry. `over` is laid on top of the style for jobs.
+    fn style(&self, over: fn(&Formatting) -> Style) -> Style {
+        self.formatting
+            .map(|f| Formatting::layer(f.job, over(f)))
+            .unwrap_or_default()
+    }
+    fn notes(&self, entry: &DayEntry) -> Option<&'a str> {
+        entry.task(self.tasks).and_then(|t| t.notes.as_deref())
+    }
+
+    fn views(&self, entries: Option<&'a Vec<DayEntry>>) -> Vec<EntryView<'_>> {
+        entries
+            .into_iter()
+            .flatten()
+            .map(|entry| EntryView {
+                task_id: entry.task_id,
+                entry: entry.label(self.tasks),
+                notes: self.notes(entry),
+            })
+            .collect()
+    }
+
+    fn write_entry(
+        &self,
+        f: &mut Formatter<'_>,
+        entry: &DayEntry,
+        style: Style,
+    ) -> std::fmt::Result {
+        let mut line = format!("-\t{}", entry.label(self.tasks));
+        if let Some(notes) = self.notes(entry) {
+            line += &format!(" - {}", task::summarize_notes(notes));
+        }
+        writeln!(f, "{}", style.paint(line))
+    }
+}
+
+impl Serialize for IvyLeeScheduleDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("today", &self.views(self.today))?;
+        map.serialize_entry("tomorrow", &self.views(self.tomorrow))?;
+        map.end()
+    }
 }
 
 impl Display for IvyLeeScheduleDisplay<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if let Some(tasks_today) = self.today {
             writeln!(f, "Here are tasks for you to do today:")?;
-            for t in tasks_today {
-                writeln!(f, "-\t{}", t)?;
+            for (i, t) in tasks_today.iter().enumerate() {
+                let style = if i == 0 {
+                    self.style(|f| f.current)
+                } else {
+                    self.style(|_| Style::new())
+                };
+                self.write_entry(f, t, style)?;
             }
         } else {
             writeln!(f, "Nothing to do today :) Enjoy your day off!")?;
@@ -177,7 +383,7 @@ impl Display for IvyLeeScheduleDisplay<'_> {
         if let Some(tasks_tomorrow) = self.tomorrow {
             writeln!(f, "There are tasks for you to do tomorrow:")?;
             for t in tasks_tomorrow {
-                writeln!(f, "-\t{}", t)?;
+                self.write_entry(f, t, self.style(|f| f.later))?;
             }
         } else {
             writeln!(f, "Nothing to do tomorrow :) Have a good day!")?;
@@ -1,2 +1,6 @@
 pub mod pomodoro;
 pub mod ivy_lee;
+pub mod eisenhower;
+pub mod time_block;
+pub mod edf;
+pub mod frog;
@@ -1,16 +1,42 @@
 use crate::{
     errors::SparrowResult,
-    spans::{CalendarEventType, Repeat},
-    task::{Task, TaskDuration},
-    Bedtime, CalendarEvent, Config, Schedule, SparrowError, TimeSpan,
+    goals, output,
+    recurrence::RecurrenceCache,
+    spans::{self, CalendarEventType, EventId},
+    task::{self, SubtaskId, Task, TaskDuration, TaskId},
+    metrics::TaskMetrics,
+    schedule::check_canceled,
+    work_windows,
+    Bedtime, CalendarEvent, Config, Formatting, Repeat, Schedule, ScheduleMetrics, SparrowError,
+    TimeSpan,
 };
 use chrono::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
 use std::fmt::Display;
+use std::sync::atomic::AtomicBool;
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct PomodoroSchedule {
     entries: Vec<PomodoroScheduleEntry>,
+
+    /// Work there wasn't room for when the schedule was made, kept for `sparrow why`.
+    #[serde(default)]
+    unscheduled: Vec<UnscheduledWork>,
+}
+
+/// Work on a task that didn't fit in a schedule.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnscheduledWork {
+    pub task_id: TaskId,
+    pub subtask: Option<SubtaskId>,
+    pub minutes: u32,
+
+    /// The time the work could have gone in: from when the schedule was made (or the task was
+    /// deferred until) until the task (or the instance of a repeating task) was due.
+    pub from: DateTime<Local>,
+    pub due_date: DateTime<Local>,
 }
 
 impl<'a> Schedule<'a> for PomodoroSchedule {
@@ -21,10 +47,77 @@ impl<'a> Schedule<'a> for PomodoroSchedule {
         tasks: &[Task],
         events: &[CalendarEvent],
         bedtime: &Bedtime,
+        cancel: &AtomicBool,
     ) -> Result<Self, SparrowError> {
+        Self::make_keeping(Vec::new(), config, tasks, events, bedtime, cancel)
+    }
+
+    fn display(&'a self, config: &'a Config, tasks: &'a [Task]) -> Self::Display {
+        Self::Display {
+            schedule: self,
+            config,
+            tasks,
+            formatting: None,
+        }
+    }
+
+    fn metrics(&self, config: &Config, tasks: &[Task]) -> ScheduleMetrics {
+        entry_metrics(config, &self.entries, tasks)
+    }
+}
+
+impl PomodoroSchedule {
+    /// Makes a new schedule that keeps the entries of this one that have already started, so that
+    /// past jobs (and the one in progress) survive. Only the time from now on is filled again, and
+    /// work that's done (or in progress) isn't scheduled again.
+    pub fn remake(
+        &self,
+        config: &Config,
+        tasks: &[Task],
+        events: &[CalendarEvent],
+        bedtime: &Bedtime,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<Self> {
+        let now = Local::now();
+        let kept: Vec<PomodoroScheduleEntry> = self
+            .entries
+            .iter()
+            .filter(|e| *e.span().start() < now)
+            .cloned()
+            .collect();
+        let tasks = without_worked(tasks, &kept, now);
+        Self::make_keeping(kept, config, &tasks, events, bedtime, cancel)
+    }
+
+    /// Makes a new schedule around `kept`, entries that have already started.
+    fn make_keeping(
+        kept: Vec<PomodoroScheduleEntry>,
+        config: &Config,
+        tasks: &[Task],
+        events: &[CalendarEvent],
+        bedtime: &Bedtime,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<Self> {
+        let now = Local::now();
+
+        // pinned work stays where it was put, and only the rest of each task is scheduled. work
+        // pinned to the job in progress is already kept
+        let pinned: Vec<PomodoroScheduleEntry> = pinned_jobs(tasks, now)
+            .into_iter()
+            .filter(|p| {
+                !kept
+                    .iter()
+                    .any(|k| k.span().start() == p.span().start() && k.kind() == p.kind())
+            })
+            .collect();
+        let tasks = without_worked(tasks, &pinned, now);
+
+        // repeating tasks are scheduled one instance at a time
+        let instances = task::expand_recurring(config, &tasks, now);
+
         // intentionally shadow `tasks`. we want `tasks` to be mutable (for sorting) but we don't
-        // want to modify the original reference
-        let mut tasks = tasks.to_vec();
+        // want to modify the original tasks
+        let mut tasks: Vec<&Task> = instances.iter().collect();
 
         // make sure tasks are sorted by due date
         tasks.sort_by_cached_key(|t| t.due_date);
@@ -32,15 +125,24 @@ impl<'a> Schedule<'a> for PomodoroSchedule {
         if let Some(last_due_date) = tasks.last().map(|t| t.due_date) {
             let mut entries = Self::breaks_to_schedule_entries(events, last_due_date, bedtime);
 
+            // events and sleep already in progress are kept, so they shouldn't be added twice
+            entries.retain(|e| {
+                !kept
+                    .iter()
+                    .any(|k| k.span().start() == e.span().start() && k.kind() == e.kind())
+            });
+            entries.extend(kept);
+            entries.extend(pinned);
+
             // entries should stay sorted
             sort_entries(&mut entries);
 
-            #[cfg(debug_assertions)]
-            dbg!(&entries);
-
-            let mut result = Self { entries };
+            let mut result = Self {
+                entries,
+                unscheduled: Vec::new(),
+            };
 
-            result.fill_free_time(config, &tasks, last_due_date);
+            result.fill_free_time(config, &tasks, last_due_date, cancel)?;
 
             // make sure entries are sorted correctly
             result.entries.sort_by_cached_key(|e| *e.span().start());
@@ -52,40 +154,262 @@ impl<'a> Schedule<'a> for PomodoroSchedule {
             ))
         }
     }
+}
 
-    fn display(&'a self, config: &'a Config) -> Self::Display {
-        Self::Display {
-            schedule: self,
-            config,
+/// Returns `tasks` with the work in `kept` taken off their estimates: jobs marked done, and jobs
+/// that aren't over at `now`, like the one in progress or pinned ones. Repeating tasks keep their
+/// estimates, since jobs don't say which instance they were for.
+fn without_worked(
+    tasks: &[Task],
+    kept: &[PomodoroScheduleEntry],
+    now: DateTime<Local>,
+) -> Vec<Task> {
+    let mut tasks = tasks.to_vec();
+    for t in tasks.iter_mut().filter(|t| t.repeat == Repeat::No) {
+        for e in kept {
+            if let PomodoroScheduleEntry::Job {
+                task_id,
+                subtask,
+                status,
+                span,
+            } = e
+            {
+                let worked = *status == JobStatus::Done
+                    || (*status == JobStatus::Planned && span.end() > now);
+                if *task_id == t.id && worked {
+                    t.duration.take_minutes(*subtask, span.minutes() as u64);
+                }
+            }
+        }
+    }
+    tasks
+}
+
+/// Returns jobs for the work pinned to `tasks` that isn't over by `now`. Finished tasks don't keep
+/// their pins.
+fn pinned_jobs(tasks: &[Task], now: DateTime<Local>) -> Vec<PomodoroScheduleEntry> {
+    tasks
+        .iter()
+        .filter(|t| !t.done)
+        .flat_map(|t| {
+            t.pins
+                .iter()
+                .filter(move |p| p.span.end() > now)
+                .map(move |p| PomodoroScheduleEntry::Job {
+                    task_id: t.id,
+                    subtask: p.subtask,
+                    status: JobStatus::Planned,
+                    span: p.span,
+                })
+        })
+        .collect()
+}
+
+/// Returns how many work periods `minutes` of `task`'s estimate take. Estimates are scaled by how
+/// long tasks like this one have taken before.
+fn periods_for(config: &Config, task: &Task, minutes: u64) -> u32 {
+    let minutes = config.estimate_corrections.correct(task, minutes);
+    (minutes as f64 / config.work_minutes as f64).ceil() as u32
+}
+
+/// Returns how many work periods `task` takes, with each subtask rounded up on its own, the way
+/// schedules are made.
+pub(crate) fn periods_needed(config: &Config, task: &Task) -> u32 {
+    match &task.duration {
+        TaskDuration::Minutes(m) => periods_for(config, task, *m),
+        TaskDuration::Subtasks(subs) => {
+            subs.iter().map(|s| periods_for(config, task, s.duration)).sum()
         }
     }
 }
 
+/// Returns how many work periods fit in the free time around `entries` from `from` until `until`,
+/// in whole work sessions like the ones schedules are made of.
+pub(crate) fn periods_free(
+    config: &Config,
+    entries: &[PomodoroScheduleEntry],
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+) -> u32 {
+    let session_len = WorkSession::len_minutes(config);
+    free_time(entries, from, until)
+        .iter()
+        .flat_map(|free| work_windows::within(&config.work_windows, free))
+        .map(|free| free.minutes() / session_len * config.work_periods_per_job_session)
+        .sum()
+}
+
+/// Returns the time from `from` until `until` that none of `entries` takes.
+pub(crate) fn free_time(
+    entries: &[PomodoroScheduleEntry],
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+) -> Vec<TimeSpan> {
+    let busy: Vec<TimeSpan> = entries.iter().map(|e| *e.span()).collect();
+    spans::free_time(&busy, from, until)
+}
+
+/// Measures how well the jobs in `entries` fit `tasks`.
+pub(crate) fn entry_metrics(
+    config: &Config,
+    entries: &[PomodoroScheduleEntry],
+    tasks: &[Task],
+) -> ScheduleMetrics {
+    let instances = task::expand_recurring(config, tasks, Local::now());
+    let unfinished: Vec<&Task> = instances.iter().filter(|t| !t.done).collect();
+    let mut task_metrics: Vec<TaskMetrics> =
+        unfinished.iter().map(|t| TaskMetrics::new(t)).collect();
+
+    // the task worked on in the latest job. breaks don't interrupt a block of work, but
+    // anything else does
+    let mut last_task: Option<usize> = None;
+    for e in entries {
+        match e {
+            PomodoroScheduleEntry::Job { task_id, span, .. } => {
+                // instances of a repeating task share its ID, so work goes to the first
+                // instance that still needs it
+                let mut matching =
+                    (0..unfinished.len()).filter(|i| unfinished[*i].id == *task_id);
+                let index = matching
+                    .clone()
+                    .find(|i| {
+                        task_metrics[*i].scheduled_minutes < task_metrics[*i].requested_minutes
+                    })
+                    .or_else(|| matching.next_back());
+                if let Some(i) = index {
+                    task_metrics[i].add_piece(
+                        span.end(),
+                        span.minutes() as u64,
+                        last_task == Some(i),
+                    );
+                }
+                last_task = index;
+            }
+            PomodoroScheduleEntry::Break(_) => {}
+            _ => last_task = None,
+        }
+    }
+
+    ScheduleMetrics::new(task_metrics)
+}
+
 impl PomodoroSchedule {
-    fn fill_free_time(&mut self, config: &Config, tasks: &[Task], until: DateTime<Local>) {
+    fn fill_free_time(
+        &mut self,
+        config: &Config,
+        tasks: &[&Task],
+        until: DateTime<Local>,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<()> {
         if tasks.is_empty() {
-            return;
+            return Ok(());
+        }
+
+        // higher priority tasks get free time first. sorting is stable, so tasks of the same
+        // priority stay in due date order
+        let mut periods = Self::unscheduled_periods_from_tasks(config, tasks);
+        periods.sort_by_key(|p| Reverse(p.task.priority));
+        order_by_dependencies(&mut periods)?;
+        let open_sessions = self.get_open_work_sessions(&config, until);
+
+        let (mut filled_sessions, mut periods_left) =
+            Self::assign_periods(open_sessions.clone(), periods.clone(), cancel)?;
+
+        // when there isn't time for everything, try letting the heaviest tasks go first, and keep
+        // whichever way leaves less weight unscheduled
+        if !periods_left.is_empty() {
+            // sorting is stable, so tasks of the same priority and weight stay in due date order
+            let mut by_weight = periods;
+            by_weight.sort_by(|a, b| {
+                b.task.priority.cmp(&a.task.priority).then(
+                    b.task
+                        .weight
+                        .partial_cmp(&a.task.weight)
+                        .unwrap_or(Ordering::Equal),
+                )
+            });
+            order_by_dependencies(&mut by_weight)?;
+
+            let (weighted_sessions, weighted_periods_left) =
+                Self::assign_periods(open_sessions, by_weight, cancel)?;
+            if unscheduled_weight(&weighted_periods_left) < unscheduled_weight(&periods_left) {
+                filled_sessions = weighted_sessions;
+                periods_left = weighted_periods_left;
+            }
+        }
+
+        let now = Local::now();
+        self.unscheduled = periods_left
+            .iter()
+            .map(|p| UnscheduledWork {
+                task_id: p.task.id,
+                subtask: p.subtask,
+                minutes: p.periods_left * config.work_minutes,
+                from: p.task.deferred_until.map_or(now, |d| d.max(now)),
+                due_date: p.task.due_date,
+            })
+            .collect();
+
+        if !periods_left.is_empty() {
+            let unscheduled: Vec<String> = periods_left
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{}, {} minutes unscheduled",
+                        p.title(),
+                        p.periods_left * config.work_minutes
+                    )
+                })
+                .collect();
+            output::warn(
+                "there wasn't enough free time to finish scheduling the following tasks",
+                &unscheduled,
+                None,
+            );
+        }
+
+        for work_session in filled_sessions {
+            let long_break = PomodoroScheduleEntry::Break(TimeSpan::new(
+                work_session.ending(),
+                config.long_break_minutes,
+            ));
+            self.entries.append(&mut work_session.into());
+            self.entries.push(long_break);
         }
+        goals::reserve(config, tasks, &mut self.entries, until);
 
-        let mut periods_left = Self::unscheduled_periods_from_tasks(config, tasks);
-        let mut open_sessions = self.get_open_work_sessions(&config, until);
+        Ok(())
+    }
 
+    /// Fills `sessions` with `periods`, giving periods earlier in the list the earlier sessions.
+    /// Returns the filled sessions and the periods that didn't fit.
+    fn assign_periods<'a>(
+        mut sessions: Vec<WorkSession<'a>>,
+        mut periods_left: Vec<UnscheduledPeriod<'a>>,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<(Vec<WorkSession<'a>>, Vec<UnscheduledPeriod<'a>>)> {
         let now = Local::now();
 
         let should_retain =
             |u: &UnscheduledPeriod| u.periods_left > 0 && u.task.due_date > now && !u.task.done;
 
-        'sessions: for open_session in open_sessions.iter_mut() {
+        'sessions: for open_session in sessions.iter_mut() {
+            check_canceled(cancel)?;
+
             periods_left.retain(should_retain);
 
             for unscheduled in periods_left.iter_mut() {
-                if !should_retain(unscheduled) {
+                if !should_retain(unscheduled)
+                    || unscheduled.task.is_deferred(&open_session.start)
+                {
                     continue;
                 } else if open_session.full() {
                     continue 'sessions;
                 } else {
                     while unscheduled.periods_left > 0 && !open_session.full() {
-                        open_session.add_job(&unscheduled.name).unwrap();
+                        open_session
+                            .add_job(unscheduled.task, unscheduled.subtask)
+                            .unwrap();
                         unscheduled.periods_left -= 1;
                     }
                 }
@@ -94,51 +418,31 @@ impl PomodoroSchedule {
 
         periods_left.retain(|p| p.periods_left > 0);
 
-        if !periods_left.is_empty() {
-            println!(
-                "WARNING: There wasn't enough free time to finish scheduling the following tasks:"
-            );
-            for p in periods_left {
-                println!(
-                    "\t{}, {} minutes unscheduled",
-                    p.name,
-                    p.periods_left * config.work_minutes
-                )
-            }
-        }
-
-        for work_session in open_sessions {
-            let long_break = PomodoroScheduleEntry::Break(TimeSpan::new(
-                work_session.ending(),
-                config.long_break_minutes,
-            ));
-            self.entries.append(&mut work_session.into());
-            self.entries.push(long_break);
-        }
+        Ok((sessions, periods_left))
     }
 
     fn unscheduled_periods_from_tasks<'a>(
         config: &Config,
-        tasks: &'a [Task],
+        tasks: &[&'a Task],
     ) -> Vec<UnscheduledPeriod<'a>> {
         // why aren't we using iter().map()? see match pattern for TaskDuration::Subtasks. not
         // every pattern can be mapped to a *single* UnscheduledPeriod, and the implementation with
         // flat_map is kinda awkward. this just makes better sense to me, at least.
         let mut v = Vec::new();
         for t in tasks {
+            let periods = |minutes: u64| periods_for(config, t, minutes);
             match &t.duration {
                 TaskDuration::Minutes(m) => v.push(UnscheduledPeriod {
                     task: t,
-                    name: t.name.clone(),
-                    periods_left: (*m as f64 / config.work_minutes as f64).ceil() as u32,
+                    subtask: None,
+                    periods_left: periods(*m),
                 }),
                 TaskDuration::Subtasks(subs) => {
                     for s in subs {
                         v.push(UnscheduledPeriod {
                             task: t,
-                            name: format!("{}: {}", t.name, s.name),
-                            periods_left: (s.duration as f64 / config.work_minutes as f64).ceil()
-                                as u32,
+                            subtask: Some(s.id),
+                            periods_left: periods(s.duration),
                         });
                     }
                 }
@@ -148,65 +452,46 @@ impl PomodoroSchedule {
         v
     }
 
-    fn get_open_work_sessions(&self, config: &Config, until: DateTime<Local>) -> Vec<WorkSession> {
-        use std::iter::once;
-
-        let now = Local::now();
-        let filtered_entries = self
-            .entries
-            .iter()
-            .skip_while(|e| e.span().end() <= now)
-            .take_while(|e| *e.span().start() < until);
-        let span_beginnings = filtered_entries
-            .clone()
-            .map(|e| *e.span().start())
-            .chain(once(until));
-        let span_endings = once(now).chain(filtered_entries.clone().map(|e| e.span().end()));
-
+    fn get_open_work_sessions<'a>(
+        &self,
+        config: &Config,
+        until: DateTime<Local>,
+    ) -> Vec<WorkSession<'a>> {
         let work_session_len = WorkSession::len_minutes(config) as i64;
-        span_endings
-            .zip(span_beginnings)
-            .flat_map(|pair| {
-                #[cfg(debug_assertions)]
-                dbg!(&pair);
-
-                let mut v = Vec::new();
-                let end = pair.0;
-                let beginning_next = pair.1;
-
-                if beginning_next > end {
-                    let free_minutes = (beginning_next - end).num_minutes();
-                    let num_possible_work_sessions = free_minutes / work_session_len;
-
-                    for i in 0..num_possible_work_sessions {
-                        v.push(WorkSession::new(
-                            end + chrono::Duration::minutes(i * work_session_len),
-                            config,
-                        ));
-                    }
-                }
-
-                v
+        free_time(&self.entries, Local::now(), until)
+            .iter()
+            .flat_map(|free| work_windows::within(&config.work_windows, free))
+            .flat_map(|free| {
+                let num_possible_work_sessions = free.minutes() as i64 / work_session_len;
+                (0..num_possible_work_sessions).map(move |i| {
+                    WorkSession::new(
+                        *free.start() + chrono::Duration::minutes(i * work_session_len),
+                        config,
+                    )
+                })
             })
             .collect()
     }
 
-    fn breaks_to_schedule_entries(
+    /// Returns entries for the events and sleep between now and `until`, in order.
+    pub(crate) fn breaks_to_schedule_entries(
         events: &[CalendarEvent],
         until: DateTime<Local>,
         bedtime: &Bedtime,
     ) -> Vec<PomodoroScheduleEntry> {
+        // nothing before now matters to a new schedule
+        let now = Local::now();
+        let mut cache = RecurrenceCache::shared();
+
         let mut v: Vec<PomodoroScheduleEntry> = events
             .iter()
-            .cloned()
             .flat_map(|e| {
-                PomodoroScheduleEntryIter::from(&e)
-                    .take_while(|s: &PomodoroScheduleEntry| *s.span().start() < until)
-                    .collect::<Vec<PomodoroScheduleEntry>>()
+                cache
+                    .occurrences(e, now, until)
+                    .into_iter()
+                    .map(move |span| PomodoroScheduleEntry::from_event(e, span))
             })
-            .chain(
-                BedtimeScheduleEntryIter::from(bedtime).take_while(|s| *s.span().start() < until),
-            )
+            .chain(bedtime.occurrences(now, until).map(PomodoroScheduleEntry::Sleep))
             .collect();
 
         v.sort_by_cached_key(|e| *e.span().start());
@@ -217,19 +502,335 @@ impl PomodoroSchedule {
     pub fn get_entries(&self) -> &[PomodoroScheduleEntry] {
         &self.entries
     }
+
+    /// Returns the entries that take up any part of `day`.
+    pub fn entries_on(&self, day: Date<Local>) -> impl Iterator<Item = &PomodoroScheduleEntry> {
+        self.entries
+            .iter()
+            .filter(move |e| e.span().start().date() <= day && e.span().end().date() >= day)
+    }
+
+    /// Returns a display of the next seven days at a glance, fit to `width` columns.
+    pub fn week_display<'a>(
+        &'a self,
+        config: &'a Config,
+        tasks: &'a [Task],
+        width: usize,
+    ) -> PomodoroWeekDisplay<'a> {
+        PomodoroWeekDisplay {
+            schedule: self,
+            config,
+            tasks,
+            width,
+        }
+    }
+
+    /// Returns the index of the entry going on at `when`, if any.
+    pub fn entry_index_at(&self, when: DateTime<Local>) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|e| *e.span().start() <= when && when < e.span().end())
+    }
+
+    /// Keeps only the entries `keep` returns true for.
+    pub fn retain_entries<F>(&mut self, keep: F)
+    where
+        F: FnMut(&PomodoroScheduleEntry) -> bool,
+    {
+        self.entries.retain(keep);
+    }
+
+    /// Removes the work periods for the task with `task_id`, leaving their time free.
+    pub fn remove_task(&mut self, task_id: TaskId) {
+        self.entries.retain(|e| match e {
+            PomodoroScheduleEntry::Job { task_id: id, .. } => *id != task_id,
+            _ => true,
+        });
+        self.unscheduled.retain(|u| u.task_id != task_id);
+    }
+
+    /// Returns the work there wasn't room for when the schedule was made.
+    pub fn get_unscheduled(&self) -> &[UnscheduledWork] {
+        &self.unscheduled
+    }
+
+    /// Removes the entries that came from `event`.
+    pub fn remove_event(&mut self, event: &CalendarEvent) {
+        self.entries.retain(|e| {
+            let span = *e.span();
+            let from_event = match e {
+                PomodoroScheduleEntry::Calendar { event_id, name, .. } => {
+                    event.event_type == CalendarEventType::Event
+                        && match event_id {
+                            0 => *name == event.name,
+                            id => *id == event.id,
+                        }
+                }
+                PomodoroScheduleEntry::Break(_) => event.event_type == CalendarEventType::Break,
+                PomodoroScheduleEntry::Habit { name, .. } => {
+                    event.event_type == CalendarEventType::Habit && *name == event.name
+                }
+                _ => false,
+            };
+            !(from_event && event.occurrences(*span.start(), span.end()).any(|o| o == span))
+        });
+    }
+
+    /// Records what became of the job at `index`.
+    pub fn set_job_status(&mut self, index: usize, new_status: JobStatus) -> SparrowResult<()> {
+        match self.entries.get_mut(index) {
+            Some(PomodoroScheduleEntry::Job { status, .. }) => {
+                *status = new_status;
+                Ok(())
+            }
+            Some(_) => Err(SparrowError::BasicMessage(
+                "only work periods can be marked done or skipped".to_string(),
+            )),
+            None => Err(SparrowError::BasicMessage(
+                "there's no such entry in the schedule".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the index of the first planned job that isn't over at `now`, for the task with
+    /// `task_id` if given: the job in progress, or else the next one.
+    pub fn next_job_index(&self, task_id: Option<TaskId>, now: DateTime<Local>) -> Option<usize> {
+        self.entries.iter().position(|e| match e {
+            PomodoroScheduleEntry::Job {
+                task_id: id,
+                status: JobStatus::Planned,
+                span,
+                ..
+            } => task_id.is_none_or(|t| t == *id) && span.end() > now,
+            _ => false,
+        })
+    }
+
+    /// Moves the job at `index` to start at `to`, or else to the first free time after it with
+    /// room for the job and a short break. Returns where the job went.
+    pub fn move_job(
+        &mut self,
+        config: &Config,
+        index: usize,
+        to: Option<DateTime<Local>>,
+    ) -> SparrowResult<TimeSpan> {
+        let (task_id, subtask, span) = job_at(&self.entries, index)?;
+        let start = match to {
+            Some(to) => {
+                let moved = TimeSpan::new(to, span.minutes());
+                let clashes = self.entries.iter().enumerate().any(|(i, e)| {
+                    i != index
+                        && e.span().overlaps(&moved)
+                        && !matches!(e, PomodoroScheduleEntry::Break(_))
+                });
+                if clashes {
+                    return Err(SparrowError::BasicMessage(
+                        "something else is already planned then".to_string(),
+                    ));
+                }
+                to
+            }
+            None => self
+                .free_slot(config, span.minutes(), span.end(), Some(index))
+                .ok_or_else(|| {
+                    SparrowError::BasicMessage(
+                        "there's no free time left in the schedule to move the work to. remake \
+                         your schedule with `sparrow make`"
+                            .to_string(),
+                    )
+                })?,
+        };
+
+        let labels = self.planned_labels(task_id);
+        self.entries.remove(index);
+        let moved = self.add_job_at(config, task_id, subtask, start, span.minutes());
+        self.relabel(task_id, labels);
+        Ok(moved)
+    }
+
+    /// Marks the job at `index` skipped, and plans its work again in the first free time after it
+    /// with room for it. Returns where the work went, or None if there wasn't room anywhere.
+    pub fn skip_job(&mut self, config: &Config, index: usize) -> SparrowResult<Option<TimeSpan>> {
+        self.skip_job_before(config, index, None)
+    }
+
+    /// Like `skip_job`, but the work is only planned again if it can be done by `deadline`.
+    pub fn skip_job_before(
+        &mut self,
+        config: &Config,
+        index: usize,
+        deadline: Option<DateTime<Local>>,
+    ) -> SparrowResult<Option<TimeSpan>> {
+        let (task_id, subtask, span) = job_at(&self.entries, index)?;
+        let labels = self.planned_labels(task_id);
+        self.set_job_status(index, JobStatus::Skipped)?;
+
+        let after = span.end().max(Local::now());
+        let start = match self.free_slot(config, span.minutes(), after, None) {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+        if deadline.is_some_and(|d| TimeSpan::new(start, span.minutes()).end() > d) {
+            return Ok(None);
+        }
+        let redone = self.add_job_at(config, task_id, subtask, start, span.minutes());
+        self.relabel(task_id, labels);
+        Ok(Some(redone))
+    }
+
+    /// Returns when the first free time after `after` starts that has room for `minutes` of work
+    /// and a short break, within the work windows and before the schedule ends. The entry at
+    /// `ignore`, if given, counts as free.
+    fn free_slot(
+        &self,
+        config: &Config,
+        minutes: u32,
+        after: DateTime<Local>,
+        ignore: Option<usize>,
+    ) -> Option<DateTime<Local>> {
+        let until = self.entries.iter().map(|e| e.span().end()).max()?;
+        let busy: Vec<TimeSpan> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != ignore)
+            .map(|(_, e)| *e.span())
+            .collect();
+        spans::free_time(&busy, after, until)
+            .iter()
+            .flat_map(|free| work_windows::within(&config.work_windows, free))
+            .find(|free| free.minutes() >= minutes + config.short_break_minutes)
+            .map(|free| *free.start())
+    }
+
+    /// Adds a planned job at `start`, with a short break after it if there's room, and returns
+    /// its span.
+    fn add_job_at(
+        &mut self,
+        config: &Config,
+        task_id: TaskId,
+        subtask: Option<SubtaskId>,
+        start: DateTime<Local>,
+        minutes: u32,
+    ) -> TimeSpan {
+        let span = TimeSpan::new(start, minutes);
+        let short_break = TimeSpan::new(span.end(), config.short_break_minutes);
+        if config.short_break_minutes > 0
+            && !self.entries.iter().any(|e| e.span().overlaps(&short_break))
+        {
+            self.entries.push(PomodoroScheduleEntry::Break(short_break));
+        }
+        self.entries.push(PomodoroScheduleEntry::Job {
+            task_id,
+            subtask,
+            status: JobStatus::Planned,
+            span,
+        });
+        sort_entries(&mut self.entries);
+        span
+    }
+
+    /// Returns the subtasks of the planned jobs for `task_id` that aren't over yet, in order.
+    fn planned_labels(&self, task_id: TaskId) -> Vec<Option<SubtaskId>> {
+        self.planned_jobs(task_id)
+            .map(|e| match e {
+                PomodoroScheduleEntry::Job { subtask, .. } => *subtask,
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Gives the planned jobs for `task_id` that aren't over yet the subtasks in `labels`, in
+    /// order, so that a task's subtasks are still worked on in order after its jobs move around.
+    fn relabel(&mut self, task_id: TaskId, labels: Vec<Option<SubtaskId>>) {
+        let jobs: Vec<&mut PomodoroScheduleEntry> = self.planned_jobs_mut(task_id).collect();
+        for (job, label) in jobs.into_iter().zip(labels) {
+            if let PomodoroScheduleEntry::Job { subtask, .. } = job {
+                *subtask = label;
+            }
+        }
+    }
+
+    fn planned_jobs(&self, task_id: TaskId) -> impl Iterator<Item = &PomodoroScheduleEntry> {
+        let now = Local::now();
+        self.entries
+            .iter()
+            .filter(move |e| is_planned_job(e, task_id, now))
+    }
+
+    fn planned_jobs_mut(
+        &mut self,
+        task_id: TaskId,
+    ) -> impl Iterator<Item = &mut PomodoroScheduleEntry> {
+        let now = Local::now();
+        self.entries
+            .iter_mut()
+            .filter(move |e| is_planned_job(e, task_id, now))
+    }
 }
 
-#[derive(Debug)]
-struct WorkSession {
+/// Returns the task, subtask, and span of the job at `index` in `entries`.
+fn job_at(
+    entries: &[PomodoroScheduleEntry],
+    index: usize,
+) -> SparrowResult<(TaskId, Option<SubtaskId>, TimeSpan)> {
+    match entries.get(index) {
+        Some(PomodoroScheduleEntry::Job {
+            task_id,
+            subtask,
+            span,
+            ..
+        }) => Ok((*task_id, *subtask, *span)),
+        Some(_) => Err(SparrowError::BasicMessage(
+            "only work periods can be moved or skipped".to_string(),
+        )),
+        None => Err(SparrowError::BasicMessage(
+            "there's no such entry in the schedule".to_string(),
+        )),
+    }
+}
+
+/// Returns true if `entry` is a planned job for `task_id` that isn't over at `now`.
+fn is_planned_job(entry: &PomodoroScheduleEntry, task_id: TaskId, now: DateTime<Local>) -> bool {
+    match entry {
+        PomodoroScheduleEntry::Job {
+            task_id: id,
+            status: JobStatus::Planned,
+            span,
+            ..
+        } => *id == task_id && span.end() > now,
+        _ => false,
+    }
+}
+
+/// Moves the periods of prerequisite tasks ahead of the periods of tasks that depend on them.
+/// Periods are assigned in order, so a dependent task won't get any work until its prerequisites'
+/// work is all scheduled.
+fn order_by_dependencies(periods: &mut Vec<UnscheduledPeriod>) -> SparrowResult<()> {
+    let mut tasks: Vec<&Task> = Vec::new();
+    for p in periods.iter() {
+        if !tasks.iter().any(|t| t.id == p.task.id) {
+            tasks.push(p.task);
+        }
+    }
+
+    let order = task::dependency_order(&tasks)?;
+    periods.sort_by_key(|p| order.iter().position(|id| *id == p.task.id));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+struct WorkSession<'a> {
     start: DateTime<Local>,
-    job_names: Vec<String>,
+    jobs: Vec<(&'a Task, Option<SubtaskId>)>,
 
     max_jobs: usize,
     job_len_minutes: u32,
     break_len_minutes: u32,
 }
 
-impl WorkSession {
+impl<'a> WorkSession<'a> {
     fn len_minutes(config: &Config) -> u32 {
         config.work_periods_per_job_session * config.work_minutes
             + (config.work_periods_per_job_session - 1) * config.short_break_minutes
@@ -239,7 +840,7 @@ impl WorkSession {
     fn new(start: DateTime<Local>, config: &Config) -> Self {
         Self {
             start,
-            job_names: Vec::new(),
+            jobs: Vec::new(),
             max_jobs: config.work_periods_per_job_session as usize,
             job_len_minutes: config.work_minutes,
             break_len_minutes: config.short_break_minutes,
@@ -247,12 +848,12 @@ impl WorkSession {
     }
 
     fn full(&self) -> bool {
-        self.job_names.len() >= self.max_jobs
+        self.jobs.len() >= self.max_jobs
     }
 
-    fn add_job(&mut self, name: &str) -> SparrowResult<()> {
+    fn add_job(&mut self, task: &'a Task, subtask: Option<SubtaskId>) -> SparrowResult<()> {
         if !self.full() {
-            self.job_names.push(name.to_string());
+            self.jobs.push((task, subtask));
             Ok(())
         } else {
             Err(SparrowError::BasicMessage(
@@ -262,36 +863,36 @@ impl WorkSession {
     }
 
     fn ending(&self) -> DateTime<Local> {
-        if self.job_names.is_empty() {
+        if self.jobs.is_empty() {
             self.start
         } else {
             self.start
+                + chrono::Duration::minutes(self.jobs.len() as i64 * self.job_len_minutes as i64)
                 + chrono::Duration::minutes(
-                    self.job_names.len() as i64 * self.job_len_minutes as i64,
-                )
-                + chrono::Duration::minutes(
-                    (self.job_names.len() - 1) as i64 * self.break_len_minutes as i64,
+                    (self.jobs.len() - 1) as i64 * self.break_len_minutes as i64,
                 )
         }
     }
 }
 
-impl Into<Vec<PomodoroScheduleEntry>> for WorkSession {
+impl Into<Vec<PomodoroScheduleEntry>> for WorkSession<'_> {
     fn into(self) -> Vec<PomodoroScheduleEntry> {
-        if self.job_names.is_empty() {
+        if self.jobs.is_empty() {
             vec![]
         } else {
             let job_break_len = self.job_len_minutes + self.break_len_minutes;
 
-            self.job_names
+            self.jobs
                 .iter()
                 .enumerate()
                 .flat_map(|pair| {
                     let i = pair.0;
-                    let name = pair.1;
+                    let (task, subtask) = pair.1;
 
                     let job = PomodoroScheduleEntry::Job {
-                        title: name.to_string(),
+                        task_id: task.id,
+                        subtask: *subtask,
+                        status: JobStatus::Planned,
                         span: TimeSpan::new(
                             self.start + chrono::Duration::minutes(i as i64 * job_break_len as i64),
                             self.job_len_minutes,
@@ -307,7 +908,7 @@ impl Into<Vec<PomodoroScheduleEntry>> for WorkSession {
 
                     vec![job, break_time]
                 })
-                .take(self.job_names.len() * 2 - 1) // this trims off that last short break we won't need
+                .take(self.jobs.len() * 2 - 1) // this trims off that last short break we won't need
                 .collect()
         }
     }
@@ -315,173 +916,382 @@ impl Into<Vec<PomodoroScheduleEntry>> for WorkSession {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum PomodoroScheduleEntry {
-    /// Work time, part of a Task.
-    Job { title: String, span: TimeSpan },
-
-    /// Event time.
-    Calendar { name: String, span: TimeSpan },
+    /// Work time, part of a Task. Jobs refer to their Task (and Subtask) by ID instead of
+    /// copying their names, which are looked up when the schedule is displayed.
+    Job {
+        #[serde(default)]
+        task_id: TaskId,
+        #[serde(default)]
+        subtask: Option<SubtaskId>,
+        #[serde(default)]
+        status: JobStatus,
+        span: TimeSpan,
+    },
+
+    /// Event time. The event's name is kept too, since not every event has an ID.
+    Calendar {
+        #[serde(default)]
+        event_id: EventId,
+        name: String,
+        span: TimeSpan,
+    },
 
     /// Break time.
     Break(TimeSpan),
 
     /// Sleep time.
     Sleep(TimeSpan),
+
+    /// Time set aside for a habit.
+    Habit { name: String, span: TimeSpan },
 }
 
 impl PomodoroScheduleEntry {
+    pub(crate) fn from_event(event: &CalendarEvent, span: TimeSpan) -> Self {
+        match event.event_type {
+            CalendarEventType::Event => Self::Calendar {
+                event_id: event.id,
+                name: event.name.clone(),
+                span,
+            },
+            CalendarEventType::Break => Self::Break(span),
+            CalendarEventType::Habit => Self::Habit {
+                name: event.name.clone(),
+                span,
+            },
+        }
+    }
+
     pub fn span(&self) -> &TimeSpan {
         match self {
             Self::Job { span, .. } => span,
             Self::Calendar { span, .. } => span,
             Self::Break(span) => span,
             Self::Sleep(span) => span,
+            Self::Habit { span, .. } => span,
+        }
+    }
+
+    /// Names the kind of entry, like "job" or "sleep".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Job { .. } => "job",
+            Self::Calendar { .. } => "calendar",
+            Self::Break(_) => "break",
+            Self::Sleep(_) => "sleep",
+            Self::Habit { .. } => "habit",
         }
     }
 
-    pub fn title(&self) -> &str {
+    /// Returns what became of the entry, if it's a job.
+    pub fn status(&self) -> Option<JobStatus> {
         match self {
-            Self::Job { title, .. } => &title,
-            Self::Calendar { name, .. } => &name,
-            Self::Break(_) => "Break",
-            Self::Sleep(_) => "Sleep",
+            Self::Job { status, .. } => Some(*status),
+            _ => None,
         }
     }
+
+    /// Returns a self-contained view of the entry, for output meant for other programs.
+    pub fn view<'a>(&'a self, tasks: &'a [Task]) -> EntryView<'a> {
+        EntryView {
+            kind: self.kind(),
+            title: self.title(tasks),
+            start: *self.span().start(),
+            end: self.span().end(),
+            status: self.status(),
+            notes: self.notes(tasks),
+        }
+    }
+
+    /// Returns the title of the entry. `tasks` is needed to look up the names of jobs.
+    pub fn title<'a>(&'a self, tasks: &'a [Task]) -> Cow<'a, str> {
+        match self {
+            Self::Job {
+                task_id, subtask, ..
+            } => match tasks.iter().find(|t| t.id == *task_id) {
+                Some(t) => job_title(t, *subtask),
+                None => Cow::Borrowed("(deleted task)"),
+            },
+            Self::Calendar { name, .. } => Cow::Borrowed(name),
+            Self::Break(_) => Cow::Borrowed("Break"),
+            Self::Sleep(_) => Cow::Borrowed("Sleep"),
+            Self::Habit { name, .. } => Cow::Borrowed(name),
+        }
+    }
+
+    /// Returns the notes for a job's task (or subtask, if it has any notes of its own).
+    pub fn notes<'a>(&self, tasks: &'a [Task]) -> Option<&'a str> {
+        match self {
+            Self::Job {
+                task_id, subtask, ..
+            } => {
+                let task = tasks.iter().find(|t| t.id == *task_id)?;
+                subtask
+                    .and_then(|i| task.subtask(i))
+                    .and_then(|s| s.notes.as_deref())
+                    .or(task.notes.as_deref())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What became of a job.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum JobStatus {
+    /// The job hasn't been done (or skipped) yet.
+    #[default]
+    Planned,
+    Done,
+    Skipped,
+}
+
+/// A PomodoroScheduleEntry with its title looked up, ready to be serialized.
+#[derive(Clone, Debug, Serialize)]
+pub struct EntryView<'a> {
+    pub kind: &'static str,
+    pub title: Cow<'a, str>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub status: Option<JobStatus>,
+    pub notes: Option<&'a str>,
 }
 
 #[derive(Clone, Debug)]
 struct UnscheduledPeriod<'a> {
     task: &'a Task,
-    name: String,
+    subtask: Option<SubtaskId>,
     periods_left: u32,
 }
 
+impl UnscheduledPeriod<'_> {
+    fn title(&self) -> Cow<'_, str> {
+        job_title(self.task, self.subtask)
+    }
+}
+
+/// Adds up the weight of the work in `periods`.
+fn unscheduled_weight(periods: &[UnscheduledPeriod]) -> f64 {
+    periods
+        .iter()
+        .map(|p| p.task.weight * p.periods_left as f64)
+        .sum()
+}
+
+/// Names work on `task`, or on one of its subtasks by index.
+pub(crate) fn job_title(task: &Task, subtask: Option<SubtaskId>) -> Cow<'_, str> {
+    match subtask.and_then(|i| task.subtask(i)) {
+        Some(s) => Cow::Owned(format!("{}: {}", task.name, s.name)),
+        None => Cow::Borrowed(&task.name),
+    }
+}
+
 fn sort_entries(entries: &mut [PomodoroScheduleEntry]) {
     entries.sort_by_cached_key(|e| *e.span().start());
 }
 
-/// Produces `PomodoroScheduleEntry`s from repeated `CalendarEvent`s.
-struct PomodoroScheduleEntryIter<'a> {
-    calendar_event: &'a CalendarEvent,
-    next: Option<PomodoroScheduleEntry>,
+pub struct PomodoroDisplay<'a> {
+    schedule: &'a PomodoroSchedule,
+    config: &'a Config,
+    tasks: &'a [Task],
+    formatting: Option<&'a Formatting>,
 }
 
-impl<'a> From<&'a CalendarEvent> for PomodoroScheduleEntryIter<'a> {
-    fn from(event: &'a CalendarEvent) -> Self {
-        let initial_item = match event.event_type {
-            CalendarEventType::Event => PomodoroScheduleEntry::Calendar {
-                name: event.name.clone(),
-                span: event.time_span,
-            },
-            CalendarEventType::Break => PomodoroScheduleEntry::Break(event.time_span),
-        };
-
-        PomodoroScheduleEntryIter {
-            calendar_event: event,
-            next: Some(initial_item),
-        }
-    }
-}
-
-impl Iterator for PomodoroScheduleEntryIter<'_> {
-    type Item = PomodoroScheduleEntry;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        // do not mutate. this is what is returned at the end of the function. self.next is
-        // modified here to determine what entry there will be (if any) after this one.
-        let result = self.next.clone();
-
-        self.next = if let Some(current_entry) = &self.next {
-            let current_date = current_entry.span().start().date();
-            let current_time = current_entry.span().start().time();
-            let duration = current_entry.span().minutes();
-            let new_span = match &self.calendar_event.repeat {
-                Repeat::No => return None,
-                Repeat::Daily => TimeSpan::new(
-                    current_date.succ().and_time(current_time).unwrap(),
-                    duration,
-                ),
-                Repeat::Weekly => TimeSpan::new(
-                    (current_date + chrono::Duration::days(7))
-                        .and_time(current_time)
-                        .unwrap(),
-                    duration,
-                ),
-            };
+impl<'a> PomodoroDisplay<'a> {
+    /// Styles each entry by its kind, highlighting the one going on now and dimming the ones more
+    /// than an hour away. Does nothing if output can't have colors.
+    pub fn styled(self, formatting: &'a Formatting) -> Self {
+        Self {
+            formatting: Some(formatting).filter(|_| output::use_color()),
+            ..self
+        }
+    }
 
-            Some(match current_entry {
-                PomodoroScheduleEntry::Job { title, .. } => PomodoroScheduleEntry::Job {
-                    title: title.clone(),
-                    span: new_span,
-                },
-                PomodoroScheduleEntry::Calendar { name, .. } => PomodoroScheduleEntry::Calendar {
-                    name: name.clone(),
-                    span: new_span,
-                },
-                PomodoroScheduleEntry::Break(_) => PomodoroScheduleEntry::Break(new_span),
-                PomodoroScheduleEntry::Sleep(_) => PomodoroScheduleEntry::Sleep(new_span),
-            })
-        } else {
-            return None;
+    fn style(&self, e: &PomodoroScheduleEntry, now: DateTime<Local>) -> ansi_term::Style {
+        let formatting = match self.formatting {
+            Some(f) => f,
+            None => return ansi_term::Style::new(),
         };
-
-        result
+        let style = formatting.entry_style(e.kind());
+        if *e.span().start() <= now && now < e.span().end() {
+            Formatting::layer(style, formatting.current)
+        } else if *e.span().start() > now + chrono::Duration::hours(1) {
+            Formatting::layer(style, formatting.later)
+        } else {
+            style
+        }
     }
-}
 
-pub struct BedtimeScheduleEntryIter {
-    current: TimeSpan,
+    /// Returns the entries that aren't over yet.
+    fn upcoming(&self) -> impl Iterator<Item = &PomodoroScheduleEntry> {
+        let now = Local::now();
+        self.schedule
+            .entries
+            .iter()
+            .filter(move |e| e.span().end() >= now)
+    }
 }
 
-impl From<&Bedtime> for BedtimeScheduleEntryIter {
-    fn from(b: &Bedtime) -> Self {
-        Self::new(b)
+impl Serialize for PomodoroDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.upcoming().map(|e| e.view(self.tasks)))
     }
 }
 
-impl BedtimeScheduleEntryIter {
-    pub fn new(bedtime: &Bedtime) -> Self {
-        // sleep starts on the day before, in case you're like me and decide to make a
-        // schedule in the middle of bedtime (this is a reference that future me won't understand)
-        let yesterday = Local::today() - chrono::Duration::days(1);
-        Self {
-            current: TimeSpan::new(
-                yesterday.and_time(*bedtime.start()).unwrap(),
-                (bedtime.duration_hours() * 60.0) as u32,
-            ),
+impl Display for PomodoroDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let now = Local::now();
+        for e in self.upcoming() {
+            let format = format!("{} {}", self.config.date_format, self.config.time_format);
+            let mut line = format!(
+                "{} :: {}",
+                e.span().start().format(&format),
+                e.title(self.tasks)
+            );
+            if let Some(notes) = e.notes(self.tasks) {
+                line += &format!(" - {}", task::summarize_notes(notes));
+            }
+            match e.status() {
+                Some(JobStatus::Done) => line += " (done)",
+                Some(JobStatus::Skipped) => line += " (skipped)",
+                _ => {}
+            }
+            writeln!(f, "{}", self.style(e, now).paint(line))?;
         }
+
+        Ok(())
     }
 }
 
-impl Iterator for BedtimeScheduleEntryIter {
-    type Item = PomodoroScheduleEntry;
+/// The width a day's column needs to be worth showing days side by side.
+const MIN_COLUMN_WIDTH: usize = 14;
+
+/// The next seven days of the schedule, a column for each day if the terminal is wide enough, or
+/// one day after another if it isn't. Breaks and sleep are left out, and back-to-back sessions of
+/// the same thing are shown as one line.
+pub struct PomodoroWeekDisplay<'a> {
+    schedule: &'a PomodoroSchedule,
+    config: &'a Config,
+    tasks: &'a [Task],
+    width: usize,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let ret = PomodoroScheduleEntry::Sleep(self.current);
+/// A day of the week view, ready to be serialized.
+#[derive(Serialize)]
+struct DayView<'a> {
+    date: NaiveDate,
+    entries: Vec<EntryView<'a>>,
+}
 
-        self.current
-            .set_start(*self.current.start() + chrono::Duration::days(1));
+impl PomodoroWeekDisplay<'_> {
+    /// Returns the next seven days, starting today, with the entries that start on each.
+    fn days(&self) -> Vec<(Date<Local>, Vec<&PomodoroScheduleEntry>)> {
+        let today = Local::today();
+        (0..7)
+            .map(|i| {
+                let day = today + chrono::Duration::days(i);
+                let entries = self
+                    .schedule
+                    .entries_on(day)
+                    .filter(|e| e.span().start().date() == day)
+                    .filter(|e| {
+                        !matches!(
+                            e,
+                            PomodoroScheduleEntry::Break(_) | PomodoroScheduleEntry::Sleep(_)
+                        )
+                    })
+                    .collect();
+                (day, entries)
+            })
+            .collect()
+    }
 
-        Some(ret)
+    /// Returns a line for each run of `entries` with the same title.
+    fn lines(&self, entries: &[&PomodoroScheduleEntry]) -> Vec<String> {
+        let mut runs: Vec<(DateTime<Local>, Cow<str>, usize)> = Vec::new();
+        for e in entries {
+            let title = e.title(self.tasks);
+            match runs.last_mut() {
+                Some((_, last, count)) if *last == title => *count += 1,
+                _ => runs.push((*e.span().start(), title, 1)),
+            }
+        }
+        runs.into_iter()
+            .map(|(start, title, count)| {
+                let start = start.format(&self.config.time_format);
+                match count {
+                    1 => format!("{} {}", start, title),
+                    n => format!("{} {} x{}", start, title, n),
+                }
+            })
+            .collect()
     }
 }
 
-pub struct PomodoroDisplay<'a> {
-    schedule: &'a PomodoroSchedule,
-    config: &'a Config,
+/// Cuts `s` down to `width` characters, ending with "~" if anything was cut.
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return s.to_string();
+    }
+    let mut cut: String = s.chars().take(width.saturating_sub(1)).collect();
+    cut.push('~');
+    cut
 }
 
-impl Display for PomodoroDisplay<'_> {
+impl Serialize for PomodoroWeekDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.days().into_iter().map(|(day, entries)| DayView {
+            date: day.naive_local(),
+            entries: entries.into_iter().map(|e| e.view(self.tasks)).collect(),
+        }))
+    }
+}
+
+impl Display for PomodoroWeekDisplay<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for e in self
-            .schedule
-            .entries
-            .iter()
-            .filter(|e| e.span().end() >= Local::now())
-        {
-            let format = format!("{} {}", self.config.date_format, self.config.time_format);
-            writeln!(f, "{} :: {}", e.span().start().format(&format), e.title())?;
+        let header_format = format!("%a {}", self.config.date_format);
+        let days: Vec<(String, Vec<String>)> = self
+            .days()
+            .into_iter()
+            .map(|(day, entries)| (day.format(&header_format).to_string(), self.lines(&entries)))
+            .collect();
+
+        // one column per day, with a space between columns
+        let column_width = (self.width + 1) / days.len() - 1;
+        if column_width < MIN_COLUMN_WIDTH {
+            for (i, (header, lines)) in days.iter().enumerate() {
+                if i > 0 {
+                    writeln!(f)?;
+                }
+                writeln!(f, "{}", header)?;
+                if lines.is_empty() {
+                    writeln!(f, "-\tnothing scheduled")?;
+                }
+                for line in lines {
+                    writeln!(f, "-\t{}", line)?;
+                }
+            }
+            return Ok(());
         }
 
+        let rows = days.iter().map(|(_, lines)| lines.len()).max().unwrap_or(0);
+        let header_row = days.iter().map(|(header, _)| header.as_str());
+        let rule = "-".repeat(column_width);
+        let rule_row = days.iter().map(|_| rule.as_str());
+        let rows = std::iter::once(header_row.collect::<Vec<_>>())
+            .chain(std::iter::once(rule_row.collect()))
+            .chain((0..rows).map(|r| {
+                days.iter()
+                    .map(|(_, lines)| lines.get(r).map_or("", String::as_str))
+                    .collect()
+            }));
+        for row in rows {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| format!("{:<w$}", truncate(cell, column_width), w = column_width))
+                .collect();
+            writeln!(f, "{}", cells.join(" ").trim_end())?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,285 @@
+//! Time blocking: each task gets long, unbroken blocks of time (`time_block_minutes` at most) in
+//! the free time between events and sleep, with no pomodoro breaks. Some work, like writing or lab
+//! work, doesn't fit a 25-minute rhythm.
+
+use crate::{
+    methods::pomodoro::{self, PomodoroSchedule, PomodoroScheduleEntry},
+    goals, output,
+    schedule::check_canceled,
+    task::{self, SubtaskId, Task, TaskDuration},
+    Bedtime, CalendarEvent, Config, Schedule, ScheduleMetrics, SparrowError, SparrowResult,
+    TimeSpan,
+};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicBool;
+
+/// Time blocks are jobs in the same entries the pomodoro schedule uses, just longer and without
+/// breaks between them.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TimeBlockSchedule {
+    entries: Vec<PomodoroScheduleEntry>,
+}
+
+/// Work on a task (or one of its subtasks) that still needs a block.
+pub(crate) struct Unblocked<'a> {
+    pub(crate) task: &'a Task,
+    subtask: Option<SubtaskId>,
+    pub(crate) minutes_left: i64,
+}
+
+impl<'a> Schedule<'a> for TimeBlockSchedule {
+    type Display = TimeBlockDisplay<'a>;
+
+    fn make(
+        config: &Config,
+        tasks: &[Task],
+        events: &[CalendarEvent],
+        bedtime: &Bedtime,
+        cancel: &AtomicBool,
+    ) -> SparrowResult<Self> {
+        // repeating tasks are scheduled one instance at a time
+        let instances = task::expand_recurring(config, tasks, Local::now());
+        let now = Local::now();
+        let mut tasks: Vec<&Task> = instances
+            .iter()
+            .filter(|t| !t.done && t.due_date > now)
+            .collect();
+        let until = match tasks.iter().map(|t| t.due_date).max() {
+            Some(u) => u,
+            None => {
+                return Err(SparrowError::BasicMessage(
+                    "can't make a schedule without tasks".to_string(),
+                ))
+            }
+        };
+
+        // higher priority tasks go first, then the ones due sooner
+        tasks.sort_by_key(|t| (Reverse(t.priority), t.due_date));
+        let mut work: Vec<Unblocked> = tasks.iter().flat_map(|t| unblocked(t)).collect();
+
+        let mut entries = PomodoroSchedule::breaks_to_schedule_entries(events, until, bedtime);
+        let mut blocks = Vec::new();
+        for gap in pomodoro::free_time(&entries, now, until) {
+            check_canceled(cancel)?;
+            fill(
+                &mut work,
+                &mut blocks,
+                (*gap.start(), gap.end()),
+                config.time_block_minutes as i64,
+                config.min_time_block_minutes as i64,
+            );
+        }
+        warn_unscheduled(&work);
+
+        entries.append(&mut blocks);
+        goals::reserve(config, &tasks, &mut entries, until);
+        entries.sort_by_key(|e| *e.span().start());
+        Ok(Self { entries })
+    }
+
+    fn display(&'a self, config: &'a Config, tasks: &'a [Task]) -> Self::Display {
+        TimeBlockDisplay {
+            schedule: self,
+            config,
+            tasks,
+        }
+    }
+
+    fn metrics(&self, config: &Config, tasks: &[Task]) -> ScheduleMetrics {
+        pomodoro::entry_metrics(config, &self.entries, tasks)
+    }
+}
+
+/// Warns about any of `work` that didn't get blocks.
+pub(crate) fn warn_unscheduled(work: &[Unblocked]) {
+    let unscheduled: Vec<String> = work
+        .iter()
+        .filter(|w| w.minutes_left > 0)
+        .map(|w| {
+            format!(
+                "{}, {} minutes unscheduled",
+                title(w.task, w.subtask),
+                w.minutes_left
+            )
+        })
+        .collect();
+    if !unscheduled.is_empty() {
+        output::warn(
+            "there wasn't enough free time to finish scheduling the following tasks",
+            &unscheduled,
+            None,
+        );
+    }
+}
+
+impl TimeBlockSchedule {
+    pub fn get_entries(&self) -> &[PomodoroScheduleEntry] {
+        &self.entries
+    }
+
+    /// Removes the blocks for the task with `task_id`, leaving their time free.
+    pub fn remove_task(&mut self, task_id: task::TaskId) {
+        self.entries.retain(|e| match e {
+            PomodoroScheduleEntry::Job { task_id: id, .. } => *id != task_id,
+            _ => true,
+        });
+    }
+
+    pub fn retain_entries<F>(&mut self, keep: F)
+    where
+        F: FnMut(&PomodoroScheduleEntry) -> bool,
+    {
+        self.entries.retain(keep);
+    }
+}
+
+/// Splits `task` into the work that needs blocks, one for each subtask.
+pub(crate) fn unblocked(task: &Task) -> Vec<Unblocked<'_>> {
+    match &task.duration {
+        TaskDuration::Minutes(m) => vec![Unblocked {
+            task,
+            subtask: None,
+            minutes_left: *m as i64,
+        }],
+        TaskDuration::Subtasks(subtasks) => subtasks
+            .iter()
+            .map(|s| Unblocked {
+                task,
+                subtask: Some(s.id),
+                minutes_left: s.duration as i64,
+            })
+            .collect(),
+    }
+}
+
+/// Fills the free time in `gap` with blocks of `work`, in order. A block is as long as it can be,
+/// up to `longest` minutes. Blocks shorter than `shortest` minutes are only made when they finish
+/// something.
+pub(crate) fn fill(
+    work: &mut [Unblocked],
+    blocks: &mut Vec<PomodoroScheduleEntry>,
+    (start, end): (DateTime<Local>, DateTime<Local>),
+    longest: i64,
+    shortest: i64,
+) {
+    let mut cursor = start;
+    loop {
+        let free = (end - cursor).num_minutes();
+        let next = (0..work.len())
+            .find_map(|i| fit(work, i, cursor, free, longest, shortest).map(|m| (i, m)));
+        match next {
+            Some((i, minutes)) => cursor = add_block(work, i, cursor, minutes, blocks),
+            None => return,
+        }
+    }
+}
+
+/// Returns how long a block of `work[i]` starting at `start` would be, or `None` if it can't have
+/// one there. See `fill`.
+pub(crate) fn fit(
+    work: &[Unblocked],
+    i: usize,
+    start: DateTime<Local>,
+    free: i64,
+    longest: i64,
+    shortest: i64,
+) -> Option<i64> {
+    let w = &work[i];
+    let minutes = block_minutes(w, start, free, longest);
+    let fits = w.minutes_left > 0
+        && minutes > 0
+        && (minutes >= shortest || minutes == w.minutes_left)
+        && !waiting(work, w.task)
+        && !w.task.is_deferred(&start);
+    fits.then_some(minutes)
+}
+
+/// Gives `work[i]` a block of `minutes` starting at `start`, returning when the block ends.
+pub(crate) fn add_block(
+    work: &mut [Unblocked],
+    i: usize,
+    start: DateTime<Local>,
+    minutes: i64,
+    blocks: &mut Vec<PomodoroScheduleEntry>,
+) -> DateTime<Local> {
+    blocks.push(PomodoroScheduleEntry::Job {
+        task_id: work[i].task.id,
+        subtask: work[i].subtask,
+        status: Default::default(),
+        span: TimeSpan::new(start, minutes as u32),
+    });
+    work[i].minutes_left -= minutes;
+    start + Duration::minutes(minutes)
+}
+
+/// Returns how long a block of `w` starting at `start` can be, with `free` minutes available.
+fn block_minutes(w: &Unblocked, start: DateTime<Local>, free: i64, longest: i64) -> i64 {
+    let until_due = (w.task.due_date - start).num_minutes();
+    w.minutes_left.min(longest).min(free).min(until_due)
+}
+
+/// Returns true if `task` depends on a task that still has work without a block.
+fn waiting(work: &[Unblocked], task: &Task) -> bool {
+    work.iter()
+        .any(|w| w.minutes_left > 0 && task.depends_on(w.task.id) && w.task.id != task.id)
+}
+
+fn title(task: &Task, subtask: Option<SubtaskId>) -> String {
+    match subtask.and_then(|i| task.subtask(i)) {
+        Some(s) => format!("{}: {}", task.name, s.name),
+        None => task.name.clone(),
+    }
+}
+
+pub struct TimeBlockDisplay<'a> {
+    schedule: &'a TimeBlockSchedule,
+    config: &'a Config,
+    tasks: &'a [Task],
+}
+
+/// Returns the entries that aren't over yet.
+pub(crate) fn upcoming(
+    entries: &[PomodoroScheduleEntry],
+) -> impl Iterator<Item = &PomodoroScheduleEntry> {
+    let now = Local::now();
+    entries.iter().filter(move |e| e.span().end() >= now)
+}
+
+/// Writes `entries` one per line, with their start and end times.
+pub(crate) fn write_entries<'e>(
+    f: &mut Formatter<'_>,
+    entries: impl Iterator<Item = &'e PomodoroScheduleEntry>,
+    config: &Config,
+    tasks: &[Task],
+) -> std::fmt::Result {
+    let format = format!("{} {}", config.date_format, config.time_format);
+    for e in entries {
+        write!(
+            f,
+            "{} - {} :: {}",
+            e.span().start().format(&format),
+            e.span().end().format(&config.time_format),
+            e.title(tasks)
+        )?;
+        if let Some(notes) = e.notes(tasks) {
+            write!(f, " - {}", task::summarize_notes(notes))?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+impl Serialize for TimeBlockDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(upcoming(&self.schedule.entries).map(|e| e.view(self.tasks)))
+    }
+}
+
+impl Display for TimeBlockDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_entries(f, upcoming(&self.schedule.entries), self.config, self.tasks)
+    }
+}
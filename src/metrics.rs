@@ -0,0 +1,184 @@
+use crate::{Config, Task};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Measurements of how well a schedule fits the tasks it was made for, so that different
+/// scheduling methods can be compared.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ScheduleMetrics {
+    /// Total minutes of work estimated for all unfinished tasks.
+    pub requested_minutes: u64,
+
+    /// Total minutes of work that made it into the schedule.
+    pub scheduled_minutes: u64,
+
+    /// How broken up tasks are, from 0 (every task is worked on in one sitting) to 1 (no two
+    /// pieces of the same task are next to each other).
+    pub fragmentation: f64,
+
+    /// Total weight of tasks that aren't completely scheduled or that finish late.
+    pub points_at_risk: f64,
+
+    /// Metrics for each unfinished task.
+    pub tasks: Vec<TaskMetrics>,
+}
+
+impl ScheduleMetrics {
+    /// Sums up the metrics of individual tasks.
+    pub fn new(tasks: Vec<TaskMetrics>) -> Self {
+        let worked_tasks: Vec<&TaskMetrics> = tasks.iter().filter(|t| t.pieces > 0).collect();
+        let fragmentation = if worked_tasks.is_empty() {
+            0.0
+        } else {
+            worked_tasks.iter().map(|t| t.fragmentation()).sum::<f64>() / worked_tasks.len() as f64
+        };
+
+        Self {
+            requested_minutes: tasks.iter().map(|t| t.requested_minutes).sum(),
+            scheduled_minutes: tasks.iter().map(|t| t.scheduled_minutes).sum(),
+            fragmentation,
+            points_at_risk: tasks
+                .iter()
+                .filter(|t| t.is_at_risk())
+                .map(|t| t.weight)
+                .sum(),
+            tasks,
+        }
+    }
+
+    pub fn display<'a>(&'a self, config: &'a Config) -> MetricsDisplay<'a> {
+        MetricsDisplay {
+            metrics: self,
+            config,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TaskMetrics {
+    pub name: String,
+    pub due_date: DateTime<Local>,
+    pub requested_minutes: u64,
+    pub scheduled_minutes: u64,
+    pub weight: f64,
+
+    /// When the last piece of work for the task ends, if any work was scheduled.
+    pub latest_finish: Option<DateTime<Local>>,
+
+    /// Minutes between the latest finish and the due date. Negative if the task finishes late.
+    pub slack_minutes: Option<i64>,
+
+    /// How many separate blocks of time the task is split into.
+    pub blocks: u32,
+
+    #[serde(skip)]
+    pieces: u32,
+}
+
+impl TaskMetrics {
+    pub fn new(task: &Task) -> Self {
+        Self {
+            name: task.name.clone(),
+            due_date: task.due_date,
+            requested_minutes: task.duration.total_minutes(),
+            scheduled_minutes: 0,
+            weight: task.weight,
+            latest_finish: None,
+            slack_minutes: None,
+            blocks: 0,
+            pieces: 0,
+        }
+    }
+
+    /// Records a piece of scheduled work for the task. `continues_block` should be true if the
+    /// piece directly follows the previous piece of this task, with no other work in between.
+    pub fn add_piece(&mut self, end: DateTime<Local>, minutes: u64, continues_block: bool) {
+        self.scheduled_minutes += minutes;
+        self.pieces += 1;
+        if !continues_block || self.blocks == 0 {
+            self.blocks += 1;
+        }
+
+        if self.latest_finish < Some(end) {
+            self.latest_finish = Some(end);
+            self.slack_minutes = Some((self.due_date - end).num_minutes());
+        }
+    }
+
+    /// Returns true if some of the task isn't scheduled, or if it finishes late.
+    pub fn is_at_risk(&self) -> bool {
+        self.scheduled_minutes < self.requested_minutes
+            || matches!(self.slack_minutes, Some(s) if s < 0)
+    }
+
+    fn fragmentation(&self) -> f64 {
+        if self.pieces <= 1 {
+            0.0
+        } else {
+            (self.blocks - 1) as f64 / (self.pieces - 1) as f64
+        }
+    }
+}
+
+pub struct MetricsDisplay<'a> {
+    metrics: &'a ScheduleMetrics,
+    config: &'a Config,
+}
+
+impl Display for MetricsDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let m = self.metrics;
+        let percent = if m.requested_minutes == 0 {
+            100.0
+        } else {
+            m.scheduled_minutes as f64 / m.requested_minutes as f64 * 100.0
+        };
+        writeln!(
+            f,
+            "Scheduled {} of {} requested minutes ({:.0}%)",
+            m.scheduled_minutes, m.requested_minutes, percent
+        )?;
+        writeln!(f, "Fragmentation: {:.2}", m.fragmentation)?;
+        if m.points_at_risk > 0.0 {
+            writeln!(f, "Points at risk: {}", m.points_at_risk)?;
+        }
+
+        let format = format!("{} {}", self.config.date_format, self.config.time_format);
+        for t in &m.tasks {
+            write!(
+                f,
+                "-\t{}: {}/{} minutes",
+                t.name, t.scheduled_minutes, t.requested_minutes
+            )?;
+            if let (Some(finish), Some(slack)) = (t.latest_finish, t.slack_minutes) {
+                write!(f, ", finishes {}", finish.format(&format))?;
+                if slack < 0 {
+                    write!(f, " ({} late)", format_minutes(-slack))?;
+                } else {
+                    write!(f, " ({} to spare)", format_minutes(slack))?;
+                }
+            }
+            if t.blocks > 1 {
+                write!(f, ", in {} blocks", t.blocks)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats minutes like "2d 3h 15m".
+pub fn format_minutes(minutes: i64) -> String {
+    let days = minutes / (60 * 24);
+    let hours = minutes / 60 % 24;
+    let minutes = minutes % 60;
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
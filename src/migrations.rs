@@ -0,0 +1,404 @@
+//! Upgrades data written by older versions of sparrow. Data files carry the version of their
+//! layout; before a file is read into `UserData`, each migration from its version up to
+//! `CURRENT_VERSION` is run over it in order, so that old files load instead of failing with a
+//! serde error.
+//!
+//! To change the layout in a way old files can't be read with (`#[serde(default)]` covers new
+//! fields), add a migration to the end of `MIGRATIONS`.
+
+use crate::{SparrowError, SparrowResult};
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Upgrades data from one version to the next. `MIGRATIONS[n]` upgrades version n to n + 1.
+type Migration = fn(&mut Map<String, Value>) -> SparrowResult<()>;
+
+const MIGRATIONS: [Migration; 3] = [split_weekday_repeats, stable_ids, job_task_ids];
+
+/// The version of the layout this sparrow writes.
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// The layout version of a data file. Files from before versions were kept are version 0.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct SchemaVersion(pub u32);
+
+impl Default for SchemaVersion {
+    /// New data is written in the current layout.
+    fn default() -> Self {
+        Self(CURRENT_VERSION)
+    }
+}
+
+/// Upgrades `data`, a whole data file, to the current layout.
+pub fn upgrade(mut data: Value) -> SparrowResult<Value> {
+    let object = data.as_object_mut().ok_or_else(|| {
+        SparrowError::BasicMessage("the data file doesn't look like sparrow data".to_string())
+    })?;
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > CURRENT_VERSION {
+        return Err(SparrowError::BasicMessage(format!(
+            "the data file was written by a newer sparrow (layout version {}, but this sparrow \
+             only knows up to {}); update sparrow to read it",
+            version, CURRENT_VERSION
+        )));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(object)?;
+    }
+    object.insert("version".to_string(), Value::from(CURRENT_VERSION));
+    Ok(data)
+}
+
+/// Events used to repeat weekly on a set of weekdays, like `Weekly: [Mon, Wed]`. Now an event
+/// repeats on one day of the week, so each of those becomes one weekly event per day, starting on
+/// the first of that day on or after the original start.
+fn split_weekday_repeats(data: &mut Map<String, Value>) -> SparrowResult<()> {
+    let events = match data.get_mut("events").and_then(Value::as_array_mut) {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+
+    let mut upgraded = Vec::new();
+    for event in events.drain(..) {
+        let days = match event.pointer("/repeat/Weekly").and_then(Value::as_array) {
+            Some(days) => days.clone(),
+            None => {
+                upgraded.push(event);
+                continue;
+            }
+        };
+        let start: DateTime<Local> = event
+            .pointer("/time_span/start")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .ok_or_else(|| {
+                SparrowError::BasicMessage("an event in the data file has no start".to_string())
+            })?;
+
+        let days: Vec<Weekday> = days
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?;
+        if days.is_empty() {
+            // it never actually repeated
+            let mut event = event;
+            event["repeat"] = Value::from("No");
+            upgraded.push(event);
+            continue;
+        }
+
+        for day in days {
+            let mut event = event.clone();
+            event["repeat"] = Value::from("Weekly");
+            event["time_span"]["start"] = serde_json::to_value(next_on_weekday(start, day))?;
+            upgraded.push(event);
+        }
+    }
+
+    *events = upgraded;
+    Ok(())
+}
+
+/// Subtasks used to be referred to by their place in their task, and Ivy Lee lists by their
+/// tasks' names, like "Finish Essay". Now subtasks get IDs (their old place, counting from 1), and
+/// lists refer to tasks by ID.
+fn stable_ids(data: &mut Map<String, Value>) -> SparrowResult<()> {
+    // tasks need IDs to be referred to by
+    let tasks = data
+        .get_mut("tasks")
+        .and_then(Value::as_array_mut)
+        .map(std::mem::take)
+        .unwrap_or_default();
+    let mut next_id = tasks
+        .iter()
+        .filter_map(|t| t.get("id").and_then(Value::as_u64))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut ids_by_name = Map::new();
+    let mut upgraded = Vec::new();
+    for mut task in tasks {
+        let id = match task.get("id").and_then(Value::as_u64) {
+            Some(id) if id != 0 => id,
+            _ => {
+                let id = next_id;
+                next_id += 1;
+                id
+            }
+        };
+        task["id"] = Value::from(id);
+        if let Some(subtasks) = task
+            .pointer_mut("/duration/Subtasks")
+            .and_then(Value::as_array_mut)
+        {
+            for (i, subtask) in subtasks.iter_mut().enumerate() {
+                subtask["id"] = Value::from(i as u64 + 1);
+            }
+        }
+        if let Some(name) = task.get("name").and_then(Value::as_str) {
+            ids_by_name
+                .entry(name.to_string())
+                .or_insert_with(|| Value::from(id));
+        }
+        upgraded.push(task);
+    }
+    data.insert("tasks".to_string(), Value::from(upgraded));
+    let saved_next_id = data
+        .get("next_task_id")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    data.insert(
+        "next_task_id".to_string(),
+        Value::from(saved_next_id.max(next_id)),
+    );
+
+    for value in data.values_mut() {
+        shift_subtask_indexes(value);
+    }
+
+    if let Some(days) = data
+        .get_mut("ivy_lee_schedule")
+        .and_then(|s| s.get_mut("task_days"))
+        .and_then(Value::as_object_mut)
+    {
+        for list in days.values_mut() {
+            let entries = list.as_array().cloned().unwrap_or_default();
+            *list = entries
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(|entry| ivy_lee_entry(entry, &ids_by_name))
+                .collect();
+        }
+    }
+
+    Ok(())
+}
+
+/// Pomodoro jobs used to copy their task's title, like "Essay" or "Essay: Outline", and now refer
+/// to the task (and subtask) by ID instead. Jobs whose title matches no task anymore are left for
+/// a deleted task.
+fn job_task_ids(data: &mut Map<String, Value>) -> SparrowResult<()> {
+    let tasks = data
+        .get("tasks")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let mut titles = Vec::new();
+    for task in &tasks {
+        let (id, name) = match (task.get("id"), task.get("name").and_then(Value::as_str)) {
+            (Some(id), Some(name)) => (id, name),
+            _ => continue,
+        };
+        titles.push((name.to_string(), id.clone(), Value::Null));
+        let subtasks = task
+            .pointer("/duration/Subtasks")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten();
+        for subtask in subtasks {
+            if let (Some(sub_id), Some(sub_name)) = (
+                subtask.get("id"),
+                subtask.get("name").and_then(Value::as_str),
+            ) {
+                titles.push((
+                    format!("{}: {}", name, sub_name),
+                    id.clone(),
+                    sub_id.clone(),
+                ));
+            }
+        }
+    }
+
+    if let Some(entries) = data
+        .get_mut("pomodoro_schedule")
+        .and_then(|s| s.get_mut("entries"))
+        .and_then(Value::as_array_mut)
+    {
+        entries
+            .iter_mut()
+            .for_each(|entry| job_task_id(entry, &titles));
+    }
+    if let Some(history) = data.get_mut("job_history").and_then(Value::as_array_mut) {
+        history
+            .iter_mut()
+            .for_each(|entry| job_task_id(entry, &titles));
+    }
+
+    Ok(())
+}
+
+/// Replaces the title of `entry`, if it's a job with one, with the IDs of the task and subtask it
+/// names. `titles` holds every task's and subtask's title with their IDs. Jobs that already know
+/// their task only look among that task's titles, in case another task has the same name.
+fn job_task_id(entry: &mut Value, titles: &[(String, Value, Value)]) {
+    let job = match entry.get_mut("Job").and_then(Value::as_object_mut) {
+        Some(job) => job,
+        None => return,
+    };
+    let title = match job.remove("title") {
+        Some(Value::String(title)) => title,
+        _ => return,
+    };
+    let known = job
+        .get("task_id")
+        .and_then(Value::as_u64)
+        .filter(|&id| id != 0);
+    let found = titles
+        .iter()
+        .find(|(t, id, _)| *t == title && known.is_none_or(|k| id.as_u64() == Some(k)));
+    if let Some((_, task_id, subtask)) = found {
+        job.insert("task_id".to_string(), task_id.clone());
+        job.insert("subtask".to_string(), subtask.clone());
+    }
+}
+
+/// Adds 1 to every subtask index in `value`, making it the subtask's ID.
+fn shift_subtask_indexes(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value.as_u64() {
+                    Some(index) if key == "subtask" => *value = Value::from(index + 1),
+                    _ => shift_subtask_indexes(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(shift_subtask_indexes),
+        _ => {}
+    }
+}
+
+/// Reads an old Ivy Lee list entry, like "Finish Essay" or "1/3 of remaining Essay", into one
+/// that refers to the task by ID. Entries for tasks that are gone are dropped.
+fn ivy_lee_entry(entry: &str, ids_by_name: &Map<String, Value>) -> Option<Value> {
+    let (days_left, name) = match entry.strip_prefix("Finish ") {
+        Some(name) => (1, name),
+        None => {
+            let (days, name) = entry.strip_prefix("1/")?.split_once(" of remaining ")?;
+            (days.parse::<u64>().ok()?, name)
+        }
+    };
+    let mut new_entry = Map::new();
+    new_entry.insert("task_id".to_string(), ids_by_name.get(name)?.clone());
+    new_entry.insert("days_left".to_string(), Value::from(days_left));
+    Some(Value::Object(new_entry))
+}
+
+/// Returns the first time on `day` (of the week), at the same time of day as `start`, on or after
+/// `start`.
+fn next_on_weekday(start: DateTime<Local>, day: Weekday) -> DateTime<Local> {
+    let days_ahead = (day.num_days_from_monday() + 7 - start.weekday().num_days_from_monday()) % 7;
+    let naive = start.naive_local() + Duration::days(days_ahead as i64);
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| start + Duration::days(days_ahead as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        Local.ymd(2024, 5, day).and_hms(hour, 0, 0)
+    }
+
+    /// A version 0 event starting on Wednesday, May 1st 2024 at 9:00, repeating on `days`.
+    fn weekday_event(days: Value) -> Value {
+        json!({
+            "name": "Class",
+            "time_span": { "start": at(1, 9), "minutes": 60 },
+            "event_type": "Other",
+            "repeat": { "Weekly": days },
+        })
+    }
+
+    fn start_of(event: &Value) -> DateTime<Local> {
+        serde_json::from_value(event["time_span"]["start"].clone()).unwrap()
+    }
+
+    #[test]
+    fn splits_events_repeating_on_weekdays() {
+        let data = json!({ "events": [weekday_event(json!(["Mon", "Wed"]))] });
+        let upgraded = upgrade(data).unwrap();
+
+        let events = upgraded["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e["repeat"] == "Weekly"));
+        assert_eq!(start_of(&events[0]), at(6, 9));
+        assert_eq!(start_of(&events[1]), at(1, 9));
+        assert_eq!(upgraded["version"], CURRENT_VERSION);
+    }
+
+    #[test]
+    fn events_repeating_on_no_days_do_not_repeat() {
+        let mut data = Map::new();
+        data.insert("events".to_string(), json!([weekday_event(json!([]))]));
+        split_weekday_repeats(&mut data).unwrap();
+
+        let events = data["events"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["repeat"], "No");
+        assert_eq!(start_of(&events[0]), at(1, 9));
+    }
+
+    #[test]
+    fn rejects_data_from_a_newer_sparrow() {
+        assert!(upgrade(json!({ "version": CURRENT_VERSION + 1 })).is_err());
+        assert!(upgrade(json!([])).is_err());
+    }
+
+    #[test]
+    fn reads_old_ivy_lee_entries() {
+        let mut ids_by_name = Map::new();
+        ids_by_name.insert("Essay".to_string(), Value::from(3));
+
+        assert_eq!(
+            ivy_lee_entry("Finish Essay", &ids_by_name),
+            Some(json!({ "task_id": 3, "days_left": 1 }))
+        );
+        assert_eq!(
+            ivy_lee_entry("1/4 of remaining Essay", &ids_by_name),
+            Some(json!({ "task_id": 3, "days_left": 4 }))
+        );
+        assert_eq!(ivy_lee_entry("Finish Taxes", &ids_by_name), None);
+        assert_eq!(ivy_lee_entry("Essay", &ids_by_name), None);
+    }
+
+    #[test]
+    fn finds_the_tasks_of_old_job_titles() {
+        let job = |title: &str| json!({ "Job": { "title": title, "span": {} } });
+        let data = json!({
+            "tasks": [
+                { "name": "Essay", "duration": { "Subtasks": [{ "name": "Outline" }] } },
+                { "name": "Taxes", "duration": { "Minutes": 60 } },
+            ],
+            "pomodoro_schedule": {
+                "entries": [job("Essay: Outline"), job("Taxes"), job("Gone")],
+            },
+        });
+        let upgraded = upgrade(data).unwrap();
+
+        let jobs: Vec<&Value> = upgraded["pomodoro_schedule"]["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| &e["Job"])
+            .collect();
+        assert!(jobs.iter().all(|j| j.get("title").is_none()));
+        assert_eq!(
+            (&jobs[0]["task_id"], &jobs[0]["subtask"]),
+            (&json!(1), &json!(1))
+        );
+        assert_eq!(
+            (&jobs[1]["task_id"], &jobs[1]["subtask"]),
+            (&json!(2), &Value::Null)
+        );
+        assert_eq!(jobs[2].get("task_id"), None);
+    }
+}
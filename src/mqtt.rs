@@ -0,0 +1,92 @@
+//! Just enough of MQTT 3.1.1 to publish a message: connect, publish once (QoS 0), and
+//! disconnect. sparrowd only publishes when the current entry changes, so a connection per
+//! message is plenty.
+
+use crate::{MqttConfig, SparrowError, SparrowResult};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const DISCONNECT: u8 = 0xe0;
+
+const RETAIN: u8 = 0x01;
+const CLEAN_SESSION: u8 = 0x02;
+const PASSWORD_FLAG: u8 = 0x40;
+const USERNAME_FLAG: u8 = 0x80;
+
+/// Publishes `payload` to the topic in `config`, retained so that anything subscribing later
+/// still gets the latest message.
+pub fn publish(config: &MqttConfig, payload: &[u8]) -> SparrowResult<()> {
+    let mut stream = TcpStream::connect((config.host.as_str(), config.port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    // connect
+    let mut flags = CLEAN_SESSION;
+    let mut connect = Vec::new();
+    write_string(&mut connect, "MQTT");
+    connect.push(4); // protocol level 4 is MQTT 3.1.1
+    if !config.username.is_empty() {
+        flags |= USERNAME_FLAG;
+        if !config.password.is_empty() {
+            flags |= PASSWORD_FLAG;
+        }
+    }
+    connect.push(flags);
+    connect.extend_from_slice(&60u16.to_be_bytes()); // keep alive, in seconds
+    write_string(&mut connect, &config.client_id);
+    if flags & USERNAME_FLAG != 0 {
+        write_string(&mut connect, &config.username);
+    }
+    if flags & PASSWORD_FLAG != 0 {
+        write_string(&mut connect, &config.password);
+    }
+    write_packet(&mut stream, CONNECT, &connect)?;
+
+    let mut connack = [0; 4];
+    stream.read_exact(&mut connack)?;
+    if connack[0] != CONNACK || connack[3] != 0 {
+        return Err(SparrowError::BasicMessage(format!(
+            "the mqtt broker refused the connection (return code {})",
+            connack[3]
+        )));
+    }
+
+    // publish
+    let mut publish = Vec::new();
+    write_string(&mut publish, &config.topic);
+    publish.extend_from_slice(payload);
+    write_packet(&mut stream, PUBLISH | RETAIN, &publish)?;
+
+    write_packet(&mut stream, DISCONNECT, &[])?;
+    Ok(())
+}
+
+fn write_packet(stream: &mut TcpStream, header: u8, body: &[u8]) -> SparrowResult<()> {
+    let mut packet = vec![header];
+
+    // the remaining length is written seven bits at a time, lowest first
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+
+    packet.extend_from_slice(body);
+    stream.write_all(&packet)?;
+    Ok(())
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
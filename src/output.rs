@@ -0,0 +1,80 @@
+//! Whether sparrow's output is meant for people or for other programs. In JSON mode, warnings are
+//! written to stderr as one JSON object per line, so they don't get in the way of output on
+//! stdout.
+
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The environment variable that turns on JSON output when it's set to "json".
+pub const OUTPUT_VAR: &str = "SPARROW_OUTPUT";
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Turns JSON output on or off.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::SeqCst);
+}
+
+/// Returns true if output should be JSON.
+pub fn is_json() -> bool {
+    JSON.load(Ordering::SeqCst)
+}
+
+/// Returns true if output can have colors: stdout is a terminal and `NO_COLOR` isn't set (see
+/// https://no-color.org).
+pub fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Returns true if `OUTPUT_VAR` asks for JSON output.
+pub fn json_from_env() -> bool {
+    std::env::var(OUTPUT_VAR).is_ok_and(|v| v.eq_ignore_ascii_case("json"))
+}
+
+#[derive(Serialize)]
+struct Warning<'a> {
+    warning: &'a str,
+    items: &'a [String],
+    note: Option<&'a str>,
+}
+
+/// Warns about something that went wrong with a list of what it affected, like tasks that
+/// couldn't be scheduled, and an optional note.
+pub fn warn(message: &str, items: &[String], note: Option<&str>) {
+    if is_json() {
+        let warning = Warning {
+            warning: message,
+            items,
+            note,
+        };
+        if let Ok(line) = serde_json::to_string(&warning) {
+            eprintln!("{}", line);
+        }
+        return;
+    }
+
+    eprintln!("warning: {}:", message);
+    for i in items {
+        eprintln!("\t{}", i);
+    }
+    if let Some(note) = note {
+        eprintln!("note: {}", note);
+    }
+
+    // extra line
+    eprintln!();
+}
+
+/// Returns how many columns wide the terminal is, from `COLUMNS` or else the terminal itself.
+/// Assumes 80 if neither works, like when output goes to a file.
+pub fn terminal_width() -> usize {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return columns;
+    }
+
+    match crossterm::terminal::size() {
+        Ok((columns, _)) if columns > 0 => columns as usize,
+        _ => 80,
+    }
+}
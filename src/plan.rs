@@ -0,0 +1,190 @@
+//! The weekly planning ritual, as a short interactive walk-through: deal with overdue tasks, look
+//! back at finished ones, check estimates, and choose days off.
+
+use crate::{
+    prompts::*,
+    task::{parse_duration_minutes, TaskDuration},
+    Formatting, Repeat, SparrowError, SparrowResult, TaskId, UserData,
+};
+use chrono::{Local, Weekday};
+use std::collections::HashSet;
+
+/// Walks the user through planning their week. Making the new schedules is up to the caller.
+pub fn review(formatting: &Formatting, data: &mut UserData) -> SparrowResult<()> {
+    review_overdue(formatting, data)?;
+    review_finished(formatting, data);
+    review_estimates(formatting, data)?;
+    choose_skip_days(formatting, data)?;
+    Ok(())
+}
+
+enum OverdueChoice {
+    Done,
+    Reschedule,
+    Keep,
+}
+
+fn review_overdue(formatting: &Formatting, data: &mut UserData) -> SparrowResult<()> {
+    let now = Local::now();
+    let overdue: Vec<TaskId> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| !t.done && t.repeat == Repeat::No && t.is_past_due(&now))
+        .map(|t| t.id)
+        .collect();
+    if overdue.is_empty() {
+        println!("Nothing is overdue.");
+        return Ok(());
+    }
+
+    println!("{}", formatting.prompt.paint("These tasks are overdue:"));
+    for id in overdue {
+        let config = data.get_config();
+        let (date_format, time_format) = (config.date_format.clone(), config.time_format.clone());
+        let task = data.get_task_mut(id).unwrap();
+
+        let question = format!(
+            "{} (was due {})",
+            task.name,
+            task.due_date
+                .format(&format!("{} {}", date_format, time_format))
+        );
+        let choice = prompt_strict(
+            formatting,
+            &question,
+            Some("[d]one, [r]eschedule, [K]eep"),
+            None,
+            |i| match i.trim().to_lowercase().as_str() {
+                "" | "k" | "keep" => Ok(OverdueChoice::Keep),
+                "d" | "done" => Ok(OverdueChoice::Done),
+                "r" | "reschedule" => Ok(OverdueChoice::Reschedule),
+                _ => Err(SparrowError::BasicMessage(
+                    "Enter 'done', 'reschedule', or 'keep'".to_string(),
+                )),
+            },
+        )?;
+
+        match choice {
+            OverdueChoice::Done => task.done = true,
+            OverdueChoice::Reschedule => {
+                println!("{}", formatting.prompt.paint("When is it due now?"));
+                task.due_date = prompt_datetime(formatting, &date_format, &time_format, true)?;
+            }
+            OverdueChoice::Keep => {}
+        }
+    }
+
+    println!();
+    Ok(())
+}
+
+fn review_finished(formatting: &Formatting, data: &UserData) {
+    // tasks don't remember when they were finished, so show the ones due in the last week
+    let week_ago = Local::now() - chrono::Duration::days(7);
+    let finished: Vec<&str> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| t.done && t.due_date >= week_ago)
+        .map(|t| t.name.as_str())
+        .collect();
+
+    if !finished.is_empty() {
+        println!("{}", formatting.prompt.paint("Finished lately, nice work:"));
+        for name in finished {
+            println!("-\t{}", name);
+        }
+        println!();
+    }
+}
+
+fn review_estimates(formatting: &Formatting, data: &mut UserData) -> SparrowResult<()> {
+    let unfinished: Vec<TaskId> = data
+        .get_tasks()
+        .iter()
+        .filter(|t| !t.done)
+        .map(|t| t.id)
+        .collect();
+    if unfinished.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        formatting
+            .prompt
+            .paint("Do these estimates still look right? Enter a new one, or nothing to keep it.")
+    );
+    for id in unfinished {
+        let task = data.get_task_mut(id).unwrap();
+        match &mut task.duration {
+            TaskDuration::Minutes(minutes) => {
+                *minutes = prompt_estimate(formatting, &task.name, *minutes)?;
+            }
+            TaskDuration::Subtasks(subtasks) => {
+                for s in subtasks {
+                    let name = format!("{}: {}", task.name, s.name);
+                    s.duration = prompt_estimate(formatting, &name, s.duration)?;
+                }
+            }
+        }
+    }
+
+    println!();
+    Ok(())
+}
+
+fn prompt_estimate(formatting: &Formatting, name: &str, minutes: u64) -> SparrowResult<u64> {
+    prompt_strict(
+        formatting,
+        &format!("{} ({} minutes)", name, minutes),
+        Some("like 90 or 1h30m"),
+        None,
+        |i| {
+            if i.trim().is_empty() {
+                Ok(minutes)
+            } else {
+                parse_duration_minutes(i).ok_or_else(|| {
+                    SparrowError::BasicMessage("Enter a length of time, like 45m or 2h".to_string())
+                })
+            }
+        },
+    )
+}
+
+fn choose_skip_days(formatting: &Formatting, data: &mut UserData) -> SparrowResult<()> {
+    let config = data.get_config_mut();
+    let mut current: Vec<Weekday> = config.skip_days.iter().copied().collect();
+    current.sort_by_key(|d| d.num_days_from_monday());
+    let current: Vec<String> = current.iter().map(|d| d.to_string()).collect();
+    let current = if current.is_empty() {
+        "none".to_string()
+    } else {
+        current.join(", ")
+    };
+
+    config.skip_days = prompt_strict(
+        formatting,
+        &format!("Which days do you want off? (now: {})", current),
+        Some("like 'sat, sun', 'none', or nothing to keep"),
+        None,
+        |i| -> SparrowResult<HashSet<Weekday>> {
+            let i = i.trim().to_lowercase();
+            if i.is_empty() {
+                Ok(config.skip_days.clone())
+            } else if i == "none" {
+                Ok(HashSet::new())
+            } else {
+                i.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|d| !d.is_empty())
+                    .map(|d| {
+                        d.parse::<Weekday>().map_err(|_| {
+                            SparrowError::BasicMessage(format!("'{}' isn't a day of the week", d))
+                        })
+                    })
+                    .collect()
+            }
+        },
+    )?;
+
+    Ok(())
+}
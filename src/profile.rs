@@ -0,0 +1,75 @@
+//! Named profiles, like "work" and "personal", so that separate sets of tasks (each with their own
+//! config) don't have to be juggled with `--file`. A profile is a data file in
+//! `~/.config/sparrow/`, named after the profile, like `~/.config/sparrow/work.yaml`.
+
+use crate::{SparrowError, SparrowResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that picks a profile when `--profile` isn't given.
+pub const PROFILE_VAR: &str = "SPARROW_PROFILE";
+
+/// Extensions a profile's data file can have. A new profile is YAML.
+const EXTENSIONS: [&str; 5] = ["yaml", "yml", "json", "toml", "db"];
+
+/// Files in the profile directory that aren't profiles.
+const NOT_PROFILES: [&str; 2] = ["config.yaml", "gcal_token.json"];
+
+/// Returns the directory profiles are kept in.
+pub fn dir() -> SparrowResult<PathBuf> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| {
+            SparrowError::BasicMessage(
+                "there's no config directory to keep profiles in".to_string(),
+            )
+        })?
+        .join("sparrow"))
+}
+
+/// Returns the data file of the profile called `name`, which may not exist yet. The profile
+/// directory is made if it doesn't exist, so the data file can be written.
+pub fn data_path(name: &str) -> SparrowResult<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(SparrowError::BasicMessage(format!(
+            "'{}' can't be a profile name; profile names are used as file names",
+            name
+        )));
+    }
+
+    let dir = dir()?;
+    fs::create_dir_all(&dir)?;
+    let existing = EXTENSIONS
+        .iter()
+        .map(|e| dir.join(format!("{}.{}", name, e)))
+        .find(|p| p.exists());
+    Ok(existing.unwrap_or_else(|| dir.join(format!("{}.yaml", name))))
+}
+
+/// Returns the name and data file of every profile, in order of name.
+pub fn list() -> SparrowResult<Vec<(String, PathBuf)>> {
+    let dir = dir()?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut profiles = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_profile(&path) {
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                profiles.push((name.to_string(), path.clone()));
+            }
+        }
+    }
+    profiles.sort();
+    Ok(profiles)
+}
+
+fn is_profile(path: &Path) -> bool {
+    let known_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e));
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    known_extension && !NOT_PROFILES.contains(&name)
+}
@@ -1,51 +1,109 @@
 use chrono::prelude::*;
 
 use crate::errors::SparrowResult;
-use crate::{Formatting, SparrowError};
-use std::io::{stdin, stdout, Write};
+use crate::{dates, Formatting, SparrowError};
+use crossterm::terminal;
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
 
+/// Prompts the user for an input. If there's a `default`, it's what an empty input means. On a
+/// terminal, the default is already typed out to be edited, and elsewhere it's shown in brackets.
 pub fn prompt(
     formatting: &Formatting,
     prompt: &str,
     prompt_format: Option<&str>,
+    default: Option<&str>,
 ) -> Result<String, SparrowError> {
+    let terminal = stdin().is_terminal();
+    print!("{}", formatting.prompt.paint(prompt));
     if let Some(f) = prompt_format {
-        print!(
-            "{} ({})  ",
-            formatting.prompt.paint(prompt),
-            formatting.prompt_format.paint(f)
-        );
-    } else {
-        print!("{}  ", formatting.prompt.paint(prompt));
+        print!(" ({})", formatting.prompt_format.paint(f));
+    }
+    match default {
+        Some(d) if !terminal => print!(" [{}]  ", d),
+        _ => print!("  "),
+    }
+
+    let input = match default {
+        Some(d) if terminal => edit_line(d)?,
+        _ => get_input()?,
+    };
+    Ok(or_default(input, default))
+}
+
+/// Returns `default` instead of `input` if `input` is empty.
+fn or_default(input: String, default: Option<&str>) -> String {
+    match default {
+        Some(d) if input.trim().is_empty() => d.to_string(),
+        _ => input,
     }
-    get_input()
 }
 
 /// Prompts the user for an input, but will prompt the user again if a condition isn't met,
 /// specified by `checker`. `checker` takes a string, the user's input, as input. If `checker`
 /// returns Ok, `prompt_strict` returns the value inside the Ok. If `checker` returns Err, the
 /// prompt will display the error, and ask for input again, over and over until `checker` returns
-/// an Ok.
+/// an Ok. On a terminal, the input that didn't work is typed out again, to be fixed.
 pub fn prompt_strict<F, T, E>(
     formatting: &Formatting,
     prompt: &str,
     prompt_format: Option<&str>,
+    default: Option<&str>,
     checker: F,
 ) -> Result<T, SparrowError>
 where
     F: Fn(&str) -> Result<T, E>,
     E: std::error::Error,
 {
-    let mut input = self::prompt(formatting, prompt, prompt_format)?;
+    let mut input = self::prompt(formatting, prompt, prompt_format, default)?;
     loop {
         match checker(&input) {
             Ok(v) => return Ok(v),
             Err(e) => print!("{}. Try again?  ", e),
         }
-        input = get_input()?
+        input = if stdin().is_terminal() && !input.is_empty() {
+            edit_line(&input)?
+        } else {
+            get_input()?
+        };
+        input = or_default(input, default);
+    }
+}
+
+/// Prompts the user for any number of lines, until an empty line. Returns None if the first line
+/// is empty.
+pub fn prompt_lines(
+    formatting: &Formatting,
+    prompt: &str,
+    prompt_format: Option<&str>,
+) -> Result<Option<String>, SparrowError> {
+    let mut lines = Vec::new();
+    let mut line = self::prompt(formatting, prompt, prompt_format, None)?;
+    while !line.trim().is_empty() {
+        lines.push(line);
+        line = get_input()?;
+    }
+
+    if lines.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(lines.join("\n")))
     }
 }
 
+/// Splits the words starting with '#' (like "#school") out of `input`. Returns the rest of the
+/// input and the tags, without their '#'s.
+pub fn split_tags(input: &str) -> (String, Vec<String>) {
+    let mut tags = Vec::new();
+    let mut words = Vec::new();
+    for word in input.split_whitespace() {
+        match word.strip_prefix('#') {
+            Some(tag) if !tag.is_empty() => tags.push(tag.to_string()),
+            _ => words.push(word),
+        }
+    }
+    (words.join(" "), tags)
+}
+
 fn get_input() -> Result<String, SparrowError> {
     let mut s = String::new();
     stdout().flush()?;
@@ -106,32 +164,372 @@ pub fn prompt_datetime(
         time_format.to_string()
     };
 
-    let date = prompt_strict(formatting, "Date?", Some(&date_format), |i| {
-        NaiveDate::parse_from_str(i.trim(), date_format)
-    })?;
-    let time_opt = prompt_strict(formatting, "Time?", Some(&time_prompt_format), |i| {
-        if i.is_empty() && allow_midnight_on_empty {
-            Ok(None)
+    // a time can come with the date, like "tomorrow 5pm"
+    let date_prompt_format = format!("{}, or like \"tomorrow 5pm\"", date_format);
+    let (date, time_opt) =
+        prompt_strict(formatting, "Date?", Some(&date_prompt_format), None, |i| {
+            dates::parse_date_time(i, date_format, time_format, Local::now()).ok_or_else(|| {
+                SparrowError::BasicMessage(format!(
+                    "'{}' isn't a date sparrow understands",
+                    i.trim()
+                ))
+            })
+        })?;
+    let time_opt = match time_opt {
+        Some(time) => Some(time),
+        None => prompt_strict(formatting, "Time?", Some(&time_prompt_format), None, |i| {
+            if i.is_empty() && allow_midnight_on_empty {
+                Ok(None)
+            } else {
+                dates::parse_time(i, time_format).map(Some).ok_or_else(|| {
+                    SparrowError::BasicMessage(format!("'{}' isn't a time", i.trim()))
+                })
+            }
+        })?,
+    };
+
+    dates::to_local(date, time_opt)
+}
+
+/// Returns how well `query` matches `item`, or None if it doesn't. The letters of `query` have to
+/// show up in `item` in order, but not next to each other, so "lwr" matches "Lab: writeup".
+/// Letters in a row and letters starting words count for more.
+pub fn fuzzy_score(query: &str, item: &str) -> Option<i64> {
+    let item: Vec<char> = item.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut last: Option<usize> = None;
+    let mut from = 0;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = from + item[from..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == i) {
+            score += 5;
+        }
+        if i == 0 || !item[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(i);
+        from = i + 1;
+    }
+    Some(score)
+}
+
+/// Returns the indices of the `items` that match `query`, best match first.
+pub fn fuzzy_matches<S: AsRef<str>>(query: &str, items: &[S]) -> Vec<usize> {
+    let mut matches: Vec<(usize, i64)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((i, fuzzy_score(query, item.as_ref())?)))
+        .collect();
+    matches.sort_by_key(|&(i, score)| (-score, items[i].as_ref().len()));
+    matches.into_iter().map(|(i, _)| i).collect()
+}
+
+/// Returns the index of the item `query` means: the one named exactly that (ignoring case), or
+/// else the only one it matches.
+pub fn fuzzy_find<S: AsRef<str>>(query: &str, items: &[S]) -> SparrowResult<usize> {
+    let query = query.trim();
+    if let Some(i) = items
+        .iter()
+        .position(|item| item.as_ref().eq_ignore_ascii_case(query))
+    {
+        return Ok(i);
+    }
+
+    let matches = fuzzy_matches(query, items);
+    match matches.as_slice() {
+        [i] => Ok(*i),
+        [] => Err(SparrowError::BasicMessage(format!(
+            "nothing matches '{}'",
+            query
+        ))),
+        _ => {
+            let names: Vec<&str> = matches.iter().take(5).map(|&i| items[i].as_ref()).collect();
+            Err(SparrowError::BasicMessage(format!(
+                "'{}' could be {}. be more specific",
+                query,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+/// How many matches `prompt_select` shows at once.
+const SELECT_ROWS: usize = 8;
+
+/// Asks the user to pick from `items`, narrowing them down as they type. Up and down (or Ctrl-P
+/// and Ctrl-N) move the selection and Enter picks it. If `multiple` is true, Tab marks items, and
+/// Enter picks the marked ones (or the selected one, if none are marked). Escape cancels.
+///
+/// When stdin isn't a terminal, this reads a line of numbers or names, separated by commas if
+/// `multiple` is true, and matches them with `fuzzy_find`.
+pub fn prompt_select<S: AsRef<str>>(
+    formatting: &Formatting,
+    prompt: &str,
+    items: &[S],
+    multiple: bool,
+) -> SparrowResult<Vec<usize>> {
+    if !stdin().is_terminal() {
+        return prompt_select_lines(formatting, prompt, items, multiple);
+    }
+    in_raw_mode(|| select(formatting, prompt, items, multiple))
+}
+
+fn prompt_select_lines<S: AsRef<str>>(
+    formatting: &Formatting,
+    prompt: &str,
+    items: &[S],
+    multiple: bool,
+) -> SparrowResult<Vec<usize>> {
+    for (i, item) in items.iter().enumerate() {
+        println!("{}.\t{}", i + 1, item.as_ref());
+    }
+    let format = if multiple {
+        "numbers or names, separated by commas"
+    } else {
+        "a number or name"
+    };
+    prompt_strict(formatting, prompt, Some(format), None, |input| {
+        let picks = if multiple {
+            input.split(',').filter(|s| !s.trim().is_empty()).collect()
         } else {
-            NaiveTime::parse_from_str(i, &time_format).map(Some)
+            vec![input]
+        };
+        picks
+            .into_iter()
+            .map(|pick| match pick.trim().parse::<usize>() {
+                Ok(n) if (1..=items.len()).contains(&n) => Ok(n - 1),
+                Ok(n) => Err(SparrowError::BasicMessage(format!(
+                    "there's no item number {}",
+                    n
+                ))),
+                Err(_) => fuzzy_find(pick, items),
+            })
+            .collect::<SparrowResult<Vec<_>>>()
+    })
+}
+
+/// A key pressed while sparrow reads the terminal in raw mode.
+enum Key {
+    Enter,
+    Escape,
+    Interrupt,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Tab,
+    Backspace,
+    Delete,
+    ClearLine,
+    Char(char),
+}
+
+/// Reads the keys in `bytes`, which can hold more than one if they're typed (or pasted) quickly.
+fn read_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (key, len) = match bytes[i..] {
+            // escape sequences, like arrows, end with a byte from '@' to '~'
+            [0x1b, b'[', ..] => {
+                let end = bytes[i + 2..]
+                    .iter()
+                    .position(|b| (0x40..=0x7e).contains(b))
+                    .map_or(bytes.len() - i, |p| p + 3);
+                let key = match &bytes[i + 2..i + end] {
+                    [b'A'] => Some(Key::Up),
+                    [b'B'] => Some(Key::Down),
+                    [b'C'] => Some(Key::Right),
+                    [b'D'] => Some(Key::Left),
+                    [b'H'] | [b'1', b'~'] => Some(Key::Home),
+                    [b'F'] | [b'4', b'~'] => Some(Key::End),
+                    [b'3', b'~'] => Some(Key::Delete),
+                    _ => None,
+                };
+                (key, end)
+            }
+            [0x1b, ..] => (Some(Key::Escape), 1),
+            [3, ..] => (Some(Key::Interrupt), 1),
+            [b'\r', ..] | [b'\n', ..] => (Some(Key::Enter), 1),
+            [1, ..] => (Some(Key::Home), 1),
+            [5, ..] => (Some(Key::End), 1),
+            [16, ..] => (Some(Key::Up), 1),
+            [14, ..] => (Some(Key::Down), 1),
+            [b'\t', ..] => (Some(Key::Tab), 1),
+            [127, ..] | [8, ..] => (Some(Key::Backspace), 1),
+            [21, ..] => (Some(Key::ClearLine), 1),
+            _ => {
+                let c = String::from_utf8_lossy(&bytes[i..])
+                    .chars()
+                    .next()
+                    .unwrap_or_default();
+                let key = Some(Key::Char(c))
+                    .filter(|_| !c.is_control() && c != char::REPLACEMENT_CHARACTER);
+                (key, c.len_utf8())
+            }
+        };
+        keys.extend(key);
+        i += len;
+    }
+    keys
+}
+
+/// Runs `read` with the terminal in raw mode, putting it back afterward.
+fn in_raw_mode<T>(read: impl FnOnce() -> SparrowResult<T>) -> SparrowResult<T> {
+    terminal::enable_raw_mode()?;
+    let result = read();
+    terminal::disable_raw_mode()?;
+    result
+}
+
+/// Reads a line of input with `initial` already typed, so it can be edited. Ctrl-C quits, like it
+/// does at any other prompt.
+fn edit_line(initial: &str) -> SparrowResult<String> {
+    let line = in_raw_mode(|| {
+        let mut line: Vec<char> = initial.chars().collect();
+        let mut cursor = line.len();
+        let mut buf = [0; 64];
+
+        // remember where the input starts, to redraw it from there
+        print!("\x1b7");
+        loop {
+            let text: String = line.iter().collect();
+            print!("\x1b8{}\x1b[K\x1b8", text);
+            if cursor > 0 {
+                print!("\x1b[{}C", cursor);
+            }
+            stdout().flush()?;
+
+            let n = stdin().read(&mut buf)?;
+            for key in read_keys(&buf[..n]) {
+                match key {
+                    Key::Enter => {
+                        print!("\r\n");
+                        return Ok(Some(line.into_iter().collect()));
+                    }
+                    Key::Interrupt => return Ok(None),
+                    Key::Left => cursor = cursor.saturating_sub(1),
+                    Key::Right => cursor = (cursor + 1).min(line.len()),
+                    Key::Home => cursor = 0,
+                    Key::End => cursor = line.len(),
+                    Key::Backspace if cursor > 0 => {
+                        cursor -= 1;
+                        line.remove(cursor);
+                    }
+                    Key::Delete if cursor < line.len() => {
+                        line.remove(cursor);
+                    }
+                    Key::ClearLine => {
+                        line.clear();
+                        cursor = 0;
+                    }
+                    Key::Char(c) => {
+                        line.insert(cursor, c);
+                        cursor += 1;
+                    }
+                    _ => {}
+                }
+            }
         }
     })?;
 
-    // determine date/time from user input
-    if let Some(time) = time_opt {
-        if let Some(d) = Local.from_local_datetime(&date.and_time(time)).earliest() {
-            Ok(d)
+    match line {
+        Some(line) => Ok(line),
+        None => {
+            println!();
+            std::process::exit(130);
+        }
+    }
+}
+
+fn select<S: AsRef<str>>(
+    formatting: &Formatting,
+    prompt: &str,
+    items: &[S],
+    multiple: bool,
+) -> SparrowResult<Vec<usize>> {
+    let matches_for = |query: &str| -> Vec<usize> {
+        if query.is_empty() {
+            (0..items.len()).collect()
         } else {
-            Err(SparrowError::BasicMessage(
-                "Sorry, the time you entered can't be converted to your local timezone."
-                    .to_string(),
-            ))
+            fuzzy_matches(query, items)
+        }
+    };
+
+    let mut query = String::new();
+    let mut selected = 0;
+    let mut marked: Vec<usize> = Vec::new();
+    let mut buf = [0; 64];
+    loop {
+        let matches = matches_for(&query);
+        selected = selected.min(matches.len().saturating_sub(1));
+
+        // draw the prompt with the matches under it, then put the cursor back after the query
+        let mut screen = format!("\r\x1b[J{}  {}", formatting.prompt.paint(prompt), query);
+        let first = (selected + 1).saturating_sub(SELECT_ROWS);
+        let shown = &matches[first..matches.len().min(first + SELECT_ROWS)];
+        for (row, &i) in shown.iter().enumerate() {
+            let pointer = if first + row == selected { ">" } else { " " };
+            let mark = if marked.contains(&i) { "*" } else { " " };
+            screen.push_str(&format!("\r\n{}{} {}", pointer, mark, items[i].as_ref()));
+        }
+        if !shown.is_empty() {
+            screen.push_str(&format!("\x1b[{}A", shown.len()));
+        }
+        let column = prompt.chars().count() + 2 + query.chars().count();
+        screen.push_str(&format!("\r\x1b[{}C", column));
+        print!("{}", screen);
+        stdout().flush()?;
+
+        let n = stdin().read(&mut buf)?;
+        for key in read_keys(&buf[..n]) {
+            let matches = matches_for(&query);
+            match key {
+                Key::Enter => {
+                    let picked = if !marked.is_empty() {
+                        marked
+                    } else if let Some(&i) = matches.get(selected) {
+                        vec![i]
+                    } else {
+                        continue;
+                    };
+                    let names: Vec<&str> = picked.iter().map(|&i| items[i].as_ref()).collect();
+                    print!(
+                        "\r\x1b[J{}  {}\r\n",
+                        formatting.prompt.paint(prompt),
+                        names.join(", ")
+                    );
+                    return Ok(picked);
+                }
+                Key::Escape | Key::Interrupt => {
+                    print!("\r\x1b[J");
+                    return Err(SparrowError::BasicMessage("nothing was picked".to_string()));
+                }
+                Key::Up => selected = selected.saturating_sub(1),
+                Key::Down => selected = (selected + 1).min(matches.len().saturating_sub(1)),
+                Key::Tab if multiple => {
+                    if let Some(&i) = matches.get(selected) {
+                        match marked.iter().position(|&m| m == i) {
+                            Some(m) => {
+                                marked.remove(m);
+                            }
+                            None => marked.push(i),
+                        }
+                    }
+                }
+                Key::Backspace => {
+                    query.pop();
+                    selected = 0;
+                }
+                Key::Char(c) => {
+                    query.push(c);
+                    selected = 0;
+                }
+                _ => {}
+            }
         }
-    } else if let Some(d) = Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest() {
-        Ok(d)
-    } else {
-        Err(SparrowError::BasicMessage(
-            "Sorry, the date you entered can't be converted to your local timezone.".to_string(),
-        ))
     }
 }
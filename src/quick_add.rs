@@ -0,0 +1,190 @@
+//! Quick-add: a whole task in one line, like "Finish essay due fri 17:00 ~3h #school !high".
+//! Whatever's missing from the line, or can't be read, is asked for afterward.
+
+use crate::{
+    dates,
+    prompts::{prompt_datetime, prompt_strict},
+    task::{parse_duration_minutes, prompt_priority, Priority, TaskDuration},
+    Config, Formatting, SparrowError, SparrowResult, Task,
+};
+use chrono::{DateTime, Local};
+use std::convert::TryFrom;
+
+/// A task read from one line. Fields that are `None` weren't given or couldn't be read, and are
+/// asked for by `into_task`.
+#[derive(Debug)]
+pub struct QuickAdd {
+    pub name: String,
+
+    /// Read from the words after the last "due".
+    pub due_date: Option<DateTime<Local>>,
+
+    /// Read from the first word like "~3h" or "~90m" that's a duration.
+    pub minutes: Option<u64>,
+
+    /// Words starting with '#'.
+    pub tags: Vec<String>,
+
+    /// Read from the first word like "!high" that's a priority. Normal if there isn't one, and
+    /// `None` if there are only ones that aren't priorities.
+    pub priority: Option<Priority>,
+}
+
+impl QuickAdd {
+    /// Reads a task from `input`. Dates are understood relative to `now`. A '#', '~', or '!' on
+    /// its own is part of the name.
+    pub fn parse(input: &str, config: &Config, now: DateTime<Local>) -> Self {
+        let mut words = Vec::new();
+        let mut minutes = None;
+        let mut tags = Vec::new();
+        let mut priority = None;
+        let mut priority_given = false;
+        for word in input.split_whitespace() {
+            if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+                tags.push(tag.to_string());
+            } else if let Some(d) = word.strip_prefix('~').filter(|d| !d.is_empty()) {
+                minutes = minutes.or_else(|| parse_duration_minutes(d));
+            } else if let Some(p) = word.strip_prefix('!').filter(|p| !p.is_empty()) {
+                priority_given = true;
+                priority = priority.or_else(|| Priority::try_from(p).ok());
+            } else {
+                words.push(word);
+            }
+        }
+        if !priority_given {
+            priority = Some(Priority::Normal);
+        }
+
+        // the longest run of words after the last "due" that reads as a date is the due date.
+        // if nothing after it does, "due" is just part of the name
+        let mut due_date = None;
+        if let Some(due) = words.iter().rposition(|w| w.eq_ignore_ascii_case("due")) {
+            let parsed = (due + 2..=words.len()).rev().find_map(|end| {
+                let (date, time) = dates::parse_date_time(
+                    &words[due + 1..end].join(" "),
+                    &config.date_format,
+                    &config.time_format,
+                    now,
+                )?;
+                Some((end, dates::to_local_due(date, time).ok()?))
+            });
+            if let Some((end, when)) = parsed {
+                due_date = Some(when);
+                words.drain(due..end);
+            }
+        }
+
+        Self {
+            name: words.join(" "),
+            due_date,
+            minutes,
+            tags,
+            priority,
+        }
+    }
+
+    /// Makes the task, asking for whatever wasn't in the line.
+    pub fn into_task(self, formatting: &Formatting, config: &Config) -> SparrowResult<Task> {
+        let name = if self.name.is_empty() {
+            prompt_strict(
+                formatting,
+                "What do you want to name this task?",
+                None,
+                None,
+                |i| match i.trim() {
+                    "" => Err(SparrowError::BasicMessage(
+                        "Trust me, you don't want a task with a blank name".to_string(),
+                    )),
+                    name => Ok(name.to_string()),
+                },
+            )?
+        } else {
+            self.name
+        };
+
+        let due_date = match self.due_date {
+            Some(due) => due,
+            None => prompt_datetime(formatting, &config.date_format, &config.time_format, true)?,
+        };
+
+        let duration = match self.minutes {
+            Some(minutes) => TaskDuration::Minutes(minutes),
+            None => Task::prompt_task_duration(&name, formatting)?,
+        };
+
+        let priority = match self.priority {
+            Some(priority) => priority,
+            None => prompt_priority(formatting)?,
+        };
+
+        Ok(Task {
+            tags: self.tags,
+            priority,
+            ..Task::new(name, due_date, duration)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Reads `input` as if it was typed on Wednesday, May 1st 2024 at noon.
+    fn parse(input: &str) -> QuickAdd {
+        let now = Local.ymd(2024, 5, 1).and_hms(12, 0, 0);
+        QuickAdd::parse(input, &Config::default(), now)
+    }
+
+    #[test]
+    fn reads_every_part() {
+        let task = parse("Finish essay due fri 17:00 ~3h #school !high");
+        assert_eq!(task.name, "Finish essay");
+        assert_eq!(task.due_date, Some(Local.ymd(2024, 5, 3).and_hms(17, 0, 0)));
+        assert_eq!(task.minutes, Some(180));
+        assert_eq!(task.tags, vec!["school".to_string()]);
+        assert_eq!(task.priority, Some(Priority::High));
+    }
+
+    #[test]
+    fn keeps_due_in_the_name_without_a_date_after_it() {
+        let task = parse("Read about due process");
+        assert_eq!(task.name, "Read about due process");
+        assert_eq!(task.due_date, None);
+
+        let task = parse("Review due process notes due tomorrow");
+        assert_eq!(task.name, "Review due process notes");
+        assert_eq!(
+            task.due_date,
+            Some(Local.ymd(2024, 5, 2).and_hms(23, 59, 59))
+        );
+    }
+
+    #[test]
+    fn leaves_unreadable_parts_to_be_asked_for() {
+        let task = parse("Call mom ~soon !whenever");
+        assert_eq!(task.name, "Call mom");
+        assert_eq!(task.minutes, None);
+        assert_eq!(task.priority, None);
+        assert!(task.tags.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_first_readable_duration_and_priority() {
+        let task = parse("Essay ~3h ~x !nope !low !high");
+        assert_eq!(task.minutes, Some(180));
+        assert_eq!(task.priority, Some(Priority::Low));
+
+        let task = parse("Essay ~x ~90m");
+        assert_eq!(task.minutes, Some(90));
+    }
+
+    #[test]
+    fn keeps_bare_markers_in_the_name() {
+        let task = parse("Fix bug # 3 ~ soon !");
+        assert_eq!(task.name, "Fix bug # 3 ~ soon !");
+        assert_eq!(task.minutes, None);
+        assert_eq!(task.priority, Some(Priority::Normal));
+        assert!(task.tags.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+//! Quiet hours: times when sparrowd doesn't show notifications, like late at night or during
+//! meetings. Entries still start and end as usual (and focus mode, MQTT, and webhooks still hear
+//! about them); they just don't pop up.
+
+use crate::UserData;
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// A stretch of every day without notifications. Times are written like "22:00:00". Hours that
+/// end before they start go past midnight.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Returns true if `time` is within these hours.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+/// Returns true if sparrowd should keep quiet at `now`: during any of the config's quiet hours, or
+/// during an event tagged with one of its quiet tags.
+pub fn is_quiet(data: &UserData, now: DateTime<Local>) -> bool {
+    let config = data.get_config();
+    if config.quiet_hours.iter().any(|h| h.contains(now.time())) {
+        return true;
+    }
+
+    if config.quiet_tags.is_empty() {
+        return false;
+    }
+    data.all_events()
+        .iter()
+        .filter(|e| config.quiet_tags.iter().any(|tag| e.has_tag(tag)))
+        .any(|e| {
+            e.occurrences(now - Duration::days(1), now + Duration::minutes(1))
+                .any(|span| *span.start() <= now && now < span.end())
+        })
+}
@@ -0,0 +1,75 @@
+//! Remembers expansions of repeated CalendarEvents, so that asking for the same events over the
+//! same days (for example, every time a schedule is made in a long-running process) doesn't redo
+//! the same work.
+
+use crate::{CalendarEvent, TimeSpan};
+use chrono::{Date, DateTime, Local};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// How many expansions are kept before the cache starts over.
+const MAX_EXPANSIONS: usize = 512;
+
+/// Identifies an expansion by the event (as a hash of its contents) and the days it covers.
+type ExpansionKey = (u64, Date<Local>, Date<Local>);
+
+#[derive(Default)]
+pub struct RecurrenceCache {
+    expansions: HashMap<ExpansionKey, Vec<TimeSpan>>,
+}
+
+impl RecurrenceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cache shared by the whole process.
+    pub fn shared() -> MutexGuard<'static, RecurrenceCache> {
+        static SHARED: OnceLock<Mutex<RecurrenceCache>> = OnceLock::new();
+        match SHARED.get_or_init(|| Mutex::new(Self::new())).lock() {
+            Ok(g) => g,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Returns the occurrences of `event` that overlap `from..until`. Events are expanded a whole
+    /// day at a time, so that nearby ranges share the same expansion.
+    pub fn occurrences(
+        &mut self,
+        event: &CalendarEvent,
+        from: DateTime<Local>,
+        until: DateTime<Local>,
+    ) -> Vec<TimeSpan> {
+        let first_day = from.date();
+        let last_day = until.date().succ();
+        let key = (event_hash(event), first_day, last_day);
+
+        if !self.expansions.contains_key(&key) && self.expansions.len() >= MAX_EXPANSIONS {
+            self.expansions.clear();
+        }
+
+        self.expansions
+            .entry(key)
+            .or_insert_with(|| {
+                event
+                    .occurrences(first_day.and_hms(0, 0, 0), last_day.and_hms(0, 0, 0))
+                    .collect()
+            })
+            .iter()
+            .filter(|s| s.end() > from && *s.start() < until)
+            .copied()
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.expansions.clear();
+    }
+}
+
+fn event_hash(event: &CalendarEvent) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    event.hash(&mut hasher);
+    hasher.finish()
+}
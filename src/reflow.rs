@@ -0,0 +1,162 @@
+//! Make-up scheduling: when a whole day's work was missed, like on a sick day, its work periods are
+//! spread over the free time left before each task is due.
+
+use crate::{
+    methods::pomodoro::{JobStatus, PomodoroSchedule, PomodoroScheduleEntry},
+    SparrowError, SparrowResult, TimeSpan, UserData,
+};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// How many days back missed days are looked for.
+pub const LOOKBACK_DAYS: i64 = 7;
+
+/// A missed work period and where its work went.
+#[derive(Serialize)]
+pub struct MadeUp {
+    pub title: String,
+    pub missed: TimeSpan,
+
+    /// Where the work is now planned, or None if there wasn't room for it before its task is due.
+    pub now_at: Option<TimeSpan>,
+}
+
+/// Returns the index of the first work period in `schedule` on `day` that's over at `now` but was
+/// never done or skipped.
+fn missed_job_index(
+    schedule: &PomodoroSchedule,
+    day: NaiveDate,
+    now: DateTime<Local>,
+) -> Option<usize> {
+    schedule.get_entries().iter().position(|e| match e {
+        PomodoroScheduleEntry::Job {
+            status: JobStatus::Planned,
+            span,
+            ..
+        } => span.start().date().naive_local() == day && span.end() <= now,
+        _ => false,
+    })
+}
+
+/// Returns true if work was planned on `day` and is still waiting to be made up, but none of it
+/// was done or tracked, and nothing was checked off that day.
+pub fn is_missed(data: &UserData, day: NaiveDate, now: DateTime<Local>) -> bool {
+    let worked = data
+        .worked()
+        .iter()
+        .any(|w| w.span.start().date().naive_local() == day);
+    let checked_off = data
+        .get_tasks()
+        .iter()
+        .any(|t| t.done_instances.contains(&day));
+    let waiting = data
+        .get_pomodoro_schedule()
+        .as_ref()
+        .and_then(|s| missed_job_index(s, day, now))
+        .is_some();
+    !worked && !checked_off && waiting
+}
+
+/// Returns the most recent day before today that was missed, within the last `LOOKBACK_DAYS`
+/// days.
+pub fn last_missed_day(data: &UserData, now: DateTime<Local>) -> Option<NaiveDate> {
+    let today = now.date().naive_local();
+    (1..=LOOKBACK_DAYS)
+        .map(|n| today - Duration::days(n))
+        .find(|&day| is_missed(data, day, now))
+}
+
+/// Skips the work periods on `day` that were never done, and plans their work again in the free
+/// time left before each task is due. Returns what became of each one.
+pub fn make_up_day(
+    data: &mut UserData,
+    day: NaiveDate,
+    now: DateTime<Local>,
+) -> SparrowResult<Vec<MadeUp>> {
+    let mut schedule = data.get_pomodoro_schedule().clone().ok_or_else(|| {
+        SparrowError::BasicMessage(
+            "there's no pomodoro schedule yet. make one with `sparrow make pomodoro`".to_string(),
+        )
+    })?;
+
+    let mut made_up = Vec::new();
+
+    // skipped work goes back into the schedule, which moves entries around, so the next missed
+    // job is looked for again each time
+    while let Some(index) = missed_job_index(&schedule, day, now) {
+        let entry = &schedule.get_entries()[index];
+        let title = entry.title(data.get_tasks()).into_owned();
+        let missed = *entry.span();
+        let deadline = match entry {
+            PomodoroScheduleEntry::Job { task_id, .. } => data
+                .get_tasks()
+                .iter()
+                .find(|t| t.id == *task_id)
+                .and_then(|t| t.next_due_date(now)),
+            _ => None,
+        };
+        let now_at = schedule.skip_job_before(data.get_config(), index, deadline)?;
+        made_up.push(MadeUp {
+            title,
+            missed,
+            now_at,
+        });
+    }
+
+    if made_up.is_empty() {
+        return Err(SparrowError::BasicMessage(format!(
+            "there's no missed work left to make up on {}",
+            day.format(&data.get_config().date_format)
+        )));
+    }
+
+    data.set_pomodoro_schedule(schedule);
+    Ok(made_up)
+}
+
+/// Shows where a missed day's work went.
+pub struct MadeUpDisplay<'a> {
+    pub made_up: &'a [MadeUp],
+    pub data: &'a UserData,
+}
+
+impl Serialize for MadeUpDisplay<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.made_up.serialize(serializer)
+    }
+}
+
+impl Display for MadeUpDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let config = self.data.get_config();
+        let date_time = format!("{} {}", config.date_format, config.time_format);
+        for m in self.made_up {
+            match &m.now_at {
+                Some(span) => writeln!(
+                    f,
+                    "-\t{} (missed {}): now {} - {}",
+                    m.title,
+                    m.missed.start().format(&config.time_format),
+                    span.start().format(&date_time),
+                    span.end().format(&config.time_format)
+                )?,
+                None => writeln!(
+                    f,
+                    "-\t{} (missed {}): no room before it's due",
+                    m.title,
+                    m.missed.start().format(&config.time_format)
+                )?,
+            }
+        }
+
+        if self.made_up.iter().any(|m| m.now_at.is_none()) {
+            writeln!(
+                f,
+                "\nSome work didn't fit before it's due. Remake your schedule with `sparrow make` \
+                 or move a deadline to fit it in."
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,22 +1,38 @@
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
 use crate::{
-    task::Task, Bedtime, CalendarEvent, Config, SparrowError,
+    task::Task, Bedtime, CalendarEvent, Config, ScheduleMetrics, SparrowError, SparrowResult,
 };
 use serde::{Deserialize, Serialize};
 
 pub trait Schedule<'d>: Sized + Clone + Deserialize<'d> + Serialize {
-    type Display: Display;
+    type Display: Display + Serialize;
 
+    /// Makes a new schedule. Implementations should call `check_canceled` with `cancel` every so
+    /// often, so that long generations can be stopped (for example, with Ctrl-C).
     fn make(
         config: &Config,
         tasks: &[Task],
         events: &[CalendarEvent],
         bedtime: &Bedtime,
+        cancel: &AtomicBool,
     ) -> Result<Self, SparrowError>;
 
     fn display(
         &'d self,
         config: &'d Config,
+        tasks: &'d [Task],
     ) -> Self::Display;
+
+    /// Measures how well the schedule fits `tasks`.
+    fn metrics(&self, config: &Config, tasks: &[Task]) -> ScheduleMetrics;
 }
 
+/// Returns `SparrowError::ScheduleCanceled` if `cancel` has been set.
+pub fn check_canceled(cancel: &AtomicBool) -> SparrowResult<()> {
+    if cancel.load(Ordering::SeqCst) {
+        Err(SparrowError::ScheduleCanceled)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Search: finds text (or a regular expression) in the names, notes, and tags of tasks and events,
+//! and in the titles of schedule entries, and says where each match is.
+
+use crate::{
+    methods::pomodoro::PomodoroScheduleEntry,
+    task::{Subtask, TaskDuration},
+    Config, SparrowError, SparrowResult, Task, UserData,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// What to look for. Either way, case doesn't matter.
+pub enum Pattern {
+    /// Text that has to appear somewhere, lowercased.
+    Text(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn new(query: &str, regex: bool) -> SparrowResult<Self> {
+        if regex {
+            RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .map(Self::Regex)
+                .map_err(|e| SparrowError::BasicMessage(format!("that isn't a valid regex: {}", e)))
+        } else {
+            Ok(Self::Text(query.to_lowercase()))
+        }
+    }
+
+    pub fn matches(&self, haystack: &str) -> bool {
+        match self {
+            Self::Text(text) => haystack.to_lowercase().contains(text),
+            Self::Regex(regex) => regex.is_match(haystack),
+        }
+    }
+}
+
+/// Something that matched.
+#[derive(Serialize)]
+pub struct Hit {
+    /// Where the match is: "tasks", "events", or the schedule it's in, like "pomodoro".
+    pub place: &'static str,
+
+    /// The name of the task or event, or the title of the schedule entry.
+    pub title: String,
+
+    /// The parts that matched, like "name", "notes", or "tags". Schedule entries only have titles.
+    pub fields: Vec<&'static str>,
+
+    /// When a task is due, an event starts, or a schedule entry is planned for.
+    pub date: Option<NaiveDate>,
+    pub time: Option<NaiveTime>,
+
+    /// How many entries with the same title matched on the same day, for schedule entries.
+    pub count: usize,
+}
+
+impl Hit {
+    fn new(place: &'static str, title: String, when: Option<DateTime<Local>>) -> Self {
+        Self {
+            place,
+            title,
+            fields: Vec::new(),
+            date: when.map(|w| w.date().naive_local()),
+            time: when.map(|w| w.time()),
+            count: 1,
+        }
+    }
+}
+
+/// Returns everything in `data` that matches `pattern`: tasks, then events, then schedule
+/// entries.
+pub fn search(data: &UserData, pattern: &Pattern) -> Vec<Hit> {
+    let tasks = data.get_tasks();
+    let mut hits: Vec<Hit> = tasks.iter().filter_map(|t| task_hit(t, pattern)).collect();
+
+    for event in data.get_events() {
+        let mut hit = Hit::new("events", event.name.clone(), Some(*event.time_span.start()));
+        if pattern.matches(&event.name) {
+            hit.fields.push("name");
+        }
+        if event.tags.iter().any(|t| pattern.matches(t)) {
+            hit.fields.push("tags");
+        }
+        if !hit.fields.is_empty() {
+            hits.push(hit);
+        }
+    }
+
+    let schedules: [(&'static str, Option<&[PomodoroScheduleEntry]>); 4] = [
+        (
+            "pomodoro",
+            data.get_pomodoro_schedule()
+                .as_ref()
+                .map(|s| s.get_entries()),
+        ),
+        (
+            "timeblock",
+            data.get_time_block_schedule()
+                .as_ref()
+                .map(|s| s.get_entries()),
+        ),
+        (
+            "edf",
+            data.get_edf_schedule().as_ref().map(|s| s.get_entries()),
+        ),
+        (
+            "frog",
+            data.get_frog_schedule().as_ref().map(|s| s.get_entries()),
+        ),
+    ];
+    for (place, entries) in schedules {
+        for entry in entries.into_iter().flatten() {
+            let title = entry.title(tasks);
+            if pattern.matches(&title) {
+                add_entry_hit(&mut hits, place, title.into_owned(), *entry.span().start());
+            }
+        }
+    }
+
+    if let Some(ivy_lee) = data.get_ivy_lee_schedule() {
+        for (date, entries) in ivy_lee.days() {
+            for entry in entries {
+                let label = entry.label(tasks);
+                if pattern.matches(&label) {
+                    let mut hit = Hit::new("ivylee", label, None);
+                    hit.date = Some(date);
+                    hit.fields.push("title");
+                    hits.push(hit);
+                }
+            }
+        }
+    }
+
+    hits
+}
+
+/// Returns a hit for `task`, if any of its parts match `pattern`.
+fn task_hit(task: &Task, pattern: &Pattern) -> Option<Hit> {
+    let mut hit = Hit::new("tasks", task.name.clone(), Some(task.due_date));
+    if pattern.matches(&task.name) {
+        hit.fields.push("name");
+    }
+    if task.notes.as_deref().is_some_and(|n| pattern.matches(n)) {
+        hit.fields.push("notes");
+    }
+    if task.tags.iter().any(|t| pattern.matches(t)) {
+        hit.fields.push("tags");
+    }
+    if task.course.as_deref().is_some_and(|c| pattern.matches(c)) {
+        hit.fields.push("course");
+    }
+    if let TaskDuration::Subtasks(subs) = &task.duration {
+        let matches = |s: &Subtask| {
+            pattern.matches(&s.name) || s.notes.as_deref().is_some_and(|n| pattern.matches(n))
+        };
+        if subs.iter().any(matches) {
+            hit.fields.push("subtasks");
+        }
+    }
+    (!hit.fields.is_empty()).then_some(hit)
+}
+
+/// Adds a hit for a schedule entry, or counts it with the last hit if that one's for an entry
+/// with the same title on the same day.
+fn add_entry_hit(hits: &mut Vec<Hit>, place: &'static str, title: String, start: DateTime<Local>) {
+    let date = start.date().naive_local();
+    if let Some(last) = hits.last_mut() {
+        if last.place == place && last.title == title && last.date == Some(date) {
+            last.count += 1;
+            return;
+        }
+    }
+    let mut hit = Hit::new(place, title, Some(start));
+    hit.fields.push("title");
+    hits.push(hit);
+}
+
+/// Shows search hits, grouped by where they are.
+pub struct SearchDisplay<'a> {
+    pub query: &'a str,
+    pub hits: &'a [Hit],
+    pub config: &'a Config,
+}
+
+impl Serialize for SearchDisplay<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.hits.serialize(serializer)
+    }
+}
+
+impl Display for SearchDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.hits.is_empty() {
+            return write!(f, "Nothing matches '{}'.", self.query);
+        }
+
+        let mut place = "";
+        for hit in self.hits {
+            if hit.place != place {
+                place = hit.place;
+                writeln!(f, "{}:", heading(place))?;
+            }
+
+            let when = hit.date.map(|date| {
+                let date = date.format(&self.config.date_format);
+                match hit.time {
+                    Some(time) => format!("{} {}", date, time.format(&self.config.time_format)),
+                    None => date.to_string(),
+                }
+            });
+            match (hit.place, when) {
+                ("tasks", Some(when)) => write!(f, "-\t{}: due {}", hit.title, when)?,
+                (_, Some(when)) => write!(f, "-\t{} :: {}", when, hit.title)?,
+                (_, None) => write!(f, "-\t{}", hit.title)?,
+            }
+            if hit.count > 1 {
+                write!(f, " (x{})", hit.count)?;
+            }
+            if hit.fields != ["title"] && hit.fields != ["name"] {
+                write!(f, " (in {})", hit.fields.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the heading for hits in `place`.
+fn heading(place: &str) -> &str {
+    match place {
+        "tasks" => "Tasks",
+        "events" => "Events",
+        "pomodoro" => "Pomodoro schedule",
+        "timeblock" => "Time block schedule",
+        "edf" => "EDF schedule",
+        "frog" => "Frog schedule",
+        "ivylee" => "Ivy Lee lists",
+        _ => place,
+    }
+}
@@ -0,0 +1,322 @@
+//! A small HTTP API, so that other programs and devices can read and change the user's data.
+//!
+//! Every request must carry the server's token in an `Authorization: Bearer <token>` header. The
+//! data file is read again for every request (and written after every change), so the server
+//! always agrees with the `sparrow` command and sparrowd.
+//!
+//! The server also serves a dashboard page at `/`, which doesn't need the token itself. Open it as
+//! `/?token=<token>` once and the page remembers the token.
+//!
+//! Calendar apps can't send headers, so the schedule's iCalendar feed at `/feed.ics` takes the
+//! token as `/feed.ics?token=<token>` instead. Like everything else, it's made from the data file
+//! when it's requested, so subscribers see the latest schedule the next time they refresh.
+//!
+//! A token in a URL isn't kept as secret as one in a header: it ends up in browser history, in the
+//! logs of proxies along the way, and wherever the URL is pasted. Use the `?token=` forms only on
+//! networks you trust, and give the server a new token if one gets out.
+//!
+//! | Method | Path                    | Does                                       |
+//! |--------|-------------------------|--------------------------------------------|
+//! | GET    | /tasks                  | lists tasks                                |
+//! | POST   | /tasks                  | adds a task, given `name`, `due_date` (RFC 3339), and `minutes` |
+//! | POST   | /tasks/{id}/done        | marks a task as done                       |
+//! | GET    | /schedule/pomodoro      | lists pomodoro schedule entries            |
+//! | GET    | /schedule/today         | lists pomodoro schedule entries for today  |
+//! | GET    | /schedule/ivylee        | returns the ivy lee schedule               |
+//! | GET    | /now                    | returns the current and next pomodoro entries |
+//! | POST   | /now/done               | marks the current work period as done      |
+//! | POST   | /now/skip               | marks the current work period as skipped   |
+//! | GET    | /feed.ics?token={token} | returns the pomodoro schedule as an iCalendar feed |
+
+use crate::{
+    export::ical,
+    methods::pomodoro::{EntryView, JobStatus},
+    task::TaskDuration,
+    SparrowError, SparrowResult, Task, TaskId, UserData,
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+use tiny_http::{Header, Method, Request, Response};
+
+const DASHBOARD_HTML: &str = include_str!("dashboard.html");
+
+pub struct ApiServer {
+    data_path: PathBuf,
+    token: String,
+}
+
+/// Why a request couldn't be answered.
+enum ApiError {
+    Unauthorized,
+    NotFound,
+    Conflict(String),
+    BadRequest(String),
+    Internal(SparrowError),
+}
+
+impl From<SparrowError> for ApiError {
+    fn from(e: SparrowError) -> Self {
+        Self::Internal(e)
+    }
+}
+
+type ApiResult = Result<Value, ApiError>;
+
+/// The body of a request to add a task.
+#[derive(Deserialize)]
+struct NewTask {
+    name: String,
+    due_date: DateTime<Local>,
+    minutes: u64,
+}
+
+#[derive(Serialize)]
+struct Now<'a> {
+    current: Option<EntryView<'a>>,
+    next: Option<EntryView<'a>>,
+}
+
+impl ApiServer {
+    pub fn new(data_path: PathBuf, token: String) -> Self {
+        Self { data_path, token }
+    }
+
+    /// Makes up a random token from 16 bytes of the operating system's randomness, for when the
+    /// user doesn't choose one.
+    pub fn generate_token() -> SparrowResult<String> {
+        let mut bytes = [0u8; 16];
+        std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+        Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Serves requests at `address` (like "127.0.0.1:7878") until the server fails.
+    pub fn run(&self, address: &str) -> Result<(), SparrowError> {
+        let server = tiny_http::Server::http(address)
+            .map_err(|e| SparrowError::BasicMessage(format!("couldn't start server: {}", e)))?;
+
+        for mut request in server.incoming_requests() {
+            if *request.method() == Method::Get && request.url().split('?').next() == Some("/") {
+                let _ = request.respond(Self::dashboard_response());
+                continue;
+            }
+
+            if *request.method() == Method::Get
+                && request.url().split('?').next() == Some("/feed.ics")
+            {
+                let response = if self.has_token_in_query(&request) {
+                    self.feed_response()
+                } else {
+                    Self::response(Err(ApiError::Unauthorized))
+                };
+                let _ = request.respond(response);
+                continue;
+            }
+
+            let result = if self.is_authorized(&request) {
+                self.handle(&mut request)
+            } else {
+                Err(ApiError::Unauthorized)
+            };
+
+            let _ = request.respond(Self::response(result));
+        }
+
+        Ok(())
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        request.headers().iter().any(|h| {
+            h.field.equiv("Authorization")
+                && h.value
+                    .as_str()
+                    .strip_prefix("Bearer ")
+                    .is_some_and(|token| self.is_token(token))
+        })
+    }
+
+    fn has_token_in_query(&self, request: &Request) -> bool {
+        request.url().split_once('?').is_some_and(|(_, query)| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.strip_prefix("token="))
+                .any(|token| self.is_token(token))
+        })
+    }
+
+    /// Compares `given` to the server's token in time that only depends on their lengths, so that
+    /// how long a wrong token takes to be turned down doesn't give away how much of it was right.
+    fn is_token(&self, given: &str) -> bool {
+        let (given, token) = (given.as_bytes(), self.token.as_bytes());
+        given.len() == token.len()
+            && given
+                .iter()
+                .zip(token)
+                .fold(0, |difference, (a, b)| difference | (a ^ b))
+                == 0
+    }
+
+    fn handle(&self, request: &mut Request) -> ApiResult {
+        let method = request.method().clone();
+        let path = request.url().split('?').next().unwrap_or("").to_string();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let mut body = String::new();
+        request
+            .as_reader()
+            .read_to_string(&mut body)
+            .map_err(SparrowError::from)?;
+
+        match (method, segments.as_slice()) {
+            (Method::Get, ["tasks"]) => self.list_tasks(),
+            (Method::Post, ["tasks"]) => self.add_task(&body),
+            (Method::Post, ["tasks", id, "done"]) => self.mark_done(id),
+            (Method::Get, ["schedule", "pomodoro"]) => self.pomodoro_schedule(),
+            (Method::Get, ["schedule", "today"]) => self.today_schedule(),
+            (Method::Get, ["schedule", "ivylee"]) => self.ivy_lee_schedule(),
+            (Method::Get, ["now"]) => self.now(),
+            (Method::Post, ["now", "done"]) => self.set_current_status(JobStatus::Done),
+            (Method::Post, ["now", "skip"]) => self.set_current_status(JobStatus::Skipped),
+            _ => Err(ApiError::NotFound),
+        }
+    }
+
+    fn load(&self) -> Result<UserData, ApiError> {
+        Ok(UserData::from_file(&self.data_path)?)
+    }
+
+    fn save(&self, data: &UserData) -> Result<(), ApiError> {
+        Ok(data.write_to_file(&self.data_path)?)
+    }
+
+    fn list_tasks(&self) -> ApiResult {
+        Ok(json!(self.load()?.get_tasks()))
+    }
+
+    fn add_task(&self, body: &str) -> ApiResult {
+        let new_task: NewTask =
+            serde_json::from_str(body).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        if new_task.name.trim().is_empty() {
+            return Err(ApiError::BadRequest("tasks can't have blank names".to_string()));
+        }
+
+        let mut data = self.load()?;
+        data.add_task(Task::new(
+            new_task.name.trim().to_string(),
+            new_task.due_date,
+            TaskDuration::Minutes(new_task.minutes),
+        ));
+        self.save(&data)?;
+
+        Ok(json!(data.get_tasks().last()))
+    }
+
+    fn mark_done(&self, id: &str) -> ApiResult {
+        let id: TaskId = id.parse().map_err(|_| ApiError::NotFound)?;
+
+        let mut data = self.load()?;
+        let task = data.get_task_mut(id).ok_or(ApiError::NotFound)?;
+        task.mark_done(Local::now());
+        let result = json!(task);
+        self.save(&data)?;
+
+        Ok(result)
+    }
+
+    fn pomodoro_schedule(&self) -> ApiResult {
+        let data = self.load()?;
+        let schedule = data.get_pomodoro_schedule().as_ref().ok_or(ApiError::NotFound)?;
+        let entries: Vec<_> = schedule
+            .get_entries()
+            .iter()
+            .map(|e| e.view(data.get_tasks()))
+            .collect();
+
+        Ok(json!(entries))
+    }
+
+    fn today_schedule(&self) -> ApiResult {
+        let data = self.load()?;
+        let schedule = data.get_pomodoro_schedule().as_ref().ok_or(ApiError::NotFound)?;
+        let entries: Vec<_> = schedule
+            .entries_on(Local::today())
+            .map(|e| e.view(data.get_tasks()))
+            .collect();
+
+        Ok(json!(entries))
+    }
+
+    fn ivy_lee_schedule(&self) -> ApiResult {
+        let data = self.load()?;
+        let schedule = data.get_ivy_lee_schedule().as_ref().ok_or(ApiError::NotFound)?;
+
+        Ok(json!(schedule))
+    }
+
+    fn now(&self) -> ApiResult {
+        let data = self.load()?;
+        let schedule = data.get_pomodoro_schedule().as_ref().ok_or(ApiError::NotFound)?;
+        let now = Local::now();
+        let mut upcoming = schedule
+            .get_entries()
+            .iter()
+            .skip_while(|e| e.span().end() <= now)
+            .map(|e| e.view(data.get_tasks()));
+
+        Ok(json!(Now {
+            current: upcoming.next(),
+            next: upcoming.next(),
+        }))
+    }
+
+    fn set_current_status(&self, status: JobStatus) -> ApiResult {
+        let mut data = self.load()?;
+        let mut schedule = data
+            .get_pomodoro_schedule()
+            .clone()
+            .ok_or(ApiError::NotFound)?;
+        let index = schedule
+            .entry_index_at(Local::now())
+            .ok_or_else(|| ApiError::Conflict("nothing is scheduled right now".to_string()))?;
+        schedule
+            .set_job_status(index, status)
+            .map_err(|e| ApiError::Conflict(e.to_string()))?;
+        data.set_pomodoro_schedule(schedule);
+        self.save(&data)?;
+
+        let schedule = data.get_pomodoro_schedule().as_ref().unwrap();
+        Ok(json!(schedule.get_entries()[index].view(data.get_tasks())))
+    }
+
+    fn dashboard_response() -> Response<Cursor<Vec<u8>>> {
+        Response::from_string(DASHBOARD_HTML).with_header(
+            Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap(),
+        )
+    }
+
+    fn feed_response(&self) -> Response<Cursor<Vec<u8>>> {
+        match self.load() {
+            Ok(data) => Response::from_string(ical::schedule_calendar(&data)).with_header(
+                Header::from_bytes("Content-Type", "text/calendar; charset=utf-8").unwrap(),
+            ),
+            Err(e) => Self::response(Err(e)),
+        }
+    }
+
+    fn response(result: ApiResult) -> Response<Cursor<Vec<u8>>> {
+        let (status, body) = match result {
+            Ok(v) => (200, v),
+            Err(ApiError::Unauthorized) => (401, json!({ "error": "missing or wrong token" })),
+            Err(ApiError::NotFound) => (404, json!({ "error": "not found" })),
+            Err(ApiError::Conflict(m)) => (409, json!({ "error": m })),
+            Err(ApiError::BadRequest(m)) => (400, json!({ "error": m })),
+            Err(ApiError::Internal(e)) => (500, json!({ "error": e.to_string() })),
+        };
+
+        Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+    }
+}
@@ -0,0 +1,73 @@
+//! Other people's data files, like a partner's classes or a teammate's meetings. Their events are
+//! only read, never changed, so that joint plans can be checked against both schedules.
+
+use crate::{CalendarEvent, CalendarEventType, TimeSpan, UserData};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// How far ahead repeating events are checked for conflicts.
+const CONFLICT_CHECK_WEEKS: i64 = 8;
+
+/// Someone else's data file.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SharedCalendar {
+    /// Whose calendar it is, like "Sam".
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// An event from a shared calendar.
+pub struct SharedEvent {
+    /// The name of the calendar the event is from.
+    pub owner: String,
+    pub event: CalendarEvent,
+}
+
+/// Reads the events (including course meetings) of every shared calendar. Calendars that can't be
+/// read are skipped with a warning, since they belong to someone else and may be out of reach.
+pub fn load_events(calendars: &[SharedCalendar]) -> Vec<SharedEvent> {
+    let mut events = Vec::new();
+    for calendar in calendars {
+        let data = match UserData::from_file(&calendar.path) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("couldn't read {}'s calendar: {}", calendar.name, e);
+                continue;
+            }
+        };
+
+        events.extend(
+            data.all_events()
+                .into_iter()
+                .filter(|e| e.event_type == CalendarEventType::Event)
+                .map(|event| SharedEvent {
+                    owner: calendar.name.clone(),
+                    event,
+                }),
+        );
+    }
+    events
+}
+
+/// Returns the shared events that overlap any occurrence of `event`, with the time each one
+/// happens. Repeating events are checked for a few weeks after they start.
+pub fn conflicts<'a>(
+    shared: &'a [SharedEvent],
+    event: &CalendarEvent,
+) -> Vec<(&'a SharedEvent, TimeSpan)> {
+    let from = *event.time_span.start();
+    let until = from + Duration::weeks(CONFLICT_CHECK_WEEKS);
+
+    let mut found = Vec::new();
+    for occurrence in event.occurrences(from, until) {
+        for s in shared {
+            found.extend(
+                s.event
+                    .occurrences(*occurrence.start(), occurrence.end())
+                    .map(|span| (s, span)),
+            );
+        }
+    }
+    found
+}
@@ -0,0 +1,61 @@
+//! Sleep debt: sleep lost to late nights. The morning after a late night is kept free for the
+//! sleep that was missed.
+
+use crate::{Bedtime, CalendarEvent, CalendarEventType, Repeat, TimeSpan};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Timelike};
+use std::collections::BTreeMap;
+
+/// How many days back sleep debt is counted.
+pub const DEBT_DAYS: i64 = 14;
+
+/// Returns the date of the night that `when` belongs to. Late nights are usually recorded the
+/// morning after, so anything before noon counts toward the night before.
+pub fn night_of(when: DateTime<Local>) -> NaiveDate {
+    let day = when.date().naive_local();
+    if when.hour() < 12 {
+        day.pred()
+    } else {
+        day
+    }
+}
+
+/// Returns the minutes of sleep lost in the `DEBT_DAYS` nights up to `today`.
+pub fn sleep_debt(late_nights: &BTreeMap<NaiveDate, u32>, today: NaiveDate) -> u32 {
+    late_nights
+        .range(today - Duration::days(DEBT_DAYS)..=today)
+        .map(|(_, minutes)| minutes)
+        .sum()
+}
+
+/// Returns events that keep the mornings after late nights free, for mornings that aren't over
+/// yet.
+pub fn recovery_events(
+    late_nights: &BTreeMap<NaiveDate, u32>,
+    bedtime: &Bedtime,
+    now: DateTime<Local>,
+) -> Vec<CalendarEvent> {
+    late_nights
+        .iter()
+        .filter_map(|(night, minutes)| {
+            let wake = night.succ().and_time(bedtime.wake_on(night.succ()));
+            let start = Local.from_local_datetime(&wake).earliest()?;
+            let time_span = TimeSpan::new(start, *minutes);
+            if time_span.end() <= now {
+                return None;
+            }
+
+            Some(CalendarEvent {
+                id: 0,
+                name: "Sleeping in".to_string(),
+                time_span,
+                event_type: CalendarEventType::Event,
+                repeat: Repeat::No,
+                tags: Vec::new(),
+                repeat_until: None,
+                repeat_count: None,
+                exceptions: Vec::new(),
+                timezone: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,44 @@
+//! Sound alerts, for when sparrowd's notifications are too easy to miss. Sounds are played by
+//! whatever player the desktop has, so sparrow doesn't need to know about audio itself.
+
+use crate::{SparrowError, SparrowResult};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+/// Players tried in order when the user doesn't choose one.
+const PLAYERS: [&str; 3] = ["paplay", "pw-play", "aplay"];
+
+/// Plays the sound file at `sound` with `command` (a program and its arguments, with the file's
+/// path added to the end), or with the first of paplay, pw-play, or aplay that's installed if
+/// `command` is empty. Returns once the sound is over.
+pub fn play(sound: &Path, command: &[String]) -> SparrowResult<()> {
+    if let Some((program, args)) = command.split_first() {
+        return run(program, args, sound);
+    }
+
+    for player in PLAYERS.iter() {
+        match run(player, &[], sound) {
+            Err(SparrowError::Io(e)) if e.kind() == ErrorKind::NotFound => continue,
+            result => return result,
+        }
+    }
+    Err(SparrowError::BasicMessage(format!(
+        "couldn't find a program to play sounds with. install one of {} or set `alert_command`",
+        PLAYERS.join(", ")
+    )))
+}
+
+fn run(program: &str, args: &[String], sound: &Path) -> SparrowResult<()> {
+    let status = Command::new(program).args(args).arg(sound).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(SparrowError::BasicMessage(format!(
+            "`{}` couldn't play {} ({})",
+            program,
+            sound.display(),
+            status
+        )))
+    }
+}
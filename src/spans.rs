@@ -4,51 +4,185 @@ use crate::SparrowError;
 use crate::errors::SparrowResult;
 use crate::prompts::*;
 use chrono::prelude::*;
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Identifies a CalendarEvent, the way `TaskId` identifies a Task.
+pub type EventId = u32;
 
 /// A CalendarEvent that can optionally be repeated. TODO: Make this an enum instead of containing
 /// an enum type like CalendarEventType.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct CalendarEvent {
+    /// Given by `UserData` when the event is added. Events that aren't kept in the data file, like
+    /// synced events and course meetings, have 0.
+    #[serde(default)]
+    pub id: EventId,
+
     pub name: String,
     pub time_span: TimeSpan,
     pub event_type: CalendarEventType,
     pub repeat: Repeat,
+
+    /// Labels for grouping events, like "school" or "work".
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// When a repeating event stops, like the end of a semester. Nothing starting after this
+    /// happens.
+    #[serde(default)]
+    pub repeat_until: Option<DateTime<Local>>,
+
+    /// How many times a repeating event happens in all, if it stops after some.
+    #[serde(default)]
+    pub repeat_count: Option<u32>,
+
+    /// Days a repeating event doesn't happen on, like a class cancelled for a holiday.
+    #[serde(default)]
+    pub exceptions: Vec<NaiveDate>,
+
+    /// The IANA timezone the event was planned in, like "America/New_York". Repeats stay at the
+    /// same time of day there, wherever the user is.
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 impl CalendarEvent {
     pub fn prompt_event(formatting: &Formatting, config: &Config) -> SparrowResult<Self> {
-        let name = prompt(&formatting, "What should this event be called?", None)?;
-        let span = TimeSpan::prompt(&formatting, "When?", &config.date_format, &config.time_format)?;
-        let repeat = Repeat::prompt(&formatting)?;
+        let input = prompt(
+            &formatting,
+            "What should this event be called?",
+            Some("add tags like #school"),
+            None,
+        )?;
+        let (name, tags) = split_tags(&input);
+        let (span, timezone) = TimeSpan::prompt_with_timezone(
+            &formatting,
+            "When?",
+            &config.date_format,
+            &config.time_format,
+        )?;
+        let repeat = Repeat::prompt(&formatting, span.start())?;
+        let (repeat_until, repeat_count) = prompt_repeat_end(formatting, config, &repeat)?;
         Ok(Self {
+            id: 0,
             name,
             time_span: span,
             event_type: CalendarEventType::Event,
             repeat,
+            tags,
+            repeat_until,
+            repeat_count,
+            exceptions: Vec::new(),
+            timezone: timezone.map(|tz| tz.name().to_string()),
         })
     }
 
     pub fn prompt_break(formatting: &Formatting, config: &Config) -> SparrowResult<Self> {
         let span = TimeSpan::prompt(&formatting, "When?", &config.date_format, &config.time_format)?;
-        let repeat = Repeat::prompt(&formatting)?;
+        let repeat = Repeat::prompt(&formatting, span.start())?;
+        let (repeat_until, repeat_count) = prompt_repeat_end(formatting, config, &repeat)?;
         Ok(Self {
+            id: 0,
             name: String::new(),
             time_span: span,
             event_type: CalendarEventType::Break,
             repeat,
+            tags: Vec::new(),
+            repeat_until,
+            repeat_count,
+            exceptions: Vec::new(),
+            timezone: None,
         })
     }
+
+    /// Returns true if the event is tagged with `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Returns the times this event happens that overlap `from..until`, without stepping through
+    /// every repetition before `from`.
+    pub fn occurrences(&self, from: DateTime<Local>, until: DateTime<Local>) -> Occurrences {
+        Occurrences::new(self.time_span, self.repeat.clone(), from, until)
+            .ending(self.repeat_until, self.repeat_count)
+            .except(&self.exceptions)
+            .in_timezone(self.tz())
+    }
+
+    /// Returns the timezone the event was planned in, if it has one that sparrow knows.
+    pub fn tz(&self) -> Option<Tz> {
+        self.timezone.as_ref().and_then(|t| t.parse().ok())
+    }
+
+    /// Returns the time this event happens on `date`, if it does.
+    pub fn occurrence_on(&self, date: NaiveDate) -> Option<TimeSpan> {
+        let start = Local.from_local_date(&date).earliest()?;
+        self.occurrences(start.and_hms(0, 0, 0), start.succ().and_hms(0, 0, 0))
+            .find(|o| o.start().date().naive_local() == date)
+    }
+
+    /// Returns when the last time this event happens starts, or None if it repeats forever.
+    pub fn last_start(&self) -> Option<DateTime<Local>> {
+        if self.repeat == Repeat::No {
+            return Some(*self.time_span.start());
+        }
+
+        let last_counted = self
+            .repeat_count
+            .and_then(|c| self.repeat.nth_start(self.time_span.start(), c as i64 - 1));
+        match (self.repeat_until, last_counted) {
+            (Some(until), Some(counted)) => Some(until.min(counted)),
+            (until, counted) => until.or(counted),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Asks when a repeating event stops, as a date or a number of times. Returns nothing for events
+/// that don't repeat.
+fn prompt_repeat_end(
+    formatting: &Formatting,
+    config: &Config,
+    repeat: &Repeat,
+) -> SparrowResult<(Option<DateTime<Local>>, Option<u32>)> {
+    if *repeat == Repeat::No {
+        return Ok((None, None));
+    }
+
+    let hint = format!("a date ({}), a number of times, or empty for never", config.date_format);
+    prompt_strict(formatting, "Stop repeating?", Some(&hint), None, |i| {
+        let i = i.trim();
+        if i.is_empty() {
+            return Ok((None, None));
+        }
+        if let Ok(count) = i.parse::<u32>() {
+            return Ok((None, Some(count)));
+        }
+
+        // the event can still happen on the day it stops
+        let today = Local::now().date().naive_local();
+        match crate::dates::parse_date(i, &config.date_format, today) {
+            Some(date) => {
+                let end_of_day = crate::dates::end_of_day();
+                Ok((Some(crate::dates::to_local(date, Some(end_of_day))?), None))
+            }
+            None => Err(SparrowError::BasicMessage(String::from("What?"))),
+        }
+    })
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub enum CalendarEventType {
     Break,
     Event,
+
+    /// Time set aside for a habit.
+    Habit,
 }
 
 /// A single block of time.
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct TimeSpan {
     start: DateTime<Local>,
     minutes: u32,
@@ -59,24 +193,52 @@ impl TimeSpan {
         Self { start, minutes }
     }
 
+    /// Returns the span from `start` to `end`, in whole minutes.
+    pub fn between(start: DateTime<Local>, end: DateTime<Local>) -> Self {
+        Self {
+            start,
+            minutes: (end - start).num_minutes().max(0) as u32,
+        }
+    }
+
     pub fn prompt(formatting: &Formatting, question: &str, date_format: &str, time_format: &str) -> SparrowResult<Self> {
+        Self::prompt_with_timezone(formatting, question, date_format, time_format).map(|(s, _)| s)
+    }
+
+    /// Like `prompt`, but the time can be followed by a timezone, like "15:00 America/New_York".
+    /// Returns the timezone too, if one was given.
+    pub fn prompt_with_timezone(
+        formatting: &Formatting,
+        question: &str,
+        date_format: &str,
+        time_format: &str,
+    ) -> SparrowResult<(Self, Option<Tz>)> {
         let initial_question = format!("{}\nDay?", question);
-        let date = prompt_strict(&formatting, &initial_question, Some(date_format), |i| {
+        let date = prompt_strict(&formatting, &initial_question, Some(date_format), None, |i| {
             NaiveDate::parse_from_str(i.trim(), date_format)
         })?;
-        let time = prompt_strict(&formatting, "Time?", Some(time_format), |i| {
-            NaiveTime::parse_from_str(i.trim(), time_format)
+        let time_hint = format!("{}, and a timezone if it isn't yours", time_format);
+        let (time, timezone) = prompt_strict(&formatting, "Time?", Some(&time_hint), None, |i| {
+            let (time, timezone) = crate::dates::split_timezone(i);
+            NaiveTime::parse_from_str(time, time_format).map(|t| (t, timezone))
         })?;
 
-        let start = Local.from_local_datetime(&date.and_time(time)).earliest().unwrap();
+        let start = match timezone {
+            Some(tz) => tz
+                .from_local_datetime(&date.and_time(time))
+                .earliest()
+                .map(|s| s.with_timezone(&Local)),
+            None => Local.from_local_datetime(&date.and_time(time)).earliest(),
+        }
+        .ok_or_else(|| {
+            SparrowError::BasicMessage("Sorry, that time doesn't exist there.".to_string())
+        })?;
 
-        let minutes = prompt_strict(&formatting, "How long?", Some("minutes"), |i| {
+        let minutes = prompt_strict(&formatting, "How long?", Some("minutes"), None, |i| {
             i.trim().parse::<u32>()
         })?;
 
-        Ok(Self {
-            start, minutes
-        })
+        Ok((Self { start, minutes }, timezone))
     }
 
     pub fn start(&self) -> &DateTime<Local> {
@@ -148,10 +310,35 @@ impl TimeSpan {
     }
 }
 
+/// Returns the time from `from` until `until` that none of `busy` takes, in order. `busy` doesn't
+/// have to be sorted, and its spans can overlap. Windows shorter than a minute are left out.
+pub fn free_time(busy: &[TimeSpan], from: DateTime<Local>, until: DateTime<Local>) -> Vec<TimeSpan> {
+    let mut busy = busy.to_vec();
+    busy.sort_by_key(|b| b.start);
+
+    let mut free = Vec::new();
+    let mut free_from = from;
+    for b in busy {
+        if free_from >= until {
+            break;
+        }
+        if b.start > free_from {
+            free.push(TimeSpan::between(free_from, b.start.min(until)));
+        }
+        free_from = free_from.max(b.end());
+    }
+    if free_from < until {
+        free.push(TimeSpan::between(free_from, until));
+    }
+    free.retain(|f| f.minutes > 0);
+    free
+}
+
 /// How to repeat a span of time.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub enum Repeat {
     /// The span of time only occurs once.
+    #[default]
     No,
 
     /// The span of time repeats daily at the same time every day.
@@ -159,52 +346,405 @@ pub enum Repeat {
 
     /// The span of time repeats weekly.
     Weekly,
+
+    /// The span of time repeats every month on `day_of_month`, or on the last day of months that
+    /// are too short.
+    Monthly { day_of_month: u32 },
+
+    /// The span of time repeats every year on the same date. Something on February 29 happens on
+    /// February 28 in other years.
+    Yearly,
+
+    /// The span of time repeats every so many days, like a workout every 3 days.
+    EveryNDays(u32),
+
+    /// The span of time repeats every so many weeks, like a meeting every other week.
+    EveryNWeeks(u32),
 }
 
 impl Repeat {
-    pub fn prompt(formatting: &Formatting) -> SparrowResult<Self> {
-        prompt_strict(formatting, "Repeat?", Some("[N]o, [d]aily, [w]eekly"), |i| {
+    /// Asks how to repeat something that first starts at `first`. Monthly repeats happen on the
+    /// same day of the month as `first`.
+    pub fn prompt(formatting: &Formatting, first: &DateTime<Local>) -> SparrowResult<Self> {
+        let hint = "[N]o, [d]aily, [w]eekly, [m]onthly, [y]early, or like \"every 2 weeks\"";
+        prompt_strict(formatting, "Repeat?", Some(hint), None, |i| {
             let i = i.trim().to_lowercase();
-            if i.is_empty() || "no".starts_with(&i) {
+            if let Some(every) = Self::parse_every(&i) {
+                Ok(every)
+            } else if i.is_empty() || "no".starts_with(&i) {
                 Ok(Self::No)
             } else if "daily".starts_with(&i) {
                 Ok(Self::Daily)
             } else if "weekly".starts_with(&i) {
                 Ok(Self::Weekly)
+            } else if "monthly".starts_with(&i) {
+                Ok(Self::Monthly { day_of_month: first.day() })
+            } else if "yearly".starts_with(&i) {
+                Ok(Self::Yearly)
             } else {
                 Err(SparrowError::BasicMessage(String::from("What?")))
             }
         })
     }
+
+    /// Reads a repeat like "every 3 days" or "every other week".
+    fn parse_every(input: &str) -> Option<Self> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (amount, unit) = match words.as_slice() {
+            ["every", unit] => (1, *unit),
+            ["every", "other", unit] => (2, *unit),
+            ["every", amount, unit] => (amount.parse().ok().filter(|a| *a > 0)?, *unit),
+            _ => return None,
+        };
+        match unit.trim_end_matches('s') {
+            "day" => Some(Self::EveryNDays(amount)),
+            "week" => Some(Self::EveryNWeeks(amount)),
+            _ => None,
+        }
+    }
+
+    /// Returns the start of the `n`th occurrence (counting from 0) of something that first starts
+    /// at `first`, if there is one.
+    pub fn nth_start<Z: TimeZone>(&self, first: &DateTime<Z>, n: i64) -> Option<DateTime<Z>> {
+        let days = match self {
+            Self::No => return if n == 0 { Some(first.clone()) } else { None },
+            Self::Daily => n,
+            Self::Weekly => n * 7,
+            Self::EveryNDays(every) => n * (*every).max(1) as i64,
+            Self::EveryNWeeks(every) => n * 7 * (*every).max(1) as i64,
+            Self::Monthly { day_of_month } => return nth_month(first, n, *day_of_month),
+            Self::Yearly => return nth_month(first, n * 12, first.day()),
+        };
+
+        (first.date() + chrono::Duration::days(days)).and_time(first.time())
+    }
+
+    /// Returns the index of an occurrence that starts no later than `when`, so that expansion can
+    /// begin there instead of at the very first occurrence.
+    fn index_before(&self, first: &DateTime<Local>, when: &DateTime<Local>) -> i64 {
+        // one day of leeway keeps daylight saving changes from skipping an occurrence
+        let days = (when.date() - first.date()).num_days() - 1;
+        let months = (when.year() - first.year()) as i64 * 12 + when.month0() as i64
+            - first.month0() as i64
+            - 1;
+        let index = match self {
+            Self::No => 0,
+            Self::Daily => days,
+            Self::Weekly => days / 7,
+            Self::EveryNDays(every) => days / (*every).max(1) as i64,
+            Self::EveryNWeeks(every) => days / (7 * (*every).max(1) as i64),
+            Self::Monthly { .. } => months,
+            Self::Yearly => months / 12,
+        };
+
+        index.max(0)
+    }
+}
+
+impl Display for Repeat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::No => write!(f, "never"),
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly => write!(f, "weekly"),
+            Self::Monthly { .. } => write!(f, "monthly"),
+            Self::Yearly => write!(f, "yearly"),
+            Self::EveryNDays(every) => write!(f, "every {} days", every),
+            Self::EveryNWeeks(every) => write!(f, "every {} weeks", every),
+        }
+    }
+}
+
+/// Returns the time `months` months after `first`, on `day` of that month, or on its last day if
+/// the month is too short.
+fn nth_month<Z: TimeZone>(first: &DateTime<Z>, months: i64, day: u32) -> Option<DateTime<Z>> {
+    let month0 = first.month0() as i64 + months;
+    let year = first.year() + month0.div_euclid(12) as i32;
+    let month = month0.rem_euclid(12) as u32 + 1;
+    let date = (1..=day).rev().find_map(|d| NaiveDate::from_ymd_opt(year, month, d))?;
+
+    first.timezone().from_local_date(&date).single()?.and_time(first.time())
+}
+
+/// The occurrences of a repeated TimeSpan within a range of time. Occurrences are computed as
+/// they're needed, and never past the end of the range.
+pub struct Occurrences {
+    first: TimeSpan,
+    repeat: Repeat,
+    next_index: i64,
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+    repeat_until: Option<DateTime<Local>>,
+    repeat_count: Option<i64>,
+    exceptions: Vec<NaiveDate>,
+    timezone: Option<Tz>,
+}
+
+impl Occurrences {
+    pub fn new(
+        first: TimeSpan,
+        repeat: Repeat,
+        from: DateTime<Local>,
+        until: DateTime<Local>,
+    ) -> Self {
+        // start early enough to include an occurrence that's already going on at `from`
+        let next_index = repeat.index_before(first.start(), &(from - first.minutes_as_duration()));
+        Self {
+            first,
+            repeat,
+            next_index,
+            from,
+            until,
+            repeat_until: None,
+            repeat_count: None,
+            exceptions: Vec::new(),
+            timezone: None,
+        }
+    }
+
+    /// Stops the occurrences after `repeat_until`, or after `repeat_count` of them, whichever
+    /// comes first.
+    pub fn ending(
+        mut self,
+        repeat_until: Option<DateTime<Local>>,
+        repeat_count: Option<u32>,
+    ) -> Self {
+        self.repeat_until = repeat_until;
+        self.repeat_count = repeat_count.map(i64::from);
+        self
+    }
+
+    /// Leaves out the occurrences that start on any of `exceptions`.
+    pub fn except(mut self, exceptions: &[NaiveDate]) -> Self {
+        self.exceptions = exceptions.to_vec();
+        self
+    }
+
+    /// Repeats at the same time of day in `timezone`, instead of the local timezone.
+    pub fn in_timezone(mut self, timezone: Option<Tz>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    fn nth_start(&self, n: i64) -> Option<DateTime<Local>> {
+        let first = self.first.start();
+        match self.timezone {
+            Some(tz) => {
+                let start = self.repeat.nth_start(&first.with_timezone(&tz), n)?;
+                Some(start.with_timezone(&Local))
+            }
+            None => self.repeat.nth_start(first, n),
+        }
+    }
+}
+
+impl Iterator for Occurrences {
+    type Item = TimeSpan;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let n = self.next_index;
+            if n > 0 && self.repeat == Repeat::No {
+                return None;
+            }
+            if self.repeat_count.is_some_and(|count| n >= count) {
+                return None;
+            }
+            self.next_index += 1;
+
+            // a start time can fail to exist locally (daylight saving), so that occurrence is
+            // skipped
+            if let Some(start) = self.nth_start(n) {
+                if start >= self.until || self.repeat_until.is_some_and(|u| start > u) {
+                    return None;
+                }
+
+                let span = TimeSpan::new(start, self.first.minutes);
+                if span.end() > self.from
+                    && !self.exceptions.contains(&start.date().naive_local())
+                {
+                    return Some(span);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Bedtime {
     start: NaiveTime,
     hours: f32,
+
+    /// Nights of the week that are different, like later ones on weekends.
+    #[serde(default)]
+    overrides: Vec<BedtimeOverride>,
+}
+
+/// Sleep on one night of the week, instead of the usual.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BedtimeOverride {
+    /// The day the night starts on.
+    pub weekday: Weekday,
+    pub start: NaiveTime,
+    pub hours: f32,
 }
 
 impl Bedtime {
-    pub fn new(start: NaiveTime, hours: f32) -> Self { Self { start, hours } }
+    pub fn new(start: NaiveTime, hours: f32) -> Self {
+        Self {
+            start,
+            hours,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Asks for the usual time to sleep and how long for, then for the nights that are
+    /// different. Answers default to `current`.
+    pub fn prompt(
+        formatting: &Formatting,
+        config: &Config,
+        current: &Bedtime,
+    ) -> SparrowResult<Self> {
+        let (start, hours) = prompt_sleep(
+            formatting,
+            config,
+            "When do you usually go to sleep?",
+            "How many hours do you usually sleep?",
+            (current.start, current.hours),
+        )?;
+        let mut bedtime = Self::new(start, hours);
+
+        let different: Vec<String> =
+            current.overrides.iter().map(|o| o.weekday.to_string()).collect();
+        let weekdays = prompt_strict(
+            formatting,
+            "Which nights are different?",
+            Some("like 'fri, sat', or nothing for none"),
+            Some(&different.join(", ")),
+            |i| -> SparrowResult<Vec<Weekday>> {
+                i.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|d| !d.is_empty())
+                    .map(|d| {
+                        d.parse::<Weekday>().map_err(|_| {
+                            SparrowError::BasicMessage(format!("'{}' isn't a day of the week", d))
+                        })
+                    })
+                    .collect()
+            },
+        )?;
+
+        for weekday in weekdays {
+            let usual = match current.overrides.iter().find(|o| o.weekday == weekday) {
+                Some(o) => (o.start, o.hours),
+                None => (start, hours),
+            };
+            let (start, hours) = prompt_sleep(
+                formatting,
+                config,
+                &format!("When do you go to sleep on {} nights?", weekday),
+                &format!("How many hours do you sleep on {} nights?", weekday),
+                usual,
+            )?;
+            bedtime.overrides.retain(|o| o.weekday != weekday);
+            bedtime.overrides.push(BedtimeOverride {
+                weekday,
+                start,
+                hours,
+            });
+        }
+
+        Ok(bedtime)
+    }
 
+    /// Returns the usual time to go to sleep.
     pub fn start(&self) -> &NaiveTime {
         &self.start
     }
 
+    /// Returns the usual time to wake up.
     pub fn end(&self) -> NaiveTime {
         self.start + chrono::Duration::minutes((self.hours * 60.0) as i64)
     }
 
+    /// Returns how many hours of sleep there usually are.
     pub fn duration_hours(&self) -> f32 {
         self.hours
     }
+
+    pub fn overrides(&self) -> &[BedtimeOverride] {
+        &self.overrides
+    }
+
+    /// Returns when sleep starts and how many hours it lasts on nights starting on `weekday`.
+    pub fn on_weekday(&self, weekday: Weekday) -> (NaiveTime, f32) {
+        match self.overrides.iter().find(|o| o.weekday == weekday) {
+            Some(o) => (o.start, o.hours),
+            None => (self.start, self.hours),
+        }
+    }
+
+    /// Returns the time to wake up on `day`, after the night before it.
+    pub fn wake_on(&self, day: NaiveDate) -> NaiveTime {
+        let (start, hours) = self.on_weekday(day.pred().weekday());
+        start + chrono::Duration::minutes((hours * 60.0) as i64)
+    }
+
+    /// Returns the sleep on the night starting on `night`.
+    pub fn on_night(&self, night: NaiveDate) -> Option<TimeSpan> {
+        let (start, hours) = self.on_weekday(night.weekday());
+
+        // going to sleep in the morning means after midnight, on the next day
+        let day = if start.hour() < 12 { night.succ() } else { night };
+        let start = Local.from_local_datetime(&day.and_time(start)).earliest()?;
+        Some(TimeSpan::new(start, (hours * 60.0) as u32))
+    }
+
+    /// Returns every time of sleep that overlaps `from..until`.
+    pub fn occurrences(
+        &self,
+        from: DateTime<Local>,
+        until: DateTime<Local>,
+    ) -> impl Iterator<Item = TimeSpan> + '_ {
+        // sleep can start on the day before `from` and still be going on
+        let first_night = from.date().naive_local().pred();
+        let last_night = until.date().naive_local();
+        std::iter::successors(Some(first_night), |n| n.succ_opt())
+            .take_while(move |n| *n <= last_night)
+            .filter_map(move |n| self.on_night(n))
+            .filter(move |s| s.end() > from && *s.start() < until)
+    }
+}
+
+/// Asks when sleep starts and how many hours it lasts, defaulting to `current`.
+fn prompt_sleep(
+    formatting: &Formatting,
+    config: &Config,
+    start_question: &str,
+    hours_question: &str,
+    current: (NaiveTime, f32),
+) -> SparrowResult<(NaiveTime, f32)> {
+    let current_start = current.0.format(&config.time_format).to_string();
+    let start = prompt_strict(
+        formatting,
+        start_question,
+        Some(&config.time_format),
+        Some(&current_start),
+        |i| {
+            crate::dates::parse_time(i, &config.time_format)
+                .ok_or_else(|| SparrowError::BasicMessage(String::from("What?")))
+        },
+    )?;
+    let hours = prompt_strict(formatting, hours_question, None, Some(&current.1.to_string()), |i| {
+        match i.trim().parse::<f32>() {
+            Ok(h) if h > 0.0 && h < 24.0 => Ok(h),
+            _ => Err(SparrowError::BasicMessage(String::from("That's not a number of hours."))),
+        }
+    })?;
+    Ok((start, hours))
 }
 
 impl Default for Bedtime {
     fn default() -> Self {
-        Self {
-            start: NaiveTime::from_hms(20, 0, 0),
-            hours: 10.0,
-        }
+        Self::new(NaiveTime::from_hms(20, 0, 0), 10.0)
     }
 }
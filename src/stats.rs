@@ -0,0 +1,218 @@
+//! `sparrow stats`: work periods done, time worked by task, course, and tag, tasks finished, and
+//! how much work is left without a place in the schedule.
+
+use crate::{
+    goals::GoalProgress,
+    methods::pomodoro::{self, JobStatus},
+    metrics::format_minutes,
+    UserData,
+};
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+#[derive(Serialize)]
+pub struct Stats {
+    /// The first day counted, or None if everything is.
+    pub since: Option<NaiveDate>,
+
+    /// Work periods that have started, and what became of them.
+    pub scheduled_periods: u32,
+    pub done_periods: u32,
+    pub skipped_periods: u32,
+
+    /// Minutes worked, tracked or in work periods marked done.
+    pub minutes_by_task: BTreeMap<String, i64>,
+    pub minutes_by_course: BTreeMap<String, i64>,
+    pub minutes_by_tag: BTreeMap<String, i64>,
+
+    /// Tasks (and instances of repeating ones) due by now, and how many of them are done.
+    pub tasks_due: u32,
+    pub tasks_done: u32,
+
+    /// Minutes of unfinished work that the pomodoro schedule has no room for.
+    pub backlog_minutes: u64,
+    pub points_at_risk: f64,
+
+    pub streak: u32,
+    pub sleep_debt_minutes: u32,
+
+    /// How each goal is going this week, whatever `since` is.
+    pub goals: Vec<GoalProgress>,
+}
+
+impl Stats {
+    /// Adds up everything since the day `since` if given, or else ever.
+    pub fn new(data: &UserData, since: Option<NaiveDate>) -> Self {
+        let now = Local::now();
+        let counts = |date: NaiveDate| since.is_none_or(|s| date >= s);
+
+        let mut stats = Self {
+            since,
+            scheduled_periods: 0,
+            done_periods: 0,
+            skipped_periods: 0,
+            minutes_by_task: BTreeMap::new(),
+            minutes_by_course: BTreeMap::new(),
+            minutes_by_tag: BTreeMap::new(),
+            tasks_due: 0,
+            tasks_done: 0,
+            backlog_minutes: 0,
+            points_at_risk: 0.0,
+            streak: data.streak(),
+            sleep_debt_minutes: data.sleep_debt(),
+            goals: Vec::new(),
+        };
+
+        for job in data.all_jobs() {
+            let start = *job.span().start();
+            if start > now || !counts(start.date().naive_local()) {
+                continue;
+            }
+            stats.scheduled_periods += 1;
+            match job.status() {
+                Some(JobStatus::Done) => stats.done_periods += 1,
+                Some(JobStatus::Skipped) => stats.skipped_periods += 1,
+                _ => {}
+            }
+        }
+
+        let worked = data.worked();
+        stats.goals = data
+            .get_config()
+            .goals
+            .iter()
+            .map(|g| GoalProgress::new(g, &worked, data.get_tasks(), now))
+            .collect();
+
+        for w in worked {
+            if !counts(w.span.start().date().naive_local()) {
+                continue;
+            }
+            let task = match data.get_tasks().iter().find(|t| t.id == w.task_id) {
+                Some(t) => t,
+                None => continue,
+            };
+            let minutes = w.span.minutes() as i64;
+            *stats.minutes_by_task.entry(task.name.clone()).or_insert(0) += minutes;
+            if let Some(course) = &task.course {
+                *stats.minutes_by_course.entry(course.clone()).or_insert(0) += minutes;
+            }
+            for tag in &task.tags {
+                *stats.minutes_by_tag.entry(tag.clone()).or_insert(0) += minutes;
+            }
+        }
+
+        for task in data.get_tasks() {
+            // a repeating task's instances start after the day before its first due date
+            let from = match since {
+                Some(s) => Local
+                    .from_local_date(&s)
+                    .earliest()
+                    .map_or(task.due_date, |d| d.and_hms(0, 0, 0)),
+                None => task.due_date - chrono::Duration::days(1),
+            };
+            for instance in task.instances(from, now) {
+                if instance.due_date <= now && instance.due_date >= from {
+                    stats.tasks_due += 1;
+                    if instance.done {
+                        stats.tasks_done += 1;
+                    }
+                }
+            }
+        }
+
+        let entries = match data.get_pomodoro_schedule() {
+            Some(schedule) => schedule.get_entries(),
+            None => &[],
+        };
+        let metrics = pomodoro::entry_metrics(data.get_config(), entries, data.get_tasks());
+        stats.backlog_minutes = metrics
+            .requested_minutes
+            .saturating_sub(metrics.scheduled_minutes);
+        stats.points_at_risk = metrics.points_at_risk;
+
+        stats
+    }
+}
+
+/// Returns `part` as a percentage of `whole`, or 100 if `whole` is 0.
+fn percent(part: u32, whole: u32) -> u32 {
+    (part * 100).checked_div(whole).unwrap_or(100)
+}
+
+fn write_minutes(
+    f: &mut Formatter<'_>,
+    heading: &str,
+    minutes: &BTreeMap<String, i64>,
+) -> std::fmt::Result {
+    if minutes.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "\n{}:", heading)?;
+    for (name, m) in minutes {
+        writeln!(f, "-\t{}: {}", name, format_minutes(*m))?;
+    }
+    Ok(())
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.since {
+            Some(since) => writeln!(f, "Since {}:", since.format("%a %b %-d"))?,
+            None => writeln!(f, "All time:")?,
+        }
+
+        write!(
+            f,
+            "Work periods:\t{} of {} done ({}%)",
+            self.done_periods,
+            self.scheduled_periods,
+            percent(self.done_periods, self.scheduled_periods)
+        )?;
+        if self.skipped_periods > 0 {
+            write!(f, ", {} skipped", self.skipped_periods)?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Tasks due:\t{} of {} done ({}%)",
+            self.tasks_done,
+            self.tasks_due,
+            percent(self.tasks_done, self.tasks_due)
+        )?;
+        write!(
+            f,
+            "Backlog:\t{} unscheduled",
+            format_minutes(self.backlog_minutes as i64)
+        )?;
+        if self.points_at_risk > 0.0 {
+            write!(f, " ({} points at risk)", self.points_at_risk)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Streak:\t\t{} days", self.streak)?;
+        if self.sleep_debt_minutes > 0 {
+            writeln!(
+                f,
+                "Sleep debt:\t{}",
+                format_minutes(self.sleep_debt_minutes as i64)
+            )?;
+        }
+
+        write_minutes(f, "By task", &self.minutes_by_task)?;
+        write_minutes(f, "By course", &self.minutes_by_course)?;
+        write_minutes(f, "By tag", &self.minutes_by_tag)?;
+
+        if !self.goals.is_empty() {
+            writeln!(f, "\nGoals:")?;
+            for g in &self.goals {
+                writeln!(f, "-\t{}", g)?;
+            }
+        }
+        Ok(())
+    }
+}
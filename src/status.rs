@@ -0,0 +1,170 @@
+//! `sparrow status`: what's going on now, how long it has left, and what's next, all on one line
+//! for status bars like i3bar, polybar, or waybar.
+
+use crate::{
+    methods::pomodoro::PomodoroScheduleEntry, metrics::format_minutes, tracking::TimeEntry, Config,
+    Task, UserData,
+};
+use chrono::{DateTime, Local};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+pub struct Status<'a> {
+    current: Option<&'a PomodoroScheduleEntry>,
+    next: Option<&'a PomodoroScheduleEntry>,
+    now: DateTime<Local>,
+    config: &'a Config,
+    tasks: &'a [Task],
+
+    /// The task being timed with `sparrow start`, and its running entry.
+    tracking: Option<(&'a Task, &'a TimeEntry)>,
+}
+
+impl<'a> Status<'a> {
+    /// Returns the status of the pomodoro schedule in `data` at `now`.
+    pub fn new(data: &'a UserData, now: DateTime<Local>) -> Self {
+        let entries = match data.get_pomodoro_schedule() {
+            Some(schedule) => schedule.get_entries(),
+            None => &[],
+        };
+        let tracking = data.running_time_entry().and_then(|entry| {
+            let task = data.get_tasks().iter().find(|t| t.id == entry.task_id)?;
+            Some((task, entry))
+        });
+        Self {
+            tracking,
+            ..Self::of_entries(entries, now, data.get_config(), data.get_tasks())
+        }
+    }
+
+    /// Returns the status of `entries` at `now`.
+    pub fn of_entries(
+        entries: &'a [PomodoroScheduleEntry],
+        now: DateTime<Local>,
+        config: &'a Config,
+        tasks: &'a [Task],
+    ) -> Self {
+        Self {
+            current: entries
+                .iter()
+                .find(|e| *e.span().start() <= now && now < e.span().end()),
+            next: entries.iter().find(|e| *e.span().start() > now),
+            now,
+            config,
+            tasks,
+            tracking: None,
+        }
+    }
+
+    /// Returns what's going on now, as a word for bars to style by: "work", "break", "sleep",
+    /// "calendar", "habit", or "idle" if nothing is.
+    pub fn class(&self) -> &'static str {
+        match self.current {
+            Some(PomodoroScheduleEntry::Job { .. }) => "work",
+            Some(e) => e.kind(),
+            None => "idle",
+        }
+    }
+
+    /// Returns the minutes left in the current entry, or until the next one if nothing is going
+    /// on.
+    pub fn minutes_left(&self) -> Option<i64> {
+        let until = match self.current {
+            Some(current) => current.span().end(),
+            None => *self.next?.span().start(),
+        };
+        Some((until - self.now).num_minutes().max(0))
+    }
+
+    /// Returns how much of the current entry is over, from 0 to 100.
+    pub fn percentage(&self) -> u32 {
+        match self.current {
+            Some(current) => {
+                let span = current.span();
+                let elapsed = (self.now - *span.start()).num_minutes() as u32;
+                (elapsed * 100).checked_div(span.minutes()).unwrap_or(0)
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns the name of the task being timed and the minutes on its clock, if one's running.
+    pub fn tracking(&self) -> Option<(&str, i64)> {
+        self.tracking
+            .map(|(task, entry)| (task.name.as_str(), entry.minutes(self.now)))
+    }
+
+    /// Returns the status in the JSON format waybar expects from custom modules.
+    pub fn waybar(&self) -> WaybarStatus {
+        let mut tooltip = match self.next {
+            Some(next) => format!(
+                "Next: {} at {}",
+                next.title(self.tasks),
+                next.span().start().format(&self.config.time_format)
+            ),
+            None => "Nothing else scheduled".to_string(),
+        };
+        if let Some((name, minutes)) = self.tracking() {
+            tooltip += &format!("\nTracking: {} for {}", name, format_minutes(minutes));
+        }
+        WaybarStatus {
+            text: self.to_string(),
+            tooltip,
+            class: self.class(),
+            percentage: self.percentage(),
+        }
+    }
+}
+
+impl Serialize for Status<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry("current", &self.current.map(|e| e.view(self.tasks)))?;
+        map.serialize_entry("minutes_left", &self.minutes_left())?;
+        map.serialize_entry("next", &self.next.map(|e| e.view(self.tasks)))?;
+        map.serialize_entry("class", self.class())?;
+        map.serialize_entry(
+            "tracking",
+            &self
+                .tracking()
+                .map(|(task, minutes)| serde_json::json!({ "task": task, "minutes": minutes })),
+        )?;
+        map.end()
+    }
+}
+
+impl Display for Status<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.current, self.minutes_left()) {
+            (Some(current), Some(left)) => write!(
+                f,
+                "{} ({} left)",
+                current.title(self.tasks),
+                format_minutes(left)
+            )?,
+            (None, Some(left)) => write!(f, "Free for {}", format_minutes(left))?,
+            _ => return write!(f, "Nothing scheduled"),
+        }
+        if let Some(next) = self.next {
+            write!(
+                f,
+                " | Next: {} at {}",
+                next.title(self.tasks),
+                next.span().start().format(&self.config.time_format)
+            )?;
+        }
+        if let Some((name, minutes)) = self.tracking() {
+            write!(f, " | Tracking: {} for {}", name, format_minutes(minutes))?;
+        }
+        Ok(())
+    }
+}
+
+/// A status line for a waybar custom module.
+#[derive(Serialize)]
+pub struct WaybarStatus {
+    pub text: String,
+    pub tooltip: String,
+    pub class: &'static str,
+    pub percentage: u32,
+}
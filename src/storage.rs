@@ -0,0 +1,350 @@
+//! Where user data is kept. Data files are YAML, JSON, or TOML, going by their extension (YAML if
+//! there isn't one). With the `sqlite` feature, data files ending in `.db` are SQLite databases
+//! instead. Tasks and events get tables of their own there, so they can be queried with `sqlite3`;
+//! everything else is kept as JSON by name.
+//!
+//! Whatever the format, data from older versions of sparrow is upgraded by `migrations` as it's
+//! loaded.
+//!
+//! By default, data is kept in `$XDG_DATA_HOME/sparrow/data.yaml` and the config in
+//! `$XDG_CONFIG_HOME/sparrow/config.yaml`, so changing settings (by hand or otherwise) doesn't
+//! mean touching task data. Any other data file, including the old `~/.sparrow` (which is still
+//! used if it's there and the new one isn't), has its config inside it.
+
+use crate::{migrations, Config, SparrowError, SparrowResult, UserData};
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a data file is written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    Yaml,
+    Json,
+    Toml,
+    Sqlite,
+}
+
+impl DataFormat {
+    /// Goes by the extension of `path`. Files without a known extension, like `~/.sparrow`, are
+    /// YAML.
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| Self::try_from(e).ok())
+            .unwrap_or(Self::Yaml)
+    }
+}
+
+impl TryFrom<&str> for DataFormat {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "db" | "sqlite" | "sqlite3" => Ok(Self::Sqlite),
+            _ => Err(SparrowError::BasicMessage(format!(
+                "'{}' isn't a data format sparrow knows; try yaml, json, toml, or sqlite",
+                value
+            ))),
+        }
+    }
+}
+
+/// Returns the data file to use when none is given: the XDG data file, or the old `~/.sparrow` if
+/// only it exists.
+pub fn default_data_path() -> PathBuf {
+    let legacy = dirs::home_dir().unwrap_or_default().join(".sparrow");
+    match xdg_data_path() {
+        Some(path) if path.exists() || !legacy.exists() => path,
+        _ => legacy,
+    }
+}
+
+fn xdg_data_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("sparrow").join("data.yaml"))
+}
+
+/// Returns the separate config file for the data file at `data_path`, if it has one. Only the XDG
+/// data file does.
+pub fn config_path_for(data_path: &Path) -> Option<PathBuf> {
+    if Some(data_path) == xdg_data_path().as_deref() {
+        Some(dirs::config_dir()?.join("sparrow").join("config.yaml"))
+    } else {
+        None
+    }
+}
+
+/// Returns the config from the separate config file for the data file at `data_path`, if it has
+/// one and it exists.
+pub fn load_config(data_path: &Path) -> SparrowResult<Option<Config>> {
+    match config_path_for(data_path) {
+        Some(path) if path.exists() => Ok(Some(serde_yaml::from_str(&fs::read_to_string(path)?)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Writes `config` to the separate config file for the data file at `data_path`, if it has one.
+/// The config file is left alone if it wouldn't change.
+pub fn save_config(data_path: &Path, config: &Config) -> SparrowResult<()> {
+    let path = match config_path_for(data_path) {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+    let text = serde_yaml::to_string(config)?;
+    if fs::read_to_string(&path).is_ok_and(|old| old == text) {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, text)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// Loads and saves user data somewhere.
+pub trait Storage {
+    fn load(&self) -> SparrowResult<UserData>;
+    fn save(&self, data: &UserData) -> SparrowResult<()>;
+}
+
+/// Returns the storage for the data file at `path`, going by its extension.
+pub fn for_path(path: &Path) -> SparrowResult<Box<dyn Storage>> {
+    open(path, DataFormat::from_path(path))
+}
+
+/// Returns the storage for the data file at `path`, written in `format`.
+pub fn open(path: &Path, format: DataFormat) -> SparrowResult<Box<dyn Storage>> {
+    let separate_config = config_path_for(path).is_some();
+    match format {
+        DataFormat::Sqlite => sqlite(path, separate_config),
+        _ => Ok(Box::new(FileStorage {
+            path: path.to_path_buf(),
+            format,
+            separate_config,
+        })),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite(path: &Path, separate_config: bool) -> SparrowResult<Box<dyn Storage>> {
+    Ok(Box::new(sqlite::SqliteStorage::new(path, separate_config)))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite(path: &Path, _: bool) -> SparrowResult<Box<dyn Storage>> {
+    Err(SparrowError::BasicMessage(format!(
+        "{} looks like a SQLite database, but this sparrow was built without SQLite support \
+         (build it with `--features sqlite`)",
+        path.display()
+    )))
+}
+
+/// Reads user data from `value`, upgrading it to the current layout first.
+fn from_value(value: Value) -> SparrowResult<UserData> {
+    Ok(serde_json::from_value(migrations::upgrade(value)?)?)
+}
+
+/// A data file in one of the text formats.
+pub struct FileStorage {
+    path: PathBuf,
+    format: DataFormat,
+
+    /// Leaves the config out of the data file, since it's kept in a file of its own.
+    separate_config: bool,
+}
+
+impl Storage for FileStorage {
+    fn load(&self) -> SparrowResult<UserData> {
+        let text = fs::read_to_string(&self.path)?;
+        let value: Value = match self.format {
+            DataFormat::Json => serde_json::from_str(&text)?,
+            DataFormat::Toml => toml::from_str(&text)?,
+            _ => serde_yaml::from_str(&text)?,
+        };
+        from_value(value)
+    }
+
+    /// Writes the data to a temporary file next to the data file first, then moves it over the
+    /// data file, so that an interrupted write never leaves a half-written data file behind.
+    fn save(&self, data: &UserData) -> SparrowResult<()> {
+        let text = if self.separate_config {
+            // serde_yaml's values keep fields in order, unlike serde_json's
+            let mut value = serde_yaml::to_value(data)?;
+            if let serde_yaml::Value::Mapping(fields) = &mut value {
+                fields.remove(&serde_yaml::Value::from("config"));
+            }
+            match self.format {
+                DataFormat::Json => serde_json::to_string_pretty(&value)?,
+                DataFormat::Toml => toml::to_string(&without_nulls(value))?,
+                _ => serde_yaml::to_string(&value)?,
+            }
+        } else {
+            match self.format {
+                DataFormat::Json => serde_json::to_string_pretty(data)?,
+                DataFormat::Toml => toml::to_string(data)?,
+                _ => serde_yaml::to_string(data)?,
+            }
+        };
+
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, text)?;
+        fs::rename(temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// TOML has no null, so fields set to nothing are left out instead (like TOML does for `None`).
+fn without_nulls(value: serde_yaml::Value) -> serde_yaml::Value {
+    match value {
+        serde_yaml::Value::Mapping(fields) => serde_yaml::Value::Mapping(
+            fields
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect(),
+        ),
+        serde_yaml::Value::Sequence(items) => {
+            serde_yaml::Value::Sequence(items.into_iter().map(without_nulls).collect())
+        }
+        other => other,
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::{from_value, Storage};
+    use crate::{SparrowResult, UserData};
+    use rusqlite::{params, Connection};
+    use serde_json::{Map, Value};
+    use std::path::{Path, PathBuf};
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS tasks (
+            position INTEGER PRIMARY KEY,
+            id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            due TEXT NOT NULL,
+            minutes INTEGER NOT NULL,
+            done INTEGER NOT NULL,
+            json TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS events (
+            position INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            start TEXT NOT NULL,
+            minutes INTEGER NOT NULL,
+            json TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS data (
+            key TEXT PRIMARY KEY,
+            json TEXT NOT NULL
+        );";
+
+    pub struct SqliteStorage {
+        path: PathBuf,
+
+        /// Leaves the config out of the database, since it's kept in a file of its own.
+        separate_config: bool,
+    }
+
+    impl SqliteStorage {
+        pub fn new(path: &Path, separate_config: bool) -> Self {
+            Self {
+                path: path.to_path_buf(),
+                separate_config,
+            }
+        }
+
+        fn open(&self) -> SparrowResult<Connection> {
+            let connection = Connection::open(&self.path)?;
+            connection.execute_batch(SCHEMA)?;
+            Ok(connection)
+        }
+    }
+
+    impl Storage for SqliteStorage {
+        fn load(&self) -> SparrowResult<UserData> {
+            let connection = self.open()?;
+            let mut object = Map::new();
+
+            let mut statement = connection.prepare("SELECT key, json FROM data")?;
+            let rows = statement.query_map([], |r| Ok((r.get(0)?, r.get::<_, String>(1)?)))?;
+            for row in rows {
+                let (key, json) = row?;
+                object.insert(key, serde_json::from_str(&json)?);
+            }
+
+            for table in &["tasks", "events"] {
+                let mut statement =
+                    connection.prepare(&format!("SELECT json FROM {} ORDER BY position", table))?;
+                let items = statement
+                    .query_map([], |r| r.get::<_, String>(0))?
+                    .map(|json| Ok(serde_json::from_str(&json?)?))
+                    .collect::<SparrowResult<Vec<Value>>>()?;
+                object.insert(table.to_string(), Value::Array(items));
+            }
+
+            from_value(Value::Object(object))
+        }
+
+        /// Replaces everything in the database in one transaction, so readers never see half of
+        /// a save.
+        fn save(&self, data: &UserData) -> SparrowResult<()> {
+            let mut connection = self.open()?;
+            let transaction = connection.transaction()?;
+            transaction
+                .execute_batch("DELETE FROM tasks; DELETE FROM events; DELETE FROM data;")?;
+
+            for (position, task) in data.get_tasks().iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO tasks (position, id, name, due, minutes, done, json)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        position as i64,
+                        task.id,
+                        task.name,
+                        task.due_date.to_rfc3339(),
+                        task.duration.total_minutes() as i64,
+                        task.done,
+                        serde_json::to_string(task)?
+                    ],
+                )?;
+            }
+            for (position, event) in data.get_events().iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO events (position, name, start, minutes, json)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        position as i64,
+                        event.name,
+                        event.time_span.start().to_rfc3339(),
+                        event.time_span.minutes(),
+                        serde_json::to_string(event)?
+                    ],
+                )?;
+            }
+
+            if let Value::Object(object) = serde_json::to_value(data)? {
+                for (key, value) in object {
+                    let separate = self.separate_config && key == "config";
+                    if key != "tasks" && key != "events" && !separate {
+                        transaction.execute(
+                            "INSERT INTO data (key, json) VALUES (?1, ?2)",
+                            params![key, value.to_string()],
+                        )?;
+                    }
+                }
+            }
+
+            transaction.commit()?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Streaks count the days in a row on which every scheduled work period was done.
+
+use crate::methods::pomodoro::{JobStatus, PomodoroScheduleEntry};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Returns, for each day with any jobs in `entries`, whether all of that day's jobs are done.
+pub fn work_day_results<'a, I>(entries: I) -> BTreeMap<NaiveDate, bool>
+where
+    I: IntoIterator<Item = &'a PomodoroScheduleEntry>,
+{
+    let mut results = BTreeMap::new();
+    for e in entries {
+        if let Some(status) = e.status() {
+            let day = e.span().start().date().naive_local();
+            let all_done = results.entry(day).or_insert(true);
+            *all_done &= status == JobStatus::Done;
+        }
+    }
+    results
+}
+
+/// Counts the days in a row, up to `today`, on which every job was done. Days without jobs
+/// neither count nor break the streak, and neither does today until it's finished.
+pub fn current_streak(work_days: &BTreeMap<NaiveDate, bool>, today: NaiveDate) -> u32 {
+    let mut streak = 0;
+    for (day, all_done) in work_days.range(..=today).rev() {
+        if *all_done {
+            streak += 1;
+        } else if *day != today {
+            break;
+        }
+    }
+    streak
+}
@@ -2,12 +2,31 @@ use crate::errors::SparrowError;
 use crate::prompts::*;
 use crate::Config;
 use crate::Formatting;
+use crate::Repeat;
+use crate::TimeSpan;
 use chrono::DateTime;
+use chrono::Duration;
 use chrono::Local;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// Identifies a Task. IDs are assigned by `UserData` when a Task is added; 0 means the Task hasn't
+/// been given one yet.
+pub type TaskId = u32;
+
+/// Identifies a Subtask within its Task. Like Task IDs, they're assigned when the Task is added,
+/// and 0 means the Subtask hasn't been given one yet.
+pub type SubtaskId = u32;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Task {
+    /// Identifies the task, even if it's renamed.
+    #[serde(default)]
+    pub id: TaskId,
+
     /// The name of the task.
     pub name: String,
 
@@ -23,41 +42,167 @@ pub struct Task {
     /// How many days in advance of the Task's due date this Task should be considered for
     /// scheduling.
     pub consideration_period_days: u32,
+
+    /// Work on the Task isn't scheduled before this, like when it's waiting on feedback.
+    #[serde(default)]
+    pub deferred_until: Option<DateTime<Local>>,
+
+    /// The name of the course the Task is for, if any.
+    #[serde(default)]
+    pub course: Option<String>,
+
+    /// How much the Task is worth, like assignment points or course credits. When there isn't
+    /// time for everything, heavier Tasks are scheduled first.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+
+    /// How urgent the Task is. Higher priority Tasks get free time before lower priority ones,
+    /// whatever their due dates.
+    #[serde(default)]
+    pub priority: Priority,
+
+    /// True if the Task matters in the long run, rather than just being due soon. Used by the
+    /// Eisenhower matrix.
+    #[serde(default)]
+    pub important: bool,
+
+    /// Labels for grouping tasks, like "school" or "work".
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// The IDs of Tasks that have to be finished before work on this Task starts.
+    #[serde(default)]
+    pub depends_on: Vec<TaskId>,
+
+    /// How often the Task comes back, like homework that's due every Friday. `due_date` is when
+    /// the first instance is due.
+    #[serde(default)]
+    pub repeat: Repeat,
+
+    /// The due dates of the instances of a repeating Task that are finished.
+    #[serde(default)]
+    pub done_instances: Vec<NaiveDate>,
+
+    /// Anything else the user wants to remember about the Task. Can be more than one line.
+    #[serde(default)]
+    pub notes: Option<String>,
+
+    /// Times set aside for work on the Task, like "Tuesday 14:00 to 16:00". Schedules keep these
+    /// where they are and fit the rest of the Task around them.
+    #[serde(default)]
+    pub pins: Vec<Pin>,
+}
+
+/// Work on a Task, or one of its subtasks (by ID), fixed to a certain time.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Pin {
+    pub subtask: Option<SubtaskId>,
+    pub span: TimeSpan,
+}
+
+pub(crate) fn default_weight() -> f64 {
+    1.0
 }
 
 impl Task {
-    pub fn prompt_new(formatting: &Formatting, config: &Config) -> Result<Self, SparrowError> {
-        let name = prompt_strict(
+    /// Asks for a new task. If `due` is given, the user isn't asked when it's due.
+    pub fn prompt_new(
+        formatting: &Formatting,
+        config: &Config,
+        due: Option<DateTime<Local>>,
+    ) -> Result<Self, SparrowError> {
+        let (name, tags) = prompt_strict(
             formatting,
             "What do you want to name this task?",
+            Some("add tags like #school"),
             None,
             |i| {
-                let i = i.trim();
-                if i.is_empty() {
+                let (name, tags) = split_tags(i);
+                if name.is_empty() {
                     Err(SparrowError::BasicMessage(
                         "Trust me, you don't want a task with a blank name".to_string(),
                     ))
                 } else {
-                    Ok(i.to_string())
+                    Ok((name, tags))
                 }
             },
         )?;
 
         // determine due date from user input
-        let due_date = prompt_datetime(formatting, &config.date_format, &config.time_format, true)?;
+        let due_date = match due {
+            Some(due) => due,
+            None => prompt_datetime(formatting, &config.date_format, &config.time_format, true)?,
+        };
+        let repeat = Repeat::prompt(formatting, &due_date)?;
 
         let duration = Self::prompt_task_duration(&name, formatting)?;
 
+        let weight = prompt_strict(
+            formatting,
+            "How much is it worth?",
+            Some("points or credits, or empty for 1"),
+            None,
+            |i| {
+                let i = i.trim();
+                if i.is_empty() {
+                    Ok(default_weight())
+                } else {
+                    match i.parse::<f64>() {
+                        Ok(w) if w >= 0.0 => Ok(w),
+                        _ => Err(SparrowError::BasicMessage(
+                            "Enter a number that isn't negative".to_string(),
+                        )),
+                    }
+                }
+            },
+        )?;
+
+        let priority = prompt_priority(formatting)?;
+
+        let important = prompt_yn(&format!(
+            "{} {}",
+            formatting.prompt.paint("Is it important?"),
+            formatting.prompt_format.paint("[y/N]")
+        ))?
+        .is_some_and(|d| d.is_yes());
+
+        let notes = prompt_notes(formatting)?;
+
         Ok(Self {
+            weight,
+            priority,
+            important,
+            tags,
+            repeat,
+            notes,
+            ..Self::new(name, due_date, duration)
+        })
+    }
+
+    /// Makes a new, unfinished Task. The Task gets an ID once it's added to `UserData`.
+    pub fn new(name: String, due_date: DateTime<Local>, duration: TaskDuration) -> Self {
+        Self {
+            id: 0,
             name,
             due_date,
             duration,
             done: false,
             consideration_period_days: 3,
-        })
+            deferred_until: None,
+            course: None,
+            weight: default_weight(),
+            priority: Priority::Normal,
+            important: false,
+            tags: Vec::new(),
+            depends_on: Vec::new(),
+            repeat: Repeat::No,
+            done_instances: Vec::new(),
+            notes: None,
+            pins: Vec::new(),
+        }
     }
 
-    fn prompt_task_duration(
+    pub(crate) fn prompt_task_duration(
         task_name: &str,
         formatting: &Formatting,
     ) -> Result<TaskDuration, SparrowError> {
@@ -101,9 +246,123 @@ impl Task {
         }
     }
 
-    /// Returns true if `when` is within or after the Task's consideration period.
+    /// Returns true if `when` is within or after the Task's consideration period, and the Task
+    /// isn't deferred past it.
     pub fn is_considered(&self, when: &DateTime<Local>) -> bool {
         (self.due_date - *when).num_days() < self.consideration_period_days as i64
+            && !self.is_deferred(when)
+    }
+
+    /// Returns true if work on the Task can't be scheduled at `when` yet.
+    pub fn is_deferred(&self, when: &DateTime<Local>) -> bool {
+        self.deferred_until.is_some_and(|d| *when < d)
+    }
+
+    /// Returns true if the Task is tagged with `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Returns true if the Task has to wait for the Task with `id`.
+    pub fn depends_on(&self, id: TaskId) -> bool {
+        self.depends_on.contains(&id)
+    }
+
+    /// Returns the due dates of the Task's instances, in order. A Task that doesn't repeat has
+    /// one instance.
+    fn instance_due_dates(&self) -> impl Iterator<Item = DateTime<Local>> + '_ {
+        let count = if self.repeat == Repeat::No {
+            1
+        } else {
+            i64::MAX
+        };
+
+        // a due date can fail to exist locally (daylight saving), so that instance is skipped
+        (0..count).filter_map(move |n| self.repeat.nth_start(&self.due_date, n))
+    }
+
+    /// Returns true if the instance of the Task due at `due_date` is finished.
+    pub fn is_instance_done(&self, due_date: &DateTime<Local>) -> bool {
+        self.done || self.done_instances.contains(&due_date.naive_local().date())
+    }
+
+    /// Returns the instances of the Task that are due after `from` or haven't been finished, up to
+    /// and including the first one due after `until`, so that there's always at least one. A Task
+    /// that doesn't repeat is its own only instance. Instances don't repeat, and are done if
+    /// they've been finished.
+    pub fn instances(&self, from: DateTime<Local>, until: DateTime<Local>) -> Vec<Task> {
+        if self.repeat == Repeat::No {
+            return vec![self.clone()];
+        }
+
+        let mut v = Vec::new();
+        for due_date in self.instance_due_dates() {
+            // missed instances still need doing
+            if due_date <= from && self.is_instance_done(&due_date) {
+                continue;
+            }
+            v.push(Task {
+                due_date,
+                done: self.is_instance_done(&due_date),
+                repeat: Repeat::No,
+                done_instances: Vec::new(),
+                ..self.clone()
+            });
+            if due_date > until {
+                break;
+            }
+        }
+        v
+    }
+
+    /// Returns when the next unfinished instance of the Task is due, after `now`. A Task that
+    /// doesn't repeat is due when it's due, even if that has passed.
+    pub fn next_due_date(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if self.repeat == Repeat::No {
+            return Some(self.due_date).filter(|_| !self.done);
+        }
+        if self.done {
+            return None;
+        }
+
+        self.instance_due_dates()
+            .filter(|d| *d > now)
+            .find(|d| !self.is_instance_done(d))
+    }
+
+    /// Marks the Task done. For a repeating Task, only one instance is marked done: the earliest
+    /// one that was missed, or else the next unfinished one after `now`.
+    pub fn mark_done(&mut self, now: DateTime<Local>) {
+        if self.repeat == Repeat::No {
+            self.done = true;
+            return;
+        }
+
+        let missed = self
+            .instance_due_dates()
+            .take_while(|d| *d <= now)
+            .find(|d| !self.is_instance_done(d));
+        if let Some(due) = missed.or_else(|| self.next_due_date(now)) {
+            self.done_instances.push(due.naive_local().date());
+        }
+    }
+
+    /// Returns the subtask with `id`, if the Task has one.
+    pub fn subtask(&self, id: SubtaskId) -> Option<&Subtask> {
+        match &self.duration {
+            TaskDuration::Subtasks(subs) => subs.iter().find(|s| s.id == id),
+            TaskDuration::Minutes(_) => None,
+        }
+    }
+
+    /// Gives IDs to the Task's subtasks that don't have one.
+    pub(crate) fn assign_subtask_ids(&mut self) {
+        if let TaskDuration::Subtasks(subs) = &mut self.duration {
+            let first_id = subs.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+            for (id, s) in (first_id..).zip(subs.iter_mut().filter(|s| s.id == 0)) {
+                s.id = id;
+            }
+        }
     }
 
     /// Returns true if `when` is on or after the Task's due date
@@ -112,16 +371,163 @@ impl Task {
     }
 }
 
+/// Replaces each repeating Task in `tasks` with its instances, for scheduling. Instances are
+/// expanded `repeat_horizon_days` ahead (see `Config`), or as far as the last Task that doesn't
+/// repeat is due if that's further, and at least up to the next instance. Instances keep the ID of
+/// the Task they come from.
+pub fn expand_recurring(config: &Config, tasks: &[Task], now: DateTime<Local>) -> Vec<Task> {
+    let ahead = now + Duration::days(config.repeat_horizon_days as i64);
+    let horizon = tasks
+        .iter()
+        .filter(|t| t.repeat == Repeat::No && !t.done)
+        .map(|t| t.due_date)
+        .fold(ahead, DateTime::max);
+
+    tasks
+        .iter()
+        .flat_map(|t| t.instances(now, horizon))
+        .collect()
+}
+
+/// Returns the IDs of `tasks` ordered so that every Task comes after the Tasks it depends on.
+/// Otherwise, the order of `tasks` is kept: prerequisites are moved up to just before the first
+/// Task that needs them. Dependencies on Tasks that aren't in `tasks` are ignored.
+pub fn dependency_order(tasks: &[&Task]) -> Result<Vec<TaskId>, SparrowError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        InProgress,
+        Done,
+    }
+
+    fn visit(
+        task: &Task,
+        by_id: &HashMap<TaskId, &Task>,
+        visits: &mut HashMap<TaskId, Visit>,
+        path: &mut Vec<TaskId>,
+        order: &mut Vec<TaskId>,
+    ) -> Result<(), SparrowError> {
+        match visits.get(&task.id) {
+            Some(Visit::Done) => return Ok(()),
+            Some(Visit::InProgress) => {
+                // everything on the path since the first visit to this task is part of the cycle
+                let start = path.iter().position(|id| *id == task.id).unwrap_or(0);
+                let names = path[start..]
+                    .iter()
+                    .chain(std::iter::once(&task.id))
+                    .map(|id| by_id[id].name.clone())
+                    .collect();
+                return Err(SparrowError::DependencyCycle(names));
+            }
+            None => {}
+        }
+
+        visits.insert(task.id, Visit::InProgress);
+        path.push(task.id);
+        for id in &task.depends_on {
+            if let Some(prerequisite) = by_id.get(id) {
+                visit(prerequisite, by_id, visits, path, order)?;
+            }
+        }
+        path.pop();
+        visits.insert(task.id, Visit::Done);
+        order.push(task.id);
+
+        Ok(())
+    }
+
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|t| (t.id, *t)).collect();
+    let mut visits = HashMap::new();
+    let mut path = Vec::new();
+    let mut order = Vec::new();
+    for t in tasks {
+        visit(t, &by_id, &mut visits, &mut path, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl TryFrom<&str> for Priority {
+    type Error = SparrowError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.to_lowercase();
+        if "low".starts_with(&value) {
+            Ok(Self::Low)
+        } else if "normal".starts_with(&value) {
+            Ok(Self::Normal)
+        } else if "high".starts_with(&value) {
+            Ok(Self::High)
+        } else if "urgent".starts_with(&value) {
+            Ok(Self::Urgent)
+        } else {
+            Err(SparrowError::BasicMessage(format!(
+                "'{}' isn't a priority",
+                value
+            )))
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Normal => write!(f, "normal"),
+            Self::High => write!(f, "high"),
+            Self::Urgent => write!(f, "urgent"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum TaskDuration {
     Minutes(u64),
     Subtasks(Vec<Subtask>),
 }
 
+impl TaskDuration {
+    /// Returns the total estimated minutes, including all subtasks.
+    pub fn total_minutes(&self) -> u64 {
+        match self {
+            Self::Minutes(m) => *m,
+            Self::Subtasks(subs) => subs.iter().map(|s| s.duration).sum(),
+        }
+    }
+
+    /// Takes `minutes` of work off the estimate, or off the estimate of the subtask with ID
+    /// `subtask`.
+    pub fn take_minutes(&mut self, subtask: Option<SubtaskId>, minutes: u64) {
+        match (self, subtask) {
+            (Self::Minutes(m), _) => *m = m.saturating_sub(minutes),
+            (Self::Subtasks(subs), Some(id)) => {
+                if let Some(s) = subs.iter_mut().find(|s| s.id == id) {
+                    s.duration = s.duration.saturating_sub(minutes);
+                }
+            }
+            (Self::Subtasks(_), None) => {}
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Subtask {
+    #[serde(default)]
+    pub id: SubtaskId,
+
     pub name: String,
     pub duration: u64,
+
+    #[serde(default)]
+    pub notes: Option<String>,
 }
 
 impl Subtask {
@@ -130,23 +536,97 @@ impl Subtask {
             formatting,
             "What do you want to name this subtask?",
             Some("leave blank to finish"),
+            None,
         )?;
 
         if name.trim().is_empty() {
             Ok(None)
         } else {
             let duration = prompt_time_duration(&name, formatting)?;
+            let notes = prompt_notes(formatting)?;
+
+            Ok(Some(Self {
+                id: 0,
+                name,
+                duration,
+                notes,
+            }))
+        }
+    }
+}
+
+/// How many characters of notes are shown where there's only room for a summary.
+const NOTES_SUMMARY_CHARS: usize = 40;
+
+fn prompt_notes(formatting: &Formatting) -> Result<Option<String>, SparrowError> {
+    prompt_lines(
+        formatting,
+        "Any notes?",
+        Some("finish with an empty line, or leave blank for none"),
+    )
+}
 
-            Ok(Some(Self { name, duration }))
+/// Returns the first line of `notes`, cut short if it's long.
+pub fn summarize_notes(notes: &str) -> String {
+    let first_line = notes.lines().next().unwrap_or_default();
+    if first_line.chars().count() > NOTES_SUMMARY_CHARS {
+        let cut: String = first_line.chars().take(NOTES_SUMMARY_CHARS).collect();
+        format!("{}...", cut.trim_end())
+    } else if notes.lines().nth(1).is_some() {
+        format!("{}...", first_line)
+    } else {
+        first_line.to_string()
+    }
+}
+
+/// Parses a duration like "90", "90m", "2h", "1.5h", or "1h30m" into minutes.
+pub fn parse_duration_minutes(input: &str) -> Option<u64> {
+    let input = input.trim().to_lowercase();
+    let (hours, minutes) = match input.find('h') {
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => ("", input.as_str()),
+    };
+    let minutes = minutes.trim().trim_end_matches("min").trim_end_matches('m');
+
+    let parse = |s: &str| -> Option<f64> {
+        if s.trim().is_empty() {
+            Some(0.0)
+        } else {
+            s.trim().parse::<f64>().ok().filter(|n| *n >= 0.0)
         }
+    };
+    let total = parse(hours)? * 60.0 + parse(minutes)?;
+
+    if total > 0.0 {
+        Some(total.round() as u64)
+    } else {
+        None
     }
 }
 
+pub(crate) fn prompt_priority(formatting: &Formatting) -> Result<Priority, SparrowError> {
+    prompt_strict(
+        formatting,
+        "How urgent is it?",
+        Some("[l]ow, [N]ormal, [h]igh, or [u]rgent"),
+        None,
+        |i| {
+            let i = i.trim();
+            if i.is_empty() {
+                Ok(Priority::Normal)
+            } else {
+                Priority::try_from(i)
+            }
+        },
+    )
+}
+
 fn prompt_time_duration(task_name: &str, formatting: &Formatting) -> Result<u64, SparrowError> {
     prompt_strict(
         &formatting,
         &format!("How long will \"{}\" take to complete?", task_name),
         Some("minutes"),
+        None,
         |i| match i.trim().parse::<f64>() {
             Ok(n) => Ok(n as u64),
             Err(_) => Err(SparrowError::BasicMessage(String::from(
@@ -155,3 +635,129 @@ fn prompt_time_duration(task_name: &str, formatting: &Formatting) -> Result<u64,
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        Local.ymd(2024, 5, day).and_hms(hour, 0, 0)
+    }
+
+    /// Wednesday, May 1st 2024 at noon
+    fn now() -> DateTime<Local> {
+        at(1, 12)
+    }
+
+    fn task(id: TaskId, due_date: DateTime<Local>, repeat: Repeat) -> Task {
+        serde_json::from_value(json!({
+            "id": id,
+            "name": format!("Task {}", id),
+            "due_date": due_date,
+            "duration": { "Minutes": 30 },
+            "done": false,
+            "consideration_period_days": 7,
+            "repeat": repeat,
+        }))
+        .unwrap()
+    }
+
+    fn due_dates(tasks: &[Task]) -> Vec<DateTime<Local>> {
+        tasks.iter().map(|t| t.due_date).collect()
+    }
+
+    #[test]
+    fn instances_include_missed_ones() {
+        let monday = Local.ymd(2024, 4, 29).and_hms(17, 0, 0);
+        let mut t = task(1, monday, Repeat::Daily);
+        t.done_instances.push(monday.naive_local().date());
+
+        let instances = t.instances(now(), at(2, 12));
+        assert_eq!(
+            due_dates(&instances),
+            vec![
+                Local.ymd(2024, 4, 30).and_hms(17, 0, 0),
+                at(1, 17),
+                at(2, 17)
+            ]
+        );
+        assert!(instances.iter().all(|i| !i.done && i.repeat == Repeat::No));
+    }
+
+    #[test]
+    fn marks_missed_instances_done_first() {
+        let monday = Local.ymd(2024, 4, 29).and_hms(17, 0, 0);
+        let mut t = task(1, monday, Repeat::Daily);
+        for _ in 0..3 {
+            t.mark_done(now());
+        }
+        assert_eq!(
+            t.done_instances,
+            vec![
+                monday.naive_local().date(),
+                monday.naive_local().date().succ(),
+                now().naive_local().date(),
+            ]
+        );
+        assert_eq!(t.next_due_date(now()), Some(at(2, 17)));
+
+        let mut once = task(2, at(1, 17), Repeat::No);
+        once.mark_done(now());
+        assert!(once.done);
+    }
+
+    #[test]
+    fn expands_repeating_tasks_ahead_by_the_horizon() {
+        let config = Config::default();
+        let daily = task(1, at(1, 17), Repeat::Daily);
+
+        // on their own, repeating tasks are expanded a week ahead, and one instance past that
+        let instances = expand_recurring(&config, &[daily.clone()], now());
+        assert_eq!(instances.len(), 8);
+        assert_eq!(instances.last().unwrap().due_date, at(8, 17));
+
+        // or as far as the last task that doesn't repeat
+        let later = task(2, at(20, 9), Repeat::No);
+        let instances = expand_recurring(&config, &[daily, later], now());
+        assert_eq!(instances.iter().filter(|t| t.id == 1).count(), 20);
+    }
+
+    fn depending(id: TaskId, depends_on: Vec<TaskId>) -> Task {
+        let mut t = task(id, at(2, 17), Repeat::No);
+        t.depends_on = depends_on;
+        t
+    }
+
+    fn cycle_in(tasks: &[Task]) -> Vec<String> {
+        match dependency_order(&tasks.iter().collect::<Vec<_>>()) {
+            Err(SparrowError::DependencyCycle(names)) => names,
+            _ => panic!("expected a dependency cycle"),
+        }
+    }
+
+    #[test]
+    fn orders_prerequisites_first() {
+        let tasks = [
+            depending(1, vec![3]),
+            depending(2, vec![]),
+            depending(3, vec![9]),
+        ];
+        let order = dependency_order(&tasks.iter().collect::<Vec<_>>());
+        assert_eq!(order.ok(), Some(vec![3, 1, 2]));
+    }
+
+    #[test]
+    fn finds_dependency_cycles() {
+        let tasks = [
+            depending(1, vec![2]),
+            depending(2, vec![3]),
+            depending(3, vec![2]),
+        ];
+        assert_eq!(cycle_in(&tasks), vec!["Task 2", "Task 3", "Task 2"]);
+
+        let tasks = [depending(1, vec![1])];
+        assert_eq!(cycle_in(&tasks), vec!["Task 1", "Task 1"]);
+    }
+}
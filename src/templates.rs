@@ -0,0 +1,115 @@
+//! Task templates: the shape of a kind of task that comes up again and again, like a problem set
+//! with the same three parts every week, set in the config so it doesn't have to be entered every
+//! time.
+
+use crate::{
+    metrics::format_minutes,
+    task::{default_weight, Priority, TaskDuration},
+    Task,
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Everything about a kind of task but its name and due date.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TaskTemplate {
+    /// What the template's called, like "problem set".
+    pub name: String,
+
+    pub duration: TaskDuration,
+
+    #[serde(default = "default_consideration_period_days")]
+    pub consideration_period_days: u32,
+
+    #[serde(default)]
+    pub course: Option<String>,
+
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+
+    #[serde(default)]
+    pub priority: Priority,
+
+    #[serde(default)]
+    pub important: bool,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+fn default_consideration_period_days() -> u32 {
+    3
+}
+
+impl TaskTemplate {
+    /// Makes a template out of `task`, called `name`, with all of the task's work left to do.
+    pub fn from_task(name: String, task: &Task) -> Self {
+        let mut duration = task.duration.clone();
+        if let TaskDuration::Subtasks(subs) = &mut duration {
+            for s in subs.iter_mut() {
+                s.notes = None;
+            }
+        }
+        Self {
+            name,
+            duration,
+            consideration_period_days: task.consideration_period_days,
+            course: task.course.clone(),
+            weight: task.weight,
+            priority: task.priority,
+            important: task.important,
+            tags: task.tags.clone(),
+            notes: task.notes.clone(),
+        }
+    }
+
+    /// Makes a new task called `name`, due at `due_date`, from the template.
+    pub fn instantiate(&self, name: String, due_date: DateTime<Local>) -> Task {
+        Task {
+            consideration_period_days: self.consideration_period_days,
+            course: self.course.clone(),
+            weight: self.weight,
+            priority: self.priority,
+            important: self.important,
+            tags: self.tags.clone(),
+            notes: self.notes.clone(),
+            ..Task::new(name, due_date, self.duration.clone())
+        }
+    }
+}
+
+/// Lists templates, with what a task made from each would need.
+pub struct TemplateList<'a>(pub &'a [TaskTemplate]);
+
+impl Display for TemplateList<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(
+                f,
+                "No templates yet. Save a task as one with `sparrow templates save`"
+            );
+        }
+
+        for t in self.0 {
+            write!(
+                f,
+                "-\t{}: {}",
+                t.name,
+                format_minutes(t.duration.total_minutes() as i64)
+            )?;
+            if let TaskDuration::Subtasks(subs) = &t.duration {
+                let names: Vec<&str> = subs.iter().map(|s| s.name.as_str()).collect();
+                write!(f, " ({})", names.join(", "))?;
+            }
+            for tag in &t.tags {
+                write!(f, " #{}", tag)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! Time tracking: `sparrow start` and `sparrow stop` record the time actually spent on tasks, so it
+//! can be held up against their estimates.
+
+use crate::{
+    methods::pomodoro::{JobStatus, PomodoroScheduleEntry},
+    task::SubtaskId,
+    TaskId, TimeSpan,
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Time spent on a task, from `sparrow start` until `sparrow stop`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TimeEntry {
+    pub task_id: TaskId,
+    pub start: DateTime<Local>,
+
+    /// When the clock was stopped, or None if it's still running.
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimeEntry {
+    pub fn new(task_id: TaskId, start: DateTime<Local>) -> Self {
+        Self {
+            task_id,
+            start,
+            end: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.end.is_none()
+    }
+
+    /// Returns the minutes tracked, counting up to `now` if the clock is still running.
+    pub fn minutes(&self, now: DateTime<Local>) -> i64 {
+        (self.end.unwrap_or(now) - self.start).num_minutes().max(0)
+    }
+}
+
+/// Time spent working on a task (or one of its subtasks).
+#[derive(Clone, Debug)]
+pub struct Worked {
+    pub task_id: TaskId,
+    pub subtask: Option<SubtaskId>,
+    pub span: TimeSpan,
+}
+
+/// Returns the time worked, in order: the stopped entries in `entries`, and the work periods in
+/// `jobs` marked done that no entry overlaps, for time that wasn't tracked.
+pub fn worked<'a, I>(entries: &[TimeEntry], jobs: I) -> Vec<Worked>
+where
+    I: IntoIterator<Item = &'a PomodoroScheduleEntry>,
+{
+    let mut worked: Vec<Worked> = entries
+        .iter()
+        .filter_map(|e| {
+            Some(Worked {
+                task_id: e.task_id,
+                subtask: None,
+                span: TimeSpan::between(e.start, e.end?),
+            })
+        })
+        .collect();
+    let tracked = worked.len();
+
+    for job in jobs {
+        if let PomodoroScheduleEntry::Job {
+            task_id,
+            subtask,
+            status: JobStatus::Done,
+            span,
+        } = job
+        {
+            if !worked[..tracked].iter().any(|w| w.span.overlaps(span)) {
+                worked.push(Worked {
+                    task_id: *task_id,
+                    subtask: *subtask,
+                    span: *span,
+                });
+            }
+        }
+    }
+
+    worked.sort_by_key(|w| *w.span.start());
+    worked
+}
+
+/// Returns the minutes tracked on the task with `task_id` in `entries`, up to `now`.
+pub fn tracked_minutes(entries: &[TimeEntry], task_id: TaskId, now: DateTime<Local>) -> i64 {
+    entries
+        .iter()
+        .filter(|e| e.task_id == task_id)
+        .map(|e| e.minutes(now))
+        .sum()
+}
@@ -0,0 +1,468 @@
+//! `sparrow tui`: the task list, calendar events, and today's schedule side by side, for managing
+//! more than a few tasks without going through the prompts one at a time.
+//!
+//! The screen is drawn with ratatui on a crossterm terminal. Adding and editing still use the
+//! usual prompts; the screen steps aside while they run. The data
+//! file is written after every change and read again whenever it changes on disk, so sparrowd and
+//! other `sparrow` commands stay in sync with the screen.
+//!
+//! | Key           | Does                                      |
+//! |---------------|-------------------------------------------|
+//! | Tab           | moves to the next pane                    |
+//! | j/k, arrows   | moves the selection                       |
+//! | a             | adds a task (or event, in the events pane) |
+//! | e             | edits the selected task                   |
+//! | d             | deletes the selected task or event        |
+//! | c or Space    | checks off the selected task              |
+//! | q             | quits                                     |
+
+use crate::{
+    dates, methods::pomodoro::PomodoroScheduleEntry, prompts, storage::DataFormat, CalendarEvent,
+    CalendarEventType, Formatting, SparrowError, SparrowResult, TaskId, UserData,
+};
+use ansi_term::Style;
+use chrono::Local;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, terminal,
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::{self as tui_style, Color, Modifier},
+    text::Span,
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use std::fs;
+use std::io::{self, Stdout};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Tasks,
+    Events,
+    Schedule,
+}
+
+impl Pane {
+    fn next(self) -> Self {
+        match self {
+            Self::Tasks => Self::Events,
+            Self::Events => Self::Schedule,
+            Self::Schedule => Self::Tasks,
+        }
+    }
+}
+
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    Tab,
+}
+
+/// Runs the TUI on the data file at `data_path` until the user quits. `add` asks for a new task
+/// (or event, if the events pane is selected) and adds it to the data.
+pub fn run<F>(
+    data_path: &Path,
+    data_format: DataFormat,
+    formatting: &Formatting,
+    mut add: F,
+) -> SparrowResult<()>
+where
+    F: FnMut(&mut UserData, Pane),
+{
+    let mut app = App {
+        data: UserData::load(data_path, data_format)?,
+        modified: modified_time(data_path),
+        pane: Pane::Tasks,
+        selected: [0; 3],
+        message: String::new(),
+    };
+    let mut terminal = Terminal::start()?;
+
+    loop {
+        // live refresh: pick up changes made by sparrowd or other commands
+        if modified_time(data_path) != app.modified {
+            app.data = UserData::load(data_path, data_format)?;
+            app.modified = modified_time(data_path);
+        }
+        terminal.draw(&app, formatting)?;
+
+        let key = match terminal.read_key()? {
+            Some(key) => key,
+            None => continue,
+        };
+        app.message.clear();
+        match key {
+            Key::Char('q') => break,
+            Key::Tab => app.pane = app.pane.next(),
+            Key::Up | Key::Char('k') => app.move_selection(-1),
+            Key::Down | Key::Char('j') => app.move_selection(1),
+            Key::Char('a') if app.pane != Pane::Schedule => {
+                terminal.suspend()?;
+                add(&mut app.data, app.pane);
+                terminal.resume()?;
+                app.save(data_path, data_format)?;
+            }
+            Key::Char('e') => match app.selected_task() {
+                Some(id) => {
+                    terminal.suspend()?;
+                    let result = edit_task(&mut app.data, id, formatting);
+                    terminal.resume()?;
+                    result?;
+                    app.save(data_path, data_format)?;
+                }
+                None => app.message = "only tasks can be edited here".to_string(),
+            },
+            Key::Char('d') if app.delete_selected() => app.save(data_path, data_format)?,
+            Key::Char('c') | Key::Char(' ') => match app.selected_task() {
+                Some(id) => {
+                    if let Some(task) = app.data.get_task_mut(id) {
+                        task.mark_done(Local::now());
+                        app.message = format!("Checked off {}", task.name);
+                    }
+                    app.save(data_path, data_format)?;
+                }
+                None => app.message = "only tasks can be checked off".to_string(),
+            },
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+struct App {
+    data: UserData,
+    modified: Option<SystemTime>,
+    pane: Pane,
+
+    /// The selected row in each pane.
+    selected: [usize; 3],
+    message: String,
+}
+
+impl App {
+    /// Returns the tasks that aren't done yet, soonest due first.
+    fn tasks(&self) -> Vec<&crate::Task> {
+        let mut tasks: Vec<_> = self.data.get_tasks().iter().filter(|t| !t.done).collect();
+        tasks.sort_by_key(|t| t.due_date);
+        tasks
+    }
+
+    fn today(&self) -> Vec<&PomodoroScheduleEntry> {
+        match self.data.get_pomodoro_schedule() {
+            Some(schedule) => schedule.entries_on(Local::today()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn len(&self, pane: Pane) -> usize {
+        match pane {
+            Pane::Tasks => self.tasks().len(),
+            Pane::Events => self.data.get_events().len(),
+            Pane::Schedule => self.today().len(),
+        }
+    }
+
+    fn selected(&self, pane: Pane) -> usize {
+        self.selected[pane as usize].min(self.len(pane).saturating_sub(1))
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        let last = self.len(self.pane).saturating_sub(1);
+        let row = self.selected(self.pane) as isize + by;
+        self.selected[self.pane as usize] = row.clamp(0, last as isize) as usize;
+    }
+
+    fn selected_task(&self) -> Option<TaskId> {
+        if self.pane != Pane::Tasks {
+            return None;
+        }
+        self.tasks().get(self.selected(Pane::Tasks)).map(|t| t.id)
+    }
+
+    /// Deletes the selected task or event. Returns true if anything was deleted.
+    fn delete_selected(&mut self) -> bool {
+        match self.pane {
+            Pane::Tasks => match self
+                .selected_task()
+                .and_then(|id| self.data.remove_task(id))
+            {
+                Some(task) => {
+                    self.message = format!("Deleted task {}", task.name);
+                    true
+                }
+                None => false,
+            },
+            Pane::Events if !self.data.get_events().is_empty() => {
+                let event = self.data.remove_event(self.selected(Pane::Events));
+                self.message = format!("Deleted {}", event_name(&event));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn save(&mut self, data_path: &Path, data_format: DataFormat) -> SparrowResult<()> {
+        self.data.save(data_path, data_format)?;
+        self.modified = modified_time(data_path);
+        Ok(())
+    }
+
+    /// Returns the rows of `pane`, styled like `sparrow show` styles them.
+    fn rows(&self, pane: Pane, formatting: &Formatting) -> Vec<(String, Style)> {
+        let config = self.data.get_config();
+        let date_time_format = format!("{} {}", config.date_format, config.time_format);
+        match pane {
+            Pane::Tasks => self
+                .tasks()
+                .into_iter()
+                .map(|t| {
+                    let due = t.due_date.format(&date_time_format);
+                    (format!("{}  (due {})", t.name, due), Style::new())
+                })
+                .collect(),
+            Pane::Events => self
+                .data
+                .get_events()
+                .iter()
+                .map(|e| {
+                    let start = e.time_span.start().format(&date_time_format);
+                    (format!("{}  ({})", event_name(e), start), Style::new())
+                })
+                .collect(),
+            Pane::Schedule => {
+                let now = Local::now();
+                self.today()
+                    .into_iter()
+                    .map(|e| {
+                        let text = format!(
+                            "{} - {}  {}",
+                            e.span().start().format(&config.time_format),
+                            e.span().end().format(&config.time_format),
+                            e.title(self.data.get_tasks())
+                        );
+                        let mut style = formatting.entry_style(e.kind());
+                        if *e.span().start() <= now && now < e.span().end() {
+                            style = Formatting::layer(style, formatting.current);
+                        }
+                        (text, style)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Asks for a task's new name and due date, starting from what they are now.
+fn edit_task(data: &mut UserData, id: TaskId, formatting: &Formatting) -> SparrowResult<()> {
+    let config = data.get_config().clone();
+    let task = match data.get_task_mut(id) {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+
+    println!("Editing {}", task.name);
+    task.name = prompts::prompt_strict(formatting, "Name?", None, Some(&task.name), |i| {
+        match i.trim() {
+            "" => Err(SparrowError::BasicMessage(
+                "Tasks can't have blank names".to_string(),
+            )),
+            name => Ok(name.to_string()),
+        }
+    })?;
+
+    let due = task
+        .due_date
+        .format(&format!("{} {}", config.date_format, config.time_format))
+        .to_string();
+    let (date, time) = prompts::prompt_strict(
+        formatting,
+        "Due?",
+        Some("like \"friday 5pm\""),
+        Some(&due),
+        |i| {
+            dates::parse_date_time(i, &config.date_format, &config.time_format, Local::now())
+                .ok_or_else(|| SparrowError::BasicMessage(format!("'{}' isn't a date", i.trim())))
+        },
+    )?;
+    // a new date without a time keeps the old time
+    task.due_date = dates::to_local(date, Some(time.unwrap_or_else(|| task.due_date.time())))?;
+
+    Ok(())
+}
+
+/// Returns the name of `event`, or "Break" for breaks, which don't have names.
+fn event_name(event: &CalendarEvent) -> &str {
+    match event.event_type {
+        CalendarEventType::Break => "Break",
+        _ => &event.name,
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// The terminal, in raw mode on the alternate screen until it's dropped.
+struct Terminal {
+    inner: ratatui::Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Terminal {
+    fn start() -> SparrowResult<Self> {
+        let mut terminal = Self {
+            inner: ratatui::Terminal::new(CrosstermBackend::new(io::stdout()))?,
+        };
+        terminal.resume()?;
+        Ok(terminal)
+    }
+
+    /// Enters raw mode on the alternate screen.
+    fn resume(&mut self) -> SparrowResult<()> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        // whatever the prompts printed is gone, so everything is drawn again
+        self.inner.clear()?;
+        Ok(())
+    }
+
+    /// Leaves raw mode, for prompts.
+    fn suspend(&self) -> SparrowResult<()> {
+        execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Waits up to half a second for a key, so the screen can refresh while nothing is pressed.
+    fn read_key(&self) -> SparrowResult<Option<Key>> {
+        if !event::poll(Duration::from_millis(500))? {
+            return Ok(None);
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => return Ok(None),
+        };
+        Ok(match key.code {
+            KeyCode::Tab => Some(Key::Tab),
+            KeyCode::Up => Some(Key::Up),
+            KeyCode::Down => Some(Key::Down),
+            // Ctrl-C doesn't interrupt in raw mode
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Key::Char('q'))
+            }
+            KeyCode::Char(c) => Some(Key::Char(c)),
+            _ => None,
+        })
+    }
+
+    /// Draws the tasks and events on the left, and today's schedule on the right.
+    fn draw(&mut self, app: &App, formatting: &Formatting) -> SparrowResult<()> {
+        self.inner.draw(|frame| {
+            let [body, help] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+            let [left, right] = Layout::horizontal([Constraint::Percentage(50); 2]).areas(body);
+            let [tasks, events] = Layout::vertical([Constraint::Percentage(50); 2]).areas(left);
+
+            draw_pane(frame, tasks, app, Pane::Tasks, "Tasks", formatting);
+            draw_pane(frame, events, app, Pane::Events, "Events", formatting);
+            draw_pane(frame, right, app, Pane::Schedule, "Today", formatting);
+
+            let text = if app.message.is_empty() {
+                "tab: pane  j/k: move  a: add  e: edit  d: delete  c: check off  q: quit"
+            } else {
+                &app.message
+            };
+            frame.render_widget(
+                Paragraph::new(text).style(tui_style_of(formatting.prompt_format)),
+                help,
+            );
+        })?;
+        Ok(())
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.suspend();
+    }
+}
+
+/// Draws `pane` in `area` under `title`, scrolled so the selected row shows.
+fn draw_pane(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    pane: Pane,
+    title: &str,
+    formatting: &Formatting,
+) {
+    let focused = app.pane == pane;
+    let title_style = if focused {
+        formatting.prompt.reverse()
+    } else {
+        formatting.prompt
+    };
+    let items: Vec<ListItem> = app
+        .rows(pane, formatting)
+        .into_iter()
+        .map(|(text, style)| ListItem::new(text).style(tui_style_of(style)))
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(Span::styled(title, tui_style_of(title_style))))
+        .highlight_style(tui_style::Style::new().add_modifier(Modifier::REVERSED));
+
+    // only the focused pane shows its selection
+    let mut state = ListState::default().with_selected(focused.then(|| app.selected(pane)));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// Converts one of the `Formatting` styles for the terminal into one ratatui can draw.
+fn tui_style_of(style: Style) -> tui_style::Style {
+    let mut converted = tui_style::Style::new();
+    if let Some(fg) = style.foreground {
+        converted = converted.fg(color_of(fg));
+    }
+    if let Some(bg) = style.background {
+        converted = converted.bg(color_of(bg));
+    }
+
+    let modifiers = [
+        (style.is_bold, Modifier::BOLD),
+        (style.is_dimmed, Modifier::DIM),
+        (style.is_italic, Modifier::ITALIC),
+        (style.is_underline, Modifier::UNDERLINED),
+        (style.is_blink, Modifier::SLOW_BLINK),
+        (style.is_reverse, Modifier::REVERSED),
+        (style.is_hidden, Modifier::HIDDEN),
+        (style.is_strikethrough, Modifier::CROSSED_OUT),
+    ];
+    for (on, modifier) in modifiers.iter() {
+        if *on {
+            converted = converted.add_modifier(*modifier);
+        }
+    }
+    converted
+}
+
+fn color_of(color: ansi_term::Colour) -> Color {
+    use ansi_term::Colour::*;
+    match color {
+        Black => Color::Black,
+        Red => Color::Red,
+        Green => Color::Green,
+        Yellow => Color::Yellow,
+        Blue => Color::Blue,
+        Purple => Color::Magenta,
+        Cyan => Color::Cyan,
+        // ratatui's white is the bright one, and gray is plain white
+        White => Color::Gray,
+        Fixed(n) => Color::Indexed(n),
+        RGB(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
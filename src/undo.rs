@@ -0,0 +1,163 @@
+//! `sparrow undo`. Commands that change data record which backup (see `backup`) holds the data
+//! from just before they ran, in a journal kept with the backups. Undoing pops the last command off
+//! the journal and restores its backup, so anything else that changed the data file since then
+//! (like sparrowd marking jobs done) is undone along with it.
+
+use crate::{backup, SparrowError, SparrowResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn journal_path(data_path: &Path) -> PathBuf {
+    backup::dir(data_path).join("journal")
+}
+
+/// Each line of the journal is a backup's file name and the command that ran after it was taken,
+/// separated by a tab. The last line is the latest command.
+fn read_journal(data_path: &Path) -> SparrowResult<Vec<(String, String)>> {
+    let path = journal_path(data_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.splitn(2, '\t');
+            Some((parts.next()?.to_string(), parts.next()?.to_string()))
+        })
+        .collect())
+}
+
+fn write_journal(data_path: &Path, journal: &[(String, String)]) -> SparrowResult<()> {
+    let text: String = journal
+        .iter()
+        .map(|(backup, command)| format!("{}\t{}\n", backup, command))
+        .collect();
+    fs::write(journal_path(data_path), text)?;
+    Ok(())
+}
+
+/// Records that `command` is about to change the data file at `data_path`, backing up the data
+/// file first if it hasn't been already. Does nothing if backups are off (`keep` is 0) or there's
+/// no data file yet.
+pub fn record(data_path: &Path, command: &str, keep: u32) -> SparrowResult<()> {
+    backup::back_up(data_path, keep)?;
+
+    // back_up makes sure the newest backup matches the data file
+    let newest = match backup::list(data_path)?.into_iter().next() {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let name = match newest.path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n.to_string(),
+        None => return Ok(()),
+    };
+
+    let mut journal = read_journal(data_path)?;
+    journal.push((name, command.to_string()));
+
+    // entries older than the oldest backup can't be undone anyway
+    let excess = journal.len().saturating_sub(keep as usize);
+    journal.drain(..excess);
+    write_journal(data_path, &journal)
+}
+
+/// Takes back the last recorded command if the data file at `data_path` is the same as the backup
+/// from before it ran, so that undoing doesn't skip over a command that did nothing (like `sparrow
+/// delete` when the user changes their mind). Call this after the command has saved its data.
+pub fn forget_if_unchanged(data_path: &Path) -> SparrowResult<()> {
+    let mut journal = read_journal(data_path)?;
+    let name = match journal.last() {
+        Some((name, _)) => name,
+        None => return Ok(()),
+    };
+
+    let backup = fs::read(backup::dir(data_path).join(name));
+    if backup.is_ok_and(|b| fs::read(data_path).is_ok_and(|current| current == b)) {
+        journal.pop();
+        write_journal(data_path, &journal)?;
+    }
+    Ok(())
+}
+
+/// Undoes the last recorded command, returning it, or `None` if there's nothing to undo.
+pub fn undo(data_path: &Path, keep: u32) -> SparrowResult<Option<String>> {
+    let mut journal = read_journal(data_path)?;
+    let (name, command) = match journal.pop() {
+        Some(last) => last,
+        None => return Ok(None),
+    };
+    write_journal(data_path, &journal)?;
+
+    let backups = backup::list(data_path)?;
+    match backups
+        .iter()
+        .find(|b| b.path.file_name() == Some(name.as_ref()))
+    {
+        Some(b) => {
+            backup::restore(data_path, b, keep)?;
+            Ok(Some(command))
+        }
+        None => Err(SparrowError::BasicMessage(format!(
+            "the backup from before `sparrow {}` has been deleted, so it can't be undone",
+            command
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    /// Returns a data file path in a new, empty directory.
+    fn data_path(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("sparrow-undo-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(".sparrow")
+    }
+
+    /// Runs a command that writes `contents` to the data file, like `sparrow` does.
+    fn run(path: &Path, command: &str, contents: &str) {
+        // backups are named by the millisecond
+        sleep(Duration::from_millis(5));
+        record(path, command, 5).unwrap();
+        fs::write(path, contents).unwrap();
+        forget_if_unchanged(path).unwrap();
+    }
+
+    #[test]
+    fn undoes_the_last_command() {
+        let path = data_path("last");
+        fs::write(&path, "first").unwrap();
+        run(&path, "add", "second");
+        run(&path, "done", "third");
+
+        assert_eq!(undo(&path, 5).unwrap(), Some("done".to_string()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(undo(&path, 5).unwrap(), Some("add".to_string()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert_eq!(undo(&path, 5).unwrap(), None);
+    }
+
+    #[test]
+    fn forgets_commands_that_change_nothing() {
+        let path = data_path("nothing");
+        fs::write(&path, "first").unwrap();
+        run(&path, "add", "second");
+        run(&path, "delete", "second");
+
+        assert_eq!(undo(&path, 5).unwrap(), Some("add".to_string()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+    }
+
+    #[test]
+    fn records_nothing_without_a_data_file() {
+        let path = data_path("none");
+        record(&path, "add", 5).unwrap();
+        assert!(read_journal(&path).unwrap().is_empty());
+    }
+}
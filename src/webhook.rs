@@ -0,0 +1,68 @@
+//! POSTs JSON to the user's webhook URLs when things happen, so other services can react without
+//! sparrow knowing about each of them.
+//!
+//! Every payload has an `event` field saying what happened, and the same short message in both
+//! `text` and `content`, which is what Slack and Discord webhooks display.
+
+use crate::{methods::pomodoro::PomodoroScheduleEntry, Task};
+use serde_json::{json, Value};
+use std::thread;
+use std::time::Duration;
+
+/// How many times a payload is sent before giving up.
+const ATTEMPTS: u32 = 5;
+
+/// How long to wait before the first retry. Each retry after waits twice as long as the last.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Tells the webhooks that `entry` just started.
+pub fn entry_started(urls: &[String], entry: &PomodoroScheduleEntry, tasks: &[Task]) {
+    let message = format!("Now: {}", entry.title(tasks));
+    post_in_background(
+        urls,
+        json!({
+            "event": "entry_started",
+            "text": message,
+            "content": message,
+            "entry": entry.view(tasks),
+        }),
+    );
+}
+
+/// Tells the webhooks that `task` was finished.
+pub fn task_done(urls: &[String], task: &Task) {
+    let message = format!("Finished: {}", task.name);
+    post_in_background(
+        urls,
+        json!({
+            "event": "task_done",
+            "text": message,
+            "content": message,
+            "task": task,
+        }),
+    );
+}
+
+/// Sends `payload` to each URL on its own thread, retrying with backoff, so that a slow or
+/// unreachable service never holds anything else up.
+fn post_in_background(urls: &[String], payload: Value) {
+    for url in urls {
+        let url = url.clone();
+        let payload = payload.clone();
+        thread::spawn(move || {
+            let mut delay = FIRST_RETRY_DELAY;
+            for attempt in 1..=ATTEMPTS {
+                match ureq::post(&url).send_json(&payload) {
+                    Ok(_) => return,
+                    Err(e) if attempt == ATTEMPTS => {
+                        eprintln!("giving up on webhook {}: {}", url, e);
+                    }
+                    Err(_) => {
+                        thread::sleep(delay);
+                        delay *= 2;
+                    }
+                }
+            }
+        });
+    }
+}
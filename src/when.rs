@@ -0,0 +1,96 @@
+//! `sparrow when`: where a task's work landed in the pomodoro schedule.
+
+use crate::{
+    methods::pomodoro::{EntryView, JobStatus, PomodoroScheduleEntry},
+    metrics::format_minutes,
+    Config, Task, UserData,
+};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+/// The work periods scheduled for a task, and how they add up against its estimate.
+pub struct TaskPlacement<'a> {
+    pub task: &'a Task,
+
+    /// Work on the task and its subtasks, in order.
+    pub jobs: Vec<&'a PomodoroScheduleEntry>,
+
+    config: &'a Config,
+    tasks: &'a [Task],
+}
+
+impl<'a> TaskPlacement<'a> {
+    /// Finds the work for `task` in the pomodoro schedule in `data`, if there is one.
+    pub fn new(data: &'a UserData, task: &'a Task) -> Self {
+        let is_work_on_task = |e: &&PomodoroScheduleEntry| match e {
+            PomodoroScheduleEntry::Job { task_id, .. } => *task_id == task.id,
+            _ => false,
+        };
+        let jobs = match data.get_pomodoro_schedule() {
+            Some(schedule) => schedule
+                .get_entries()
+                .iter()
+                .filter(is_work_on_task)
+                .collect(),
+            None => Vec::new(),
+        };
+        Self {
+            task,
+            jobs,
+            config: data.get_config(),
+            tasks: data.get_tasks(),
+        }
+    }
+
+    pub fn scheduled_minutes(&self) -> u64 {
+        self.jobs.iter().map(|e| e.span().minutes() as u64).sum()
+    }
+}
+
+impl Serialize for TaskPlacement<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let jobs: Vec<EntryView> = self.jobs.iter().map(|e| e.view(self.tasks)).collect();
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("task", &self.task.name)?;
+        map.serialize_entry("estimated_minutes", &self.task.duration.total_minutes())?;
+        map.serialize_entry("scheduled_minutes", &self.scheduled_minutes())?;
+        map.serialize_entry("jobs", &jobs)?;
+        map.end()
+    }
+}
+
+impl Display for TaskPlacement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.jobs.is_empty() {
+            return write!(
+                f,
+                "{} isn't in the pomodoro schedule. Make one with `sparrow make pomodoro`.",
+                self.task.name
+            );
+        }
+
+        let format = format!("{} {}", self.config.date_format, self.config.time_format);
+        for e in &self.jobs {
+            let span = e.span();
+            write!(
+                f,
+                "-\t{} - {} :: {}",
+                span.start().format(&format),
+                span.end().format(&self.config.time_format),
+                e.title(self.tasks)
+            )?;
+            match e.status() {
+                Some(JobStatus::Done) => write!(f, " (done)")?,
+                Some(JobStatus::Skipped) => write!(f, " (skipped)")?,
+                _ => {}
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{} scheduled of {} estimated",
+            format_minutes(self.scheduled_minutes() as i64),
+            format_minutes(self.task.duration.total_minutes() as i64)
+        )
+    }
+}
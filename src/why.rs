@@ -0,0 +1,151 @@
+//! `sparrow why`: for work that didn't fit in the pomodoro schedule, what took the time before it
+//! was due, so it's clear what could be moved to make room.
+
+use crate::{
+    methods::pomodoro::{self, PomodoroScheduleEntry, UnscheduledWork},
+    metrics::format_minutes,
+    work_windows, Config, UserData,
+};
+use chrono::{DateTime, Local};
+use serde::{Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+/// Why some work didn't fit.
+#[derive(Serialize)]
+pub struct Explanation {
+    pub title: String,
+    pub unscheduled_minutes: u32,
+    pub due_date: DateTime<Local>,
+
+    /// What the time until the work was due went to, most first.
+    pub taken_by: Vec<TimeTaken>,
+}
+
+#[derive(Serialize)]
+pub struct TimeTaken {
+    pub what: String,
+    pub minutes: u64,
+}
+
+/// Explains each piece of work that the pomodoro schedule in `data` had no room for.
+pub fn explain(data: &UserData) -> Vec<Explanation> {
+    let schedule = match data.get_pomodoro_schedule() {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+    schedule
+        .get_unscheduled()
+        .iter()
+        .filter_map(|work| explain_work(data, schedule.get_entries(), work))
+        .collect()
+}
+
+fn explain_work(
+    data: &UserData,
+    entries: &[PomodoroScheduleEntry],
+    work: &UnscheduledWork,
+) -> Option<Explanation> {
+    let config = data.get_config();
+    let format = format!("{} {}", config.date_format, config.time_format);
+    let task = data.get_tasks().iter().find(|t| t.id == work.task_id)?;
+    let (from, until) = (work.from, work.due_date);
+    let minutes_within = |start: DateTime<Local>, end: DateTime<Local>| {
+        (end.min(until) - start.max(from)).num_minutes().max(0) as u64
+    };
+
+    let mut taken: HashMap<String, u64> = HashMap::new();
+    for e in entries {
+        let minutes = minutes_within(*e.span().start(), e.span().end());
+        if minutes == 0 {
+            continue;
+        }
+        let what = match e {
+            PomodoroScheduleEntry::Job { task_id, .. } if *task_id == task.id => {
+                "other work on this task".to_string()
+            }
+            PomodoroScheduleEntry::Job { task_id, .. } => {
+                match data.get_tasks().iter().find(|t| t.id == *task_id) {
+                    Some(t) => format!(
+                        "work on {}, due {}",
+                        t.name,
+                        t.next_due_date(from).unwrap_or(t.due_date).format(&format)
+                    ),
+                    None => "work on a deleted task".to_string(),
+                }
+            }
+            PomodoroScheduleEntry::Calendar { name, .. } => name.clone(),
+            PomodoroScheduleEntry::Break(_) => "breaks and meals".to_string(),
+            PomodoroScheduleEntry::Sleep(_) => "sleep".to_string(),
+            PomodoroScheduleEntry::Habit { name, .. } => format!("{} (habit)", name),
+        };
+        *taken.entry(what).or_insert(0) += minutes;
+    }
+
+    // what's left is either outside the work windows, or too short to fit a work session in
+    let free = pomodoro::free_time(entries, from, until);
+    let free_minutes: u64 = free.iter().map(|f| f.minutes() as u64).sum();
+    let in_windows: u64 = free
+        .iter()
+        .flat_map(|f| work_windows::within(&config.work_windows, f))
+        .map(|f| f.minutes() as u64)
+        .sum();
+    taken.insert(
+        "outside your work windows".to_string(),
+        free_minutes - in_windows,
+    );
+    taken.insert("gaps too short for a work session".to_string(), in_windows);
+
+    let mut taken_by: Vec<TimeTaken> = taken
+        .into_iter()
+        .filter(|(_, minutes)| *minutes > 0)
+        .map(|(what, minutes)| TimeTaken { what, minutes })
+        .collect();
+    taken_by.sort_by(|a, b| b.minutes.cmp(&a.minutes).then(a.what.cmp(&b.what)));
+
+    Some(Explanation {
+        title: pomodoro::job_title(task, work.subtask).into_owned(),
+        unscheduled_minutes: work.minutes,
+        due_date: work.due_date,
+        taken_by,
+    })
+}
+
+pub struct WhyDisplay<'a> {
+    pub explanations: &'a [Explanation],
+    pub config: &'a Config,
+}
+
+impl Serialize for WhyDisplay<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.explanations.serialize(serializer)
+    }
+}
+
+impl Display for WhyDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.explanations.is_empty() {
+            return write!(f, "Everything fit in the pomodoro schedule.");
+        }
+
+        let format = format!("{} {}", self.config.date_format, self.config.time_format);
+        for (i, e) in self.explanations.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "{}: {} didn't fit before {}. The time until then went to:",
+                e.title,
+                format_minutes(e.unscheduled_minutes as i64),
+                e.due_date.format(&format)
+            )?;
+            for t in &e.taken_by {
+                writeln!(f, "-\t{}: {}", t.what, format_minutes(t.minutes as i64))?;
+            }
+        }
+        Ok(())
+    }
+}
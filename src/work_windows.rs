@@ -0,0 +1,57 @@
+//! Work windows: the times of day work can be scheduled in, like 09:00–12:00 and 13:00–18:00, so
+//! that free time at 3 AM doesn't get filled with deep work.
+
+use crate::spans::{free_time, TimeSpan};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// A stretch of every day that work can happen in. Times are written like "09:00:00". Windows
+/// that end before they start go past midnight.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WorkWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl WorkWindow {
+    /// Returns this window on each day it overlaps `from..until`.
+    fn occurrences(&self, from: DateTime<Local>, until: DateTime<Local>) -> Vec<TimeSpan> {
+        // a window past midnight can start on the day before `from`
+        let mut day = from.date().naive_local() - Duration::days(1);
+        let mut spans = Vec::new();
+        while day <= until.date().naive_local() {
+            let end_day = if self.end <= self.start {
+                day + Duration::days(1)
+            } else {
+                day
+            };
+            let start = Local
+                .from_local_datetime(&day.and_time(self.start))
+                .earliest();
+            let end = Local
+                .from_local_datetime(&end_day.and_time(self.end))
+                .earliest();
+            if let (Some(start), Some(end)) = (start, end) {
+                spans.push(TimeSpan::between(start, end));
+            }
+            day += Duration::days(1);
+        }
+        spans
+    }
+}
+
+/// Returns the parts of `span` inside any of `windows`, in order. Without any windows, all of
+/// `span` is.
+pub fn within(windows: &[WorkWindow], span: &TimeSpan) -> Vec<TimeSpan> {
+    if windows.is_empty() {
+        return vec![*span];
+    }
+
+    let (from, until) = (*span.start(), span.end());
+    let open: Vec<TimeSpan> = windows
+        .iter()
+        .flat_map(|w| w.occurrences(from, until))
+        .collect();
+    let closed = free_time(&open, from, until);
+    free_time(&closed, from, until)
+}